@@ -1,6 +1,6 @@
 use chrono::{Duration, NaiveDate, TimeZone};
 use chrono_tz::America::New_York;
-use clickhouse::{Client, Row};
+use clickhouse::{Client, Row, RowOwned, RowWrite};
 use csv::WriterBuilder;
 use polars::prelude::*;
 use serde::{Deserialize, Serialize};
@@ -34,6 +34,88 @@ struct OHLCV {
     volume: Option<f64>,
 }
 
+/// Target database and table names, so scoring and price pulls can be pointed at a
+/// staging database or test table without code edits
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClickhouseConfig {
+    pub database: String,
+    pub score_table: String,
+    pub trade_table: String,
+    pub crypto_table: String,
+    pub stock_table: String,
+    pub universe_table: String,
+    /// Price chunk queries in flight at once
+    pub max_concurrent_queries: usize,
+}
+
+impl Default for ClickhouseConfig {
+    fn default() -> Self {
+        Self {
+            database: "tiingo".to_string(),
+            score_table: "strategy".to_string(),
+            trade_table: "strategy_trades".to_string(),
+            crypto_table: "crypto".to_string(),
+            stock_table: "usd".to_string(),
+            universe_table: "univ".to_string(),
+            max_concurrent_queries: 4,
+        }
+    }
+}
+
+impl ClickhouseConfig {
+    /// Defaults overridden by CLICKHOUSE_DATABASE, CLICKHOUSE_SCORE_TABLE,
+    /// CLICKHOUSE_TRADE_TABLE, CLICKHOUSE_CRYPTO_TABLE, CLICKHOUSE_STOCK_TABLE,
+    /// CLICKHOUSE_UNIVERSE_TABLE and CLICKHOUSE_MAX_CONCURRENT_QUERIES
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        let var = |key: &str, fallback: String| {
+            env::var(key).ok().filter(|v| !v.trim().is_empty()).unwrap_or(fallback)
+        };
+        Self {
+            database: var("CLICKHOUSE_DATABASE", default.database),
+            score_table: var("CLICKHOUSE_SCORE_TABLE", default.score_table),
+            trade_table: var("CLICKHOUSE_TRADE_TABLE", default.trade_table),
+            crypto_table: var("CLICKHOUSE_CRYPTO_TABLE", default.crypto_table),
+            stock_table: var("CLICKHOUSE_STOCK_TABLE", default.stock_table),
+            universe_table: var("CLICKHOUSE_UNIVERSE_TABLE", default.universe_table),
+            max_concurrent_queries: var("CLICKHOUSE_MAX_CONCURRENT_QUERIES", String::new())
+                .parse()
+                .ok()
+                .filter(|&n: &usize| n > 0)
+                .unwrap_or(default.max_concurrent_queries),
+        }
+    }
+
+    /// Database-qualified table name, e.g. "tiingo.strategy"
+    pub fn table(&self, name: &str) -> String {
+        format!("{}.{}", self.database, name)
+    }
+}
+
+/// Run `fetch` on every ticker chunk with at most `limit` queries in flight and return
+/// all rows sorted by ticker, then date, whatever order the chunks finish in
+async fn fetch_chunks<F, Fut>(chunks: Vec<Vec<String>>, limit: usize, fetch: F) -> Result<Vec<OHLCV>, Box<dyn StdError>>
+where
+    F: Fn(usize, Vec<String>) -> Fut,
+    Fut: std::future::Future<Output = Result<Vec<OHLCV>, Box<dyn StdError>>>,
+{
+    let permits = tokio::sync::Semaphore::new(limit.max(1));
+    let queries = chunks.into_iter().enumerate().map(|(i, chunk)| {
+        let query = fetch(i, chunk);
+        let permits = &permits;
+        async move {
+            let _permit = permits.acquire().await?;
+            query.await
+        }
+    });
+    let mut rows = Vec::new();
+    for chunk_rows in futures::future::join_all(queries).await {
+        rows.extend(chunk_rows?);
+    }
+    rows.sort_by(|a, b| (&a.ticker, &a.date).cmp(&(&b.ticker, &b.date)));
+    Ok(rows)
+}
+
 // Helper struct for get_universe_tickers
 #[derive(Row, Deserialize, Debug)]
 struct TickerRow {
@@ -41,7 +123,11 @@ struct TickerRow {
     ticker: String,
 }
 
-pub async fn write_price_file(univ: String, is_production: bool) -> Result<(), Box<dyn StdError>> {
+pub async fn write_price_file(
+    univ: String,
+    is_production: bool,
+    ch_config: &ClickhouseConfig,
+) -> Result<(), Box<dyn StdError>> {
     let user_path = match env::var("CLICKHOUSE_USER_PATH") {
         Ok(path) => path,
         Err(_) => String::from("/srv"),
@@ -55,7 +141,7 @@ pub async fn write_price_file(univ: String, is_production: bool) -> Result<(), B
     );
 
     // Get the list of tickers in the universe that are already pre-filtered for validity
-    let tickers = get_universe_tickers(&univ).await?;
+    let tickers = get_universe_tickers(&univ, ch_config).await?;
 
     // Process in chunks of 500 tickers (adjust based on your memory constraints)
     let chunk_size = 500; // Reduced chunk size to avoid server memory limit
@@ -65,10 +151,12 @@ pub async fn write_price_file(univ: String, is_production: bool) -> Result<(), B
         .collect();
 
     // Get a client connection once
-    let client = get_ch_client(ChConnectionType::Ace).await?;
+    let client = get_ch_client(ChConnectionType::Ace, ch_config).await?;
 
-    // Create the final CSV file and writer once
-    let file = File::create(&filename)?;
+    // Write to a partial file and rename on success, so a failed pull never
+    // clobbers the cached CSV from the previous run
+    let partial = format!("{}.partial", filename);
+    let file = File::create(&partial)?;
     let mut wtr = WriterBuilder::new().has_headers(false).from_writer(file);
 
     // Write the header record once
@@ -76,83 +164,94 @@ pub async fn write_price_file(univ: String, is_production: bool) -> Result<(), B
         "Date", "Ticker", "Universe", "Open", "High", "Low", "Close", "Volume",
     ])?;
 
-    // Process each chunk
-    for (i, chunk) in ticker_chunks.iter().enumerate() {
+    // Query the chunks concurrently, a bounded number at a time
+    let n_chunks = ticker_chunks.len();
+    let rows = fetch_chunks(ticker_chunks, ch_config.max_concurrent_queries, |i, chunk| {
         // Join the ticker list into a quoted, comma-separated string for SQL IN clause
         let ticker_list = chunk
             .iter()
             .map(|t| format!("'{}'", t))
             .collect::<Vec<_>>()
             .join(",");
-
-        let query = build_price_query(&univ, &ticker_list, is_production);
-
-        println!(
-            "Executing query for chunk {}/{}",
-            i + 1,
-            ticker_chunks.len()
-        );
-        // println!("Query: {}", query);
-
-        // Execute the query and write to the single CSV file
-        let mut cursor = client.query(&query).fetch::<OHLCV>()?;
-
-        // Write all rows from the cursor to the CSV file
-        while let Some(row) = cursor.next().await? {
-            wtr.serialize(row)?;
+        let query = build_price_query(&univ, &ticker_list, is_production, ch_config);
+        let client = client.clone();
+        async move {
+            log::info!("Executing query for chunk {}/{}", i + 1, n_chunks);
+            let mut cursor = client.query(&query).fetch::<OHLCV>()?;
+            let mut rows = Vec::new();
+            while let Some(row) = cursor.next().await? {
+                rows.push(row);
+            }
+            Ok(rows)
         }
+    })
+    .await?;
+
+    // Write all rows to the single CSV file
+    for row in rows {
+        wtr.serialize(row)?;
     }
 
     wtr.flush()?;
+    drop(wtr);
+    std::fs::rename(&partial, &filename)?;
     Ok(())
 }
 
 // Helper function to build price queries
-fn build_price_query(univ: &str, ticker_list: &str, is_production: bool) -> String {
-    let is_crypto = univ == "Crypto";
+fn build_price_query(univ: &str, ticker_list: &str, is_production: bool, ch_config: &ClickhouseConfig) -> String {
+    let is_crypto = crate::config::is_crypto_universe(univ);
+    let crypto_table = ch_config.table(&ch_config.crypto_table);
+    let stock_table = ch_config.table(&ch_config.stock_table);
 
     if is_crypto {
         let min_days = if is_production { 120 } else { 360 };
         let date_filter = if is_production {
             // Relax date filter: allow tickers with data within 7 days of the latest date
-            "WHERE p.date >= subtractDays(now(), 252)
-                and maxdate >= subtractDays((select toDate(max(date)) from tiingo.crypto), 7)"
+            format!(
+                "WHERE p.date >= subtractDays(now(), 252)
+                and maxdate >= subtractDays((select toDate(max(date)) from {}), 7)",
+                crypto_table
+            )
         } else {
-            ""
+            String::new()
         };
 
         format!(
             "WITH univ AS (
             SELECT baseCurrency ticker, toDate(max(date)) maxdate
-            FROM tiingo.crypto
+            FROM {}
             WHERE baseCurrency IN ({})
             group by ticker
             having count(date) > {} and COUNT(*) * 2 - COUNT(high) - COUNT(low) = 0
             )
             SELECT toString(date(formatDateTime(p.date, '%Y-%m-%d %H:%i:%s'))) Date, u.ticker Ticker, 'Crypto' as Universe,
             open AS Open, high AS High, low AS Low, close AS Close, volume AS Volume
-            FROM tiingo.crypto p
+            FROM {} p
             INNER JOIN univ u
             ON u.ticker = p.baseCurrency
             {}
             order by ticker, date",
-            ticker_list, min_days, date_filter
+            crypto_table, ticker_list, min_days, crypto_table, date_filter
         )
     } else {
         let min_days = if is_production { 250 } else { 1000 };
         let date_filter = if is_production {
             // Relax date filter: allow tickers with data within 7 days of the latest date
             // This handles holidays, weekends, and slight data delays
-            "WHERE p.date >= subtractDays(now(), 365)
-                and m.maxdate >= subtractDays((select max(date(formatDateTime(date, '%Y-%m-%d %H:%i:%s'))) from tiingo.usd), 7)"
+            format!(
+                "WHERE p.date >= subtractDays(now(), 365)
+                and m.maxdate >= subtractDays((select max(date(formatDateTime(date, '%Y-%m-%d %H:%i:%s'))) from {}), 7)",
+                stock_table
+            )
         } else {
-            ""
+            String::new()
         };
 
         format!(
             "WITH mdate AS (
             SELECT symbol, max(date(formatDateTime(date, '%Y-%m-%d %H:%i:%s'))) AS maxdate
-            FROM tiingo.usd p
+            FROM {} p
             WHERE symbol IN ({})
             group by symbol
             having count(date) >= {} and COUNT(*) * 2 - COUNT(adjHigh) - COUNT(adjLow) = 0
@@ -165,24 +264,31 @@ fn build_price_query(univ: &str, ticker_list: &str, is_production: bool) -> Stri
             , round(toFloat64(adjLow), 2) AS Low
             , round(toFloat64(adjClose), 2) AS Close
             , toFloat64(adjVolume) AS Volume
-            FROM tiingo.usd p
+            FROM {} p
             INNER JOIN mdate m
             ON m.symbol = p.symbol
             {}
             order by Ticker, date",
-            ticker_list, min_days, univ, date_filter
+            stock_table, ticker_list, min_days, univ, stock_table, date_filter
         )
     }
 }
 
 // Helper function to get the list of tickers in a universe
-async fn get_universe_tickers(univ: &str) -> Result<Vec<String>, Box<dyn StdError>> {
-    let client = get_ch_client(ChConnectionType::Ace).await?;
+async fn get_universe_tickers(univ: &str, ch_config: &ClickhouseConfig) -> Result<Vec<String>, Box<dyn StdError>> {
+    let client = get_ch_client(ChConnectionType::Ace, ch_config).await?;
 
-    let query = if univ == "Crypto" {
-        "SELECT DISTINCT baseCurrency AS Ticker FROM tiingo.crypto".to_string()
+    let query = if crate::config::is_crypto_universe(univ) {
+        format!(
+            "SELECT DISTINCT baseCurrency AS Ticker FROM {}",
+            ch_config.table(&ch_config.crypto_table)
+        )
     } else {
-        format!("SELECT DISTINCT Ticker FROM univ WHERE batch = '{}'", univ)
+        format!(
+            "SELECT DISTINCT Ticker FROM {} WHERE batch = '{}'",
+            ch_config.table(&ch_config.universe_table),
+            univ
+        )
     };
 
     // println!("Fetching tickers for universe: {}", univ);
@@ -202,11 +308,34 @@ async fn get_universe_tickers(univ: &str) -> Result<Vec<String>, Box<dyn StdErro
     Ok(tickers)
 }
 
+/// TCP reachability check of the ClickHouse HTTP port (5 second timeout)
+pub async fn test_connection(connection_type: ChConnectionType) -> bool {
+    let host = match connection_type {
+        ChConnectionType::Ace => "192.168.86.46",
+        ChConnectionType::Mini => "192.168.86.56",
+    };
+    let connect = tokio::net::TcpStream::connect((host, 8123));
+    match time::timeout(std::time::Duration::from_secs(5), connect).await {
+        Ok(Ok(_)) => true,
+        Ok(Err(e)) => {
+            log::warn!("✗ TCP connection to {}:8123 failed: {}", host, e);
+            false
+        }
+        Err(_) => {
+            log::warn!("✗ TCP connection to {}:8123 timed out after 5 seconds", host);
+            false
+        }
+    }
+}
+
 fn read_env_var(key: &str) -> String {
     env::var(key).unwrap_or_else(|_| panic!("{key} env variable should be set"))
 }
 
-pub async fn get_ch_client(connection_type: ChConnectionType) -> Result<Client, Box<dyn StdError>> {
+pub async fn get_ch_client(
+    connection_type: ChConnectionType,
+    ch_config: &ClickhouseConfig,
+) -> Result<Client, Box<dyn StdError>> {
     let (host, conn_type_str) = match connection_type {
         ChConnectionType::Ace => ("192.168.86.46", "Ace"),
         ChConnectionType::Mini => ("192.168.86.56", "Mini"),
@@ -216,169 +345,214 @@ pub async fn get_ch_client(connection_type: ChConnectionType) -> Result<Client,
         .with_url(format!("http://{}:8123", host))
         .with_user("roger")
         .with_password(read_env_var("PG"))
-        .with_database("tiingo");
+        .with_database(&ch_config.database);
 
     match client.query("SELECT version()").fetch_one::<String>().await {
         Ok(version) => {
-            println!("Successfully connected to ClickHouse {}. Server version: {}", conn_type_str, version);
+            log::info!("Successfully connected to ClickHouse {}. Server version: {}", conn_type_str, version);
             Ok(client)
         }
         Err(e) => {
-            println!("Failed to connect to ClickHouse {}: {:?}", conn_type_str, e);
+            log::warn!("Failed to connect to ClickHouse {}: {:?}", conn_type_str, e);
             Err(Box::new(e))
         }
     }
 }
 
-pub async fn insert_score_dataframe(df: DataFrame) -> Result<(), Box<dyn StdError>> {
-    // Create both clients
-    let client_ace = get_ch_client(ChConnectionType::Ace).await?;
-    let client_mini = get_ch_client(ChConnectionType::Mini).await?;
-
-    // Extract all columns once
-    let date_column = df.column("date")?.date()?;
-    let universe_column = df.column("universe")?.str()?;
-    let ticker_column = df.column("ticker")?.str()?;
-    let side_column = df.column("side")?.i64()?;
-    let risk_reward_column = df.column("risk_reward")?.f64()?;
-    let sharpe_ratio_column = df.column("sharpe_ratio")?.f64()?;
-    let sortino_ratio_column = df.column("sortino_ratio")?.f64()?;
-    let max_drawdown_column = df.column("max_drawdown")?.f64()?;
-    let calmar_ratio_column = df.column("calmar_ratio")?.f64()?;
-    let win_loss_ratio_column = df.column("win_loss_ratio")?.f64()?;
-    let recovery_factor_column = df.column("recovery_factor")?.f64()?;
-    let profit_per_trade_column = df.column("profit_per_trade")?.f64()?;
-    let expectancy_column = df.column("expectancy")?.f64()?;
-    let profit_factor_column = df.column("profit_factor")?.f64()?;
-
-    // Create a vector of (client, name, use_binary, batch_size) tuples to process
-    // Both servers now support binary mode with clickhouse-rs 0.14.2
-    let clients = vec![
-        (client_ace, "ace", true, 1000),
-        (client_mini, "mimi", true, 1000),
-    ];
-
-    for (client, location, use_binary, batch_size) in clients {
-        let result = async {
-            if use_binary {
-                // Use binary format
-                for batch_start in (0..df.height()).step_by(batch_size) {
-                    let batch_end = (batch_start + batch_size).min(df.height());
-                    let mut insert = client.insert::<Score>("strategy").await?;
-
-                    for i in batch_start..batch_end {
-                        let date_days = date_column.get(i).unwrap();
-                        let naive_date =
-                            NaiveDate::from_ymd_opt(1970, 1, 1).unwrap() + Duration::days(date_days as i64);
-                        let naive_datetime = naive_date.and_hms_opt(0, 0, 0).unwrap();
-                        let ny_datetime = New_York
-                            .from_local_datetime(&naive_datetime)
-                            .single()
-                            .unwrap()
-                            .timestamp()
-                            * 1000;
-
-                        let universe_str = universe_column.get(i).unwrap().to_string();
-                        let ticker_str = ticker_column.get(i).unwrap().to_string();
-
-                        let row = Score {
-                            date: ny_datetime,
-                            universe: universe_str,
-                            ticker: ticker_str,
-                            side: Some(side_column.get(i).unwrap()),
-                            risk_reward: Some(risk_reward_column.get(i).unwrap()),
-                            sharpe_ratio: Some(sharpe_ratio_column.get(i).unwrap()),
-                            sortino_ratio: Some(sortino_ratio_column.get(i).unwrap()),
-                            max_drawdown: Some(max_drawdown_column.get(i).unwrap()),
-                            calmar_ratio: Some(calmar_ratio_column.get(i).unwrap()),
-                            win_loss_ratio: Some(win_loss_ratio_column.get(i).unwrap()),
-                            recovery_factor: Some(recovery_factor_column.get(i).unwrap()),
-                            profit_per_trade: Some(profit_per_trade_column.get(i).unwrap()),
-                            expectancy: Some(expectancy_column.get(i).unwrap()),
-                            profit_factor: Some(profit_factor_column.get(i).unwrap()),
-                        };
-                        insert.write(&row).await?;
-                    }
-                    insert.end().await?;
-                }
-            } else {
-                // Use SQL VALUES format (more compatible across versions)
-                for batch_start in (0..df.height()).step_by(batch_size) {
-                    let batch_end = (batch_start + batch_size).min(df.height());
-
-                    let mut values = Vec::new();
-                    for i in batch_start..batch_end {
-                        let date_days = date_column.get(i).unwrap();
-                        let naive_date =
-                            NaiveDate::from_ymd_opt(1970, 1, 1).unwrap() + Duration::days(date_days as i64);
-                        let naive_datetime = naive_date.and_hms_opt(0, 0, 0).unwrap();
-                        let ny_datetime = New_York
-                            .from_local_datetime(&naive_datetime)
-                            .single()
-                            .unwrap()
-                            .timestamp()
-                            * 1000;
-
-                        let universe = universe_column.get(i).unwrap();
-                        let ticker = ticker_column.get(i).unwrap().replace("'", "''"); // Escape single quotes
-                        let side = side_column.get(i).unwrap();
-                        let risk_reward = risk_reward_column.get(i).unwrap();
-                        let sharpe_ratio = sharpe_ratio_column.get(i).unwrap();
-                        let sortino_ratio = sortino_ratio_column.get(i).unwrap();
-                        let max_drawdown = max_drawdown_column.get(i).unwrap();
-                        let calmar_ratio = calmar_ratio_column.get(i).unwrap();
-                        let win_loss_ratio = win_loss_ratio_column.get(i).unwrap();
-                        let recovery_factor = recovery_factor_column.get(i).unwrap();
-                        let profit_per_trade = profit_per_trade_column.get(i).unwrap();
-                        let expectancy = expectancy_column.get(i).unwrap();
-                        let profit_factor = profit_factor_column.get(i).unwrap();
-
-                        values.push(format!(
-                            "({}, '{}', '{}', {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {})",
-                            ny_datetime, universe, ticker, side, risk_reward, sharpe_ratio,
-                            sortino_ratio, max_drawdown, calmar_ratio, win_loss_ratio,
-                            recovery_factor, profit_per_trade, expectancy, profit_factor
-                        ));
-                    }
-
-                    let query = format!(
-                        "INSERT INTO strategy (date, universe, ticker, side, risk_reward, sharpe_ratio, \
-                         sortino_ratio, max_drawdown, calmar_ratio, win_loss_ratio, recovery_factor, \
-                         profit_per_trade, expectancy, profit_factor) VALUES {}",
-                        values.join(", ")
-                    );
-
-                    client.query(&query).execute().await?;
-
-                    // Progress indicator
-                    if batch_end % 100 == 0 || batch_end == df.height() {
-                        println!("Progress {}: {}/{} rows", location, batch_end, df.height());
-                    }
-
-                    // Small delay between batches
-                    time::sleep(time::Duration::from_millis(100)).await;
-                }
+/// SQL deleting every score row of `tickers` from `first` to `last` (New York calendar
+/// days, inclusive)
+fn score_delete_sql(ch_config: &ClickhouseConfig, tickers: &[String], first: NaiveDate, last: NaiveDate) -> String {
+    let quote = |v: &str| format!("'{}'", v.replace("'", "''"));
+    let tickers: Vec<String> = tickers.iter().map(|t| quote(t)).collect();
+    format!(
+        "ALTER TABLE {} DELETE WHERE ticker IN ({}) AND toDate(date, 'America/New_York') BETWEEN {} AND {} \
+         SETTINGS mutations_sync = 1",
+        ch_config.table(&ch_config.score_table),
+        tickers.join(", "),
+        quote(&first.to_string()),
+        quote(&last.to_string())
+    )
+}
+
+/// Table replacement rows for `table` are staged in before they are swapped into it
+fn staging_table(table: &str) -> String {
+    format!("{}_staging", table)
+}
+
+/// Attempts at each staged write before its error is returned
+const WRITE_ATTEMPTS: u64 = 3;
+
+/// Run `write` until it succeeds, at most `WRITE_ATTEMPTS` times with a pause that grows
+/// by a second per failed attempt; the last error is returned
+async fn with_retries<F, Fut>(what: &str, mut write: F) -> Result<(), Box<dyn StdError>>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<(), Box<dyn StdError>>>,
+{
+    let mut attempt = 1;
+    loop {
+        match write().await {
+            Ok(()) => return Ok(()),
+            Err(e) if attempt < WRITE_ATTEMPTS => {
+                log::warn!("{} failed (attempt {} of {}): {}", what, attempt, WRITE_ATTEMPTS, e);
+                time::sleep(time::Duration::from_secs(attempt)).await;
+                attempt += 1;
             }
-            Ok::<(), Box<dyn StdError>>(())
+            Err(e) => return Err(e),
         }
+    }
+}
+
+/// Replace rows of `table` on both servers: `rows` are first staged in a copy of the table
+/// on each server, retried until the copy holds every row, and only then is `delete` run
+/// and the staged rows moved in. A failed insert therefore leaves the stored rows as they
+/// were; `delete` must cover every staged row so a retried swap doesn't duplicate them.
+async fn stage_and_swap<R>(
+    ch_config: &ClickhouseConfig,
+    table: &str,
+    rows: &[R],
+    delete: &str,
+) -> Result<(), Box<dyn StdError>>
+where
+    R: RowOwned + RowWrite + Clone,
+{
+    let staging = staging_table(table);
+    for connection_type in [ChConnectionType::Ace, ChConnectionType::Mini] {
+        let client = get_ch_client(connection_type, ch_config).await?;
+        let (client, staging) = (&client, &staging);
+        client
+            .query(&format!("CREATE TABLE IF NOT EXISTS {} AS {}", staging, table))
+            .execute()
+            .await?;
+        with_retries(&format!("Staging {} rows in {}", rows.len(), staging), || async move {
+            client.query(&format!("TRUNCATE TABLE {}", staging)).execute().await?;
+            insert_rows_with(rows, 1000, |batch| async move {
+                let mut insert = client.insert::<R>(staging).await?;
+                for row in &batch {
+                    insert.write(row).await?;
+                }
+                insert.end().await?;
+                Ok(())
+            })
+            .await?;
+            let staged = client.query(&format!("SELECT count() FROM {}", staging)).fetch_one::<u64>().await?;
+            if staged != rows.len() as u64 {
+                return Err(format!("staged {} of {} rows in {}", staged, rows.len(), staging).into());
+            }
+            Ok(())
+        })
+        .await?;
+    }
+
+    for connection_type in [ChConnectionType::Ace, ChConnectionType::Mini] {
+        let client = get_ch_client(connection_type, ch_config).await?;
+        let (client, staging) = (&client, &staging);
+        with_retries(&format!("Swapping {} into {}", staging, table), || async move {
+            client.query(delete).execute().await?;
+            client.query(&format!("INSERT INTO {} SELECT * FROM {}", table, staging)).execute().await?;
+            Ok(())
+        })
+        .await?;
+        client.query(&format!("DROP TABLE {}", staging)).execute().await?;
+    }
+    Ok(())
+}
+
+/// Rows of a score frame, dates as New York midnight in epoch milliseconds
+fn score_rows(df: &DataFrame) -> Result<Vec<Score>, Box<dyn StdError>> {
+    let epoch = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap();
+    let (dates, universes, tickers) = (df.column("date")?.date()?, df.column("universe")?.str()?, df.column("ticker")?.str()?);
+    let sides = df.column("side")?.i64()?;
+    let number = |name: &str| -> Result<Vec<Option<f64>>, Box<dyn StdError>> {
+        Ok(df.column(name)?.f64()?.into_iter().collect())
+    };
+    let (risk_reward, sharpe_ratio, sortino_ratio) = (number("risk_reward")?, number("sharpe_ratio")?, number("sortino_ratio")?);
+    let (max_drawdown, calmar_ratio, win_loss_ratio) = (number("max_drawdown")?, number("calmar_ratio")?, number("win_loss_ratio")?);
+    let (recovery_factor, profit_per_trade) = (number("recovery_factor")?, number("profit_per_trade")?);
+    let (expectancy, profit_factor) = (number("expectancy")?, number("profit_factor")?);
+    (0..df.height())
+        .map(|i| {
+            let days = dates.get(i).ok_or("score row without a date")?;
+            Ok(Score {
+                date: ny_midnight_millis(epoch + Duration::days(days as i64)),
+                universe: universes.get(i).unwrap_or_default().to_string(),
+                ticker: tickers.get(i).unwrap_or_default().to_string(),
+                side: sides.get(i),
+                risk_reward: risk_reward[i],
+                sharpe_ratio: sharpe_ratio[i],
+                sortino_ratio: sortino_ratio[i],
+                max_drawdown: max_drawdown[i],
+                calmar_ratio: calmar_ratio[i],
+                win_loss_ratio: win_loss_ratio[i],
+                recovery_factor: recovery_factor[i],
+                profit_per_trade: profit_per_trade[i],
+                expectancy: expectancy[i],
+                profit_factor: profit_factor[i],
+            })
+        })
+        .collect()
+}
+
+/// Replace the stored scores of `tickers` with the rows in `df`: each ticker's full
+/// history between the first and last date of `df` is swapped for the rows through
+/// `stage_and_swap`, so a failed insert leaves the old scores in place.
+pub async fn replace_score_tickers(
+    df: DataFrame,
+    tickers: &[String],
+    ch_config: &ClickhouseConfig,
+) -> Result<(), Box<dyn StdError>> {
+    let rows = score_rows(&df)?;
+    let epoch = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap();
+    let dates: Vec<NaiveDate> = df
+        .column("date")?
+        .date()?
+        .into_iter()
+        .flatten()
+        .map(|days| epoch + Duration::days(days as i64))
+        .collect();
+    let (Some(&first), Some(&last)) = (dates.iter().min(), dates.iter().max()) else {
+        log::warn!("No scores: skipping replace.");
+        return Ok(());
+    };
+    if tickers.is_empty() {
+        return insert_score_dataframe(df, ch_config).await;
+    }
+
+    let delete = score_delete_sql(ch_config, tickers, first, last);
+    stage_and_swap(ch_config, &ch_config.table(&ch_config.score_table), &rows, &delete).await?;
+    log::info!("Replaced the scores of {} tickers from {} to {} with {} rows", tickers.len(), first, last, rows.len());
+    Ok(())
+}
+
+/// Insert the rows of a score frame (see `score_rows`) into the configured score table
+/// on both servers; a failed server is logged and skipped
+pub async fn insert_score_dataframe(df: DataFrame, ch_config: &ClickhouseConfig) -> Result<(), Box<dyn StdError>> {
+    let rows = score_rows(&df)?;
+    let score_table = ch_config.table(&ch_config.score_table);
+
+    for (connection_type, location) in [(ChConnectionType::Ace, "ace"), (ChConnectionType::Mini, "mimi")] {
+        let client = get_ch_client(connection_type, ch_config).await?;
+        let (client, score_table) = (&client, &score_table);
+        let result = insert_rows_with(&rows, 1000, |batch| async move {
+            let mut insert = client.insert::<Score>(score_table).await?;
+            for row in &batch {
+                insert.write(row).await?;
+            }
+            insert.end().await?;
+            Ok(())
+        })
         .await;
 
         match result {
-            Ok(_) => println!(
-                "Successfully inserted {} rows into ClickHouse {}",
-                df.height(), location
-            ),
-            Err(e) => eprintln!(
-                "Failed to insert rows into ClickHouse {}: {:?}",
-                location, e
-            ),
+            Ok(inserted) => log::info!("Successfully inserted {} rows into ClickHouse {}", inserted, location),
+            Err(e) => log::error!("Failed to insert rows into ClickHouse {}: {:?}", location, e),
         }
     }
 
     Ok(())
 }
 
-#[derive(Debug, Row, Serialize, Deserialize)]
+#[derive(Debug, Clone, Row, Serialize, Deserialize)]
 struct Score {
     date: i64,
     universe: String,
@@ -396,6 +570,109 @@ struct Score {
     profit_factor: Option<f64>,
 }
 
+/// One exported trade; dates are New York midnight in epoch milliseconds like `Score`
+#[derive(Debug, Clone, PartialEq, Row, Serialize, Deserialize)]
+pub struct TradeRow {
+    pub ticker: String,
+    pub strategy: String,
+    pub direction: String,
+    pub entry_date: Option<i64>,
+    pub exit_date: Option<i64>,
+    pub entry_price: f64,
+    pub exit_price: f64,
+    pub pnl: f64,
+    pub exit_reason: String,
+}
+
+/// New York midnight of `date` in epoch milliseconds
+fn ny_midnight_millis(date: NaiveDate) -> i64 {
+    New_York
+        .from_local_datetime(&date.and_hms_opt(0, 0, 0).unwrap())
+        .single()
+        .unwrap()
+        .timestamp()
+        * 1000
+}
+
+/// Rows of a `trades_dataframe` frame
+pub fn trade_rows(df: &DataFrame) -> Result<Vec<TradeRow>, Box<dyn StdError>> {
+    let epoch = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap();
+    let text = |name: &str| -> Result<Vec<String>, Box<dyn StdError>> {
+        Ok(df.column(name)?.str()?.into_iter().map(|v| v.unwrap_or_default().to_string()).collect())
+    };
+    let number = |name: &str| -> Result<Vec<f64>, Box<dyn StdError>> {
+        Ok(df.column(name)?.f64()?.into_iter().map(|v| v.unwrap_or(f64::NAN)).collect())
+    };
+    let date = |name: &str| -> Result<Vec<Option<i64>>, Box<dyn StdError>> {
+        Ok(df
+            .column(name)?
+            .date()?
+            .into_iter()
+            .map(|days| days.map(|d| ny_midnight_millis(epoch + Duration::days(d as i64))))
+            .collect())
+    };
+    let (tickers, strategies, directions, reasons) = (text("ticker")?, text("strategy")?, text("direction")?, text("exit_reason")?);
+    let (entry_dates, exit_dates) = (date("entry_date")?, date("exit_date")?);
+    let (entry_prices, exit_prices, pnls) = (number("entry_price")?, number("exit_price")?, number("pnl")?);
+    Ok((0..df.height())
+        .map(|i| TradeRow {
+            ticker: tickers[i].clone(),
+            strategy: strategies[i].clone(),
+            direction: directions[i].clone(),
+            entry_date: entry_dates[i],
+            exit_date: exit_dates[i],
+            entry_price: entry_prices[i],
+            exit_price: exit_prices[i],
+            pnl: pnls[i],
+            exit_reason: reasons[i].clone(),
+        })
+        .collect())
+}
+
+/// SQL deleting the stored trades of the (ticker, strategy) pairs in `keys`
+fn trade_delete_sql(ch_config: &ClickhouseConfig, keys: &[(String, String)]) -> String {
+    let quote = |v: &str| format!("'{}'", v.replace("'", "''"));
+    let pairs: Vec<String> = keys.iter().map(|(t, s)| format!("({}, {})", quote(t), quote(s))).collect();
+    format!(
+        "ALTER TABLE {} DELETE WHERE (ticker, strategy) IN ({}) SETTINGS mutations_sync = 1",
+        ch_config.table(&ch_config.trade_table),
+        pairs.join(", ")
+    )
+}
+
+/// Write `rows` in batches of `batch_size` through `write`; returns the number of rows
+/// written
+pub async fn insert_rows_with<R, F, Fut>(rows: &[R], batch_size: usize, mut write: F) -> Result<usize, Box<dyn StdError>>
+where
+    R: Clone,
+    F: FnMut(Vec<R>) -> Fut,
+    Fut: std::future::Future<Output = Result<(), Box<dyn StdError>>>,
+{
+    for batch in rows.chunks(batch_size.max(1)) {
+        write(batch.to_vec()).await?;
+    }
+    Ok(rows.len())
+}
+
+/// Insert trades (see `trades_dataframe`) into the configured trade table on both
+/// servers. The stored trades of each (ticker, strategy) in `df` are replaced through
+/// `stage_and_swap`, so re-exporting a backtest doesn't duplicate its trades and a failed
+/// export leaves the old ones in place.
+pub async fn insert_trades_dataframe(df: DataFrame, ch_config: &ClickhouseConfig) -> Result<(), Box<dyn StdError>> {
+    let rows = trade_rows(&df)?;
+    let mut keys: Vec<(String, String)> = rows.iter().map(|row| (row.ticker.clone(), row.strategy.clone())).collect();
+    keys.sort();
+    keys.dedup();
+    if keys.is_empty() {
+        log::warn!("No trades: skipping insert.");
+        return Ok(());
+    }
+    let delete = trade_delete_sql(ch_config, &keys);
+    stage_and_swap(ch_config, &ch_config.table(&ch_config.trade_table), &rows, &delete).await?;
+    log::info!("Replaced the trades of {} backtests with {} trades", keys.len(), rows.len());
+    Ok(())
+}
+
 // async fn _create_score_table() -> Result<(), Box<dyn StdError>> {
 //     let client = get_ch_client().await?;
 //     let txt: &str = "CREATE OR REPLACE TABLE strategy_score (
@@ -412,3 +689,194 @@ struct Score {
 
 //     Ok(())
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_custom_config_targets_configured_tables() {
+        let ch_config = ClickhouseConfig {
+            database: "staging".to_string(),
+            score_table: "strategy_test".to_string(),
+            ..ClickhouseConfig::default()
+        };
+
+        let date = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let sql = score_delete_sql(&ch_config, &["AAPL".to_string()], date, date);
+        assert!(sql.starts_with("ALTER TABLE staging.strategy_test DELETE"));
+        assert!(!sql.contains("tiingo"));
+
+        let query = build_price_query("Crypto", "'btc'", true, &ch_config);
+        assert!(query.contains("FROM staging.crypto p"));
+        assert!(!query.contains("tiingo"));
+    }
+
+    #[test]
+    fn test_score_delete_targets_tickers_and_date_range() {
+        let ch_config = ClickhouseConfig::default();
+        let (first, last) = (NaiveDate::from_ymd_opt(2024, 3, 1).unwrap(), NaiveDate::from_ymd_opt(2024, 3, 8).unwrap());
+        let sql = score_delete_sql(&ch_config, &["btc".to_string(), "o'eth".to_string()], first, last);
+        assert!(sql.starts_with("ALTER TABLE tiingo.strategy DELETE WHERE ticker IN ('btc', 'o''eth')"));
+        // Every row in the range goes, not just the dates being re-inserted
+        assert!(sql.contains("BETWEEN '2024-03-01' AND '2024-03-08'"));
+        assert_eq!(staging_table(&ch_config.table(&ch_config.score_table)), "tiingo.strategy_staging");
+    }
+
+    #[test]
+    fn test_score_rows_keep_every_row() {
+        let df = df![
+            "date" => [19783i32, 19784],
+            "universe" => ["Crypto", "Crypto"],
+            "ticker" => ["btc", "eth"],
+            "side" => [1i64, -1],
+            "risk_reward" => [1.5, 0.5],
+            "sharpe_ratio" => [Some(1.0), None],
+            "sortino_ratio" => [1.1, 0.3],
+            "max_drawdown" => [0.1, 0.4],
+            "calmar_ratio" => [2.0, 0.1],
+            "win_loss_ratio" => [1.2, 0.8],
+            "recovery_factor" => [3.0, 0.5],
+            "profit_per_trade" => [0.02, -0.01],
+            "expectancy" => [0.01, -0.02],
+            "profit_factor" => [1.8, 0.6],
+        ]
+        .unwrap()
+        .lazy()
+        .with_column(col("date").cast(DataType::Date))
+        .collect()
+        .unwrap();
+        let rows = score_rows(&df).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1].ticker, "eth");
+        assert_eq!(rows[1].side, Some(-1));
+        assert_eq!(rows[1].sharpe_ratio, None);
+        assert_eq!(rows[0].date, ny_midnight_millis(NaiveDate::from_ymd_opt(2024, 3, 1).unwrap()));
+    }
+
+    #[tokio::test]
+    async fn test_price_chunks_run_concurrently_up_to_limit() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let chunks: Vec<Vec<String>> = [["sol", "ada"], ["btc", "xrp"], ["eth", "dot"], ["bnb", "ltc"], ["avax", "link"]]
+            .iter()
+            .map(|c| c.iter().map(|t| t.to_string()).collect())
+            .collect();
+        let (in_flight, peak) = (AtomicUsize::new(0), AtomicUsize::new(0));
+        let rows = fetch_chunks(chunks, 2, |i, chunk| {
+            let (in_flight, peak) = (&in_flight, &peak);
+            async move {
+                let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                // Later chunks answer first
+                tokio::time::sleep(std::time::Duration::from_millis(5 * (5 - i as u64))).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                let bar = |ticker: &String, date: &str| OHLCV {
+                    date: date.to_string(),
+                    ticker: ticker.clone(),
+                    universe: "Crypto".to_string(),
+                    open: None,
+                    high: None,
+                    low: None,
+                    close: Some(1.0),
+                    volume: None,
+                };
+                Ok(chunk.iter().flat_map(|t| [bar(t, "2024-01-02"), bar(t, "2024-01-01")]).collect())
+            }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+        assert_eq!(rows.len(), 20);
+        let keys: Vec<(String, String)> = rows.iter().map(|r| (r.ticker.clone(), r.date.clone())).collect();
+        let mut sorted = keys.clone();
+        sorted.sort();
+        assert_eq!(keys, sorted);
+        assert_eq!(keys[0], ("ada".to_string(), "2024-01-01".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_trades_insert_column_mapping() {
+        let day = |d: u32| NaiveDate::from_ymd_opt(2024, 3, d);
+        let trades = vec![
+            crate::Trade {
+                long: true,
+                entry_index: 0,
+                exit_index: 2,
+                entry_date: day(1),
+                exit_date: day(3),
+                entry_price: 100.0,
+                exit_price: 110.0,
+                exit_reason: crate::ExitReason::NextSignal,
+                initial_risk: None,
+            },
+            crate::Trade {
+                long: false,
+                entry_index: 2,
+                exit_index: 4,
+                entry_date: day(3),
+                exit_date: day(5),
+                entry_price: 110.0,
+                exit_price: 99.0,
+                exit_reason: crate::ExitReason::StopLoss,
+                initial_risk: None,
+            },
+        ];
+        let df = crate::trades_dataframe("btc", "hammer", &trades).unwrap();
+
+        let mut batches: Vec<Vec<TradeRow>> = Vec::new();
+        let n = insert_rows_with(&trade_rows(&df).unwrap(), 1, |batch| {
+            batches.push(batch);
+            async { Ok(()) }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!((n, batches.len()), (2, 2));
+        let short = &batches[1][0];
+        assert_eq!((short.ticker.as_str(), short.strategy.as_str()), ("btc", "hammer"));
+        assert_eq!((short.direction.as_str(), short.exit_reason.as_str()), ("short", "stop_loss"));
+        assert_eq!((short.entry_price, short.exit_price, short.pnl), (110.0, 99.0, 11.0));
+        assert_eq!(short.entry_date, Some(ny_midnight_millis(day(3).unwrap())));
+        assert_eq!(short.exit_date, Some(ny_midnight_millis(day(5).unwrap())));
+        assert_eq!(batches[0][0].direction, "long");
+
+        let sql = trade_delete_sql(&ClickhouseConfig::default(), &[("btc".to_string(), "hammer".to_string())]);
+        assert!(sql.starts_with("ALTER TABLE tiingo.strategy_trades DELETE WHERE (ticker, strategy) IN (('btc', 'hammer'))"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_staged_writes_retry_then_give_up() {
+        use std::cell::Cell;
+
+        // Two failures and a success: the write goes through on the third attempt
+        let calls = Cell::new(0);
+        let result = with_retries("test", || {
+            calls.set(calls.get() + 1);
+            let n = calls.get();
+            async move { if n < 3 { Err(format!("attempt {}", n).into()) } else { Ok(()) } }
+        })
+        .await;
+        assert!(result.is_ok());
+        assert_eq!(calls.get(), 3);
+
+        // Failing every time returns the last error instead of Ok
+        calls.set(0);
+        let result = with_retries("test", || {
+            calls.set(calls.get() + 1);
+            let n = calls.get();
+            async move { Err::<(), Box<dyn StdError>>(format!("attempt {}", n).into()) }
+        })
+        .await;
+        assert_eq!(result.unwrap_err().to_string(), "attempt 3");
+        assert_eq!(calls.get() as u64, WRITE_ATTEMPTS);
+    }
+
+    #[test]
+    fn test_default_config_matches_production_tables() {
+        let ch_config = ClickhouseConfig::default();
+        assert_eq!(ch_config.table(&ch_config.score_table), "tiingo.strategy");
+        assert!(build_price_query("LC1", "'IBM'", false, &ch_config).contains("FROM tiingo.usd p"));
+    }
+}
@@ -1,8 +1,9 @@
 // Configuration module for backtester
 // Contains execution mode enum and configuration structs
 
-use std::{env, error::Error as StdError};
-use chrono::Local;
+use std::{collections::{BTreeMap, BTreeSet, HashMap}, env, error::Error as StdError, path::PathBuf};
+use chrono::{Datelike, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
 
 /// Execution mode for the backtester
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -60,18 +61,98 @@ impl ExecutionMode {
     }
 }
 
+/// Bars per year used to annualize Sharpe/Sortino and to turn a bar count into years for CAGR
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum AnnualizationBasis {
+    /// 252 bars per year for Sharpe/Sortino, and the calendar span of the bars
+    /// (days / 365.25) as the years for CAGR, whatever the universe
+    #[default]
+    Calendar,
+    /// Stock trading calendar
+    Days252,
+    /// Markets that trade every day (crypto)
+    Days365,
+    /// Distinct dates per calendar year observed in the data
+    FromData,
+}
+
+impl AnnualizationBasis {
+    /// Conventional bar-count basis for a universe, for callers that opt in: 365 for
+    /// crypto, 252 for stocks
+    pub fn for_universe(universe: &str) -> Self {
+        if UniverseConfig::is_crypto(universe) { Self::Days365 } else { Self::Days252 }
+    }
+
+    /// Annualization factor for a series with the given bar dates
+    pub fn periods_per_year(&self, dates: &[NaiveDate]) -> f64 {
+        match self {
+            Self::Calendar | Self::Days252 => 252.0,
+            Self::Days365 => 365.0,
+            Self::FromData => bars_per_year(dates).unwrap_or(252.0),
+        }
+    }
+
+    /// Years covered by the bars: the calendar span for `Calendar`, otherwise the number of
+    /// bar-to-bar steps over the annualization factor
+    pub fn years(&self, dates: &[NaiveDate]) -> f64 {
+        if dates.len() < 2 {
+            return 0.0;
+        }
+        match self {
+            Self::Calendar => (dates[dates.len() - 1] - dates[0]).num_days() as f64 / 365.25,
+            _ => (dates.len() - 1) as f64 / self.periods_per_year(dates),
+        }
+    }
+}
+
+/// Distinct dates per calendar year. The first and last years are usually partial, so
+/// the average over the complete years in between is used when there are any; otherwise
+/// the bar density over the covered span is scaled to a full year.
+pub fn bars_per_year(dates: &[NaiveDate]) -> Option<f64> {
+    let mut by_year: BTreeMap<i32, BTreeSet<NaiveDate>> = BTreeMap::new();
+    for date in dates {
+        by_year.entry(date.year()).or_default().insert(*date);
+    }
+    if by_year.len() >= 3 {
+        let interior: Vec<usize> = by_year.values().skip(1).take(by_year.len() - 2).map(|d| d.len()).collect();
+        return Some(interior.iter().sum::<usize>() as f64 / interior.len() as f64);
+    }
+    let first = by_year.values().next()?.first()?;
+    let last = by_year.values().last()?.last()?;
+    let span_days = (*last - *first).num_days();
+    if span_days <= 0 {
+        return None;
+    }
+    let distinct: usize = by_year.values().map(|d| d.len()).sum();
+    Some((distinct - 1) as f64 * 365.25 / span_days as f64)
+}
+
 /// Path configuration for consistent file/directory access
 #[derive(Debug, Clone)]
 pub struct PathConfig {
     pub base: String,
     /// Optional date suffix override for testing mode (e.g., "20260204" -> "testing_20260204")
     pub output_suffix: Option<String>,
+    /// Optional root that every output is written under instead of `base` (price data
+    /// is still read from `base`), so an experimental run cannot clobber real outputs
+    pub output_root: Option<String>,
 }
 
 impl PathConfig {
     /// Create new PathConfig with base directory
     pub fn new(base: String, output_suffix: Option<String>) -> Self {
-        Self { base, output_suffix }
+        Self { base, output_suffix, output_root: None }
+    }
+
+    /// Redirect all outputs under `root`
+    pub fn with_output_root(mut self, root: Option<String>) -> Self {
+        self.output_root = root;
+        self
+    }
+
+    /// Directory outputs are written under: the output root override, else `base`
+    pub fn output_base(&self) -> &str {
+        self.output_root.as_deref().unwrap_or(&self.base)
     }
 
     /// Get the folder name for a mode, using the output_suffix override if available
@@ -93,35 +174,50 @@ impl PathConfig {
         }
     }
 
+    /// Pattern for year-sharded price files (e.g. `LC1_2023.csv`) used when the
+    /// single universe file is absent
+    pub fn data_shard_pattern(&self, universe: &str, mode: ExecutionMode) -> String {
+        if mode.is_demo() {
+            format!("{}_*.csv", universe)
+        } else {
+            format!("{}/data/{}/{}_*.csv", self.base, mode.base_folder_name(), universe)
+        }
+    }
+
     /// Get output directory for a universe
     pub fn output_dir(&self, universe: &str, mode: ExecutionMode) -> String {
         let output_type = UniverseConfig::output_folder_type(universe);
-        format!("{}/{}/{}", self.base, output_type, self.get_folder_name(mode))
+        format!("{}/{}/{}", self.output_base(), output_type, self.get_folder_name(mode))
     }
 
     /// Get output file path for a ticker
     pub fn output_file(&self, universe: &str, ticker: &str, mode: ExecutionMode) -> String {
         let folder = self.get_folder_name(mode);
         let output_type = UniverseConfig::output_folder_type(universe);
-        format!("{}/{}/{}/{}.csv", self.base, output_type, folder, ticker)
+        format!("{}/{}/{}/{}.csv", self.output_base(), output_type, folder, ticker)
+    }
+
+    /// Get the results manifest path for a universe (next to its ticker files)
+    pub fn manifest_file(&self, universe: &str, mode: ExecutionMode) -> String {
+        format!("{}/manifest_{}.json", self.output_dir(universe, mode), universe)
     }
 
     /// Get decision directory path
     pub fn decision_dir(&self, is_crypto: bool) -> String {
         let asset_type = if is_crypto { "crypto" } else { "stocks" };
-        format!("{}/decisions/{}", self.base, asset_type)
+        format!("{}/decisions/{}", self.output_base(), asset_type)
     }
 
     /// Get decision directory path by universe
     pub fn decision_dir_for_universe(&self, universe: &str) -> String {
         let asset_type = UniverseConfig::asset_type_tag(universe);
-        format!("{}/decisions/{}", self.base, asset_type)
+        format!("{}/decisions/{}", self.output_base(), asset_type)
     }
 
     /// Get decision file path for a ticker
     pub fn decision_file(&self, universe: &str, ticker: &str) -> String {
         let asset_type = UniverseConfig::asset_type_tag(universe);
-        format!("{}/decisions/{}/{}.csv", self.base, asset_type, ticker)
+        format!("{}/decisions/{}/{}.csv", self.output_base(), asset_type, ticker)
     }
 
     /// Get data directory path
@@ -132,29 +228,29 @@ impl PathConfig {
     /// Get performance file path
     pub fn performance_file(&self, tag: &str, datetag: &str, is_production: bool) -> String {
         if is_production {
-            format!("{}/performance/{}_all_{}.csv", self.base, tag, datetag)
+            format!("{}/performance/{}_all_{}.csv", self.output_base(), tag, datetag)
         } else {
-            format!("{}/performance/{}_testing.csv", self.base, tag)
+            format!("{}/performance/{}_testing.csv", self.output_base(), tag)
         }
     }
 
     /// Get buy/sell performance file path
     pub fn buys_file(&self, tag: &str, datetag: &str) -> String {
-        format!("{}/performance/{}_buys_{}.csv", self.base, tag, datetag)
+        format!("{}/performance/{}_buys_{}.csv", self.output_base(), tag, datetag)
     }
 
     pub fn sells_file(&self, tag: &str, datetag: &str) -> String {
-        format!("{}/performance/{}_sells_{}.csv", self.base, tag, datetag)
+        format!("{}/performance/{}_sells_{}.csv", self.output_base(), tag, datetag)
     }
 
     /// Get score file path
     pub fn score_file(&self, file_tag: &str, datetag: &str) -> String {
-        format!("{}/score/{}_{}.csv", self.base, file_tag, datetag)
+        format!("{}/score/{}_{}.csv", self.output_base(), file_tag, datetag)
     }
 
     /// Get final testing file path (uses universe label like SC, MC, Crypto)
     pub fn final_testing_file(&self, label: &str) -> String {
-        format!("{}/final_testing/{}_testing.csv", self.base, label)
+        format!("{}/final_testing/{}_testing.csv", self.output_base(), label)
     }
 
     /// Get summary testing file path in output directory (output/testing_YYYYMMDD or output_crypto/testing_YYYYMMDD)
@@ -162,7 +258,7 @@ impl PathConfig {
     pub fn output_testing_summary_file(&self, universe: &str, label: &str, mode: ExecutionMode) -> String {
         let output_type = UniverseConfig::output_folder_type(universe);
         let folder = self.get_folder_name(mode);
-        format!("{}/{}/{}/{}_testing.csv", self.base, output_type, folder, label)
+        format!("{}/{}/{}/{}_testing.csv", self.output_base(), output_type, folder, label)
     }
 }
 
@@ -177,6 +273,31 @@ pub struct BacktestConfig {
     pub batch_size: usize,
     pub custom_tickers: Option<Vec<String>>,
     pub strategy_filter: Option<String>,
+    /// File listing exactly which strategies to run (overrides the universe tag)
+    pub strategy_file: Option<String>,
+    /// Maximum age in days of the newest price bar before the data is considered stale
+    pub max_staleness_days: i64,
+    /// Skip tickers whose median close is below this price
+    pub min_price: Option<f64>,
+    /// Give up on a strategy still running on a ticker after this long
+    pub strategy_timeout: Option<std::time::Duration>,
+    /// Backtest only the bars on or after this date
+    pub start_date: Option<NaiveDate>,
+    /// Backtest only the bars on or before this date
+    pub end_date: Option<NaiveDate>,
+    /// Fall back to cached price files when the ClickHouse pull fails
+    pub price_fallback: bool,
+    /// Cached price files read this run in place of a universe's own file after its
+    /// pull failed
+    pub price_fallbacks: HashMap<String, PathBuf>,
+    /// After scoring, write position statuses that carry earlier buys forward as holds
+    pub carry_positions: bool,
+    /// How a ticker with both a buy and a sell today is scored
+    pub score_aggregation: crate::ScoreAggregation,
+    /// Keep the existing output and only process tickers without a complete results file
+    pub resume: bool,
+    /// Directory of the `BacktestCache`; `None` backtests every strategy afresh
+    pub cache_dir: Option<String>,
 }
 
 impl BacktestConfig {
@@ -200,6 +321,7 @@ impl BacktestConfig {
             .map_err(|e| format!("Invalid mode: {}", e))?;
 
         // Expand universe
+        let universe = UniverseConfig::canonical(&universe);
         let universes = expand_universe(&universe);
 
         // Parse custom tickers if provided
@@ -217,10 +339,62 @@ impl BacktestConfig {
             batch_size: 2,
             custom_tickers,
             strategy_filter: strategy,
+            strategy_file: None,
+            max_staleness_days: 4,
+            min_price: None,
+            strategy_timeout: None,
+            start_date: None,
+            end_date: None,
+            price_fallback: true,
+            price_fallbacks: HashMap::new(),
+            carry_positions: false,
+            score_aggregation: Default::default(),
+            resume: false,
+            cache_dir: None,
         })
     }
 }
 
+/// Universes of one asset class (crypto or stocks) that are summarized and scored together
+#[derive(Debug, Clone, PartialEq)]
+pub struct AssetClassGroup {
+    pub stocks: bool,
+    pub universes: Vec<String>,
+    /// Label used for summary/score file names
+    pub label: String,
+}
+
+impl BacktestConfig {
+    /// Split the configured universes into crypto and stock groups for the post-run
+    /// summary and scoring step. A mixed run (e.g. "All") labels the groups
+    /// "Crypto" and "Stocks"; otherwise the original universe label is kept.
+    pub fn asset_class_groups(&self) -> Vec<AssetClassGroup> {
+        let (stocks, crypto): (Vec<String>, Vec<String>) = self
+            .universes
+            .iter()
+            .cloned()
+            .partition(|u| UniverseConfig::is_stock(u));
+        let mixed = !stocks.is_empty() && !crypto.is_empty();
+
+        let mut groups = Vec::new();
+        if !crypto.is_empty() {
+            groups.push(AssetClassGroup {
+                stocks: false,
+                universes: crypto,
+                label: if mixed { "Crypto".to_string() } else { self.universe_label.clone() },
+            });
+        }
+        if !stocks.is_empty() {
+            groups.push(AssetClassGroup {
+                stocks: true,
+                universes: stocks,
+                label: if mixed { "Stocks".to_string() } else { self.universe_label.clone() },
+            });
+        }
+        groups
+    }
+}
+
 /// Universe configuration and management
 pub struct UniverseConfig;
 
@@ -236,21 +410,39 @@ impl UniverseConfig {
             "Micro1", "Micro2", "Micro3", "Micro4",
         ]),
         ("Crypto", &["Crypto"]),
+        ("All", &[
+            "Crypto", "SC1", "SC2", "SC3", "SC4", "MC1", "MC2", "LC1", "LC2",
+            "Micro1", "Micro2", "Micro3", "Micro4",
+        ]),
     ];
 
+    /// Canonical spelling of a universe name as written in a price file or on the command
+    /// line: trimmed, and matched case-insensitively against the known names (" crypto "
+    /// becomes "Crypto", "lc1" becomes "LC1"). Unknown names are only trimmed.
+    pub fn canonical(universe: &str) -> String {
+        let universe = universe.trim();
+        Self::DEFINITIONS
+            .iter()
+            .flat_map(|(name, universes)| std::iter::once(name).chain(universes.iter()))
+            .find(|known| known.eq_ignore_ascii_case(universe))
+            .map(|known| known.to_string())
+            .unwrap_or_else(|| universe.to_string())
+    }
+
     /// Expand universe shorthand to full list
     pub fn expand(name: &str) -> Vec<String> {
+        let name = Self::canonical(name);
         Self::DEFINITIONS
             .iter()
             .find(|(n, _)| *n == name)
             .map(|(_, universes)| universes.iter().map(|s| s.to_string()).collect())
-            .unwrap_or_else(|| vec![name.to_string()])
+            .unwrap_or_else(|| vec![name])
     }
 
     /// Check if a universe is a stock universe
     pub fn is_stock(universe: &str) -> bool {
         matches!(
-            universe,
+            Self::canonical(universe).as_str(),
             "LC1" | "LC2" | "MC1" | "MC2" | "SC1" | "SC2" | "SC3" | "SC4"
                 | "Micro1" | "Micro2" | "Micro3" | "Micro4"
         )
@@ -258,7 +450,7 @@ impl UniverseConfig {
 
     /// Check if a universe is crypto
     pub fn is_crypto(universe: &str) -> bool {
-        universe == "Crypto"
+        universe.trim().eq_ignore_ascii_case("Crypto")
     }
 
     /// Check if any universe in the list is a stock universe
@@ -271,14 +463,9 @@ impl UniverseConfig {
         universes.iter().any(|u| Self::is_crypto(u))
     }
 
-    /// Normalize ticker case based on universe type
-    /// Crypto tickers are lowercase, stock tickers are uppercase
+    /// Normalize ticker case based on universe type (see the free `normalize_ticker`)
     pub fn normalize_ticker(ticker: &str, universe: &str) -> String {
-        if Self::is_crypto(universe) {
-            ticker.to_lowercase()
-        } else {
-            ticker.to_uppercase()
-        }
+        normalize_ticker(ticker, universe)
     }
 
     /// Get the output folder type for a universe
@@ -300,6 +487,17 @@ impl UniverseConfig {
     }
 }
 
+/// Normalize ticker case for comparisons and filtering.
+/// Crypto tickers are lowercase, stock tickers are uppercase; `asset_type` may be
+/// "crypto"/"stock" or a universe name ("Crypto", "LC1", ...), case-insensitive.
+pub fn normalize_ticker(ticker: &str, asset_type: &str) -> String {
+    if asset_type.trim().eq_ignore_ascii_case("crypto") {
+        ticker.trim().to_lowercase()
+    } else {
+        ticker.trim().to_uppercase()
+    }
+}
+
 /// Expand universe shorthand to full list (legacy function for backward compatibility)
 fn expand_universe(universe: &str) -> Vec<String> {
     UniverseConfig::expand(universe)
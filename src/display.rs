@@ -53,3 +53,13 @@ pub fn format_single_backtest_result(signal_name: &str) -> String {
 pub fn format_sized_backtest_result(signal_name: &str) -> String {
     format!("Backtest (Sized) result for signal '{}':", signal_name)
 }
+
+/// Format the header of the `--list-strategies` table
+pub fn format_strategy_header() -> String {
+    format!("{:<40} {:<16} {:>8}", "name", "category", "default")
+}
+
+/// Format one row of the `--list-strategies` table
+pub fn format_strategy_row(name: &str, category: &str, default_param: f64) -> String {
+    format!("{:<40} {:<16} {:>8}", name, category, default_param)
+}
@@ -1,9 +1,9 @@
 use polars::datatypes::DataType;
 use polars::prelude::*;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::{
-    cmp, collections::HashSet, env, error::Error as StdError, fmt::Debug, fs::File, io::Cursor,
-    path::Path, sync::Arc,
+    collections::{BTreeMap, HashMap, HashSet}, env, error::Error as StdError, fmt::Debug, fs::File, io::Cursor,
+    path::{Path, PathBuf}, sync::{Arc, OnceLock},
 };
 use tokio::{fs, task::JoinError};
 pub mod config;
@@ -13,15 +13,20 @@ mod signals {
 }
 
 pub mod clickhouse;
-use crate::clickhouse::{insert_score_dataframe, write_price_file};
+use crate::config::{AnnualizationBasis, UniverseConfig};
+use crate::clickhouse::{insert_score_dataframe, replace_score_tickers, test_connection, write_price_file, ChConnectionType, ClickhouseConfig};
+use crate::portfolio_accounting::PortfolioAccounting;
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Backtest {
     pub ticker: String,
     pub universe: String,
     pub strategy: String,
     pub expectancy: f64,
     pub profit_factor: f64,
+    /// True when `profit_factor` is the cap rather than the actual ratio (no losses, or
+    /// gains more than the cap times the losses), so aggregates can exclude or clip it
+    pub profit_factor_capped: bool,
     pub hit_ratio: f64,
     pub realized_risk_reward: f64,
     pub avg_gain: f64,
@@ -30,31 +35,122 @@ pub struct Backtest {
     pub max_loss: f64,
     pub sharpe_ratio: f64,
     pub sortino_ratio: f64,
+    /// Newey-West t-statistic of the mean return behind `sharpe_ratio`, see
+    /// `sharpe_tstat`; NaN for fewer than `MIN_TSTAT_OBSERVATIONS` returns (null in JSON)
+    #[serde(deserialize_with = "nan_if_null")]
+    pub sharpe_tstat: f64,
     pub max_drawdown: f64,
     pub calmar_ratio: f64,
+    pub profit_to_dd: f64,
     pub win_loss_ratio: f64,
     pub recovery_factor: f64,
     pub profit_per_trade: f64,
+    pub max_consecutive_wins: i32,
+    pub max_consecutive_losses: i32,
+    /// Compound annual growth (%) of the trade returns, as a fraction of a notional
+    pub cagr: f64,
+    /// Cumulative trade return (%) divided by the years covered, without compounding
+    pub annualized_return: f64,
+    /// `avg_gain` in multiples of each trade's entry-bar ATR (0 unless
+    /// `PerformanceConfig::atr_lookback` is set)
+    pub avg_gain_atr: f64,
+    /// `avg_loss` in multiples of each trade's entry-bar ATR
+    pub avg_loss_atr: f64,
+    /// Annualized mean over tracking error of the daily returns in excess of
+    /// `PerformanceConfig::benchmark` (0 without a benchmark)
+    pub information_ratio: f64,
+    /// Slope of the strategy's daily returns on the benchmark's
+    pub beta_to_benchmark: f64,
+    /// Mean of the rolling beta to the benchmark over
+    /// `PerformanceConfig::rolling_beta_window` days (0 without one), see `rolling_beta`
+    pub avg_rolling_beta: f64,
+    /// Summed daily return (%) left after hedging each day with the rolling beta: what the
+    /// strategy earned beyond its market exposure
+    pub beta_adjusted_return: f64,
+    /// `expectancy` divided by the average holding period in calendar days (0 without
+    /// trades), so edge that ties capital up for longer ranks lower
+    pub expectancy_per_day: f64,
+    /// Mean R-multiple of the trades: P&L in units of the entry-to-stop distance
+    /// (0 unless `PerformanceConfig::stop_loss_pct` is set)
+    pub expectancy_r: f64,
+    /// Standard deviation of the trades' R-multiples, see `r_multiple_distribution`
+    pub r_multiple_std: f64,
+    /// Lump-sum buy-and-hold return (%) of `Close` from the strategy's first signal to
+    /// its last (the whole window with `PerformanceConfig::full_window_benchmark`)
+    pub buy_and_hold_return: f64,
+    /// Dollar-cost-averaged buy-and-hold return (%): equal dollars bought at every
+    /// close of the same window (0 unless `PerformanceConfig::dca_benchmark` is set)
+    pub dca_return: f64,
     pub buys: i32,
     pub sells: i32,
     pub trades: i32,
+    /// Trades closed by a signal (the next signal, or the opposing one for `_se`)
+    pub signal_exits: i32,
+    /// Trades closed by `PerformanceConfig::stop_loss_pct`
+    pub stop_loss_exits: i32,
+    /// Trades still open on the last bar and closed there (`mark_open_at_end`)
+    pub end_of_data_exits: i32,
+    /// Trades per holding-period bucket as (lower bound in days, count), see
+    /// `holding_period_histogram`; for display only, so not written to the CSV output
+    /// or kept by `BacktestCache`
+    #[serde(skip)]
+    pub holding_period_histogram: Vec<(i64, usize)>,
     pub date: String,
     pub buy: i32,
     pub sell: i32,
 }
 
-#[derive(Debug, Clone, Serialize)]
+impl Backtest {
+    /// "buy", "sell" or "hold" for the last bar, from the reported `buy`/`sell`
+    pub fn action(&self) -> &'static str {
+        if self.buy == 1 {
+            "buy"
+        } else if self.sell == -1 {
+            "sell"
+        } else {
+            "hold"
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Decision {
     pub date: String,
     pub action: String,
 }
 
+/// Per-bar signals from a strategy: `buy` is 1 on an entry bar and 0 otherwise, `sell`
+/// is -1 on an exit (or short) bar and 0 otherwise. A `sell` of +1 is not a sell: the
+/// backtests match `sell == -1` and would silently trade nothing. Build with `new`, or
+/// `validate` a literal, to catch a malformed signal before it is backtested.
 #[derive(Debug, Serialize)]
 pub struct BuySell {
     pub buy: Vec<i32>,
     pub sell: Vec<i32>,
 }
 
+impl BuySell {
+    pub fn new(buy: Vec<i32>, sell: Vec<i32>) -> Result<Self, Box<dyn StdError>> {
+        let side = Self { buy, sell };
+        side.validate()?;
+        Ok(side)
+    }
+
+    /// Checks equal lengths, `buy` in {0, 1} and `sell` in {0, -1}
+    pub fn validate(&self) -> Result<(), Box<dyn StdError>> {
+        if self.buy.len() != self.sell.len() {
+            return Err(format!("buy has {} bars but sell has {}", self.buy.len(), self.sell.len()).into());
+        }
+        if let Some((i, v)) = self.buy.iter().enumerate().find(|(_, &v)| v != 0 && v != 1) {
+            return Err(format!("buy[{}] is {}; buy signals must be 0 or 1", i, v).into());
+        }
+        if let Some((i, v)) = self.sell.iter().enumerate().find(|(_, &v)| v != 0 && v != -1) {
+            return Err(format!("sell[{}] is {}; sell signals must be 0 or -1", i, v).into());
+        }
+        Ok(())
+    }
+}
+
 // Define the function type for your signals.
 pub type SignalFunctionWithParam = fn(DataFrame, f64) -> BuySell;
 
@@ -69,14 +165,14 @@ pub async fn delete_all_files_in_folder<P: AsRef<Path> + std::fmt::Debug>(
                 if path.is_file() {
                     // Delete the file
                     if let Err(e) = fs::remove_file(path).await {
-                        eprintln!("Error deleting file: {:?}", e);
+                        log::error!("Error deleting file: {:?}", e);
                     }
                 }
             }
         }
         Err(e) => {
             // Handle the error if the directory cannot be read (e.g., folder does not exist)
-            eprintln!("Failed to read directory {:?}: {:?}", folder_path, e);
+            log::warn!("Failed to read directory {:?}: {:?}", folder_path, e);
         }
     }
     Ok(())
@@ -89,6 +185,43 @@ pub struct Signal {
     pub param: f64,
 }
 
+/// Insert a score CSV written by `score` into ClickHouse, e.g. after the insert failed
+/// during the run. Rows already stored for the file's tickers within its date range
+/// are replaced, so importing the same file twice is harmless. Returns the rows imported.
+pub async fn import_score_csv(path: &str) -> Result<usize, Box<dyn StdError>> {
+    let ch_config = ClickhouseConfig::from_env();
+    let ch_config = &ch_config;
+    import_score_csv_with(path, |df, tickers| async move {
+        replace_score_tickers(df, &tickers, ch_config).await
+    })
+    .await
+}
+
+/// `import_score_csv` with the rows and their tickers handed to `insert`
+pub async fn import_score_csv_with<F, Fut>(path: &str, insert: F) -> Result<usize, Box<dyn StdError>>
+where
+    F: FnOnce(DataFrame, Vec<String>) -> Fut,
+    Fut: std::future::Future<Output = Result<(), Box<dyn StdError>>>,
+{
+    let df = LazyCsvReader::new(path)
+        .with_schema(Some(create_score_schema()))
+        .with_has_header(true)
+        .finish()?
+        .collect()?;
+    let mut tickers: Vec<String> = df.column("ticker")?.str()?.into_iter().flatten().map(String::from).collect();
+    tickers.sort();
+    tickers.dedup();
+
+    let rows = df.height();
+    if rows == 0 {
+        log::warn!("No rows in {}: nothing to import", path);
+        return Ok(0);
+    }
+    insert(df, tickers).await?;
+    log::info!("Imported {} score rows from {}", rows, path);
+    Ok(rows)
+}
+
 // Helper function to create common aggregation columns
 fn create_metric_aggregations(negate: bool) -> Vec<Expr> {
     let multiplier = if negate { -1.0 } else { 1.0 };
@@ -118,17 +251,211 @@ fn create_buysell_schema() -> Arc<Schema> {
     Arc::new(schema)
 }
 
-pub async fn score(datetag: &str, univ_str: &str, universe_label: &str) -> Result<(), Box<dyn StdError>> {
-    // read in the testing file to get the historical performance for scoring
+// Helper function to create the schema of a score file written by `score`
+fn create_score_schema() -> Arc<Schema> {
+    let mut schema = Schema::with_capacity(14);
+    schema.with_column("date".into(), DataType::Date);
+    schema.with_column("universe".into(), DataType::String);
+    schema.with_column("ticker".into(), DataType::String);
+    schema.with_column("side".into(), DataType::Int64);
+    for metric in [
+        "risk_reward", "sharpe_ratio", "sortino_ratio", "max_drawdown", "calmar_ratio",
+        "win_loss_ratio", "recovery_factor", "profit_per_trade", "expectancy", "profit_factor",
+    ] {
+        schema.with_column(metric.into(), DataType::Float64);
+    }
+    Arc::new(schema)
+}
+
+// Helper function to create the schema of a saved backtest file (one column per Backtest field)
+fn create_backtest_schema() -> Arc<Schema> {
+    let mut schema = Schema::with_capacity(40);
+    schema.with_column("ticker".into(), DataType::String);
+    schema.with_column("universe".into(), DataType::String);
+    schema.with_column("strategy".into(), DataType::String);
+    schema.with_column("expectancy".into(), DataType::Float64);
+    schema.with_column("profit_factor".into(), DataType::Float64);
+    schema.with_column("profit_factor_capped".into(), DataType::Boolean);
+    schema.with_column("hit_ratio".into(), DataType::Float64);
+    schema.with_column("realized_risk_reward".into(), DataType::Float64);
+    schema.with_column("avg_gain".into(), DataType::Float64);
+    schema.with_column("avg_loss".into(), DataType::Float64);
+    schema.with_column("max_gain".into(), DataType::Float64);
+    schema.with_column("max_loss".into(), DataType::Float64);
+    schema.with_column("sharpe_ratio".into(), DataType::Float64);
+    schema.with_column("sortino_ratio".into(), DataType::Float64);
+    schema.with_column("sharpe_tstat".into(), DataType::Float64);
+    schema.with_column("max_drawdown".into(), DataType::Float64);
+    schema.with_column("calmar_ratio".into(), DataType::Float64);
+    schema.with_column("profit_to_dd".into(), DataType::Float64);
+    schema.with_column("win_loss_ratio".into(), DataType::Float64);
+    schema.with_column("recovery_factor".into(), DataType::Float64);
+    schema.with_column("profit_per_trade".into(), DataType::Float64);
+    schema.with_column("max_consecutive_wins".into(), DataType::Int64);
+    schema.with_column("max_consecutive_losses".into(), DataType::Int64);
+    schema.with_column("cagr".into(), DataType::Float64);
+    schema.with_column("annualized_return".into(), DataType::Float64);
+    schema.with_column("avg_gain_atr".into(), DataType::Float64);
+    schema.with_column("avg_loss_atr".into(), DataType::Float64);
+    schema.with_column("information_ratio".into(), DataType::Float64);
+    schema.with_column("beta_to_benchmark".into(), DataType::Float64);
+    schema.with_column("avg_rolling_beta".into(), DataType::Float64);
+    schema.with_column("beta_adjusted_return".into(), DataType::Float64);
+    schema.with_column("expectancy_per_day".into(), DataType::Float64);
+    schema.with_column("expectancy_r".into(), DataType::Float64);
+    schema.with_column("r_multiple_std".into(), DataType::Float64);
+    schema.with_column("buy_and_hold_return".into(), DataType::Float64);
+    schema.with_column("dca_return".into(), DataType::Float64);
+    schema.with_column("buys".into(), DataType::Float64);
+    schema.with_column("sells".into(), DataType::Float64);
+    schema.with_column("trades".into(), DataType::Float64);
+    schema.with_column("signal_exits".into(), DataType::Float64);
+    schema.with_column("stop_loss_exits".into(), DataType::Float64);
+    schema.with_column("end_of_data_exits".into(), DataType::Float64);
+    schema.with_column("date".into(), DataType::Date);
+    schema.with_column("buy".into(), DataType::Int64);
+    schema.with_column("sell".into(), DataType::Int64);
+    Arc::new(schema)
+}
+
+/// How `score` combines a ticker's buy-side and sell-side rows for the same day. Sell
+/// rows carry negated metrics, so a ticker with both signals sees them pull against each
+/// other only under `Net`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScoreAggregation {
+    /// One row per ticker: buy and sell `side` and metrics summed, so they offset
+    #[default]
+    Net,
+    /// One row per ticker: only the side whose summed `profit_factor` is larger in
+    /// magnitude (the buy side on a tie), the other side dropped
+    MaxConviction,
+    /// The buy row and the sell row kept as two rows for the ticker, unnetted
+    Separate,
+}
+
+impl std::str::FromStr for ScoreAggregation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "net" => Ok(Self::Net),
+            "max-conviction" => Ok(Self::MaxConviction),
+            "separate" => Ok(Self::Separate),
+            _ => Err(format!("Unknown score aggregation: {} (expected net, max-conviction or separate)", s)),
+        }
+    }
+}
+
+impl ScoreAggregation {
+    /// Combine the per-side scores, which share the score columns
+    fn combine(self, buys: LazyFrame, sells: LazyFrame) -> Result<LazyFrame, PolarsError> {
+        let keys = [col("date"), col("universe"), col("ticker")];
+        let sides = concat(&[buys, sells], Default::default())?;
+        Ok(match self {
+            Self::Net => sides.group_by_stable(keys).agg([
+                col("side").sum().alias("side"),
+                col("risk_reward").sum().round(2).alias("risk_reward"),
+                col("sharpe_ratio").sum().round(2).alias("sharpe_ratio"),
+                col("sortino_ratio").sum().round(2).alias("sortino_ratio"),
+                col("max_drawdown").sum().round(2).alias("max_drawdown"),
+                col("calmar_ratio").sum().round(2).alias("calmar_ratio"),
+                col("win_loss_ratio").sum().round(2).alias("win_loss_ratio"),
+                col("recovery_factor")
+                    .sum()
+                    .round(2)
+                    .alias("recovery_factor"),
+                col("profit_per_trade")
+                    .sum()
+                    .round(2)
+                    .alias("profit_per_trade"),
+                col("expectancy").sum().round(2).alias("expectancy"),
+                col("profit_factor").sum().round(2).alias("profit_factor"),
+            ]),
+            Self::MaxConviction => sides
+                .sort_by_exprs(
+                    [col("profit_factor").abs()],
+                    SortMultipleOptions {
+                        descending: vec![true],
+                        nulls_last: vec![true],
+                        maintain_order: true,
+                        ..Default::default()
+                    },
+                )
+                .group_by_stable(keys)
+                .agg([all().first()]),
+            Self::Separate => sides,
+        })
+    }
+}
+
+/// How `score` runs, beyond the day and universe it scores
+#[derive(Debug, Clone, Default)]
+pub struct ScoreOptions {
+    /// Rescore just these tickers: their rows replace the matching rows in the score file
+    /// and in ClickHouse, everything else is kept
+    pub only_tickers: Option<Vec<String>>,
+    /// Write the day's position statuses with `score_positions` after scoring
+    pub carry_positions: bool,
+    /// Read the day's buys/sells from and write the score files under this directory; the
+    /// historical `final/` performance still comes from the home directory. Scores of such
+    /// an experimental run are not inserted into ClickHouse.
+    pub output_root: Option<String>,
+    /// How a ticker with both a buy and a sell today is scored
+    pub aggregation: ScoreAggregation,
+}
+
+/// Score today's buys and sells against the historical testing performance, see
+/// `ScoreOptions`
+pub async fn score(
+    datetag: &str,
+    univ_str: &str,
+    universe_label: &str,
+    options: &ScoreOptions,
+) -> Result<(), Box<dyn StdError>> {
     let user_path = match env::var("CLICKHOUSE_USER_PATH") {
         Ok(path) => path,
         Err(_) => String::from("/srv"),
     };
     let path = format!("{}/rust_home/backtester", user_path);
-    let path: &str = &path;
 
-    let tag = if univ_str == "Crypto" { "crypto" } else { "stocks" };
+    score_from(&path, datetag, univ_str, universe_label, options, |both, tickers| async move {
+        let ch_config = ClickhouseConfig::from_env();
+        match tickers {
+            Some(tickers) => replace_score_tickers(both, &tickers, &ch_config).await,
+            None => insert_score_dataframe(both, &ch_config).await,
+        }
+    })
+    .await?;
+
+    if options.carry_positions {
+        score_positions(options.output_root.as_deref().unwrap_or(&path), universe_label, datetag).await?;
+    }
+    Ok(())
+}
+
+/// `score` against an arbitrary backtester home directory and score sink
+pub async fn score_from<F, Fut>(
+    path: &str,
+    datetag: &str,
+    univ_str: &str,
+    universe_label: &str,
+    options: &ScoreOptions,
+    insert: F,
+) -> Result<(), Box<dyn StdError>>
+where
+    F: FnOnce(DataFrame, Option<Vec<String>>) -> Fut,
+    Fut: std::future::Future<Output = Result<(), Box<dyn StdError>>>,
+{
+    // read in the testing file to get the historical performance for scoring
+    let tag = UniverseConfig::asset_type_tag(univ_str);
     let file_path = format!("{}/final/{}_testing.csv", path, tag);
+    let out = options.output_root.as_deref().unwrap_or(path);
+    let only_tickers = options.only_tickers.clone();
+
+    let in_scope = match &only_tickers {
+        Some(tickers) => ticker_in(tickers),
+        None => lit(true),
+    };
 
     let buysell_schema = create_buysell_schema();
 
@@ -136,12 +463,12 @@ pub async fn score(datetag: &str, univ_str: &str, universe_label: &str) -> Resul
     let testing = CsvReader::new(file).finish()?; // Pass the file handle to CsvReader
 
     // read in the buys
-    let buy_path = format!("{}/performance/{}_buys_{}.csv", path, tag, datetag);
+    let buy_path = format!("{}/performance/{}_buys_{}.csv", out, tag, datetag);
     let buys = LazyCsvReader::new(buy_path)
         .with_schema(Some(buysell_schema.clone()))
         .with_has_header(true)
         .finish()?
-        .lazy()
+        .filter(in_scope.clone())
         .join(
             testing.clone().lazy(),
             [col("universe"), col("strategy")],
@@ -165,11 +492,12 @@ pub async fn score(datetag: &str, univ_str: &str, universe_label: &str) -> Resul
     // println!("buys columns: {:?}", buys.clone().collect()?);
 
     // read in the sells
-    let sell_path = format!("{}/performance/{}_sells_{}.csv", path, tag, datetag);
+    let sell_path = format!("{}/performance/{}_sells_{}.csv", out, tag, datetag);
     let sells = LazyCsvReader::new(sell_path)
         .with_schema(Some(buysell_schema))
         .with_has_header(true)
         .finish()?
+        .filter(in_scope)
         .join(
             testing.clone().lazy(),
             [col("universe"), col("strategy")],
@@ -219,27 +547,9 @@ pub async fn score(datetag: &str, univ_str: &str, universe_label: &str) -> Resul
             },
         );
 
-    let both = concat(&[buys, sells], Default::default())?
-        .group_by_stable([col("date"), col("universe"), col("ticker")])
-        .agg([
-            col("side").sum().alias("side"),
-            col("risk_reward").sum().round(2).alias("risk_reward"),
-            col("sharpe_ratio").sum().round(2).alias("sharpe_ratio"),
-            col("sortino_ratio").sum().round(2).alias("sortino_ratio"),
-            col("max_drawdown").sum().round(2).alias("max_drawdown"),
-            col("calmar_ratio").sum().round(2).alias("calmar_ratio"),
-            col("win_loss_ratio").sum().round(2).alias("win_loss_ratio"),
-            col("recovery_factor")
-                .sum()
-                .round(2)
-                .alias("recovery_factor"),
-            col("profit_per_trade")
-                .sum()
-                .round(2)
-                .alias("profit_per_trade"),
-            col("expectancy").sum().round(2).alias("expectancy"),
-            col("profit_factor").sum().round(2).alias("profit_factor"),
-        ])
+    let both = options
+        .aggregation
+        .combine(buys, sells)?
         .sort(
             vec!["side"],
             SortMultipleOptions {
@@ -250,28 +560,422 @@ pub async fn score(datetag: &str, univ_str: &str, universe_label: &str) -> Resul
         )
         .collect()?;
 
-    println!("Scoring...4");
-    println!("both columns: {:?}", both.clone());
+    log::info!("Scoring {} ({} rows)", universe_label, both.height());
+    log::debug!("both columns: {:?}", both);
 
     // Ensure score directory exists
-    let score_dir = format!("{}/score", path);
+    let score_dir = format!("{}/score", out);
     fs::create_dir_all(&score_dir).await?;
 
     // Use universe-specific filename
-    let both_path = format!("{}/score/{}_{}.csv", path, universe_label, datetag);
-    let mut file = File::create(both_path)?;
-    let _ = CsvWriter::new(&mut file).finish(&mut both.clone());
+    let both_path = format!("{}/score/{}_{}.csv", out, universe_label, datetag);
+
+    // A targeted rescore keeps the other tickers' rows from the last full run
+    let mut scores = match &only_tickers {
+        Some(tickers) if Path::new(&both_path).exists() => {
+            let kept = LazyCsvReader::new(both_path.clone())
+                .with_schema(Some(both.schema().clone()))
+                .with_has_header(true)
+                .finish()?
+                .filter(ticker_in(tickers).not());
+            concat(&[kept, both.clone().lazy()], Default::default())?
+                .sort(
+                    vec!["side"],
+                    SortMultipleOptions {
+                        descending: vec![true],
+                        nulls_last: vec![true],
+                        maintain_order: true,
+                        ..Default::default()
+                    },
+                )
+                .collect()?
+        }
+        _ => both.clone(),
+    };
+    write_csv_atomic(&mut scores, both_path)?;
 
-    if both.height() > 0 {
-        if let Err(e) = insert_score_dataframe(both).await {
-            eprintln!("Error in insert_score_dataframe: {}", e);
+    if let Some(root) = &options.output_root {
+        log::info!("Scores written under output root {}: skipping insert", root);
+    } else if both.height() > 0 {
+        if let Err(e) = insert(both, only_tickers).await {
+            log::error!("Error in insert_score_dataframe: {}", e);
         }
     } else {
-        println!("No observations: skipping insert.");
+        log::warn!("No observations: skipping insert.");
     }
     Ok(())
 }
 
+/// True for rows whose `ticker` is one of `tickers`
+fn ticker_in(tickers: &[String]) -> Expr {
+    tickers
+        .iter()
+        .fold(lit(false), |acc, t| acc.or(col("ticker").eq(lit(t.clone()))))
+}
+
+/// Every `{tag}_{YYYYMMDD}.csv` score file in `dir` stacked into one long frame, with the
+/// file's date in a leading `score_date` column (sorted by `score_date`, then `ticker`).
+/// Files whose suffix is not a date are skipped with a warning.
+pub async fn load_score_history(dir: &str, tag: &str) -> Result<DataFrame, Box<dyn StdError>> {
+    let prefix = format!("{}_", tag);
+    let mut entries = fs::read_dir(dir).await?;
+    let mut csv_paths = Vec::new();
+    while let Some(entry) = entries.next_entry().await? {
+        csv_paths.push(entry.path());
+    }
+    csv_paths.sort();
+
+    let mut frames = Vec::new();
+    for path in csv_paths {
+        if path.extension().and_then(|s| s.to_str()) != Some("csv") {
+            continue;
+        }
+        let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+        let Some(datetag) = stem.strip_prefix(&prefix) else { continue };
+        let Ok(score_date) = chrono::NaiveDate::parse_from_str(datetag, "%Y%m%d") else {
+            log::warn!("Skipping score file {}: '{}' is not a YYYYMMDD date", path.display(), datetag);
+            continue;
+        };
+        let lf = LazyCsvReader::new(&path)
+            .with_schema(Some(create_score_schema()))
+            .with_has_header(true)
+            .finish()?
+            .select([lit(score_date).cast(DataType::Date).alias("score_date"), col("*")]);
+        frames.push(lf);
+    }
+    if frames.is_empty() {
+        return Ok(DataFrame::empty());
+    }
+
+    let out = concat(&frames, UnionArgs::default())?
+        .sort(
+            ["score_date", "ticker"],
+            SortMultipleOptions::default().with_maintain_order(true),
+        )
+        .collect()?;
+    Ok(out)
+}
+
+/// The newest score file for `label` in `score_dir` as JSON for a dashboard: its date
+/// and two ranked lists, `buys` (positive side, strongest first) and `sells` (negative
+/// side, strongest first). `label` is matched like a universe name, so "crypto" reads
+/// `Crypto_<date>.csv` and "stocks" reads `Stocks_<date>.csv`.
+pub async fn latest_scores_json(score_dir: &str, label: &str) -> Result<serde_json::Value, Box<dyn StdError>> {
+    let label = UniverseConfig::canonical(label);
+    let history = load_score_history(score_dir, &label).await?;
+    if history.height() == 0 {
+        return Err(format!("No score files for '{}' in {}", label, score_dir).into());
+    }
+    let latest = history
+        .clone()
+        .lazy()
+        .filter(col("score_date").eq(col("score_date").max()))
+        .collect()?;
+    let date = latest.column("score_date")?.get(0)?.to_string();
+
+    let ranked = |side: Expr, descending: bool| -> Result<Vec<serde_json::Value>, Box<dyn StdError>> {
+        let df = latest
+            .clone()
+            .lazy()
+            .filter(side)
+            .sort(
+                ["side", "profit_factor", "ticker"],
+                SortMultipleOptions {
+                    descending: vec![descending, descending, false],
+                    nulls_last: vec![true, true, true],
+                    ..Default::default()
+                },
+            )
+            .collect()?;
+        let tickers = df.column("ticker")?.str()?;
+        let universes = df.column("universe")?.str()?;
+        let sides = df.column("side")?.i64()?;
+        let (pf, expectancy, sharpe) =
+            (df.column("profit_factor")?.f64()?, df.column("expectancy")?.f64()?, df.column("sharpe_ratio")?.f64()?);
+        Ok((0..df.height())
+            .map(|i| {
+                serde_json::json!({
+                    "rank": i + 1,
+                    "ticker": tickers.get(i),
+                    "universe": universes.get(i),
+                    "side": sides.get(i),
+                    "profit_factor": pf.get(i),
+                    "expectancy": expectancy.get(i),
+                    "sharpe_ratio": sharpe.get(i),
+                })
+            })
+            .collect())
+    };
+
+    Ok(serde_json::json!({
+        "label": label,
+        "date": date,
+        "buys": ranked(col("side").gt(lit(0)), true)?,
+        "sells": ranked(col("side").lt(lit(0)), false)?,
+    }))
+}
+
+/// Position status of each ticker on `datetag`, carrying forward open longs so a ticker
+/// bought earlier without a signal today is reported as held instead of dropped.
+///
+/// Reads today's `score/<label>_<datetag>.csv` and the positions file of the latest
+/// earlier score file (`score/positions/<label>_<date>.csv` for the same date). That
+/// positions file missing is an error, since carrying an older one forward would hold
+/// tickers sold in between; only before any positions file exists is the earlier score
+/// file read instead. Each ticker gets a `status`:
+/// - "new buy": positive side today
+/// - "new sell": negative side today
+/// - "hold": held before and no sell today (side 0, or no row at all)
+///
+/// Tickers neither signalled nor held are left out. A score file from
+/// `ScoreAggregation::Separate` can hold a buy row and a sell row for the same ticker;
+/// each keeps its own status, and a ticker sold on a day is not held after it. The
+/// result (date, universe, ticker, side, status) is also written to
+/// `score/positions/<label>_<datetag>.csv`.
+pub async fn score_positions(path: &str, universe_label: &str, datetag: &str) -> Result<DataFrame, Box<dyn StdError>> {
+    let date = chrono::NaiveDate::parse_from_str(datetag, "%Y%m%d")?;
+    let score_dir = format!("{}/score", path);
+    let positions_dir = format!("{}/positions", score_dir);
+    let read = |file: &str| -> Result<DataFrame, Box<dyn StdError>> {
+        let schema = if file.starts_with(&positions_dir) {
+            Arc::new(Schema::from_iter([
+                Field::new("date".into(), DataType::Date),
+                Field::new("universe".into(), DataType::String),
+                Field::new("ticker".into(), DataType::String),
+                Field::new("side".into(), DataType::Int64),
+                Field::new("status".into(), DataType::String),
+            ]))
+        } else {
+            create_score_schema()
+        };
+        Ok(LazyCsvReader::new(file).with_schema(Some(schema)).with_has_header(true).finish()?.collect()?)
+    };
+
+    // Latest earlier file named <label>_<YYYYMMDD>.csv in `dir`, with its date
+    let latest_before = |dir: &str| -> Option<(chrono::NaiveDate, String)> {
+        let prefix = format!("{}_", universe_label);
+        std::fs::read_dir(dir)
+            .ok()?
+            .flatten()
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                let stem = name.strip_suffix(".csv")?.strip_prefix(&prefix)?.to_string();
+                let day = chrono::NaiveDate::parse_from_str(&stem, "%Y%m%d").ok()?;
+                (day < date).then(|| (day, entry.path().to_string_lossy().into_owned()))
+            })
+            .max()
+    };
+
+    // Tickers held going into today, with their universe
+    let mut held: BTreeMap<String, String> = BTreeMap::new();
+    let prior = match latest_before(&score_dir) {
+        Some((day, score_file)) => {
+            let positions_file = format!("{}/{}_{}.csv", positions_dir, universe_label, day.format("%Y%m%d"));
+            if Path::new(&positions_file).exists() {
+                Some(positions_file)
+            } else if latest_before(&positions_dir).is_none() {
+                Some(score_file)
+            } else {
+                return Err(format!(
+                    "Missing positions file {} for the previous score file {}: run score_positions for {} first",
+                    positions_file,
+                    score_file,
+                    day.format("%Y%m%d")
+                )
+                .into());
+            }
+        }
+        None => None,
+    };
+    if let Some(prior) = &prior {
+        let df = read(prior)?;
+        let statuses = df.column("status").ok().map(|c| c.str().cloned()).transpose()?;
+        let (universes, tickers, sides) = (df.column("universe")?.str()?, df.column("ticker")?.str()?, df.column("side")?.i64()?);
+        let mut sold = HashSet::new();
+        for i in 0..df.height() {
+            let (Some(universe), Some(ticker)) = (universes.get(i), tickers.get(i)) else { continue };
+            let side = sides.get(i).unwrap_or(0);
+            let long = match &statuses {
+                Some(statuses) => matches!(statuses.get(i), Some("new buy") | Some("hold")),
+                None => side > 0,
+            };
+            if side < 0 {
+                sold.insert(ticker.to_string());
+            } else if long {
+                held.insert(ticker.to_string(), universe.to_string());
+            }
+        }
+        held.retain(|ticker, _| !sold.contains(ticker));
+        log::info!("Carrying {} open positions forward from {}", held.len(), prior);
+    }
+
+    let today = read(&format!("{}/{}_{}.csv", score_dir, universe_label, datetag))?;
+    let (universes, tickers, sides) = (today.column("universe")?.str()?, today.column("ticker")?.str()?, today.column("side")?.i64()?);
+    // Keyed by ticker and the sign of its side, so both rows of a separately scored
+    // ticker are kept
+    let mut rows: BTreeMap<(String, i64), (String, i64, &str)> = BTreeMap::new();
+    for i in 0..today.height() {
+        let (Some(universe), Some(ticker)) = (universes.get(i), tickers.get(i)) else { continue };
+        let side = sides.get(i).unwrap_or(0);
+        let status = match side {
+            s if s > 0 => "new buy",
+            s if s < 0 => "new sell",
+            _ if held.contains_key(ticker) => "hold",
+            _ => continue,
+        };
+        rows.insert((ticker.to_string(), side.signum()), (universe.to_string(), side, status));
+    }
+    let signalled: HashSet<String> = rows.keys().map(|(ticker, _)| ticker.clone()).collect();
+    for (ticker, universe) in held {
+        if !signalled.contains(&ticker) {
+            rows.insert((ticker, 0), (universe, 0, "hold"));
+        }
+    }
+
+    let n = rows.len();
+    let mut out = DataFrame::new(vec![
+        Column::new("date".into(), vec![date; n]),
+        Column::new("universe".into(), rows.values().map(|r| r.0.as_str()).collect::<Vec<_>>()),
+        Column::new("ticker".into(), rows.keys().map(|(t, _)| t.as_str()).collect::<Vec<_>>()),
+        Column::new("side".into(), rows.values().map(|r| r.1).collect::<Vec<_>>()),
+        Column::new("status".into(), rows.values().map(|r| r.2).collect::<Vec<_>>()),
+    ])?;
+    fs::create_dir_all(&positions_dir).await?;
+    write_csv_atomic(&mut out, format!("{}/{}_{}.csv", positions_dir, universe_label, datetag))?;
+    Ok(out)
+}
+
+/// One score file row for `score_diff`: (ticker, universe, side)
+type ScoreSide = (String, String, i64);
+
+/// What changed between two score runs of `tag` (`score/<tag>_<YYYYMMDD>.csv` under
+/// `path`), one row per ticker and side (ticker, universe, prev_side, side, change), for
+/// alerts:
+/// - "new buy" / "new sell": a buy (sell) today that was flat or missing before
+/// - "flipped to buy" / "flipped to sell": a sell before and a buy today, or the reverse
+///
+/// Signals with the same sign on both days, or flat today, are left out. Under
+/// `ScoreAggregation::Separate` a ticker can have a buy row and a sell row; each is
+/// compared on its own. Sorted by change, then ticker.
+pub fn score_diff(path: &str, prev_date: &str, curr_date: &str, tag: &str) -> Result<DataFrame, Box<dyn StdError>> {
+    let sides = |datetag: &str| -> Result<Vec<ScoreSide>, Box<dyn StdError>> {
+        let df = LazyCsvReader::new(format!("{}/score/{}_{}.csv", path, tag, datetag))
+            .with_schema(Some(create_score_schema()))
+            .with_has_header(true)
+            .finish()?
+            .collect()?;
+        let (universes, tickers, sides) = (df.column("universe")?.str()?, df.column("ticker")?.str()?, df.column("side")?.i64()?);
+        Ok((0..df.height())
+            .filter_map(|i| Some((tickers.get(i)?.to_string(), universes.get(i)?.to_string(), sides.get(i).unwrap_or(0))))
+            .collect())
+    };
+    let mut prev: BTreeMap<String, Vec<i64>> = BTreeMap::new();
+    for (ticker, _, side) in sides(prev_date)? {
+        prev.entry(ticker).or_default().push(side);
+    }
+
+    let mut rows: Vec<(&str, String, String, i64, i64)> = sides(curr_date)?
+        .into_iter()
+        .filter_map(|(ticker, universe, side)| {
+            // The earlier side on the same side of zero if there was one, else the
+            // opposite one, else flat
+            let before = prev.get(&ticker).map(Vec::as_slice).unwrap_or_default();
+            let prev_side = before
+                .iter()
+                .find(|p| p.signum() == side.signum())
+                .or_else(|| before.iter().find(|p| p.signum() == -side.signum()))
+                .cloned()
+                .unwrap_or(0);
+            let change = match (prev_side.signum(), side.signum()) {
+                (0, 1) => "new buy",
+                (0, -1) => "new sell",
+                (-1, 1) => "flipped to buy",
+                (1, -1) => "flipped to sell",
+                _ => return None,
+            };
+            Some((change, ticker, universe, prev_side, side))
+        })
+        .collect();
+    rows.sort();
+
+    Ok(DataFrame::new(vec![
+        Column::new("ticker".into(), rows.iter().map(|r| r.1.as_str()).collect::<Vec<_>>()),
+        Column::new("universe".into(), rows.iter().map(|r| r.2.as_str()).collect::<Vec<_>>()),
+        Column::new("prev_side".into(), rows.iter().map(|r| r.3).collect::<Vec<_>>()),
+        Column::new("side".into(), rows.iter().map(|r| r.4).collect::<Vec<_>>()),
+        Column::new("change".into(), rows.iter().map(|r| r.0).collect::<Vec<_>>()),
+    ])?)
+}
+
+/// Tickers with fewer backtest results than `expected_strategies` (by default the number
+/// of distinct strategies in `results`), joined to their price coverage (`Ticker`,
+/// `observations`, `last date`) and sorted by how few strategies they got
+pub fn strategy_coverage(
+    price_coverage: DataFrame,
+    results: &DataFrame,
+    expected_strategies: Option<usize>,
+) -> Result<DataFrame, PolarsError> {
+    let expected = match expected_strategies {
+        Some(n) => n,
+        None => results.column("strategy")?.n_unique()?,
+    };
+
+    let df_grouped = results
+        .clone()
+        .lazy()
+        .group_by_stable([col("ticker")])
+        .agg([col("strategy").count().alias("strategies")])
+        .sort(
+            vec!["ticker"],
+            SortMultipleOptions {
+                descending: vec![false],
+                nulls_last: vec![true],
+                ..Default::default()
+            },
+        );
+
+    price_coverage
+        .lazy()
+        .inner_join(df_grouped, col("Ticker"), col("ticker"))
+        .filter(col("strategies").lt(lit(expected as u32)))
+        .sort(
+            vec!["strategies"],
+            SortMultipleOptions {
+                descending: vec![false],
+                nulls_last: vec![true],
+                maintain_order: true,
+                ..Default::default()
+            },
+        )
+        .collect()
+}
+
+/// Read a results file written by `save_backtest`, matching columns by header name so a
+/// file saved before a column was added still lines up; those columns come back null.
+/// `None` when the file lacks any of the `required` columns (not a results file).
+fn read_backtest_file(path: &Path, schema: &Schema, required: &[&str]) -> Result<Option<DataFrame>, Box<dyn StdError>> {
+    let header: HashSet<String> = csv::Reader::from_path(path)?.headers()?.iter().map(String::from).collect();
+    if !required.iter().all(|name| header.contains(*name)) {
+        return Ok(None);
+    }
+    let mut present = Schema::with_capacity(header.len());
+    let mut missing = Vec::new();
+    for (name, dtype) in schema.iter() {
+        if header.contains(name.as_str()) {
+            present.with_column(name.clone(), dtype.clone());
+        } else {
+            missing.push(lit(NULL).cast(dtype.clone()).alias(name.clone()));
+        }
+    }
+    let df = LazyCsvReader::new(path)
+        .with_has_header(true)
+        .with_dtype_overwrite(Some(Arc::new(present)))
+        .finish()?
+        .with_columns(missing)
+        .collect()?;
+    Ok(Some(df))
+}
+
 async fn concat_dataframes(dfs: Vec<DataFrame>) -> Result<DataFrame, PolarsError> {
     let lazy_frames: Vec<LazyFrame> = dfs.into_iter().map(|df| df.lazy()).collect();
 
@@ -291,33 +995,8 @@ pub async fn summary_performance_file(
     univ: Vec<String>,
     universe_label: &str,
 ) -> Result<(String, DataFrame), Box<dyn StdError>> {
-    let bt_names = vec![
-        "ticker",
-        "universe",
-        "strategy",
-        "expectancy",
-        "profit_factor",
-        "hit_ratio",
-        "realized_risk_reward",
-        "avg_gain",
-        "avg_loss",
-        "max_gain",
-        "max_loss",
-        "sharpe_ratio",
-        "sortino_ratio",
-        "max_drawdown",
-        "calmar_ratio",
-        "win_loss_ratio",
-        "recovery_factor",
-        "profit_per_trade",
-        "buys",
-        "sells",
-        "trades",
-        "date",
-        "buy",
-        "sell",
-    ];
-    let set_bt: HashSet<_> = bt_names.iter().cloned().collect();
+    let bt_schema = create_backtest_schema();
+    let bt_names: Vec<&str> = bt_schema.iter_names().map(|name| name.as_str()).collect();
 
     let b_names = vec!["ticker", "universe", "strategy", "date", "buy", "sell"];
 
@@ -345,8 +1024,14 @@ pub async fn summary_performance_file(
         Err(e) => return Err(Box::new(e)),
     };
 
+    // Read files in name order so the concatenated frame is the same on every run
+    let mut csv_paths = Vec::new();
     while let Some(entry) = entries.next_entry().await? {
-        let path = entry.path();
+        csv_paths.push(entry.path());
+    }
+    csv_paths.sort();
+
+    for path in csv_paths {
         if path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("csv") {
             // Skip decision files (they have "_decisions" suffix)
             if let Some(filename) = path.file_stem() {
@@ -355,50 +1040,13 @@ pub async fn summary_performance_file(
                 }
             }
 
-            let mut schema = Schema::with_capacity(24);
-            schema.with_column("ticker".into(), DataType::String);
-            schema.with_column("universe".into(), DataType::String);
-            schema.with_column("strategy".into(), DataType::String);
-            schema.with_column("expectancy".into(), DataType::Float64);
-            schema.with_column("profit_factor".into(), DataType::Float64);
-            schema.with_column("hit_ratio".into(), DataType::Float64);
-            schema.with_column("realized_risk_reward".into(), DataType::Float64);
-            schema.with_column("avg_gain".into(), DataType::Float64);
-            schema.with_column("avg_loss".into(), DataType::Float64);
-            schema.with_column("max_gain".into(), DataType::Float64);
-            schema.with_column("max_loss".into(), DataType::Float64);
-            schema.with_column("sharpe_ratio".into(), DataType::Float64);
-            schema.with_column("sortino_ratio".into(), DataType::Float64);
-            schema.with_column("max_drawdown".into(), DataType::Float64);
-            schema.with_column("calmar_ratio".into(), DataType::Float64);
-            schema.with_column("win_loss_ratio".into(), DataType::Float64);
-            schema.with_column("recovery_factor".into(), DataType::Float64);
-            schema.with_column("profit_per_trade".into(), DataType::Float64);
-            schema.with_column("buys".into(), DataType::Float64);
-            schema.with_column("sells".into(), DataType::Float64);
-            schema.with_column("trades".into(), DataType::Float64);
-            schema.with_column("date".into(), DataType::Date);
-            schema.with_column("buy".into(), DataType::Int64);
-            schema.with_column("sell".into(), DataType::Int64);
-            let schema = Arc::new(schema);
-
-            let lf = LazyCsvReader::new(&path)
-                .with_schema(Some(schema))
-                .with_has_header(true)
-                .finish()?
-                .collect();
-
-            match lf {
-                Ok(df) => {
-                    // Ensure all required columns are present
-                    let df_names = df.get_column_names();
-                    let set_df: HashSet<&str> = df_names.iter().map(|s| s.as_str()).collect();
-                    if set_bt.is_subset(&set_df) {
-                        a.push(df.select(bt_names.clone())?);
-                        b.push(df.select(b_names.clone())?);
-                    }
+            match read_backtest_file(&path, &bt_schema, &b_names) {
+                Ok(Some(df)) => {
+                    a.push(df.select(bt_names.clone())?);
+                    b.push(df.select(b_names.clone())?);
                 }
-                Err(e) => println!("Error processing file {}: {}", path.display(), e),
+                Ok(None) => {}
+                Err(e) => log::error!("Error processing file {}: {}", path.display(), e),
             }
         }
     }
@@ -407,7 +1055,7 @@ pub async fn summary_performance_file(
     let df = concat_dataframes(a).await?;
     // println!("ALL: {}", df.to_string());
 
-    let out = summary_performance(df.clone())?;
+    let out = summary_performance(df.clone(), None)?;
     // println!("Average Performance by Strategy:\n {:?}", out);
 
     let datetag = df
@@ -420,8 +1068,10 @@ pub async fn summary_performance_file(
     let tag: &str = if stocks { "stocks" } else { "crypto" };
 
     let perf_filename = paths.performance_file(tag, &datetag, is_production);
-    let mut file = File::create(perf_filename)?;
-    let _ = CsvWriter::new(&mut file).finish(&mut out.clone());
+    if let Some(parent) = std::path::Path::new(&perf_filename).parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    write_csv_atomic(&mut out.clone(), perf_filename)?;
 
     // In testing mode, also save to final_testing folder and output/testing_YYYYMMDD folder
     if !is_production {
@@ -431,8 +1081,7 @@ pub async fn summary_performance_file(
         if let Some(parent) = std::path::Path::new(&output_filename).parent() {
             tokio::fs::create_dir_all(parent).await?;
         }
-        let mut output_file = File::create(output_filename)?;
-        let _ = CsvWriter::new(&mut output_file).finish(&mut out.clone());
+        write_csv_atomic(&mut out.clone(), output_filename)?;
 
         // Also save to output/testing_YYYYMMDD or output_crypto/testing_YYYYMMDD folder
         let mode = config::ExecutionMode::Testing;
@@ -443,8 +1092,7 @@ pub async fn summary_performance_file(
         if let Some(parent) = std::path::Path::new(&testing_output_filename).parent() {
             tokio::fs::create_dir_all(parent).await?;
         }
-        let mut testing_output_file = File::create(testing_output_filename)?;
-        let _ = CsvWriter::new(&mut testing_output_file).finish(&mut out.clone());
+        write_csv_atomic(&mut out.clone(), testing_output_filename)?;
     }
 
     // coverage
@@ -494,34 +1142,8 @@ pub async fn summary_performance_file(
 
         let all_p = concat_dataframes(p).await?;
 
-        let df_grouped = df
-            .clone()
-            .lazy()
-            .group_by_stable([col("ticker")])
-            .agg([col("strategy").count().alias("strategies")])
-            .sort(
-                vec!["ticker"],
-                SortMultipleOptions {
-                    descending: vec![false],
-                    nulls_last: vec![true],
-                    ..Default::default()
-                },
-            );
-
-        let both = all_p
-            .lazy()
-            .inner_join(df_grouped, col("Ticker"), col("ticker"))
-            .filter(col("strategies").lt(lit(121)))
-            .sort(
-                vec!["strategies"],
-                SortMultipleOptions {
-                    descending: vec![false],
-                    nulls_last: vec![true],
-                    ..Default::default()
-                },
-            )
-            .collect();
-        println!("Strategy Coverage: {:?}", both);
+        let both = strategy_coverage(all_p, &df, None);
+        log::info!("Strategy Coverage: {:?}", both);
 
         // buys and sells for the current date
         let df_b = concat_dataframes(b).await?;
@@ -554,12 +1176,10 @@ pub async fn summary_performance_file(
             .collect()?;
 
         let buy_filename = paths.buys_file(tag, &datetag);
-        let mut buy_file = File::create(buy_filename)?;
-        let _ = CsvWriter::new(&mut buy_file).finish(&mut buys);
+        write_csv_atomic(&mut buys, buy_filename)?;
 
         let sell_filename = paths.sells_file(tag, &datetag);
-        let mut sell_file = File::create(sell_filename)?;
-        let _ = CsvWriter::new(&mut sell_file).finish(&mut sells);
+        write_csv_atomic(&mut sells, sell_filename)?;
     };
 
     // only show for testing
@@ -577,11 +1197,10 @@ pub async fn summary_performance_file(
 
         match lc {
             Ok(ref _df) => {
-                let perf_filename = format!("{}/performance/{}.csv", paths.base, "LC");
-                let mut file = File::create(perf_filename)?;
-                let _ = CsvWriter::new(&mut file).finish(&mut lc?);
+                let perf_filename = format!("{}/performance/{}.csv", paths.output_base(), "LC");
+                write_csv_atomic(&mut lc?, perf_filename)?;
             }
-            Err(ref e) => println!("Error filtering DataFrame for LC: \n{:?}", e),
+            Err(ref e) => log::error!("Error filtering DataFrame for LC: \n{:?}", e),
         }
 
         // MC
@@ -597,11 +1216,10 @@ pub async fn summary_performance_file(
 
         match mc {
             Ok(ref _df) => {
-                let perf_filename = format!("{}/performance/{}.csv", paths.base, "MC");
-                let mut file = File::create(perf_filename)?;
-                let _ = CsvWriter::new(&mut file).finish(&mut mc?);
+                let perf_filename = format!("{}/performance/{}.csv", paths.output_base(), "MC");
+                write_csv_atomic(&mut mc?, perf_filename)?;
             }
-            Err(ref e) => println!("Error filtering DataFrame for MC: \n{:?}", e),
+            Err(ref e) => log::error!("Error filtering DataFrame for MC: \n{:?}", e),
         }
 
         // SC
@@ -619,11 +1237,10 @@ pub async fn summary_performance_file(
 
         match sc {
             Ok(ref _df) => {
-                let perf_filename = format!("{}/performance/{}.csv", paths.base, "SC");
-                let mut file = File::create(perf_filename)?;
-                let _ = CsvWriter::new(&mut file).finish(&mut sc?);
+                let perf_filename = format!("{}/performance/{}.csv", paths.output_base(), "SC");
+                write_csv_atomic(&mut sc?, perf_filename)?;
             }
-            Err(ref e) => println!("Error filtering DataFrame for SC: \n{:?}", e),
+            Err(ref e) => log::error!("Error filtering DataFrame for SC: \n{:?}", e),
         }
 
         // Microcap
@@ -639,18 +1256,69 @@ pub async fn summary_performance_file(
 
         match micro {
             Ok(ref _df) => {
-                let perf_filename = format!("{}/performance/{}.csv", paths.base, "Micro");
-                let mut file = File::create(perf_filename)?;
-                let _ = CsvWriter::new(&mut file).finish(&mut micro?);
+                let perf_filename = format!("{}/performance/{}.csv", paths.output_base(), "Micro");
+                write_csv_atomic(&mut micro?, perf_filename)?;
             }
-            Err(ref e) => println!("Error filtering DataFrame for Micro: \n{:?}", e),
+            Err(ref e) => log::error!("Error filtering DataFrame for Micro: \n{:?}", e),
         }
     }
 
     Ok((datetag, out))
 }
 
-pub fn summary_performance(df: DataFrame) -> Result<DataFrame, Box<dyn StdError>> {
+/// How missing metrics (null or NaN, e.g. no `sharpe_ratio` for a ticker without trades)
+/// enter the per-strategy means of `summary_performance`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NullPolicy {
+    /// Average only the tickers that have a value
+    #[default]
+    Skip,
+    /// Count missing values as zero
+    ZeroFill,
+    /// Any missing value makes the mean null
+    Propagate,
+}
+
+impl NullPolicy {
+    /// Mean of `values` under this policy (NaNs must already be nulls)
+    fn mean(&self, values: Expr) -> Expr {
+        match self {
+            Self::Skip => values.mean(),
+            Self::ZeroFill => values.fill_null(lit(0)).mean(),
+            Self::Propagate => when(values.clone().null_count().gt(lit(0)))
+                .then(lit(NULL).cast(DataType::Float64))
+                .otherwise(values.mean()),
+        }
+    }
+}
+
+/// Per-strategy summary; `columns` picks and orders the output columns (`None` keeps all)
+pub fn summary_performance(df: DataFrame, columns: Option<Vec<&str>>) -> Result<DataFrame, Box<dyn StdError>> {
+    let out = summary_performance_with(df, NullPolicy::default())?;
+    match columns {
+        Some(columns) => select_summary_columns(&out, &columns),
+        None => Ok(out),
+    }
+}
+
+/// Select `columns` from a summary frame in the given order, naming any unknown column
+pub fn select_summary_columns(out: &DataFrame, columns: &[&str]) -> Result<DataFrame, Box<dyn StdError>> {
+    let names = out.get_column_names_str();
+    if let Some(unknown) = columns.iter().find(|c| !names.contains(c)) {
+        return Err(format!("unknown summary column '{}' (available: {})", unknown, names.join(", ")).into());
+    }
+    Ok(out.select(columns.iter().copied())?)
+}
+
+/// `summary_performance` with an explicit null policy.
+///
+/// Rows are ranked by `profit_factor` (descending), and ties are broken by `expectancy`
+/// (descending), then `N` (descending, so the strategy backed by more tickers wins), then
+/// `strategy` and `universe` by name. `profit_factor` averages only the tickers whose
+/// ratio isn't capped (null when all are), so one ticker without losses at
+/// `PROFIT_FACTOR_CAP` doesn't swamp the mean or the score sums built on it;
+/// `profit_factor_capped` counts the tickers left out.
+pub fn summary_performance_with(df: DataFrame, null_policy: NullPolicy) -> Result<DataFrame, Box<dyn StdError>> {
     // Normalize universe names to combine LC1/LC2 -> LC, MC1/MC2 -> MC, etc.
     let df = df
         .lazy()
@@ -666,38 +1334,70 @@ pub fn summary_performance(df: DataFrame) -> Result<DataFrame, Box<dyn StdError>
                 .otherwise(col("universe"))
                 .alias("universe")
         )
+        // NaN and null both mean "no value", so the null policy treats them alike
+        .with_column(dtype_col(&DataType::Float64).fill_nan(lit(NULL)))
+        // Fixed row order so the means (and the CSV) don't depend on file read order
+        .sort(
+            ["strategy", "universe", "ticker"],
+            SortMultipleOptions::default().with_maintain_order(true),
+        )
         .collect()?;
 
+    let mean = |name: &str, alias: &str| null_policy.mean(col(name)).alias(alias);
+    // Files saved before the flag existed have no capped rows
+    let capped = col("profit_factor_capped").fill_null(lit(false));
     let out = df
         .lazy()
         .group_by_stable([col("strategy"), col("universe")])
         .agg([
-            col("hit_ratio").mean().alias("hit_ratio"),
-            col("realized_risk_reward").mean().alias("risk_reward"),
-            col("avg_gain").mean().alias("avg_gain"),
-            col("avg_loss").mean().alias("avg_loss"),
-            col("max_gain").mean().alias("max_gain"),
-            col("max_loss").mean().alias("max_loss"),
-            col("buys").mean().alias("buys"),
-            col("sells").mean().alias("sells"),
-            col("trades").mean().alias("trades"),
+            mean("hit_ratio", "hit_ratio"),
+            mean("realized_risk_reward", "risk_reward"),
+            mean("avg_gain", "avg_gain"),
+            mean("avg_loss", "avg_loss"),
+            mean("max_gain", "max_gain"),
+            mean("max_loss", "max_loss"),
+            mean("buys", "buys"),
+            mean("sells", "sells"),
+            mean("trades", "trades"),
+            mean("signal_exits", "signal_exits"),
+            mean("stop_loss_exits", "stop_loss_exits"),
+            mean("end_of_data_exits", "end_of_data_exits"),
             len().alias("N"),
-            col("sharpe_ratio").mean().alias("sharpe_ratio"),
-            col("sortino_ratio").mean().alias("sortino_ratio"),
-            col("max_drawdown").mean().alias("max_drawdown"),
-            col("calmar_ratio").mean().alias("calmar_ratio"),
-            col("win_loss_ratio").mean().alias("win_loss_ratio"),
-            col("recovery_factor").mean().alias("recovery_factor"),
-            col("profit_per_trade").mean().alias("profit_per_trade"),
-            col("expectancy").mean().alias("expectancy"),
-            col("profit_factor").mean().alias("profit_factor"),
+            mean("sharpe_ratio", "sharpe_ratio"),
+            mean("sortino_ratio", "sortino_ratio"),
+            mean("sharpe_tstat", "sharpe_tstat"),
+            mean("max_drawdown", "max_drawdown"),
+            mean("calmar_ratio", "calmar_ratio"),
+            mean("profit_to_dd", "profit_to_dd"),
+            mean("win_loss_ratio", "win_loss_ratio"),
+            mean("recovery_factor", "recovery_factor"),
+            mean("profit_per_trade", "profit_per_trade"),
+            mean("max_consecutive_wins", "max_consecutive_wins"),
+            mean("max_consecutive_losses", "max_consecutive_losses"),
+            mean("cagr", "cagr"),
+            mean("annualized_return", "annualized_return"),
+            mean("avg_gain_atr", "avg_gain_atr"),
+            mean("avg_loss_atr", "avg_loss_atr"),
+            mean("information_ratio", "information_ratio"),
+            mean("beta_to_benchmark", "beta_to_benchmark"),
+            mean("avg_rolling_beta", "avg_rolling_beta"),
+            mean("beta_adjusted_return", "beta_adjusted_return"),
+            mean("expectancy_per_day", "expectancy_per_day"),
+            mean("expectancy_r", "expectancy_r"),
+            mean("r_multiple_std", "r_multiple_std"),
+            mean("buy_and_hold_return", "buy_and_hold_return"),
+            mean("dca_return", "dca_return"),
+            mean("expectancy", "expectancy"),
+            null_policy.mean(col("profit_factor").filter(capped.clone().not())).alias("profit_factor"),
+            capped.cast(DataType::UInt32).sum().alias("profit_factor_capped"),
         ])
         .filter(col("trades").gt(lit(3)))
         .sort(
-            vec!["profit_factor"],
+            vec!["profit_factor", "expectancy", "N", "strategy", "universe"],
             SortMultipleOptions {
-                descending: vec![true],
-                nulls_last: vec![true],
+                descending: vec![true, true, true, false, false],
+                nulls_last: vec![true, true, true, true, true],
+                maintain_order: true,
                 ..Default::default()
             },
         )
@@ -712,8 +1412,30 @@ pub async fn sig(
     param: f64,
     signal_name: String,
 ) -> Result<(Backtest, Backtest, Vec<Decision>), Box<dyn StdError>> {
-    let s = (func)(df.clone().collect()?, param); // Call the signal function
-    let (bt, bt_se, decisions) = backtest_performance(df.collect()?, s, &signal_name)?;
+    run_signal(df, func, param, &signal_name, &std::sync::atomic::AtomicBool::new(false))
+}
+
+/// Body of `sig`; synchronous so it can run on a worker thread. `cancel` is checked between
+/// the stages (the signal function itself can't be interrupted), so a timed-out strategy
+/// stops at the next one instead of finishing its backtest.
+fn run_signal(
+    df: LazyFrame,
+    func: SignalFunctionWithParam,
+    param: f64,
+    signal_name: &str,
+    cancel: &std::sync::atomic::AtomicBool,
+) -> Result<(Backtest, Backtest, Vec<Decision>), Box<dyn StdError>> {
+    let cancelled = || cancel.load(std::sync::atomic::Ordering::Relaxed);
+    let data = df.collect()?;
+    if cancelled() {
+        return Err(format!("Signal '{}' cancelled", signal_name).into());
+    }
+    let s = (func)(data.clone(), param); // Call the signal function
+    if cancelled() {
+        return Err(format!("Signal '{}' cancelled", signal_name).into());
+    }
+    s.validate().map_err(|e| format!("Signal '{}' is malformed: {}", signal_name, e))?;
+    let (bt, bt_se, decisions) = backtest_performance(data, s, signal_name)?;
     // println!("Backtest for signal '{}': {:?}", signal_name, decisions);
     Ok((bt, bt_se, decisions))
 }
@@ -723,35 +1445,199 @@ pub async fn sig_sized(
     func: SignalFunctionWithParam, // Use the correct type
     param: f64,
     signal_name: String,
-    entry_amount: f64,
-    exit_amount: f64,
+    sizing: &SizingConfig,
 ) -> Result<(Backtest, Vec<Decision>), Box<dyn StdError>> {
     let s = (func)(df.clone().collect()?, param); // Call the signal function
-    let (bt, decisions) =
-        backtest_performance_sized(df.collect()?, s, &signal_name, entry_amount, exit_amount)?;
+    s.validate().map_err(|e| format!("Signal '{}' is malformed: {}", signal_name, e))?;
+    let (bt, decisions) = backtest_performance_sized(df.collect()?, s, &signal_name, sizing)?;
     Ok((bt, decisions))
 }
 
-pub async fn run_all_backtests(
-    df: LazyFrame,
-    signals: Vec<Signal>,
-) -> Result<Vec<(Backtest, Vec<Decision>)>, JoinError> {
-    // Wrap df in an Arc for shared ownership across tasks
-    let df = Arc::new(df);
+/// On-disk store of `sig` results, one JSON file per (ticker, strategy, param, price
+/// data) key, so unchanged strategies on unchanged data are not backtested again
+#[derive(Debug)]
+pub struct BacktestCache {
+    dir: PathBuf,
+    misses: std::sync::atomic::AtomicUsize,
+}
 
-    let futures: Vec<_> = signals
-        .into_iter()
-        .map(|signal| {
-            // Clone Arc for each task
-            let df_clone = Arc::clone(&df);
-            let func = signal.func.clone(); // Extract the function from the Signal struct
-            let _p = signal.param; // Use default value if no parameter is provided
+type CachedBacktest = (Backtest, Backtest, Vec<Decision>);
+
+/// Bump whenever the backtest logic or the `Backtest` fields change, so entries written by
+/// an older build are recomputed rather than served
+const BACKTEST_CACHE_VERSION: u32 = 1;
+
+/// A cache file. The two backtests are kept as CSV rows, which write NaN and infinite
+/// metrics as such where JSON would turn them into null, plus the histograms CSV skips.
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    backtests: String,
+    holding_periods: [Vec<(i64, usize)>; 2],
+    decisions: Vec<Decision>,
+}
+
+impl CacheEntry {
+    fn new((bt, bt_se, decisions): &CachedBacktest) -> Result<Self, Box<dyn StdError>> {
+        let mut wtr = csv::Writer::from_writer(Vec::new());
+        wtr.serialize(bt)?;
+        wtr.serialize(bt_se)?;
+        Ok(Self {
+            backtests: String::from_utf8(wtr.into_inner().map_err(|e| e.to_string())?)?,
+            holding_periods: [bt.holding_period_histogram.clone(), bt_se.holding_period_histogram.clone()],
+            decisions: decisions.clone(),
+        })
+    }
+
+    fn into_backtests(self) -> Option<CachedBacktest> {
+        let mut rows = csv::Reader::from_reader(self.backtests.as_bytes()).into_deserialize::<Backtest>();
+        let (mut bt, mut bt_se) = (rows.next()?.ok()?, rows.next()?.ok()?);
+        let [histogram, histogram_se] = self.holding_periods;
+        bt.holding_period_histogram = histogram;
+        bt_se.holding_period_histogram = histogram_se;
+        Some((bt, bt_se, self.decisions))
+    }
+}
+
+impl BacktestCache {
+    pub fn new<P: AsRef<Path>>(dir: P) -> Result<Self, Box<dyn StdError>> {
+        std::fs::create_dir_all(dir.as_ref())?;
+        Ok(Self { dir: dir.as_ref().to_path_buf(), misses: Default::default() })
+    }
+
+    /// Number of backtests recomputed because their key was not cached
+    pub fn misses(&self) -> usize {
+        self.misses.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    /// Hash of the price rows, salted with the crate and cache versions; any changed,
+    /// added or removed bar changes it. FNV-1a, so keys survive a rebuild.
+    pub fn data_hash(df: &DataFrame) -> Result<u64, Box<dyn StdError>> {
+        let mut buf = format!("{}-{}\n", env!("CARGO_PKG_VERSION"), BACKTEST_CACHE_VERSION).into_bytes();
+        CsvWriter::new(&mut buf).finish(&mut df.clone())?;
+        Ok(fnv1a(&buf))
+    }
+
+    fn path(&self, ticker: &str, strategy: &str, param: f64, data_hash: u64) -> PathBuf {
+        let safe = |s: &str| s.replace(|c: char| !c.is_ascii_alphanumeric() && c != '_', "-");
+        self.dir.join(format!("{}_{}_{}_{:016x}.json", safe(ticker), safe(strategy), safe(&param.to_string()), data_hash))
+    }
+
+    fn get(&self, path: &Path) -> Option<CachedBacktest> {
+        let json = std::fs::read(path).ok()?;
+        serde_json::from_slice::<CacheEntry>(&json).ok()?.into_backtests()
+    }
+
+    fn put(&self, path: &Path, result: &CachedBacktest) {
+        match CacheEntry::new(result).and_then(|entry| Ok(serde_json::to_vec(&entry)?)) {
+            Ok(json) => {
+                if let Err(e) = std::fs::write(path, json) {
+                    log::warn!("Could not write backtest cache {}: {}", path.display(), e);
+                }
+            }
+            Err(e) => log::warn!("Could not serialize backtest cache entry: {}", e),
+        }
+    }
+}
+
+pub async fn run_all_backtests(
+    df: LazyFrame,
+    signals: Vec<Signal>,
+) -> Result<Vec<(Backtest, Vec<Decision>)>, JoinError> {
+    run_all_backtests_with(df, signals, None, None).await
+}
+
+/// Slots for strategies running at once, one per core, shared by every ticker
+fn strategy_slots() -> Arc<tokio::sync::Semaphore> {
+    static SLOTS: OnceLock<Arc<tokio::sync::Semaphore>> = OnceLock::new();
+    let cores = std::thread::available_parallelism().map_or(4, |n| n.get());
+    Arc::clone(SLOTS.get_or_init(|| Arc::new(tokio::sync::Semaphore::new(cores))))
+}
+
+/// `run_all_backtests` reusing `cache` entries whose key matches and storing new results.
+///
+/// At most one strategy per core runs at a time, across all tickers (see
+/// `strategy_slots`), each on its own thread. With `timeout`, a strategy still running
+/// that long after it started (time spent waiting for a slot doesn't count) is logged and
+/// left out of the results, and its slot goes to the next strategy. A timeout abandons
+/// the thread rather than killing it: the strategy is flagged as cancelled and stops at
+/// its next check (see `run_signal`), but a signal function that never returns keeps its
+/// thread until the process exits, which doesn't wait for it.
+pub async fn run_all_backtests_with(
+    df: LazyFrame,
+    signals: Vec<Signal>,
+    cache: Option<Arc<BacktestCache>>,
+    timeout: Option<std::time::Duration>,
+) -> Result<Vec<(Backtest, Vec<Decision>)>, JoinError> {
+    // The cache key needs the collected prices; without a usable key every signal recomputes
+    let cache_key = cache.and_then(|cache| {
+        let data = df.clone().collect().ok()?;
+        let ticker = data.column("Ticker").ok()?.str().ok()?.get(0)?.to_string();
+        let hash = BacktestCache::data_hash(&data).ok()?;
+        Some((cache, ticker, hash))
+    });
+
+    // Wrap df in an Arc for shared ownership across tasks
+    let df = Arc::new(df);
+
+    let futures: Vec<_> = signals
+        .into_iter()
+        .map(|signal| {
+            // Clone Arc for each task
+            let df_clone = Arc::clone(&df);
+            let func = signal.func.clone(); // Extract the function from the Signal struct
+            let _p = signal.param; // Use default value if no parameter is provided
+            let cache_key = cache_key.clone();
 
             tokio::spawn(async move {
-                // sig(df_clone.as_ref().clone(), *func, p).await.unwrap()
-                sig(df_clone.as_ref().clone(), *func, signal.param, signal.name)
-                    .await
-                    .unwrap()
+                let cached = cache_key.as_ref().map(|(cache, ticker, hash)| {
+                    (cache, cache.path(ticker, &signal.name, signal.param, *hash))
+                });
+                if let Some(hit) = cached.as_ref().and_then(|(cache, path)| cache.get(path)) {
+                    return Some(hit);
+                }
+                // Signals are CPU-bound, so they run off the runtime; the slot is held
+                // until the result arrives or the timeout gives up on it
+                let name = signal.name.clone();
+                let _slot = strategy_slots().acquire_owned().await.ok()?;
+                let cancel = Arc::new(std::sync::atomic::AtomicBool::new(false));
+                let (done_tx, done_rx) = tokio::sync::oneshot::channel();
+                let job_cancel = Arc::clone(&cancel);
+                let spawned = std::thread::Builder::new().name(format!("strategy-{}", name)).spawn(move || {
+                    let run = std::panic::AssertUnwindSafe(|| {
+                        run_signal(df_clone.as_ref().clone(), *func, signal.param, &signal.name, &job_cancel)
+                            .map_err(|e| e.to_string())
+                    });
+                    let result = std::panic::catch_unwind(run)
+                        .unwrap_or_else(|_| Err(format!("Signal '{}' panicked", signal.name)));
+                    let _ = done_tx.send(result);
+                });
+                if let Err(e) = spawned {
+                    log::error!("Strategy '{}' skipped: no thread ({})", name, e);
+                    return None;
+                }
+                let joined = match timeout {
+                    Some(limit) => match tokio::time::timeout(limit, done_rx).await {
+                        Ok(joined) => joined,
+                        Err(_) => {
+                            cancel.store(true, std::sync::atomic::Ordering::Relaxed);
+                            log::warn!("Strategy '{}' timed out after {:?}; skipped", name, limit);
+                            return None;
+                        }
+                    },
+                    None => done_rx.await,
+                };
+                let result = match joined.ok()? {
+                    Ok(result) => result,
+                    Err(e) => {
+                        log::error!("Strategy '{}' skipped: {}", name, e);
+                        return None;
+                    }
+                };
+                if let Some((cache, path)) = cached {
+                    cache.misses.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                    cache.put(&path, &result);
+                }
+                Some(result)
             })
         })
         .collect();
@@ -761,7 +1647,7 @@ pub async fn run_all_backtests(
     // Flatten both Backtest variants (buy-exit and sell-exit) into the same Vec
     let backtests: Vec<(Backtest, Vec<Decision>)> = results
         .into_iter()
-        .filter_map(Result::ok)
+        .filter_map(|joined| joined.ok().flatten())
         .flat_map(|(bt, bt_se, decisions)| {
             vec![(bt, decisions.clone()), (bt_se, decisions)]
         })
@@ -770,55 +1656,482 @@ pub async fn run_all_backtests(
     Ok(backtests)
 }
 
+/// Running per-strategy trade totals across tickers, used to spot dead signals
+#[derive(Debug, Default)]
+pub struct TradeCounter {
+    trades: std::collections::BTreeMap<String, i64>,
+}
+
+impl TradeCounter {
+    pub fn add(&mut self, bt: &Backtest) {
+        *self.trades.entry(bt.strategy.clone()).or_insert(0) += bt.trades as i64;
+    }
+
+    /// Strategies that produced no trades on any ticker, in name order
+    pub fn zero_trade_strategies(&self) -> Vec<String> {
+        self.trades
+            .iter()
+            .filter(|(_, &trades)| trades == 0)
+            .map(|(strategy, _)| strategy.clone())
+            .collect()
+    }
+
+    /// Log a warning for each strategy with zero trades universe-wide and return them
+    pub fn warn_zero_trades(&self, universe: &str) -> Vec<String> {
+        let dead = self.zero_trade_strategies();
+        for strategy in &dead {
+            log::warn!(
+                "Strategy '{}' produced zero trades across universe {} (broken signal?)",
+                strategy, universe
+            );
+        }
+        dead
+    }
+}
+
+/// Pull price files from ClickHouse. With `allow_fallback`, a universe whose pull fails
+/// (e.g. the database is unreachable) falls back to its most recent cached CSV, which is
+/// returned keyed by universe so this run can read it in place of the universe's own
+/// file. The cache is never copied over that file.
 pub async fn create_price_files(
     univ_vec: Vec<String>,
     is_production: bool,
-) -> Result<(), Box<dyn StdError>> {
+    allow_fallback: bool,
+) -> Result<HashMap<String, PathBuf>, Box<dyn StdError>> {
+    let user_path = match env::var("CLICKHOUSE_USER_PATH") {
+        Ok(path) => path,
+        Err(_) => String::from("/srv"),
+    };
+    let data_dir = format!("{}/rust_home/backtester/data", user_path);
+    let ch_config = ClickhouseConfig::from_env();
+    let ch_config = &ch_config;
+
+    create_price_files_from(&data_dir, univ_vec, is_production, allow_fallback, |u| async move {
+        if !test_connection(ChConnectionType::Ace).await {
+            return Err(format!("ClickHouse is unreachable, cannot pull prices for {}", u).into());
+        }
+        write_price_file(u, is_production, ch_config).await
+    })
+    .await
+}
+
+/// `create_price_files` against an arbitrary price source that writes
+/// `<data_dir>/<production|testing>/<universe>.csv`; returns the cached files standing in
+/// for universes whose fetch failed
+pub async fn create_price_files_from<F, Fut>(
+    data_dir: &str,
+    univ_vec: Vec<String>,
+    is_production: bool,
+    allow_fallback: bool,
+    fetch: F,
+) -> Result<HashMap<String, PathBuf>, Box<dyn StdError>>
+where
+    F: Fn(String) -> Fut,
+    Fut: std::future::Future<Output = Result<(), Box<dyn StdError>>>,
+{
     let folder = if is_production { "production" } else { "testing" };
+    let mut fallbacks = HashMap::new();
 
     for u in univ_vec {
-        let user_path = match env::var("CLICKHOUSE_USER_PATH") {
-            Ok(path) => path,
-            Err(_) => String::from("/srv"),
-        };
-        let file_path = format!(
-            "{}/rust_home/backtester/data/{}/{}.csv",
-            user_path,
-            folder.to_string(),
-            u.to_string()
-        );
-        let file_path: &str = &file_path;
+        let file_path = format!("{}/{}/{}.csv", data_dir, folder, u);
         if !is_production && Path::new(&file_path).exists() {
-            println!("Price file exists for {}", file_path);
-        } else {
-            println!("Price file generating for {}", file_path);
-            write_price_file(u, is_production).await?;
+            log::info!("Price file exists for {}", file_path);
+            continue;
+        }
+        log::info!("Price file generating for {}", file_path);
+        if let Err(e) = fetch(u.clone()).await {
+            if !allow_fallback {
+                return Err(e);
+            }
+            let Some(cached) = latest_cached_price_file(data_dir, &u) else {
+                return Err(format!("{} (no cached price file for {} to fall back on)", e, u).into());
+            };
+            let msg = format!(
+                "⚠️  STALE DATA: price pull for {} failed ({}); using cached {}",
+                u,
+                e,
+                cached.display()
+            );
+            log::warn!("{}", msg);
+            fallbacks.insert(u, cached);
         }
     }
-    Ok(())
+    Ok(fallbacks)
 }
 
-fn compute_metrics(
-    total_result: &[f64],
+/// Most recently modified `<universe>.csv` across the production and testing data folders
+fn latest_cached_price_file(data_dir: &str, universe: &str) -> Option<PathBuf> {
+    ["production", "testing"]
+        .iter()
+        .map(|folder| Path::new(data_dir).join(folder).join(format!("{}.csv", universe)))
+        .filter_map(|path| {
+            let modified = std::fs::metadata(&path).ok()?.modified().ok()?;
+            Some((modified, path))
+        })
+        .max_by_key(|(modified, _)| *modified)
+        .map(|(_, path)| path)
+}
+
+/// Parse a trade date from `YYYY-MM-DD`, `YYYY-MM-DD HH:MM:SS` or ISO 8601
+/// (`2024-01-02T15:30:00`, `2024-01-02T15:30:00Z`, `2024-01-02T15:30:00-05:00`).
+/// Offset timestamps keep the calendar date of their own offset.
+pub fn parse_trade_date(s: &str) -> Result<chrono::NaiveDate, Box<dyn StdError>> {
+    let s = s.trim();
+    if let Ok(date) = chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return Ok(date);
+    }
+    for fmt in ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"] {
+        if let Ok(datetime) = chrono::NaiveDateTime::parse_from_str(s, fmt) {
+            return Ok(datetime.date());
+        }
+    }
+    if let Ok(datetime) = chrono::DateTime::parse_from_rfc3339(s) {
+        return Ok(datetime.date_naive());
+    }
+    Err(format!(
+        "Unparseable trade date '{}': expected YYYY-MM-DD, YYYY-MM-DD HH:MM:SS or ISO 8601",
+        s
+    )
+    .into())
+}
+
+/// Convert a polars date value (Date or date string) to a NaiveDate
+fn any_value_to_date(value: AnyValue) -> Option<chrono::NaiveDate> {
+    match value {
+        // Polars stores dates as days since 1970-01-01 (day 719163 of the common era)
+        AnyValue::Date(days) => chrono::NaiveDate::from_num_days_from_ce_opt(days + 719_163),
+        AnyValue::String(s) => parse_trade_date(s).ok(),
+        AnyValue::StringOwned(ref s) => parse_trade_date(s).ok(),
+        _ => None,
+    }
+}
+
+/// Parseable dates of the frame's `Date` column, in row order
+fn frame_dates(df: &DataFrame) -> Vec<chrono::NaiveDate> {
+    let dates = match df.column("Date") {
+        Ok(c) => c,
+        Err(_) => return Vec::new(),
+    };
+    (0..df.height())
+        .filter_map(|i| dates.get(i).ok().and_then(any_value_to_date))
+        .collect()
+}
+
+/// Convention for turning a price series into per-bar returns
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReturnMethod {
+    /// `p[i] / p[i-1] - 1`, a fraction (the `Ret` column of `preprocess`)
+    Simple,
+    /// `ln(p[i] / p[i-1])`
+    Log,
+    /// `Simple` in percent
+    Pct,
+    /// `p[i] - p[i-1]` in price points (the trade P&L of `backtest_performance`)
+    PointDiff,
+}
+
+impl ReturnMethod {
+    /// Return from price `from` to price `to`
+    pub fn between(self, from: f64, to: f64) -> f64 {
+        match self {
+            ReturnMethod::Simple => to / from - 1.0,
+            ReturnMethod::Log => (to / from).ln(),
+            ReturnMethod::Pct => (to / from - 1.0) * 100.0,
+            ReturnMethod::PointDiff => to - from,
+        }
+    }
+
+    /// Lazy counterpart of `compute_returns` on the price expression `price`; wrap the
+    /// result in `.over([col("Ticker")])` for a frame holding several tickers
+    pub fn expr(self, price: Expr) -> Expr {
+        let prev = price.clone().shift(lit(1));
+        match self {
+            ReturnMethod::Simple => price / prev - lit(1.0),
+            ReturnMethod::Log => (price / prev).map(
+                |c| Ok(Some(c.as_materialized_series().f64()?.apply_values(f64::ln).into_column())),
+                GetOutput::from_type(DataType::Float64),
+            ),
+            ReturnMethod::Pct => (price / prev - lit(1.0)) * lit(100.0),
+            ReturnMethod::PointDiff => price - prev,
+        }
+    }
+}
+
+/// Per-bar returns of a price series under `method`; the first value, and any next to a
+/// missing price, is null
+pub fn compute_returns(series: &Series, method: ReturnMethod) -> Result<Series, PolarsError> {
+    let prices = series.cast(&DataType::Float64)?;
+    let prev = prices.shift(1);
+    let returns: Float64Chunked = prices
+        .f64()?
+        .into_iter()
+        .zip(prev.f64()?)
+        .map(|(price, prev)| Some(method.between(prev?, price?)))
+        .collect();
+    Ok(returns.with_name(series.name().clone()).into_series())
+}
+
+/// Annualized (geometric) return of the equity curve compounded from fractional returns
+pub fn annualized_return(returns: &[f64], years: f64) -> f64 {
+    let growth: f64 = returns.iter().map(|r| 1.0 + r).product();
+    if years > 0.0 && growth > 0.0 {
+        growth.powf(1.0 / years) - 1.0
+    } else {
+        0.0
+    }
+}
+
+/// Cumulative (non-compounded) return per year, in percent
+pub fn simple_annualized_return(returns: &[f64], years: f64) -> f64 {
+    if years > 0.0 { returns.iter().sum::<f64>() / years * 100.0 } else { 0.0 }
+}
+
+/// Longest runs of winning and losing results, as (max_wins, max_losses).
+/// Zero entries are bars without a closed trade and do not break a streak.
+pub fn consecutive_streaks(results: &[f64]) -> (i32, i32) {
+    let (mut wins, mut losses) = (0, 0);
+    let (mut max_wins, mut max_losses) = (0, 0);
+    for &result in results {
+        if result > 0.0 {
+            wins += 1;
+            losses = 0;
+        } else if result < 0.0 {
+            losses += 1;
+            wins = 0;
+        }
+        max_wins = max_wins.max(wins);
+        max_losses = max_losses.max(losses);
+    }
+    (max_wins, max_losses)
+}
+
+/// Largest peak-to-trough decline of a curve, in the curve's own units, as
+/// (drawdown, peak index, trough index). The earliest such decline wins; a curve that
+/// never falls gives (0.0, 0, 0).
+pub fn max_drawdown(equity: &[f64]) -> (f64, usize, usize) {
+    let mut peak = 0;
+    let mut max_dd = (0.0, 0, 0);
+    for (i, &value) in equity.iter().enumerate() {
+        if value > equity[peak] { peak = i; }
+        let drawdown = equity[peak] - value;
+        if drawdown > max_dd.0 { max_dd = (drawdown, peak, i); }
+    }
+    max_dd
+}
+
+/// `max_drawdown` as a fraction of the running peak, so the deepest relative (not dollar)
+/// drawdown is reported. A curve that falls to or below zero from a positive peak gives a
+/// drawdown of 1.0 or more; declines before the curve has a positive peak are skipped.
+pub fn max_relative_drawdown(equity: &[f64]) -> (f64, usize, usize) {
+    let mut peak = 0;
+    let mut max_dd = (0.0, 0, 0);
+    for (i, &value) in equity.iter().enumerate() {
+        if value > equity[peak] { peak = i; }
+        if equity[peak] <= 0.0 { continue; }
+        let drawdown = (equity[peak] - value) / equity[peak];
+        if drawdown > max_dd.0 { max_dd = (drawdown, peak, i); }
+    }
+    max_dd
+}
+
+/// Largest peak-to-trough decline, as a fraction of the peak, of the equity curve
+/// compounded from fractional returns
+pub fn max_drawdown_pct(returns: &[f64]) -> f64 {
+    let equity: Vec<f64> = std::iter::once(1.0)
+        .chain(returns.iter().scan(1.0, |equity, r| {
+            *equity *= 1.0 + r;
+            Some(*equity)
+        }))
+        .collect();
+    max_relative_drawdown(&equity).0
+}
+
+/// Calmar ratio: annualized return divided by percentage max drawdown
+pub fn calmar_ratio(returns: &[f64], years: f64) -> f64 {
+    let max_dd = max_drawdown_pct(returns);
+    if max_dd > 0.0 { annualized_return(returns, years) / max_dd } else { 0.0 }
+}
+
+/// Annualized Sharpe ratio (no risk-free rate) of a return series, using the sample
+/// standard deviation; 0 for fewer than two returns or a flat series
+pub fn sharpe_ratio(returns: &[f64], periods_per_year: f64) -> f64 {
+    if returns.len() < 2 {
+        return 0.0;
+    }
+    let mean_return = returns.iter().sum::<f64>() / returns.len() as f64;
+    let std_dev = (returns
+        .iter()
+        .map(|x| (x - mean_return).powi(2))
+        .sum::<f64>()
+        / (returns.len() as f64 - 1.0))
+        .sqrt();
+    if std_dev > 0.0 { (mean_return / std_dev) * periods_per_year.sqrt() } else { 0.0 }
+}
+
+/// Annualized Sortino ratio of a return series: the mean over the downside deviation
+/// (losses only, divided by the full count); 0 for fewer than two returns or no losses
+pub fn sortino_ratio(returns: &[f64], periods_per_year: f64) -> f64 {
+    if returns.len() < 2 {
+        return 0.0;
+    }
+    let mean_return = returns.iter().sum::<f64>() / returns.len() as f64;
+    let downside_deviation = (returns
+        .iter()
+        .filter(|&&x| x < 0.0)
+        .map(|x| x.powi(2))
+        .sum::<f64>()
+        / returns.len() as f64)
+        .sqrt();
+    if downside_deviation > 0.0 { (mean_return / downside_deviation) * periods_per_year.sqrt() } else { 0.0 }
+}
+
+/// Read a JSON null (how serde_json writes NaN) back as NaN
+fn nan_if_null<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    Ok(Option::<f64>::deserialize(deserializer)?.unwrap_or(f64::NAN))
+}
+
+/// Fewest returns `sharpe_tstat` will test
+pub const MIN_TSTAT_OBSERVATIONS: usize = 30;
+
+/// t-statistic of the mean of `returns` (and so of their Sharpe ratio) against zero,
+/// using a Newey-West standard error so autocorrelated returns don't overstate the
+/// significance: Bartlett weights out to lag `floor(4 * (n / 100)^(2/9))`. NaN for
+/// fewer than `MIN_TSTAT_OBSERVATIONS` returns, 0 for a flat series.
+pub fn sharpe_tstat(returns: &[f64]) -> f64 {
+    let n = returns.len();
+    if n < MIN_TSTAT_OBSERVATIONS {
+        return f64::NAN;
+    }
+    let mean = returns.iter().sum::<f64>() / n as f64;
+    let autocovariance = |lag: usize| {
+        (lag..n).map(|t| (returns[t] - mean) * (returns[t - lag] - mean)).sum::<f64>() / n as f64
+    };
+    let lags = (4.0 * (n as f64 / 100.0).powf(2.0 / 9.0)).floor() as usize;
+    let long_run_variance = autocovariance(0)
+        + 2.0 * (1..=lags).map(|l| (1.0 - l as f64 / (lags + 1) as f64) * autocovariance(l)).sum::<f64>();
+    if long_run_variance > 0.0 { mean / (long_run_variance / n as f64).sqrt() } else { 0.0 }
+}
+
+/// Sharpe ratio of a weighted portfolio of equity curves (e.g. one `trade_equity_curve`
+/// per ticker). The curves' period returns are combined with `weights` before the ratio
+/// is taken, so offsetting curves diversify instead of being averaged. Curves are
+/// aligned on their first bar and cut to the shortest.
+pub fn portfolio_sharpe(equity_curves: &[Vec<f64>], weights: &[f64], periods_per_year: f64) -> f64 {
+    let len = equity_curves.iter().map(|c| c.len()).min().unwrap_or(0);
+    let combined: Vec<f64> = (1..len)
+        .map(|t| {
+            equity_curves
+                .iter()
+                .zip(weights)
+                .filter(|(curve, _)| curve[t - 1] > 0.0)
+                .map(|(curve, w)| w * (curve[t] / curve[t - 1] - 1.0))
+                .sum()
+        })
+        .collect();
+    sharpe_ratio(&combined, periods_per_year)
+}
+
+/// Equity of 1.0 compounded by each trade's `pct_return` on its exit bar, one value per bar
+pub fn trade_equity_curve(trades: &[Trade], len: usize) -> Vec<f64> {
+    let mut pct_result = vec![0.0; len];
+    for t in trades.iter().filter(|t| t.exit_index < len) {
+        pct_result[t.exit_index] += t.pct_return();
+    }
+    pct_result
+        .iter()
+        .scan(1.0, |equity, r| {
+            *equity *= 1.0 + r;
+            Some(*equity)
+        })
+        .collect()
+}
+
+/// Combine per-ticker equity curves into one portfolio curve. The curves are aligned on
+/// the union of their dates: a ticker holds its last value over dates it lacks, and its
+/// first value before it starts (idle capital). `weights` give each ticker's share of the
+/// capital, equal shares when `None`. Returns `Date`, one column per ticker (sorted by
+/// name) and `equity`, the weighted sum.
+pub fn aggregate_equity_curves(
+    curves: &HashMap<String, Vec<(chrono::NaiveDate, f64)>>,
+    weights: Option<&HashMap<String, f64>>,
+) -> Result<DataFrame, Box<dyn StdError>> {
+    let mut tickers: Vec<&String> = curves.keys().collect();
+    tickers.sort();
+    let dates: Vec<chrono::NaiveDate> = curves
+        .values()
+        .flat_map(|curve| curve.iter().map(|(date, _)| *date))
+        .collect::<std::collections::BTreeSet<_>>()
+        .into_iter()
+        .collect();
+
+    let mut columns = vec![Column::new("Date".into(), &dates)];
+    let mut equity = vec![0.0; dates.len()];
+    for ticker in tickers {
+        let weight = match weights {
+            Some(weights) => *weights.get(ticker).ok_or_else(|| format!("No weight for ticker '{}'", ticker))?,
+            None => 1.0 / curves.len() as f64,
+        };
+        let by_date: BTreeMap<chrono::NaiveDate, f64> = curves[ticker].iter().cloned().collect();
+        let Some(&first) = by_date.values().next() else { continue };
+        let values: Vec<f64> = dates
+            .iter()
+            .map(|date| by_date.range(..=*date).next_back().map_or(first, |(_, &v)| v))
+            .collect();
+        for (total, v) in equity.iter_mut().zip(&values) {
+            *total += weight * v;
+        }
+        columns.push(Column::new(ticker.as_str().into(), values));
+    }
+    columns.push(Column::new("equity".into(), equity));
+    Ok(DataFrame::new(columns)?)
+}
+
+/// Default ceiling on `profit_factor`
+pub const PROFIT_FACTOR_CAP: f64 = 999.0;
+
+/// Gross profit over gross loss, limited to `cap`, and whether the cap applied. Profits
+/// without any loss report the cap; no trades at all report 0.
+fn profit_factor(gross_profit: f64, gross_loss: f64, cap: f64) -> (f64, bool) {
+    if gross_loss > 0.0 {
+        let ratio = gross_profit / gross_loss;
+        if ratio > cap { (cap, true) } else { (ratio, false) }
+    } else if gross_profit > 0.0 {
+        (cap, true)
+    } else {
+        (0.0, false)
+    }
+}
+
+/// Everything `backtest_metrics` needs besides the per-bar results, named so that
+/// neighbouring numbers and counts can't be swapped at the call site
+struct MetricsParams {
     ticker: String,
     universe: String,
     strategy: String,
+    /// Date of the last bar
+    date: String,
+    /// Buy and sell signal counts over the frame
     buys: i32,
     sells: i32,
-    date: String,
+    /// Signals reported for the last bar, see `FinalSignal`
     buy: i32,
     sell: i32,
-) -> Backtest {
+    /// Span of the frame in years, for the annualized returns
+    years: f64,
+    /// Bars per year, for the Sharpe and Sortino ratios
+    periods_per_year: f64,
+    profit_factor_cap: f64,
+}
+
+fn backtest_metrics(total_result: &[f64], pct_result: &[f64], risk_returns: &[f64], params: MetricsParams) -> Backtest {
+    let MetricsParams { ticker, universe, strategy, date, buys, sells, buy, sell, years, periods_per_year, profit_factor_cap } =
+        params;
     let total_net_profits: Vec<f64> = total_result.iter().cloned().filter(|&x| x > 0.0).collect();
     let total_net_losses: Vec<f64> = total_result.iter().cloned().filter(|&x| x < 0.0).collect();
     let sum_total_net_profits = total_net_profits.iter().sum::<f64>();
     let sum_total_net_losses = total_net_losses.iter().sum::<f64>().abs();
 
-    let profit_factor = if sum_total_net_losses > 0.0 {
-        f64::min(999.0, sum_total_net_profits / sum_total_net_losses)
-    } else {
-        0.0
-    };
+    let (profit_factor, profit_factor_capped) = profit_factor(sum_total_net_profits, sum_total_net_losses, profit_factor_cap);
     let hit_ratio: f64 = if total_net_losses.len() + total_net_profits.len() > 0 {
         (total_net_profits.len() as f64
             / (total_net_losses.len() + total_net_profits.len()) as f64)
@@ -854,54 +2167,30 @@ fn compute_metrics(
         .min_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
         .unwrap_or(0.0);
 
-    let sharpe_ratio = if total_result.len() > 1 {
-        let mean_return = total_result.iter().sum::<f64>() / total_result.len() as f64;
-        let std_dev = (total_result
-            .iter()
-            .map(|x| (x - mean_return).powi(2))
-            .sum::<f64>()
-            / (total_result.len() as f64 - 1.0))
-            .sqrt();
-        if std_dev > 0.0 { (mean_return / std_dev) * (252.0_f64).sqrt() } else { 0.0 }
-    } else {
-        0.0
-    };
-    let sortino_ratio = if total_result.len() > 1 {
-        let mean_return = total_result.iter().sum::<f64>() / total_result.len() as f64;
-        let downside_deviation = (total_result
-            .iter()
-            .filter(|&&x| x < 0.0)
-            .map(|x| x.powi(2))
-            .sum::<f64>()
-            / total_result.len() as f64)
-            .sqrt();
-        if downside_deviation > 0.0 {
-            (mean_return / downside_deviation) * (252.0_f64).sqrt()
-        } else {
-            0.0
-        }
-    } else {
-        0.0
-    };
-    let max_drawdown = {
-        let mut peak = total_result[0];
-        let mut max_dd = 0.0;
-        for &value in total_result {
-            if value > peak { peak = value; }
-            let drawdown = peak - value;
-            if drawdown > max_dd { max_dd = drawdown; }
-        }
-        max_dd
-    };
-    let calmar_ratio = if max_drawdown > 0.0 && !total_result.is_empty() {
+    let sharpe_ratio = sharpe_ratio(risk_returns, periods_per_year);
+    let sortino_ratio = sortino_ratio(risk_returns, periods_per_year);
+    let sharpe_tstat = sharpe_tstat(risk_returns);
+    let (max_drawdown, _, _) = max_drawdown(total_result);
+    // Raw average profit per bar over the dollar drawdown (formerly reported as calmar)
+    let profit_to_dd = if max_drawdown > 0.0 && !total_result.is_empty() {
         let average_return = total_result.iter().sum::<f64>() / total_result.len() as f64;
         average_return / max_drawdown
     } else {
         0.0
     };
+    let calmar_ratio = calmar_ratio(pct_result, years);
     let win_loss_ratio = if average_loss > 0.0 { average_gain / average_loss } else { 0.0 };
-    let recovery_factor = if max_drawdown > 0.0 { sum_total_net_profits / max_drawdown } else { 0.0 };
+    // Recovery factor = net profit (gains less losses) / dollar max drawdown
+    let recovery_factor = if max_drawdown > 0.0 {
+        (sum_total_net_profits - sum_total_net_losses) / max_drawdown
+    } else {
+        0.0
+    };
     let profit_per_trade = if trades > 0 { sum_total_net_profits / trades as f64 } else { 0.0 };
+    let (max_consecutive_wins, max_consecutive_losses) = consecutive_streaks(total_result);
+    // Same compounding as PortfolioAccounting::calculate_cagr, on per-trade returns
+    let cagr = annualized_return(pct_result, years) * 100.0;
+    let annualized_return = simple_annualized_return(pct_result, years);
 
     Backtest {
         ticker,
@@ -909,6 +2198,7 @@ fn compute_metrics(
         strategy,
         expectancy,
         profit_factor,
+        profit_factor_capped,
         hit_ratio,
         realized_risk_reward,
         avg_gain: average_gain,
@@ -917,107 +2207,1059 @@ fn compute_metrics(
         max_loss,
         sharpe_ratio,
         sortino_ratio,
+        sharpe_tstat,
         max_drawdown,
         calmar_ratio,
+        profit_to_dd,
         win_loss_ratio,
         recovery_factor,
         profit_per_trade,
+        max_consecutive_wins,
+        max_consecutive_losses,
+        cagr,
+        annualized_return,
+        avg_gain_atr: 0.0,
+        avg_loss_atr: 0.0,
+        information_ratio: 0.0,
+        beta_to_benchmark: 0.0,
+        avg_rolling_beta: 0.0,
+        beta_adjusted_return: 0.0,
+        expectancy_per_day: 0.0,
+        expectancy_r: 0.0,
+        r_multiple_std: 0.0,
+        buy_and_hold_return: 0.0,
+        dca_return: 0.0,
         buys,
         sells,
         trades,
+        signal_exits: 0,
+        stop_loss_exits: 0,
+        end_of_data_exits: 0,
+        holding_period_histogram: Vec::new(),
         date,
         buy,
         sell,
     }
 }
 
-pub fn backtest_performance(
-    df: DataFrame,
-    side: BuySell,
-    strategy: &str,
-) -> Result<(Backtest, Backtest, Vec<Decision>), Box<dyn StdError>> {
-    let df = df.clone();
-    let len = df.height();
+/// Metrics of a bare return series, see `compute_metrics`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetricsSummary {
+    pub periods: usize,
+    /// Compounded return over the whole series, as a fraction
+    pub total_return: f64,
+    /// Compounded return per year, as a fraction
+    pub annualized_return: f64,
+    /// Annualized sample standard deviation of the returns
+    pub volatility: f64,
+    pub sharpe_ratio: f64,
+    pub sortino_ratio: f64,
+    /// Deepest decline of the compounded equity curve, as a fraction of its peak
+    pub max_drawdown: f64,
+    pub calmar_ratio: f64,
+    pub profit_factor: f64,
+    /// Winning periods as a percentage of the non-zero periods
+    pub win_rate: f64,
+    pub avg_gain: f64,
+    pub avg_loss: f64,
+    pub expectancy: f64,
+    pub max_consecutive_wins: i32,
+    pub max_consecutive_losses: i32,
+}
 
-    let mut long_result = vec![0.0; len];
-    let mut short_result = vec![0.0; len];
-    let mut long_result_se = vec![0.0; len];
-    let mut short_result_se = vec![0.0; len];
+/// The crate's metrics for any series of fractional returns (e.g. a model's daily returns),
+/// without a price frame or signals. `annualization` is the number of periods per year
+/// (252 for trading days, 365 for crypto); the series spans `len / annualization` years.
+/// Zero returns count as flat periods, not trades, for the win rate and averages.
+pub fn compute_metrics(returns: &[f64], annualization: f64) -> MetricsSummary {
+    let years = if annualization > 0.0 { returns.len() as f64 / annualization } else { 0.0 };
+    let gains: Vec<f64> = returns.iter().cloned().filter(|&r| r > 0.0).collect();
+    let losses: Vec<f64> = returns.iter().map(|r| -r).filter(|&r| r > 0.0).collect();
+    let mean = |xs: &[f64]| if xs.is_empty() { 0.0 } else { xs.iter().sum::<f64>() / xs.len() as f64 };
+    let (avg_gain, avg_loss) = (mean(&gains), mean(&losses));
+    let decided = gains.len() + losses.len();
+    let win_fraction = if decided > 0 { gains.len() as f64 / decided as f64 } else { 0.0 };
+    let volatility = if returns.len() > 1 {
+        let mu = mean(returns);
+        let variance = returns.iter().map(|r| (r - mu).powi(2)).sum::<f64>() / (returns.len() as f64 - 1.0);
+        variance.sqrt() * annualization.sqrt()
+    } else {
+        0.0
+    };
+    let (max_consecutive_wins, max_consecutive_losses) = consecutive_streaks(returns);
+
+    MetricsSummary {
+        periods: returns.len(),
+        total_return: returns.iter().map(|r| 1.0 + r).product::<f64>() - 1.0,
+        annualized_return: annualized_return(returns, years),
+        volatility,
+        sharpe_ratio: sharpe_ratio(returns, annualization),
+        sortino_ratio: sortino_ratio(returns, annualization),
+        max_drawdown: max_drawdown_pct(returns),
+        calmar_ratio: calmar_ratio(returns, years),
+        profit_factor: profit_factor(gains.iter().sum(), losses.iter().sum(), PROFIT_FACTOR_CAP).0,
+        win_rate: win_fraction * 100.0,
+        avg_gain,
+        avg_loss,
+        expectancy: avg_gain * win_fraction - avg_loss * (1.0 - win_fraction),
+        max_consecutive_wins,
+        max_consecutive_losses,
+    }
+}
 
-    let open = df.column("Open").unwrap().f64().unwrap();
+/// Longest holding period (in bars) for a signal-to-signal trade
+pub const MAX_HOLDING_BARS: usize = 1000;
+
+/// For each bar, the index of the next later bar carrying a buy or sell signal
+fn next_signal_indices(side: &BuySell) -> Vec<Option<usize>> {
+    let len = side.buy.len();
+    let mut next = vec![None; len];
+    let mut upcoming = None;
+    for i in (0..len).rev() {
+        next[i] = upcoming;
+        if side.buy[i] == 1 || side.sell[i] == -1 {
+            upcoming = Some(i);
+        }
+    }
+    next
+}
 
-    // Approach 1: buy OR sell signal closes the position (variable holding period)
-    for i in 0..len {
+/// (entry, exit) bar index pairs of a set of trades
+pub type TradeIndices = Vec<(usize, usize)>;
+
+/// Approach 1 trades: every signal bar opens a position that is closed by the next
+/// bar with any signal, within MAX_HOLDING_BARS. Returns (entry, exit) index pairs
+/// for longs and shorts.
+pub fn signal_exit_trades(side: &BuySell) -> (TradeIndices, TradeIndices) {
+    let next = next_signal_indices(side);
+    let mut long_trades = Vec::new();
+    let mut short_trades = Vec::new();
+    for (i, exit) in next.iter().enumerate() {
+        let a = match exit {
+            Some(a) if *a < i + MAX_HOLDING_BARS => *a,
+            _ => continue,
+        };
         if side.buy[i] == 1 {
-            for a in i + 1..cmp::min(i + 1000, len) {
-                if side.buy[a] == 1 || side.sell[a] == -1 {
-                    long_result[a] = open.get(a).unwrap_or(0.0) - open.get(i).unwrap_or(0.0);
-                    break;
+            long_trades.push((i, a));
+        }
+        if side.sell[i] == -1 {
+            short_trades.push((i, a));
+        }
+    }
+    (long_trades, short_trades)
+}
+
+/// Options for `backtest_performance_with`; the default matches `backtest_performance`
+#[derive(Debug, Clone, Default)]
+pub struct PerformanceConfig {
+    /// Close any position still open on the last bar at that bar's open and count it
+    /// as a trade (like `backtest_portfolio` closing remaining positions at the end)
+    pub mark_open_at_end: bool,
+    /// Base Sharpe/Sortino on per-trade log returns `ln(exit/entry)` instead of the
+    /// dollar price differences (better behaved for volatile assets such as crypto)
+    pub use_log_returns: bool,
+    /// Annualization for Sharpe/Sortino and CAGR; `None` is `AnnualizationBasis::Calendar`
+    pub annualization: Option<AnnualizationBasis>,
+    /// ATR lookback for `avg_gain_atr`/`avg_loss_atr`, which express each trade's P&L in
+    /// multiples of the ATR on its entry bar; `None` leaves them at 0
+    pub atr_lookback: Option<usize>,
+    /// Daily open-to-open benchmark returns by date (see `load_benchmark_returns`) for
+    /// `information_ratio` and `beta_to_benchmark`; only dates present in both series count
+    pub benchmark: Option<Arc<BTreeMap<chrono::NaiveDate, f64>>>,
+    /// Days of the trailing window for `avg_rolling_beta` and `beta_adjusted_return`
+    /// (e.g. BTC returns as the market factor for altcoins); `None` leaves them at 0
+    pub rolling_beta_window: Option<usize>,
+    /// Close a long once a bar trades `stop_loss_pct` below its entry price (a short,
+    /// above it), checked from the bar after entry. The fill is the stop price, or the
+    /// open when the bar gaps through it; a signal exit on the same bar's open comes first.
+    pub stop_loss_pct: Option<f64>,
+    /// Price that trades fill at on their fill bar (the bar's open by default)
+    pub price_source: PriceSource,
+    /// Ceiling on `profit_factor` (`PROFIT_FACTOR_CAP` when `None`); `f64::INFINITY`
+    /// reports the true ratio, and infinity for a strategy without losses
+    pub profit_factor_cap: Option<f64>,
+    /// Require the market to confirm a signal before it is acted on
+    pub confirmation: Confirmation,
+    /// Leave out this fraction of the trades at each end (best and worst by P&L, at least
+    /// one each when positive) before computing metrics, to see how much of the edge rests
+    /// on outliers; see `trim_trades`
+    pub trim_fraction: f64,
+    /// How the reported `buy`/`sell` of the last bar are derived from the signals
+    pub final_signal: FinalSignal,
+    /// Also report the dollar-cost-averaged buy-and-hold return next to the lump-sum one
+    pub dca_benchmark: bool,
+    /// Measure the buy-and-hold benchmarks over the whole frame rather than only the
+    /// bars from the strategy's first signal to its last
+    pub full_window_benchmark: bool,
+    /// Cost of every fill (commission plus slippage) as a fraction of its price, charged
+    /// on the trade's exit bar; see `transaction_costs`
+    pub transaction_cost: f64,
+    /// Lower bounds (days, ascending) of the `holding_period_histogram` buckets; empty
+    /// uses `HOLDING_PERIOD_BUCKETS`
+    pub holding_period_buckets: Vec<i64>,
+    /// After a losing trade, ignore entry signals on its exit bar and the next
+    /// `post_loss_cooldown` bars (0 = off); see `apply_post_loss_cooldown`
+    pub post_loss_cooldown: usize,
+}
+
+/// What the reported `buy`/`sell` of a backtest say about its last bar
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FinalSignal {
+    /// The last bar's raw signals
+    #[default]
+    Raw,
+    /// Only a signal that changes the position of `position_states`: a buy while
+    /// already long (or a sell while already short) is reported as a hold
+    PositionState,
+}
+
+impl FinalSignal {
+    /// Reported `(buy, sell)` for the last bar of `side`
+    pub fn last_bar(self, side: &BuySell) -> (i32, i32) {
+        let buy = side.buy.last().cloned().unwrap_or(0);
+        let sell = side.sell.last().cloned().unwrap_or(0);
+        match self {
+            FinalSignal::Raw => (buy, sell),
+            FinalSignal::PositionState => {
+                let states = position_states(side);
+                let (last, prev) = match states.len() {
+                    0 => return (0, 0),
+                    n => (states[n - 1], if n > 1 { states[n - 2] } else { 0 }),
+                };
+                if last == prev {
+                    (0, 0)
+                } else {
+                    (if last == 1 { buy } else { 0 }, if last == -1 { sell } else { 0 })
                 }
             }
         }
     }
-    for i in 0..len {
-        if side.sell[i] == -1 {
-            for a in i + 1..cmp::min(i + 1000, len) {
-                if side.buy[a] == 1 || side.sell[a] == -1 {
-                    short_result[a] = open.get(i).unwrap_or(0.0) - open.get(a).unwrap_or(0.0);
-                    break;
+}
+
+/// Confirmation filter applied to the buy signals before any trade is booked
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Confirmation {
+    /// Act on every signal on its own bar
+    #[default]
+    None,
+    /// Same as `NBars(1)`
+    NextBarClose,
+    /// Act on a buy only if each of the next n bars closes above the signal bar's close;
+    /// the buy moves to the bar after the n-th, whose open is the first price known after
+    /// the confirming close. Unconfirmed buys, and confirmed ones with no bar left to fill
+    /// on, are dropped. Sells are left as they are so exits keep their timing.
+    NBars(usize),
+}
+
+impl Confirmation {
+    /// `side` with unconfirmed buys dropped and confirmed ones moved to the bar after the
+    /// one that confirms them
+    pub fn apply(&self, df: &DataFrame, side: &BuySell) -> Result<BuySell, Box<dyn StdError>> {
+        let n = match *self {
+            Self::None => return Ok(BuySell { buy: side.buy.clone(), sell: side.sell.clone() }),
+            Self::NextBarClose => 1,
+            Self::NBars(n) => n,
+        };
+        let close: Vec<Option<f64>> = df.column("Close")?.f64()?.into_iter().collect();
+        let len = side.buy.len();
+        let mut confirmed = BuySell { buy: vec![0; len], sell: side.sell.clone() };
+        let holds = |i: usize| {
+            i + n + 1 < len
+                && (i + 1..=i + n).all(|j| matches!((close[i], close[j]), (Some(signal), Some(later)) if later > signal))
+        };
+        for i in 0..len {
+            if side.buy[i] == 1 && holds(i) {
+                confirmed.buy[i + n + 1] = 1;
+            }
+        }
+        Ok(confirmed)
+    }
+}
+
+/// Fill price of a trade on its fill bar. Fills still need a valid open on that bar.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum PriceSource {
+    #[default]
+    Open,
+    Close,
+    /// (High + Low + Close) / 3
+    Typical,
+    /// Open and close blended by the given weights (normalized by their sum)
+    Weighted { open: f64, close: f64 },
+}
+
+impl PriceSource {
+    /// Fill price of every bar; `None` where the needed fields are missing or not positive
+    fn fill_prices(&self, df: &DataFrame) -> Result<Vec<Option<f64>>, Box<dyn StdError>> {
+        let field = |name: &str| -> Result<Vec<Option<f64>>, Box<dyn StdError>> {
+            Ok(df.column(name)?.f64()?.into_iter().collect())
+        };
+        let prices: Vec<Option<f64>> = match *self {
+            Self::Open => field("Open")?,
+            Self::Close => field("Close")?,
+            Self::Typical => {
+                let (high, low, close) = (field("High")?, field("Low")?, field("Close")?);
+                (0..df.height())
+                    .map(|i| Some((high[i]? + low[i]? + close[i]?) / 3.0))
+                    .collect()
+            }
+            Self::Weighted { open: w_open, close: w_close } => {
+                let (open, close) = (field("Open")?, field("Close")?);
+                (0..df.height())
+                    .map(|i| Some((open[i]? * w_open + close[i]? * w_close) / (w_open + w_close)))
+                    .collect()
+            }
+        };
+        Ok(prices.into_iter().map(|p| p.filter(|p| p.is_finite() && *p > 0.0)).collect())
+    }
+}
+
+/// Daily open-to-open returns of a benchmark file with `Date` and `Open` columns (e.g. SPY
+/// or BTC prices), each keyed by the date of the open it ends on. This is the basis of the
+/// strategy's daily returns (see `position_returns`), so both cover the same period.
+pub fn load_benchmark_returns(path: &str) -> Result<BTreeMap<chrono::NaiveDate, f64>, Box<dyn StdError>> {
+    let df = CsvReadOptions::default()
+        .with_has_header(true)
+        .map_parse_options(|o| o.with_try_parse_dates(true))
+        .try_into_reader_with_file_path(Some(path.into()))?
+        .finish()?
+        .lazy()
+        .select([col("Date").cast(DataType::Date), col("Open").cast(DataType::Float64)])
+        .sort(["Date"], Default::default())
+        .collect()?;
+    let dates = frame_dates(&df);
+    let opens = df.column("Open")?.f64()?;
+    let mut returns = BTreeMap::new();
+    for (i, date) in dates.iter().enumerate().skip(1) {
+        if let (Some(prev), Some(open)) = (opens.get(i - 1), opens.get(i)) {
+            if prev > 0.0 {
+                returns.insert(*date, open / prev - 1.0);
+            }
+        }
+    }
+    Ok(returns)
+}
+
+/// (information ratio, beta) of the strategy's daily returns against the benchmark over
+/// the dates both have; zeros when fewer than two dates overlap or a series is flat
+fn benchmark_stats(
+    strategy: &[(chrono::NaiveDate, f64)],
+    benchmark: &BTreeMap<chrono::NaiveDate, f64>,
+    periods_per_year: f64,
+) -> (f64, f64) {
+    let (ours, theirs): (Vec<f64>, Vec<f64>) = strategy
+        .iter()
+        .filter_map(|(date, r)| benchmark.get(date).map(|b| (*r, *b)))
+        .unzip();
+    let n = ours.len() as f64;
+    if n < 2.0 {
+        return (0.0, 0.0);
+    }
+    let mean = |x: &[f64]| x.iter().sum::<f64>() / n;
+    let (mean_ours, mean_theirs) = (mean(&ours), mean(&theirs));
+    let covariance = ours.iter().zip(&theirs).map(|(a, b)| (a - mean_ours) * (b - mean_theirs)).sum::<f64>() / (n - 1.0);
+    let variance = theirs.iter().map(|b| (b - mean_theirs).powi(2)).sum::<f64>() / (n - 1.0);
+    let beta = if variance > 0.0 { covariance / variance } else { 0.0 };
+
+    let active: Vec<f64> = ours.iter().zip(&theirs).map(|(a, b)| a - b).collect();
+    let mean_active = mean(&active);
+    let tracking_error = (active.iter().map(|a| (a - mean_active).powi(2)).sum::<f64>() / (n - 1.0)).sqrt();
+    let information_ratio = if tracking_error > 1e-12 {
+        mean_active / tracking_error * periods_per_year.sqrt()
+    } else {
+        0.0
+    };
+    (information_ratio, beta)
+}
+
+/// Rolling beta of (strategy, factor) daily return pairs. Each day from `window` on is
+/// hedged with the beta of the `window` days before it (no look-ahead), so the hedged
+/// return is `r - beta * f`. Returns the mean beta and the summed hedged returns in
+/// percent; (0, 0) when there are no more than `window` (at least 2) days.
+pub fn rolling_beta(pairs: &[(f64, f64)], window: usize) -> (f64, f64) {
+    let beta = |days: &[(f64, f64)]| {
+        let n = days.len() as f64;
+        let (mean_r, mean_f) = (days.iter().map(|d| d.0).sum::<f64>() / n, days.iter().map(|d| d.1).sum::<f64>() / n);
+        let covariance: f64 = days.iter().map(|(r, f)| (r - mean_r) * (f - mean_f)).sum();
+        let variance: f64 = days.iter().map(|(_, f)| (f - mean_f).powi(2)).sum();
+        if variance > 0.0 { covariance / variance } else { 0.0 }
+    };
+    let window = window.max(2);
+    let hedged: Vec<(f64, f64)> = (window..pairs.len())
+        .map(|t| {
+            let b = beta(&pairs[t - window..t]);
+            (b, pairs[t].0 - b * pairs[t].1)
+        })
+        .collect();
+    if hedged.is_empty() {
+        return (0.0, 0.0);
+    }
+    let avg_beta = hedged.iter().map(|h| h.0).sum::<f64>() / hedged.len() as f64;
+    (avg_beta, hedged.iter().map(|h| h.1).sum::<f64>() * 100.0)
+}
+
+/// Index of the last signal bar when no later signal closes it within MAX_HOLDING_BARS
+/// of the final bar (the approach 1 trade that is still open at the end of the data)
+fn open_signal_at_end(side: &BuySell) -> Option<usize> {
+    let last = side.buy.len().checked_sub(1)?;
+    let i = (0..side.buy.len()).rev().find(|&i| side.buy[i] == 1 || side.sell[i] == -1)?;
+    (i < last && last < i + MAX_HOLDING_BARS).then_some(i)
+}
+
+/// An approach 2 round trip: (long, entry bar, exit bar, entry price, exit price, reason)
+type RoundTrip = (bool, usize, usize, f64, f64, ExitReason);
+
+/// Approach 2 round trips in both directions: `buy[i]` opens a long and `sell[i]` a short
+/// when that side is flat, and the opposing signal closes it, both at `fills[i]`. A signal
+/// on a bar without a fill price carries forward to the next bar that has one.
+/// `stop(long, i, entry_price)` is the fill if a position held since an earlier bar is
+/// stopped out on bar `i`. After a losing trade, entries in either direction are dropped
+/// through `cooldown` bars past its exit. Returns the closed round trips and the (entry
+/// bar, entry price) of a long and of a short still open at the end.
+fn opposing_signal_trades<S>(
+    fills: &[Option<f64>],
+    buy: &[bool],
+    sell: &[bool],
+    stop: S,
+    cooldown: usize,
+) -> (Vec<RoundTrip>, [Option<(usize, f64)>; 2])
+where
+    S: Fn(bool, usize, f64) -> Option<f64>,
+{
+    let mut closed: Vec<RoundTrip> = Vec::new();
+    // Long then short: the open position and whether a signal is waiting for a fill
+    let mut position: [Option<(usize, f64)>; 2] = [None, None];
+    let mut pending = [false; 2];
+    // Last bar of the cool-down after the most recent losing trade
+    let mut blocked_until = None;
+    for i in 0..fills.len() {
+        let held = position.map(|p| p.is_some());
+        let closed_before = closed.len();
+        // Both directions' exits come first, so a loss on this bar also blocks an entry
+        // on it in the other direction
+        for (d, long) in [(0, true), (1, false)] {
+            let Some((bar, entry)) = position[d] else { continue };
+            pending[d] |= if long { sell[i] } else { buy[i] };
+            let exit = match (pending[d], fills[i]) {
+                (true, Some(price)) => Some((price, ExitReason::OppositeSignal)),
+                _ if bar < i => stop(long, i, entry).map(|price| (price, ExitReason::StopLoss)),
+                _ => None,
+            };
+            if let Some((price, reason)) = exit {
+                closed.push((long, bar, i, entry, price, reason));
+                position[d] = None;
+                pending[d] = false;
+            }
+        }
+        if cooldown > 0 {
+            for &(long, _, exit_bar, entry, price, _) in &closed[closed_before..] {
+                if if long { price < entry } else { price > entry } {
+                    blocked_until = Some(exit_bar + cooldown);
                 }
             }
         }
+        for (d, long) in [(0, true), (1, false)] {
+            if held[d] {
+                continue;
+            }
+            pending[d] |= if long { buy[i] } else { sell[i] };
+            if blocked_until.is_some_and(|last| i <= last) {
+                pending[d] = false;
+            }
+            if let (true, Some(price)) = (pending[d], fills[i]) {
+                position[d] = Some((i, price));
+                pending[d] = false;
+            }
+        }
     }
+    (closed, position)
+}
 
-    // Approach 2 (_se = sell exit): only an opposing signal closes the position.
-    // A subsequent buy while already long is ignored (you're already in).
-    let mut in_long = false;
-    let mut long_entry_price = 0.0;
-    for i in 0..len {
-        if !in_long && side.buy[i] == 1 {
-            in_long = true;
-            long_entry_price = open.get(i).unwrap_or(0.0);
-        } else if in_long && side.sell[i] == -1 {
-            long_result_se[i] = open.get(i).unwrap_or(0.0) - long_entry_price;
-            in_long = false;
+/// Drop the approach 1 trades entered while cooling down from a loss: on the exit bar of a
+/// losing trade or within `cooldown` bars after it. Trades are taken in entry order, so a
+/// dropped trade's own outcome doesn't extend the cool-down.
+pub fn apply_post_loss_cooldown(trades: Vec<Trade>, cooldown: usize) -> Vec<Trade> {
+    if cooldown == 0 {
+        return trades;
+    }
+    let mut order: Vec<usize> = (0..trades.len()).collect();
+    order.sort_by_key(|&k| (trades[k].entry_index, trades[k].exit_index));
+    let mut keep = vec![false; trades.len()];
+    let mut blocked_until: Option<usize> = None;
+    for k in order {
+        let t = &trades[k];
+        if blocked_until.is_some_and(|last| t.entry_index <= last) {
+            continue;
+        }
+        keep[k] = true;
+        if t.pnl() < 0.0 {
+            let until = t.exit_index + cooldown;
+            blocked_until = Some(blocked_until.map_or(until, |b| b.max(until)));
         }
     }
-    let mut in_short = false;
-    let mut short_entry_price = 0.0;
-    for i in 0..len {
-        if !in_short && side.sell[i] == -1 {
-            in_short = true;
-            short_entry_price = open.get(i).unwrap_or(0.0);
-        } else if in_short && side.buy[i] == 1 {
-            short_result_se[i] = short_entry_price - open.get(i).unwrap_or(0.0);
-            in_short = false;
+    trades.into_iter().zip(keep).filter_map(|(t, keep)| keep.then_some(t)).collect()
+}
+
+pub fn backtest_performance(
+    df: DataFrame,
+    side: BuySell,
+    strategy: &str,
+) -> Result<(Backtest, Backtest, Vec<Decision>), Box<dyn StdError>> {
+    backtest_performance_with(df, side, strategy, &PerformanceConfig::default())
+}
+
+/// Why a trade in the ledger was closed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitReason {
+    /// Approach 1: the next buy or sell signal
+    NextSignal,
+    /// Approach 2 (`_se`): the first opposing signal
+    OppositeSignal,
+    /// `PerformanceConfig::stop_loss_pct` was hit
+    StopLoss,
+    /// Still open on the last bar (`PerformanceConfig::mark_open_at_end`)
+    EndOfData,
+}
+
+impl ExitReason {
+    /// Name stored with exported trades
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExitReason::NextSignal => "next_signal",
+            ExitReason::OppositeSignal => "opposite_signal",
+            ExitReason::StopLoss => "stop_loss",
+            ExitReason::EndOfData => "end_of_data",
         }
     }
+}
+
+/// One closed round trip from the trade ledger
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trade {
+    /// true for longs, false for shorts
+    pub long: bool,
+    /// Bar of the entry fill (the signal bar, or the next bar with a valid open)
+    pub entry_index: usize,
+    /// Bar of the exit fill; the trade's P&L is booked on this bar
+    pub exit_index: usize,
+    pub entry_date: Option<chrono::NaiveDate>,
+    pub exit_date: Option<chrono::NaiveDate>,
+    pub entry_price: f64,
+    pub exit_price: f64,
+    pub exit_reason: ExitReason,
+    /// Entry-to-stop distance per share under `PerformanceConfig::stop_loss_pct`;
+    /// None without a stop
+    pub initial_risk: Option<f64>,
+}
+
+impl Trade {
+    /// Dollar P&L per share
+    pub fn pnl(&self) -> f64 {
+        self.signed(ReturnMethod::PointDiff)
+    }
+
+    /// P&L as a fraction of the entry price
+    pub fn pct_return(&self) -> f64 {
+        if self.entry_price > 0.0 { self.signed(ReturnMethod::Simple) } else { 0.0 }
+    }
+
+    /// ln(exit/entry) for longs, ln(entry/exit) for shorts
+    pub fn log_return(&self) -> f64 {
+        self.signed(ReturnMethod::Log)
+    }
+
+    /// Entry-to-exit return under `method`, negated for shorts
+    fn signed(&self, method: ReturnMethod) -> f64 {
+        let r = method.between(self.entry_price, self.exit_price);
+        if self.long { r } else { -r }
+    }
+
+    /// P&L in multiples of the initial risk; None without a stop
+    pub fn r_multiple(&self) -> Option<f64> {
+        self.initial_risk.filter(|&r| r > 0.0).map(|r| self.pnl() / r)
+    }
+
+    /// Calendar days from entry to exit, or bars when the frame has no dates
+    pub fn holding_days(&self) -> i64 {
+        match (self.entry_date, self.exit_date) {
+            (Some(entry), Some(exit)) => (exit - entry).num_days(),
+            _ => (self.exit_index - self.entry_index) as i64,
+        }
+    }
+}
+
+/// One row per trade of `ticker`/`strategy` for `clickhouse::insert_trades_dataframe`:
+/// ticker, strategy, direction ("long"/"short"), entry_date, exit_date, entry_price,
+/// exit_price, pnl and exit_reason
+pub fn trades_dataframe(ticker: &str, strategy: &str, trades: &[Trade]) -> Result<DataFrame, PolarsError> {
+    let epoch = chrono::NaiveDate::from_ymd_opt(1970, 1, 1).unwrap();
+    let days = |date: Option<chrono::NaiveDate>| date.map(|d| (d - epoch).num_days() as i32);
+    let n = trades.len();
+    let date_column = |name: &str, values: Vec<Option<i32>>| {
+        Series::new(name.into(), values).cast(&DataType::Date).map(Column::from)
+    };
+    DataFrame::new(vec![
+        Column::new("ticker".into(), vec![ticker; n]),
+        Column::new("strategy".into(), vec![strategy; n]),
+        Column::new("direction".into(), trades.iter().map(|t| if t.long { "long" } else { "short" }).collect::<Vec<_>>()),
+        date_column("entry_date", trades.iter().map(|t| days(t.entry_date)).collect())?,
+        date_column("exit_date", trades.iter().map(|t| days(t.exit_date)).collect())?,
+        Column::new("entry_price".into(), trades.iter().map(|t| t.entry_price).collect::<Vec<_>>()),
+        Column::new("exit_price".into(), trades.iter().map(|t| t.exit_price).collect::<Vec<_>>()),
+        Column::new("pnl".into(), trades.iter().map(|t| t.pnl()).collect::<Vec<_>>()),
+        Column::new("exit_reason".into(), trades.iter().map(|t| t.exit_reason.as_str()).collect::<Vec<_>>()),
+    ])
+}
+
+/// Dollar transaction costs per bar of `len`: every trade pays `cost` times its entry
+/// price and times its exit price, both booked on its exit bar next to its P&L. Charging
+/// the entry on its own bar would show up as an extra losing bar, which `backtest_metrics`
+/// counts as a trade. A flip still pays for both fills: the closed trade's exit now and
+/// the new trade's entry when that one closes. A trade only marked closed on the last
+/// bar (`ExitReason::EndOfData`) never filled an exit, so it pays for its entry alone.
+pub fn transaction_costs(trades: &[Trade], cost: f64, len: usize) -> Vec<f64> {
+    let mut costs = vec![0.0; len];
+    for t in trades {
+        costs[t.exit_index] += cost * (t.entry_price + exit_fill_price(t));
+    }
+    costs
+}
 
-    let total_result: Vec<f64> = long_result
+/// Price of the exit fill a trade pays a transaction cost on; zero for one still open at
+/// the end of the data
+fn exit_fill_price(t: &Trade) -> f64 {
+    if t.exit_reason == ExitReason::EndOfData { 0.0 } else { t.exit_price }
+}
+
+/// Default `holding_period_histogram` buckets: same day, 1, 2-4, 5-9, 10-19, 20-49,
+/// 50-99 and 100+ days
+pub const HOLDING_PERIOD_BUCKETS: &[i64] = &[0, 1, 2, 5, 10, 20, 50, 100];
+
+/// Trade counts by holding period (`Trade::holding_days`), one (lower bound, count) per
+/// bucket of `buckets` (ascending lower bounds, every bucket listed). A bucket runs up
+/// to the next bound; holds shorter than the first bound count in the first bucket.
+pub fn holding_period_histogram(trades: &[Trade], buckets: &[i64]) -> Vec<(i64, usize)> {
+    let mut counts: Vec<(i64, usize)> = buckets.iter().map(|&b| (b, 0)).collect();
+    for t in trades {
+        let days = t.holding_days();
+        let bucket = counts.iter().rposition(|(b, _)| *b <= days).unwrap_or(0);
+        if let Some((_, n)) = counts.get_mut(bucket) {
+            *n += 1;
+        }
+    }
+    counts
+}
+
+/// Trade counts per whole-R bucket of the R-multiple, keyed by the bucket's lower
+/// bound (-1 holds stopped-out losers in [-1R, 0R)); trades without a stop are left out
+pub fn r_multiple_distribution(trades: &[Trade]) -> BTreeMap<i64, usize> {
+    let mut buckets = BTreeMap::new();
+    for r in trades.iter().filter_map(Trade::r_multiple) {
+        *buckets.entry(r.floor() as i64).or_insert(0) += 1;
+    }
+    buckets
+}
+
+/// Keep only the trades matching `predicate`, e.g. to measure a strategy on Monday
+/// entries only before recomputing metrics with `metrics_from_trades`
+pub fn filter_trades<F>(trades: &[Trade], predicate: F) -> Vec<Trade>
+where
+    F: Fn(&Trade) -> bool,
+{
+    trades.iter().filter(|t| predicate(t)).cloned().collect()
+}
+
+/// Drop the best and the worst `fraction` of the trades by P&L (rounded up, so any
+/// positive fraction drops at least one of each), keeping the rest in ledger order
+pub fn trim_trades(trades: &[Trade], fraction: f64) -> Vec<Trade> {
+    if fraction <= 0.0 {
+        return trades.to_vec();
+    }
+    let n = (trades.len() as f64 * fraction).ceil() as usize;
+    if 2 * n >= trades.len() {
+        return Vec::new();
+    }
+    let mut by_pnl: Vec<usize> = (0..trades.len()).collect();
+    by_pnl.sort_by(|&a, &b| trades[a].pnl().total_cmp(&trades[b].pnl()));
+    let mut kept = by_pnl[n..trades.len() - n].to_vec();
+    kept.sort_unstable();
+    kept.into_iter().map(|i| trades[i].clone()).collect()
+}
+
+/// Net position implied by the signals, stop-and-reverse like approach 2: long (1) from a
+/// buy bar, short (-1) from a sell bar, flat (0) before the first signal or on a bar
+/// with both
+pub fn position_states(side: &BuySell) -> Vec<i32> {
+    let mut state = 0;
+    side.buy
         .iter()
-        .zip(short_result.iter())
-        .map(|(&l, &s)| l + s)
-        .collect();
-    let total_result_se: Vec<f64> = long_result_se
+        .zip(side.sell.iter())
+        .map(|(&buy, &sell)| {
+            match (buy == 1, sell == -1) {
+                (true, false) => state = 1,
+                (false, true) => state = -1,
+                (true, true) => state = 0,
+                (false, false) => {}
+            }
+            state
+        })
+        .collect()
+}
+
+/// Net position implied by decisions where a sell closes a long instead of reversing it:
+/// a buy opens a long (or covers a short), a sell goes flat from a long. With
+/// `allow_shorts` a sell while flat opens a short (-1); otherwise it stays flat.
+pub fn decision_states(side: &BuySell, allow_shorts: bool) -> Vec<i32> {
+    let mut state = 0;
+    side.buy
         .iter()
-        .zip(short_result_se.iter())
-        .map(|(&l, &s)| l + s)
+        .zip(side.sell.iter())
+        .map(|(&buy, &sell)| {
+            match (buy == 1, sell == -1, state) {
+                (true, false, 0) => state = 1,
+                (true, false, -1) => state = 0,
+                (false, true, 1) => state = 0,
+                (false, true, 0) if allow_shorts => state = -1,
+                _ => {}
+            }
+            state
+        })
+        .collect()
+}
+
+/// Return of holding `positions[i]` from open `i` to open `i + 1` (shorts earn the negative
+/// of the asset's return); zero where either open is missing
+pub fn position_returns(opens: &[Option<f64>], positions: &[i32]) -> Vec<f64> {
+    (0..opens.len().saturating_sub(1))
+        .map(|i| match (opens[i], opens[i + 1]) {
+            (Some(open), Some(next)) => positions[i] as f64 * ReturnMethod::Simple.between(open, next),
+            _ => 0.0,
+        })
+        .collect()
+}
+
+/// Pearson correlation; NaN when either series is constant
+fn correlation(x: &[f64], y: &[f64]) -> f64 {
+    let n = x.len().min(y.len()) as f64;
+    if n < 2.0 {
+        return f64::NAN;
+    }
+    let mean_x = x.iter().sum::<f64>() / n;
+    let mean_y = y.iter().sum::<f64>() / n;
+    let (mut cov, mut var_x, mut var_y) = (0.0, 0.0, 0.0);
+    for (a, b) in x.iter().zip(y.iter()) {
+        cov += (a - mean_x) * (b - mean_y);
+        var_x += (a - mean_x).powi(2);
+        var_y += (b - mean_y).powi(2);
+    }
+    if var_x > 0.0 && var_y > 0.0 { cov / (var_x * var_y).sqrt() } else { f64::NAN }
+}
+
+/// Pairwise correlation of the strategies' daily returns. Each strategy holds its
+/// `position_states` from one open to the next; with several tickers the per-ticker
+/// series are pooled. Returns a `strategy` column plus one column per strategy.
+pub fn strategy_correlation(df: &DataFrame, signals: &[Signal]) -> Result<DataFrame, Box<dyn StdError>> {
+    let mut tickers: Vec<String> = df
+        .column("Ticker")?
+        .str()?
+        .into_iter()
+        .flatten()
+        .map(|t| t.to_string())
+        .collect::<HashSet<_>>()
+        .into_iter()
         .collect();
+    tickers.sort();
+
+    let mut returns: Vec<Vec<f64>> = vec![Vec::new(); signals.len()];
+    for ticker in &tickers {
+        let ticker_df = df
+            .clone()
+            .lazy()
+            .filter(col("Ticker").eq(lit(ticker.as_str())))
+            .sort(["Date"], Default::default())
+            .collect()?;
+        let opens: Vec<Option<f64>> = ticker_df
+            .column("Open")?
+            .f64()?
+            .into_iter()
+            .map(|o| o.filter(|&o| o > 0.0))
+            .collect();
+        for (signal, series) in signals.iter().zip(returns.iter_mut()) {
+            let positions = position_states(&(signal.func)(ticker_df.clone(), signal.param));
+            series.extend(position_returns(&opens, &positions));
+        }
+    }
+
+    let mut columns = vec![Column::new(
+        "strategy".into(),
+        signals.iter().map(|s| s.name.clone()).collect::<Vec<_>>(),
+    )];
+    for (j, signal) in signals.iter().enumerate() {
+        let values: Vec<f64> = returns.iter().map(|r| correlation(r, &returns[j])).collect();
+        columns.push(Column::new(signal.name.as_str().into(), values));
+    }
+    Ok(DataFrame::new(columns)?)
+}
+
+/// Closed trades of both approaches, as (approach 1, approach 2 / sell exit), in the
+/// order `backtest_performance_with` books them
+pub fn trade_ledger(
+    df: &DataFrame,
+    side: &BuySell,
+    config: &PerformanceConfig,
+) -> Result<(Vec<Trade>, Vec<Trade>), Box<dyn StdError>> {
+    let len = df.height();
+    let open = df.column("Open")?.f64()?;
+    let dates = df.column("Date")?;
+    let date_at = |i: usize| dates.get(i).ok().and_then(any_value_to_date);
+    let trade = |long: bool, entry_index: usize, exit_index: usize, entry_price: f64, exit_price: f64, exit_reason| Trade {
+        long,
+        entry_index,
+        exit_index,
+        entry_date: date_at(entry_index),
+        exit_date: date_at(exit_index),
+        entry_price,
+        exit_price,
+        exit_reason,
+        initial_risk: config.stop_loss_pct.map(|pct| entry_price * pct),
+    };
+
+    // Bars with a missing or non-positive open can't be traded; fills scan forward
+    // to the next bar with a valid open
+    let valid_open: Vec<Option<f64>> = open.into_iter().map(|o| o.filter(|&o| o > 0.0)).collect();
+    // Fill prices under the configured source, on bars that have a valid open
+    let fills: Vec<Option<f64>> = config
+        .price_source
+        .fill_prices(df)?
+        .into_iter()
+        .zip(&valid_open)
+        .map(|(price, open)| open.and(price))
+        .collect();
+    let next_valid = |idx: usize| (idx..len).find(|&j| fills[j].is_some());
+    let last_valid = (0..len).rev().find(|&j| fills[j].is_some());
+    // Stop fill on bar `i` for a position entered at `entry_price`, if the bar reaches it
+    let (low, high) = match config.stop_loss_pct {
+        Some(_) => (Some(df.column("Low")?.f64()?), Some(df.column("High")?.f64()?)),
+        None => (None, None),
+    };
+    let stop = |long: bool, i: usize, entry_price: f64| -> Option<f64> {
+        let pct = config.stop_loss_pct?;
+        let open = valid_open[i]?;
+        if long {
+            let level = entry_price * (1.0 - pct);
+            if open <= level {
+                Some(open)
+            } else {
+                low.and_then(|l| l.get(i)).filter(|&l| l <= level).map(|_| level)
+            }
+        } else {
+            let level = entry_price * (1.0 + pct);
+            if open >= level {
+                Some(open)
+            } else {
+                high.and_then(|h| h.get(i)).filter(|&h| h >= level).map(|_| level)
+            }
+        }
+    };
+    // (entry, exit) signal bars -> (entry bar, exit bar), dropped if they collapse
+    let fill = |(i, a): (usize, usize)| {
+        let entry = next_valid(i)?;
+        let exit = next_valid(a)?;
+        (entry < exit).then_some((entry, exit))
+    };
+
+    // Approach 1: buy OR sell signal closes the position (variable holding period)
+    let (mut long_trades, mut short_trades) = signal_exit_trades(side);
+    if config.mark_open_at_end {
+        if let (Some(i), Some(last)) = (open_signal_at_end(side), last_valid) {
+            if side.buy[i] == 1 {
+                long_trades.push((i, last));
+            }
+            if side.sell[i] == -1 {
+                short_trades.push((i, last));
+            }
+        }
+    }
+    let end_of_data = last_valid.and_then(|last| open_signal_at_end(side).map(|i| (i, last)));
+    let mut trades = Vec::new();
+    for (long, pairs) in [(true, long_trades), (false, short_trades)] {
+        for (i, a) in pairs {
+            let Some((entry, exit)) = fill((i, a)) else { continue };
+            let entry_price = fills[entry].unwrap();
+            let reason = if config.mark_open_at_end && end_of_data == Some((i, a)) {
+                ExitReason::EndOfData
+            } else {
+                ExitReason::NextSignal
+            };
+            let stopped = (entry + 1..exit).find_map(|j| stop(long, j, entry_price).map(|price| (j, price)));
+            trades.push(match stopped {
+                Some((j, price)) => trade(long, entry, j, entry_price, price, ExitReason::StopLoss),
+                None => trade(long, entry, exit, entry_price, fills[exit].unwrap(), reason),
+            });
+        }
+    }
+    let trades = apply_post_loss_cooldown(trades, config.post_loss_cooldown);
+
+    // Approach 2 (_se = sell exit): only an opposing signal closes the position.
+    // A subsequent buy while already long is ignored (you're already in).
+    let buy_signal: Vec<bool> = side.buy.iter().map(|&b| b == 1).collect();
+    let sell_signal: Vec<bool> = side.sell.iter().map(|&s| s == -1).collect();
+    let (closed, still_open) =
+        opposing_signal_trades(&fills, &buy_signal, &sell_signal, stop, config.post_loss_cooldown);
+    let mut trades_se = Vec::new();
+    for (long, still_open) in [(true, still_open[0]), (false, still_open[1])] {
+        for &(_, entry, exit, entry_price, exit_price, reason) in closed.iter().filter(|t| t.0 == long) {
+            trades_se.push(trade(long, entry, exit, entry_price, exit_price, reason));
+        }
+        // Book a position still open at the last valid open (skipped if entered on that bar)
+        if config.mark_open_at_end {
+            if let (Some((entry, entry_price)), Some(last)) = (still_open, last_valid) {
+                if entry < last {
+                    trades_se.push(trade(long, entry, last, entry_price, fills[last].unwrap(), ExitReason::EndOfData));
+                }
+            }
+        }
+    }
+
+    Ok((trades, trades_se))
+}
+
+/// Backtest metrics for an arbitrary set of trades (e.g. a `filter_trades` subset).
+/// Each trade's P&L is booked on its exit bar; `side` supplies the signal counts.
+pub fn metrics_from_trades(
+    df: &DataFrame,
+    side: &BuySell,
+    strategy: &str,
+    trades: &[Trade],
+    config: &PerformanceConfig,
+) -> Result<Backtest, Box<dyn StdError>> {
+    let trimmed;
+    let trades = if config.trim_fraction > 0.0 {
+        trimmed = trim_trades(trades, config.trim_fraction);
+        &trimmed
+    } else {
+        trades
+    };
+    let len = df.height();
+    let mut total_result = vec![0.0; len];
+    // Fractional return of each closed trade, relative to its entry price
+    let mut pct_result = vec![0.0; len];
+    let mut log_result = vec![0.0; len];
+    for t in trades {
+        total_result[t.exit_index] += t.pnl();
+        pct_result[t.exit_index] += t.pct_return();
+        log_result[t.exit_index] += t.log_return();
+    }
+    if config.transaction_cost > 0.0 {
+        for (total, cost) in total_result.iter_mut().zip(transaction_costs(trades, config.transaction_cost, len)) {
+            *total -= cost;
+        }
+        // The same costs relative to each trade's entry price
+        for t in trades.iter().filter(|t| t.entry_price > 0.0) {
+            let cost = config.transaction_cost * (1.0 + exit_fill_price(t) / t.entry_price);
+            for result in [&mut pct_result, &mut log_result] {
+                result[t.exit_index] -= cost;
+            }
+        }
+    }
 
     let buys = side.buy.iter().sum::<i32>();
     let sells = side.sell.iter().sum::<i32>().abs();
-    let buy = side.buy.get(len - 1).cloned().unwrap_or(0);
-    let sell = side.sell.get(len - 1).cloned().unwrap_or(0);
+    let (buy, sell) = config.final_signal.last_bar(side);
 
-    let ticker1 = df.column("Ticker").unwrap().get(0).unwrap_or("".into()).to_string();
+    let ticker1 = df.column("Ticker")?.get(0).unwrap_or("".into()).to_string();
     let ticker = ticker1.trim_matches('"').to_string();
-    let universe1 = df.column("Universe").unwrap().get(0).unwrap_or("".into()).to_string();
-    let universe = universe1.trim_matches('"').to_string();
-    let date1 = df.column("Date").unwrap().get(len - 1).unwrap_or("".into()).to_string();
+    let universe1 = df.column("Universe")?.get(0).unwrap_or("".into()).to_string();
+    let universe = UniverseConfig::canonical(universe1.trim_matches('"'));
+    let date1 = df.column("Date")?.get(len - 1).unwrap_or("".into()).to_string();
     let date = date1.trim_matches('"').to_string();
 
+    let basis = config.annualization.unwrap_or_default();
+    let dates = frame_dates(df);
+    let risk_returns = if config.use_log_returns { &log_result } else { &total_result };
+    let params = MetricsParams {
+        ticker,
+        universe,
+        strategy: strategy.to_string(),
+        date,
+        buys,
+        sells,
+        buy,
+        sell,
+        years: basis.years(&dates),
+        periods_per_year: basis.periods_per_year(&dates),
+        profit_factor_cap: config.profit_factor_cap.unwrap_or(PROFIT_FACTOR_CAP),
+    };
+    let mut bt = backtest_metrics(&total_result, &pct_result, risk_returns, params);
+    let exits = |reasons: &[ExitReason]| trades.iter().filter(|t| reasons.contains(&t.exit_reason)).count() as i32;
+    bt.signal_exits = exits(&[ExitReason::NextSignal, ExitReason::OppositeSignal]);
+    bt.stop_loss_exits = exits(&[ExitReason::StopLoss]);
+    bt.end_of_data_exits = exits(&[ExitReason::EndOfData]);
+    let buckets = if config.holding_period_buckets.is_empty() { HOLDING_PERIOD_BUCKETS } else { &config.holding_period_buckets[..] };
+    bt.holding_period_histogram = holding_period_histogram(trades, buckets);
+    let holding_days = trades.iter().map(|t| t.holding_days()).sum::<i64>() as f64;
+    if holding_days > 0.0 {
+        bt.expectancy_per_day = bt.expectancy / (holding_days / trades.len() as f64);
+    }
+    let r_multiples: Vec<f64> = trades.iter().filter_map(Trade::r_multiple).collect();
+    if !r_multiples.is_empty() {
+        let n = r_multiples.len() as f64;
+        bt.expectancy_r = r_multiples.iter().sum::<f64>() / n;
+        bt.r_multiple_std = (r_multiples.iter().map(|r| (r - bt.expectancy_r).powi(2)).sum::<f64>() / n).sqrt();
+    }
+
+    if let Some(lookback) = config.atr_lookback {
+        let column = |name: &str| df.column(name).map(|c| c.as_materialized_series().clone());
+        let atr = signals::technical::atr(column("Close")?, column("High")?, column("Low")?, lookback);
+        // Trades entered before the ATR is defined have no volatility unit and are left out
+        let mut atr_result = vec![0.0; len];
+        for t in trades {
+            if let Some(&a) = atr.get(t.entry_index).filter(|a| a.is_finite() && **a > 0.0) {
+                atr_result[t.exit_index] += t.pnl() / a;
+            }
+        }
+        let mean = |values: Vec<f64>| if values.is_empty() { 0.0 } else { values.iter().sum::<f64>() / values.len() as f64 };
+        bt.avg_gain_atr = mean(atr_result.iter().cloned().filter(|&x| x > 0.0).collect());
+        bt.avg_loss_atr = mean(atr_result.iter().map(|x| -x).filter(|&x| x > 0.0).collect());
+    }
+
+    if let Some(benchmark) = &config.benchmark {
+        // Hold each trade's side from its entry open to its exit open; the open-to-open
+        // return is dated on the bar it ends
+        let mut positions = vec![0; len];
+        for t in trades {
+            for p in &mut positions[t.entry_index..t.exit_index] {
+                *p = if t.long { 1 } else { -1 };
+            }
+        }
+        let opens: Vec<Option<f64>> = df.column("Open")?.f64()?.into_iter().map(|o| o.filter(|&o| o > 0.0)).collect();
+        let daily: Vec<(chrono::NaiveDate, f64)> = dates
+            .iter()
+            .skip(1)
+            .cloned()
+            .zip(position_returns(&opens, &positions))
+            .collect();
+        (bt.information_ratio, bt.beta_to_benchmark) =
+            benchmark_stats(&daily, benchmark, basis.periods_per_year(&dates));
+        if let Some(window) = config.rolling_beta_window {
+            let pairs: Vec<(f64, f64)> = daily.iter().filter_map(|(date, r)| benchmark.get(date).map(|b| (*r, *b))).collect();
+            (bt.avg_rolling_beta, bt.beta_adjusted_return) = rolling_beta(&pairs, window);
+        }
+    }
+    Ok(bt)
+}
+
+pub fn backtest_performance_with(
+    df: DataFrame,
+    side: BuySell,
+    strategy: &str,
+    config: &PerformanceConfig,
+) -> Result<(Backtest, Backtest, Vec<Decision>), Box<dyn StdError>> {
+    let side = config.confirmation.apply(&df, &side)?;
+    let (trades, trades_se) = trade_ledger(&df, &side, config)?;
+
     let mut decisions = Vec::new();
-    for i in 0..len {
+    for i in 0..df.height() {
         if side.buy[i] == 1 {
             let date_str = df
                 .column("Date")
@@ -1042,72 +3284,247 @@ pub fn backtest_performance(
         }
     }
 
-    let bt = compute_metrics(
-        &total_result,
-        ticker.clone(), universe.clone(), strategy.to_string(),
-        buys, sells, date.clone(), buy, sell,
-    );
-    let bt_se = compute_metrics(
-        &total_result_se,
-        ticker, universe, format!("{}_se", strategy),
-        buys, sells, date, buy, sell,
-    );
+    let mut bt = metrics_from_trades(&df, &side, strategy, &trades, config)?;
+    let mut bt_se = metrics_from_trades(&df, &side, &format!("{}_se", strategy), &trades_se, config)?;
+
+    // Benchmarks for the bars the strategy was active on, so buy-and-hold isn't credited
+    // with periods it couldn't trade; without signals the whole frame is used, and a
+    // frame without closes leaves them at 0
+    let signal_bars = || (0..df.height()).filter(|&i| side.buy[i] == 1 || side.sell[i] == -1);
+    let window = match (signal_bars().next(), signal_bars().next_back()) {
+        (Some(first), Some(last)) if !config.full_window_benchmark => df.slice(first as i64, last - first + 1),
+        _ => df.clone(),
+    };
+    let lump_sum = buy_and_hold_return(&window, "Close").map(|r| r * 100.0).unwrap_or(0.0);
+    let dca = if config.dca_benchmark {
+        dca_return(&window, "Close").map(|r| r * 100.0).unwrap_or(0.0)
+    } else {
+        0.0
+    };
+    for b in [&mut bt, &mut bt_se] {
+        b.buy_and_hold_return = lump_sum;
+        b.dca_return = dca;
+    }
 
     Ok((bt, bt_se, decisions))
 }
 
-pub fn backtest_performance_sized(
-    df: DataFrame,
-    side: BuySell,
-    strategy: &str,
-    entry_amount: f64,
-    exit_amount: f64,
-) -> Result<(Backtest, Vec<Decision>), Box<dyn StdError>> {
-    let df = df.clone();
+/// What happens to the part of an order that exceeds a bar's volume limit
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UnfilledPolicy {
+    /// Keep working the remainder on the following bars
+    Carry,
+    /// Cancel whatever could not be filled on the signal bar
+    Drop,
+}
+
+/// Position sizing rules for `backtest_performance_sized`
+#[derive(Debug, Clone)]
+pub struct SizingConfig {
+    /// Dollar amount invested on each buy signal
+    pub entry_amount: f64,
+    /// Dollar amount trimmed on each sell signal
+    pub exit_amount: f64,
+    /// Adds are clipped so the position never exceeds this value
+    pub max_position_value: Option<f64>,
+    /// Allow buying while already long (otherwise the buy is ignored)
+    pub pyramiding: bool,
+    /// Maximum fraction of a bar's `Volume` (in shares) that can be traded
+    pub max_participation: Option<f64>,
+    pub unfilled_policy: UnfilledPolicy,
+    /// Starting account value; returns and drawdowns are relative to it
+    pub initial_cash: f64,
+    /// Annualization for Sharpe/Sortino and CAGR; `None` is `AnnualizationBasis::Calendar`
+    pub annualization: Option<AnnualizationBasis>,
+    /// Share quantities are rounded down to this increment; what a buy can't spend
+    /// stays in cash
+    pub round_shares: ShareRounding,
+}
+
+/// Tradable share increments for `SizingConfig::round_shares`
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ShareRounding {
+    /// Fractional shares (e.g. crypto)
+    #[default]
+    None,
+    /// Whole shares
+    Whole,
+    /// Multiples of a lot size, e.g. `Lot(100)` for round lots
+    Lot(u32),
+}
+
+impl ShareRounding {
+    /// `shares` rounded down to a tradable quantity
+    pub fn round(&self, shares: f64) -> f64 {
+        match *self {
+            Self::None => shares,
+            Self::Whole => shares.floor(),
+            Self::Lot(0) => shares.floor(),
+            Self::Lot(n) => (shares / n as f64).floor() * n as f64,
+        }
+    }
+}
+
+impl SizingConfig {
+    pub fn new(entry_amount: f64, exit_amount: f64) -> Self {
+        Self {
+            entry_amount,
+            exit_amount,
+            max_position_value: None,
+            pyramiding: true,
+            max_participation: None,
+            unfilled_policy: UnfilledPolicy::Carry,
+            initial_cash: 100_000.0,
+            annualization: None,
+            round_shares: ShareRounding::None,
+        }
+    }
+}
+
+/// Per-bar account state from a sized backtest
+#[derive(Debug, Clone)]
+pub struct SizedPath {
+    pub cash: Vec<f64>,
+    pub holdings_value: Vec<f64>,
+    pub portfolio_value: Vec<f64>,
+}
+
+/// Simulate the sized account bar by bar starting from `sizing.initial_cash`.
+/// Each buy invests `entry_amount` and each sell trims `exit_amount`.
+/// Without `pyramiding`, a buy while already long is ignored; with it, adds are
+/// clipped so the position never exceeds `max_position_value` (if set). With
+/// `max_participation`, fills per bar are capped at that fraction of `Volume`.
+pub fn simulate_sized(
+    df: &DataFrame,
+    side: &BuySell,
+    sizing: &SizingConfig,
+) -> Result<SizedPath, Box<dyn StdError>> {
     let len = df.height();
 
-    let mut cash = 100_000.0; // Starting cash
+    let mut cash = sizing.initial_cash; // Starting cash
     let mut holdings = 0.0; // Number of shares held
     let mut cash_value = vec![0.0; len];
     let mut holdings_value = vec![0.0; len];
     let mut portfolio_value = vec![0.0; len]; // Portfolio value over time
+    // Shares still waiting to be bought/sold because of the volume limit
+    let mut pending_buy = 0.0;
+    let mut pending_sell = 0.0;
 
     let open = df.column("Open").unwrap().f64().unwrap();
+    let volume = match sizing.max_participation {
+        Some(_) => Some(df.column("Volume")?.f64()?.clone()),
+        None => None,
+    };
+
+    for i in 0..len {
+        let price = open.get(i).unwrap_or(0.0);
+        // Shares that can trade on this bar (unlimited without a participation cap)
+        let mut capacity = match (sizing.max_participation, &volume) {
+            (Some(fraction), Some(volume)) => fraction * volume.get(i).unwrap_or(0.0),
+            _ => f64::INFINITY,
+        };
 
-    for i in 0..len {
-        if side.buy[i] == 1 {
-            // Buy entry_amount worth of the ticker
-            let price = open.get(i).unwrap_or(0.0);
-            if price > 0.0 && cash >= entry_amount {
-                let shares_to_buy = entry_amount / price;
-                holdings += shares_to_buy;
-                cash -= entry_amount;
+        if side.buy[i] == 1 && price > 0.0 {
+            // Buy entry_amount worth of the ticker, subject to pyramiding rules
+            let long = holdings > 0.0 || pending_buy > 0.0;
+            let mut amount = if long && !sizing.pyramiding { 0.0 } else { sizing.entry_amount };
+            if let Some(cap) = sizing.max_position_value {
+                amount = f64::min(amount, f64::max(cap - (holdings + pending_buy) * price, 0.0));
+            }
+            if amount > 0.0 && cash >= amount {
+                pending_buy += sizing.round_shares.round(amount / price);
             }
         }
 
-        if side.sell[i] == -1 {
+        if pending_buy > 0.0 && price > 0.0 {
+            let shares_to_buy = sizing.round_shares.round(pending_buy.min(capacity).min(cash / price));
+            holdings += shares_to_buy;
+            cash -= shares_to_buy * price;
+            capacity -= shares_to_buy;
+            pending_buy -= shares_to_buy;
+        }
+
+        if side.sell[i] == -1 && price > 0.0 && holdings > 0.0 {
             // Sell exit_amount worth of the ticker
-            let price = open.get(i).unwrap_or(0.0);
-            if price > 0.0 && holdings > 0.0 {
-                let shares_to_sell = f64::min(exit_amount / price, holdings);
-                holdings -= shares_to_sell;
-                cash += shares_to_sell * price;
-            }
+            pending_sell = f64::min(pending_sell + sizing.round_shares.round(sizing.exit_amount / price), holdings);
+        }
+        if pending_sell > 0.0 && price > 0.0 {
+            let shares_to_sell = sizing.round_shares.round(pending_sell.min(capacity)).min(holdings);
+            holdings -= shares_to_sell;
+            cash += shares_to_sell * price;
+            pending_sell -= shares_to_sell;
+        }
+        if sizing.unfilled_policy == UnfilledPolicy::Drop {
+            pending_buy = 0.0;
+            pending_sell = 0.0;
         }
 
         // Calculate portfolio value at the end of each day
-        let price = open.get(i).unwrap_or(0.0);
         cash_value[i] = cash;
         holdings_value[i] = holdings * price;
         portfolio_value[i] = cash + (holdings * price);
         if i < 10 {
-            println!(
+            log::trace!(
                 "Portfolio value at {i}: cash:{} holdings:{} port:{} buy:{} sell:{}",
                 cash_value[i], holdings_value[i], portfolio_value[i], side.buy[i], side.sell[i]
             );
         }
     }
 
+    Ok(SizedPath { cash: cash_value, holdings_value, portfolio_value })
+}
+
+/// Run a signal through `PortfolioAccounting` bar by bar for a single-ticker frame.
+/// A buy while flat invests all cash at the bar's open; a sell while long closes the
+/// position at the open. Every bar is then marked to its close and snapshotted.
+pub fn simulate(
+    df: &DataFrame,
+    signal: SignalFunctionWithParam,
+    param: f64,
+    initial_cash: f64,
+) -> Result<PortfolioAccounting, Box<dyn StdError>> {
+    let side = signal(df.clone(), param);
+    let dates = df.column("Date")?;
+    let tickers = df.column("Ticker")?.str()?;
+    let opens = df.column("Open")?.f64()?;
+    let closes = df.column("Close")?.f64()?;
+
+    let mut portfolio = PortfolioAccounting::new(initial_cash);
+    for i in 0..df.height() {
+        let date = any_value_to_date(dates.get(i)?)
+            .ok_or_else(|| format!("Unsupported Date value at row {}", i))?;
+        let ticker = tickers.get(i).unwrap_or_default();
+
+        if let Some(open) = opens.get(i).filter(|&o| o > 0.0) {
+            if side.buy[i] == 1 && !portfolio.has_position(ticker) {
+                let shares = (portfolio.get_cash_balance() / open).floor();
+                if shares > 0.0 {
+                    portfolio.execute_buy(date, ticker, shares, open, 0.0)?;
+                }
+            } else if side.sell[i] == -1 && portfolio.has_position(ticker) {
+                portfolio.execute_sell(date, ticker, open, 0.0)?;
+            }
+        }
+
+        if let Some(close) = closes.get(i) {
+            let prices = HashMap::from([(ticker.to_string(), close)]);
+            portfolio.mark_to_market(date, &prices);
+        }
+        portfolio.take_daily_snapshot(date);
+    }
+    Ok(portfolio)
+}
+
+/// Sized backtest: metrics of the account simulated by `simulate_sized`
+pub fn backtest_performance_sized(
+    df: DataFrame,
+    side: BuySell,
+    strategy: &str,
+    sizing: &SizingConfig,
+) -> Result<(Backtest, Vec<Decision>), Box<dyn StdError>> {
+    let len = df.height();
+    let portfolio_value = simulate_sized(&df, &side, sizing)?.portfolio_value;
+
     // Calculate performance metrics
     let total_result: Vec<f64> = portfolio_value.windows(2).map(|w| w[1] - w[0]).collect();
 
@@ -1120,11 +3537,7 @@ pub fn backtest_performance_sized(
     let total_net_losses: Vec<f64> = total_result.iter().cloned().filter(|&x| x < 0.0).collect();
     let sum_total_net_profits = total_net_profits.iter().sum::<f64>();
     let sum_total_net_losses = total_net_losses.iter().sum::<f64>().abs();
-    let profit_factor = if sum_total_net_losses > 0.0 {
-        f64::min(999.0, sum_total_net_profits / sum_total_net_losses)
-    } else {
-        0.0
-    };
+    let (profit_factor, profit_factor_capped) = profit_factor(sum_total_net_profits, sum_total_net_losses, PROFIT_FACTOR_CAP);
 
     let hit_ratio: f64 = if total_net_losses.len() + total_net_profits.len() > 0 {
         (total_net_profits.len() as f64 / (total_net_losses.len() + total_net_profits.len()) as f64)
@@ -1133,12 +3546,12 @@ pub fn backtest_performance_sized(
         0.0
     };
 
-    let average_gain = if total_net_profits.len() > 0 {
+    let average_gain = if !total_net_profits.is_empty() {
         sum_total_net_profits / total_net_profits.len() as f64
     } else {
         0.0
     };
-    let average_loss = if total_net_losses.len() > 0 {
+    let average_loss = if !total_net_losses.is_empty() {
         sum_total_net_losses / total_net_losses.len() as f64
     } else {
         0.0
@@ -1178,14 +3591,14 @@ pub fn backtest_performance_sized(
         .to_string()
         .trim_matches('"')
         .to_string();
-    let universe = df
-        .column("Universe")
-        .unwrap()
-        .get(0)
-        .unwrap_or("".into())
-        .to_string()
-        .trim_matches('"')
-        .to_string();
+    let universe = UniverseConfig::canonical(
+        df.column("Universe")
+            .unwrap()
+            .get(0)
+            .unwrap_or("".into())
+            .to_string()
+            .trim_matches('"'),
+    );
     let date = df
         .column("Date")
         .unwrap()
@@ -1227,7 +3640,10 @@ pub fn backtest_performance_sized(
         }
     }
 
-    // Additional Metrics
+    // Additional Metrics, annualized per the configured basis
+    let basis = sizing.annualization.unwrap_or_default();
+    let dates = frame_dates(&df);
+    let periods_per_year = basis.periods_per_year(&dates);
     let sharpe_ratio = if percentage_returns.len() > 1 {
         let mean_return = percentage_returns.iter().sum::<f64>() / percentage_returns.len() as f64;
         let std_dev = (percentage_returns
@@ -1236,9 +3652,8 @@ pub fn backtest_performance_sized(
             .sum::<f64>()
             / (percentage_returns.len() as f64 - 1.0))
             .sqrt();
-        // Annualize Sharpe ratio (assuming daily returns, multiply by sqrt(252))
         if std_dev > 0.0 {
-            (mean_return / std_dev) * (252.0_f64).sqrt()
+            (mean_return / std_dev) * periods_per_year.sqrt()
         } else {
             0.0
         }
@@ -1255,9 +3670,8 @@ pub fn backtest_performance_sized(
             .sum::<f64>()
             / percentage_returns.len() as f64)
             .sqrt();
-        // Annualize Sortino ratio (assuming daily returns, multiply by sqrt(252))
         if downside_deviation > 0.0 {
-            (mean_return / downside_deviation) * (252.0_f64).sqrt()
+            (mean_return / downside_deviation) * periods_per_year.sqrt()
         } else {
             0.0
         }
@@ -1265,37 +3679,31 @@ pub fn backtest_performance_sized(
         0.0
     };
 
-    let max_drawdown = {
-        let mut peak = total_result[0];
-        let mut max_dd = 0.0;
-        for &value in &total_result {
-            if value > peak {
-                peak = value;
-            }
-            let drawdown = peak - value;
-            if drawdown > max_dd {
-                max_dd = drawdown;
-            }
-        }
-        max_dd
-    };
+    let (max_drawdown, _, _) = max_drawdown(&total_result);
 
-    // Calmar Ratio = Average Daily Return / Max Drawdown (simplified version without annualization)
-    let calmar_ratio = if max_drawdown > 0.0 && total_result.len() > 0 {
+    // Average daily dollar change over the dollar max drawdown
+    let profit_to_dd = if max_drawdown > 0.0 && !total_result.is_empty() {
         let average_return = total_result.iter().sum::<f64>() / total_result.len() as f64;
         average_return / max_drawdown
     } else {
         0.0
     };
 
+    // Calmar Ratio = Annualized Return / Max Drawdown % of the portfolio value
+    let years = basis.years(&dates);
+    let calmar_ratio = calmar_ratio(&percentage_returns, years);
+    let cagr = annualized_return(&percentage_returns, years) * 100.0;
+    let annualized_return = simple_annualized_return(&percentage_returns, years);
+
     let win_loss_ratio = if average_loss > 0.0 {
         average_gain / average_loss
     } else {
         0.0
     };
 
+    // Recovery factor = net profit (gains less losses) / dollar max drawdown
     let recovery_factor = if max_drawdown > 0.0 {
-        sum_total_net_profits / max_drawdown
+        (sum_total_net_profits - sum_total_net_losses) / max_drawdown
     } else {
         0.0
     };
@@ -1306,6 +3714,9 @@ pub fn backtest_performance_sized(
         0.0
     };
 
+    // Streaks of up/down bars in portfolio value
+    let (max_consecutive_wins, max_consecutive_losses) = consecutive_streaks(&total_result);
+
     Ok((
         Backtest {
             ticker,
@@ -1313,6 +3724,7 @@ pub fn backtest_performance_sized(
             strategy: strategy.to_string(),
             expectancy,
             profit_factor,
+            profit_factor_capped,
             hit_ratio,
             realized_risk_reward,
             avg_gain: average_gain,
@@ -1321,14 +3733,35 @@ pub fn backtest_performance_sized(
             max_loss,
             sharpe_ratio,
             sortino_ratio,
+            sharpe_tstat: sharpe_tstat(&percentage_returns),
             max_drawdown,
             calmar_ratio,
+            profit_to_dd,
             win_loss_ratio,
             recovery_factor,
             profit_per_trade,
+            max_consecutive_wins,
+            max_consecutive_losses,
+            cagr,
+            annualized_return,
+            avg_gain_atr: 0.0,
+            avg_loss_atr: 0.0,
+            information_ratio: 0.0,
+            beta_to_benchmark: 0.0,
+            avg_rolling_beta: 0.0,
+            beta_adjusted_return: 0.0,
+            expectancy_per_day: 0.0,
+            expectancy_r: 0.0,
+            r_multiple_std: 0.0,
+            buy_and_hold_return: 0.0,
+            dca_return: 0.0,
             buys,
             sells,
             trades,
+            signal_exits: 0,
+            stop_loss_exits: 0,
+            end_of_data_exits: 0,
+            holding_period_histogram: Vec::new(),
             date,
             buy: side.buy.last().cloned().unwrap_or(0),
             sell: side.sell.last().cloned().unwrap_or(0),
@@ -1337,6 +3770,40 @@ pub fn backtest_performance_sized(
     ))
 }
 
+/// Identifier of this run (start time and process id), prefixed to every log line so
+/// the output of one run can be grepped together
+pub fn run_id() -> &'static str {
+    static RUN_ID: OnceLock<String> = OnceLock::new();
+    RUN_ID.get_or_init(|| {
+        format!("{}-{}", chrono::Local::now().format("%Y%m%dT%H%M%S"), std::process::id())
+    })
+}
+
+/// Initialize env_logger (-v info, -vv debug, -vvv trace; RUST_LOG overrides) with
+/// lines formatted as `[time run_id LEVEL target] message`
+pub fn init_logging(verbose: u8) {
+    use std::io::Write;
+    let log_level = match verbose {
+        0 => "warn",
+        1 => "info",
+        2 => "debug",
+        _ => "trace",
+    };
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(log_level))
+        .format(|buf, record| {
+            writeln!(
+                buf,
+                "[{} {} {:<5} {}] {}",
+                buf.timestamp_seconds(),
+                run_id(),
+                record.level(),
+                record.target(),
+                record.args()
+            )
+        })
+        .init();
+}
+
 pub fn showbt(bt: Backtest) -> Result<(), Box<dyn StdError>> {
     println!("");
     println!("Ticker:           {:<20}", bt.ticker);
@@ -1352,79 +3819,84 @@ pub fn showbt(bt: Backtest) -> Result<(), Box<dyn StdError>> {
     println!("Max Loss:         {:>9.1}", bt.max_loss);
     println!("sharpe_ratio:     {:>9.1}", bt.sharpe_ratio);
     println!("sortino_ratio:    {:>9.1}", bt.sortino_ratio);
+    println!("sharpe_tstat:     {:>9.2}", bt.sharpe_tstat);
     println!("max_drawdown:     {:>9.1}", bt.max_drawdown);
     println!("calmar_ratio:     {:>9.1}", bt.calmar_ratio);
+    println!("profit_to_dd:     {:>9.1}", bt.profit_to_dd);
     println!("win_loss_ratio:   {:>9.1}", bt.win_loss_ratio);
     println!("recovery_factor:  {:>9.1}", bt.recovery_factor);
     println!("profit_per_trade: {:>9.1}", bt.profit_per_trade);
+    println!("max_consec_wins:  {:>9}", bt.max_consecutive_wins);
+    println!("max_consec_losses:{:>9}", bt.max_consecutive_losses);
+    println!("CAGR %:           {:>9.1}", bt.cagr);
+    println!("Annualized %:     {:>9.1}", bt.annualized_return);
+    println!("Avg Gain (ATR):   {:>9.2}", bt.avg_gain_atr);
+    println!("Avg Loss (ATR):   {:>9.2}", bt.avg_loss_atr);
+    println!("Info Ratio:       {:>9.2}", bt.information_ratio);
+    println!("Beta to Bench:    {:>9.2}", bt.beta_to_benchmark);
+    println!("Avg Rolling Beta: {:>9.2}", bt.avg_rolling_beta);
+    println!("Beta-Adj Return:  {:>9.2}", bt.beta_adjusted_return);
+    println!("Expectancy/Day:   {:>9.2}", bt.expectancy_per_day);
+    println!("Expectancy (R):   {:>9.2}", bt.expectancy_r);
+    println!("R-Multiple Std:   {:>9.2}", bt.r_multiple_std);
+    println!("Buy & Hold %:     {:>9.1}", bt.buy_and_hold_return);
+    println!("DCA B&H %:        {:>9.1}", bt.dca_return);
     println!("Buys:             {:>9.1}", bt.buys);
     println!("Sells:            {:>9.1}", bt.sells);
     println!("Trades:           {:>9.1}", bt.trades);
+    println!("Signal Exits:     {:>9}", bt.signal_exits);
+    println!("Stop-Loss Exits:  {:>9}", bt.stop_loss_exits);
+    println!("End-of-Data Exits:{:>9}", bt.end_of_data_exits);
+    if bt.holding_period_histogram.iter().any(|(_, n)| *n > 0) {
+        let bars: Vec<String> = bt.holding_period_histogram.iter().map(|(days, n)| format!("{}d:{}", days, n)).collect();
+        println!("Holding Periods:  {}", bars.join(" "));
+    }
     Ok(())
 }
 
 pub fn preprocess(df: LazyFrame) -> Result<DataFrame, Box<dyn StdError>> {
-    let window_size_5 = RollingOptionsFixedWindow {
-        window_size: 5,
-        min_periods: 5, // Minimum number of observations in window required to have a value
-        center: false,  // Set to true to set the labels at the center of the window
-        weights: None,  // Optional weights for the window
-        ..Default::default()
-    };
-
-    let window_size_20 = RollingOptionsFixedWindow {
-        window_size: 20,
-        min_periods: 20, // Minimum number of observations in window required to have a value
-        ..Default::default()
-    };
-
-    let window_size_50 = RollingOptionsFixedWindow {
-        window_size: 50,
-        min_periods: 50, // Minimum number of observations in window required to have a value
-        ..Default::default()
-    };
-
-    let window_size_100 = RollingOptionsFixedWindow {
-        window_size: 100,
-        min_periods: 100, // Minimum number of observations in window required to have a value
-        ..Default::default()
-    };
-
-    let window_size_200 = RollingOptionsFixedWindow {
-        window_size: 200,
-        min_periods: 200, // Minimum number of observations in window required to have a value
-        ..Default::default()
-    };
+    preprocess_with(df, 1.0)
+}
 
-    let window_size_250 = RollingOptionsFixedWindow {
-        window_size: 250,
-        min_periods: 250, // Minimum number of observations in window required to have a value
+/// `preprocess` with each rolling window producing a value once `min_periods_fraction`
+/// of the window is available (1.0 = the full window, as `preprocess` does).
+///
+/// Relaxing it makes tickers with short histories usable: at 0.8, the 250-bar columns
+/// exist from bar 200 on instead of staying null for a 240-bar ticker. The tradeoff is
+/// that those early values cover fewer bars than their name says, so a 250-day high
+/// from 200 bars is a 200-day high, and signals built on them are not comparable with
+/// a full-history ticker until the window fills.
+pub fn preprocess_with(df: LazyFrame, min_periods_fraction: f64) -> Result<DataFrame, Box<dyn StdError>> {
+    // Minimum number of observations in the window required to have a value
+    let window = |size: usize| RollingOptionsFixedWindow {
+        window_size: size,
+        min_periods: ((size as f64 * min_periods_fraction).ceil() as usize).clamp(1, size),
         ..Default::default()
     };
+    let window_size_5 = window(5);
+    let window_size_20 = window(20);
+    let window_size_50 = window(50);
+    let window_size_100 = window(100);
+    let window_size_200 = window(200);
+    let window_size_250 = window(250);
 
     let out = df
         .clone()
         .select([cols([
             "Ticker", "Date", "Open", "High", "Low", "Close", "Volume",
         ])])
+        // One stable sort, so each ticker's rows are in date order for the windows below
         .sort(
-            vec!["Date"],
-            SortMultipleOptions {
-                descending: vec![false],
-                nulls_last: vec![true],
-                ..Default::default()
-            },
-        )
-        .sort(
-            vec!["Ticker"],
+            vec!["Ticker", "Date"],
             SortMultipleOptions {
-                descending: vec![false],
-                nulls_last: vec![true],
+                descending: vec![false, false],
+                nulls_last: vec![true, true],
+                maintain_order: true,
                 ..Default::default()
             },
         )
         .with_columns([
-            (col("Close") / col("Close").shift(lit(1)).over([col("Ticker")]) - lit(1)).alias("Ret"),
+            ReturnMethod::Simple.expr(col("Close")).over([col("Ticker")]).alias("Ret"),
             col("Low")
                 .rolling_min(window_size_20.clone().into())
                 .over([col("Ticker")])
@@ -1472,302 +3944,182 @@ pub fn preprocess(df: LazyFrame) -> Result<DataFrame, Box<dyn StdError>> {
     Ok(out)
 }
 
+/// OHLC columns shared, read-only, by every `postprocess` indicator
+struct IndicatorInputs {
+    open: Series,
+    high: Series,
+    low: Series,
+    close: Series,
+}
+
+/// Output column names of an indicator and the function computing them, in that order
+type IndicatorJob = (&'static [&'static str], fn(&IndicatorInputs) -> Vec<Vec<f64>>);
+
+/// Columns of the indicator battery that `postprocess` adds to the frame; the rest are
+/// computed but left out
+const POSTPROCESS_COLUMNS: &[&str] = &["upper_aug_bbands", "lower_aug_bbands", "ftp_buy", "ftp_sell"];
+
+/// The `postprocess` indicator battery. Each entry is an independent pass over the
+/// price columns, so they can run in any order or concurrently.
+fn postprocess_indicators() -> Vec<IndicatorJob> {
+    use signals::technical as t;
+    vec![
+        (&["sma_20"], |p| vec![t::sma(p.close.clone(), 20)]),
+        (&["ema_20"], |p| vec![t::ema(p.close.clone(), 0.5, 20)]),
+        (&["smoothed_ma_20"], |p| vec![t::smoothed_ma(p.close.clone(), 0.5, 20)]),
+        (&["vol_20"], |p| vec![t::volatility(p.close.clone(), 20)]),
+        (&["atr_10"], |p| vec![t::atr(p.close.clone(), p.high.clone(), p.low.clone(), 10)]),
+        (&["rsi_20"], |p| vec![t::rsi(p.close.clone(), 20)]),
+        (&["stoch_osc_out", "stoch_osc_stoch", "stoch_osc_signal"], |p| {
+            let (out, stoch, signal) = t::stochastic_oscillator(
+                p.close.clone(), p.high.clone(), p.low.clone(), 250, true, true, 3, 3,
+            );
+            vec![out, stoch, signal]
+        }),
+        (&["normalized_index"], |p| vec![t::normalized_index(p.close.clone(), 20)]),
+        (&["upper_aug_bbands", "lower_aug_bbands"], |p| {
+            let (upper, lower) = t::augmented_bollinger_bands(p.high.clone(), p.low.clone(), 20, 2.);
+            vec![upper, lower]
+        }),
+        (&["upper_bbands", "lower_bbands"], |p| {
+            let (upper, lower) = t::bollinger_bands(p.close.clone(), 20, 2.);
+            vec![upper, lower]
+        }),
+        (&["upper_kband", "lower_kband", "middle_kband"], |p| {
+            let (upper, lower, middle) = t::k_volatility_band(p.close.clone(), p.high.clone(), p.low.clone(), 20, 2.);
+            vec![upper, lower, middle]
+        }),
+        (&["rsi_atr"], |p| vec![t::rsi_atr(p.close.clone(), p.high.clone(), p.low.clone(), 3, 5, 7)]),
+        (&["trend_intensity"], |p| vec![t::trend_intensity_indicator(p.close.clone(), 20)]),
+        (&["kama_10"], |p| vec![t::kama(p.close.clone(), 10)]),
+        (&["fma_high", "fma_low"], |p| {
+            let (high, low) = t::fma(p.high.clone(), p.low.clone());
+            vec![high, low]
+        }),
+        (&["frama"], |p| vec![t::fractal_adaptive_ma(p.close.clone(), p.high.clone(), p.low.clone(), 10)]),
+        (&["lwma"], |p| vec![t::lwma(p.close.clone(), 10)]),
+        (&["hull_ma"], |p| vec![t::hull_ma(p.close.clone(), 10)]),
+        (&["vama"], |p| vec![t::volatility_adjusted_moving_average(p.close.clone(), 3, 30)]),
+        (&["ema_13"], |p| vec![t::ema(p.close.clone(), 2., 13)]),
+        (&["macd_diff", "macd_signal"], |p| {
+            let (diff, signal) = t::macd(p.close.clone(), 26, 12, 9);
+            vec![diff, signal]
+        }),
+        (&["elder"], |p| vec![t::elder_impulse(p.close.clone(), 250)]),
+        (&["aroon_up", "aroon_down"], |p| {
+            let (up, down) = t::aroon(p.close.clone(), p.high.clone(), p.low.clone(), 25);
+            vec![up, down]
+        }),
+        (&["di_plus", "di_minus", "adx", "smoothed_adx"], |p| {
+            let (di_plus, di_minus, adx, smoothed_adx) = t::adx(p.close.clone(), p.high.clone(), p.low.clone(), 14);
+            vec![di_plus, di_minus, adx, smoothed_adx]
+        }),
+        (&["awesome_oscillator"], |p| vec![t::awesome_oscillator(p.high.clone(), p.low.clone(), 34, 5)]),
+        (&["donchian_low", "donchian_high", "donchian_med"], |p| {
+            let (low, high, med) = t::donchian(p.high.clone(), p.low.clone(), 20);
+            vec![low, high, med]
+        }),
+        (&["keltner_upper", "keltner_lower"], |p| {
+            let (upper, lower) = t::keltner_channel(p.close.clone(), p.high.clone(), p.low.clone(), 60, 60, 20);
+            vec![upper, lower]
+        }),
+        (&["squeeze"], |p| vec![t::squeeze(p.close.clone(), p.high.clone(), p.low.clone(), 60, 10., 60, 20)]),
+        (&["supertrend"], |p| vec![t::supertrend(p.close.clone(), p.high.clone(), p.low.clone(), 10, 2.)]),
+        (&["trix"], |p| vec![t::trix(p.close.clone(), 20)]),
+        (&["vh_indicator"], |p| vec![t::vertical_horizontal_indicator(p.close.clone(), 60)]),
+        (&["kijun", "tenkan", "senkou_span_a", "senkou_span_b"], |p| {
+            let (kijun, tenkan, span_a, span_b) =
+                t::ichimoku(p.close.clone(), p.high.clone(), p.low.clone(), 26, 9, 26, 26, 52);
+            vec![kijun, tenkan, span_a, span_b]
+        }),
+        // extreme_duration runs on the countdown, so the two stay one job
+        (&["countdown_indicator", "downward", "upward", "net"], |p| {
+            let countdown = t::countdown_indicator(p.open.clone(), p.high.clone(), p.low.clone(), p.close.clone(), 8, 3);
+            let (downward, upward, net) = t::extreme_duration(Series::new("".into(), &countdown), 5., -5.);
+            vec![countdown, downward, upward, net]
+        }),
+        (&["demarker"], |p| vec![t::demarker(p.high.clone(), p.low.clone(), 14)]),
+        (&["disparity"], |p| vec![t::disparity_index(p.close.clone(), 14)]),
+        (&["fisher"], |p| vec![t::fisher_transform(p.high.clone(), p.low.clone(), p.close.clone(), 14)]),
+        (&["time_up"], |p| vec![t::time_up(p.close.clone(), 1)]),
+        (&["tsabm"], |p| vec![t::time_spent_above_below_mean(p.close.clone(), 34)]),
+        (&["ftp_buy", "ftp_sell"], |p| {
+            let (buy, sell) = t::fibonacci_timing_pattern(p.close.clone(), 8., 5, 3, 2);
+            vec![buy, sell]
+        }),
+    ]
+}
+
+/// `postprocess_with` on rayon's global pool (one thread per core)
 pub fn postprocess(df: DataFrame) -> Result<DataFrame, Box<dyn StdError>> {
-    let _sma = signals::technical::sma(
-        df.column("Close").unwrap().as_series().unwrap().to_owned(),
-        20,
-    );
-    let _ema = signals::technical::ema(
-        df.column("Close").unwrap().as_series().unwrap().to_owned(),
-        0.5,
-        20,
-    );
-    let _smoothed_ma = signals::technical::smoothed_ma(
-        df.column("Close").unwrap().as_series().unwrap().to_owned(),
-        0.5,
-        20,
-    );
-    let _vol = signals::technical::volatility(
-        df.column("Close").unwrap().as_series().unwrap().to_owned(),
-        20,
-    );
-    let _atr = signals::technical::atr(
-        df.column("Close").unwrap().as_series().unwrap().to_owned(),
-        df.column("High").unwrap().as_series().unwrap().to_owned(),
-        df.column("Low").unwrap().as_series().unwrap().to_owned(),
-        10,
-    );
-    let _rsi = signals::technical::rsi(
-        df.column("Close").unwrap().as_series().unwrap().to_owned(),
-        20,
-    );
-    let (_out, _stoch, _signal) = signals::technical::stochastic_oscillator(
-        df.column("Close").unwrap().as_series().unwrap().to_owned(),
-        df.column("High").unwrap().as_series().unwrap().to_owned(),
-        df.column("Low").unwrap().as_series().unwrap().to_owned(),
-        250,
-        true,
-        true,
-        3,
-        3,
-    );
-    let _normalized_index = signals::technical::normalized_index(
-        df.column("Close").unwrap().as_series().unwrap().to_owned(),
-        20,
-    );
-    let (upper_aug_bbands, lower_aug_bbands) = signals::technical::augmented_bollinger_bands(
-        df.column("High").unwrap().as_series().unwrap().to_owned(),
-        df.column("Low").unwrap().as_series().unwrap().to_owned(),
-        20,
-        2.,
-    );
-    let (_upper_bbands, _lower_bbands) = signals::technical::bollinger_bands(
-        df.column("Close").unwrap().as_series().unwrap().to_owned(),
-        20,
-        2.,
-    );
-    let (_upper_kband, _lower_kband, _middle_kband) = signals::technical::k_volatility_band(
-        df.column("Close").unwrap().as_series().unwrap().to_owned(),
-        df.column("High").unwrap().as_series().unwrap().to_owned(),
-        df.column("Low").unwrap().as_series().unwrap().to_owned(),
-        20,
-        2.,
-    );
-    let _rsi_atr = signals::technical::rsi_atr(
-        df.column("Close").unwrap().as_series().unwrap().to_owned(),
-        df.column("High").unwrap().as_series().unwrap().to_owned(),
-        df.column("Low").unwrap().as_series().unwrap().to_owned(),
-        3,
-        5,
-        7,
-    );
-    let _trend_intensity = signals::technical::trend_intensity_indicator(
-        df.column("Close").unwrap().as_series().unwrap().to_owned(),
-        20,
-    );
-    let _kama_10 = signals::technical::kama(
-        df.column("Close").unwrap().as_series().unwrap().to_owned(),
-        10,
-    );
-    let (_fma_high, _fma_low) = signals::technical::fma(
-        df.column("High").unwrap().as_series().unwrap().to_owned(),
-        df.column("Low").unwrap().as_series().unwrap().to_owned(),
-    );
-    let _frama = signals::technical::fractal_adaptive_ma(
-        df.column("Close").unwrap().as_series().unwrap().to_owned(),
-        df.column("High").unwrap().as_series().unwrap().to_owned(),
-        df.column("Low").unwrap().as_series().unwrap().to_owned(),
-        10,
-    );
-    let _lwma = signals::technical::lwma(
-        df.column("Close").unwrap().as_series().unwrap().to_owned(),
-        10,
-    );
-    let _hull_ma = signals::technical::hull_ma(
-        df.column("Close").unwrap().as_series().unwrap().to_owned(),
-        10,
-    );
-    let _vama = signals::technical::volatility_adjusted_moving_average(
-        df.column("Close").unwrap().as_series().unwrap().to_owned(),
-        3,
-        30,
-    );
-    let _ema = signals::technical::ema(
-        df.column("Close").unwrap().as_series().unwrap().to_owned(),
-        2.,
-        13,
-    );
-    let (_macd_diff, _macd_signal) = signals::technical::macd(
-        df.column("Close").unwrap().as_series().unwrap().to_owned(),
-        26,
-        12,
-        9,
-    );
-    let _elder = signals::technical::elder_impulse(
-        df.column("Close").unwrap().as_series().unwrap().to_owned(),
-        250,
-    );
-    let (_aroon_up, _aroon_down) = signals::technical::aroon(
-        df.column("Close").unwrap().as_series().unwrap().to_owned(),
-        df.column("High").unwrap().as_series().unwrap().to_owned(),
-        df.column("Low").unwrap().as_series().unwrap().to_owned(),
-        25,
-    );
-    let (_di_plus, _di_minus, _adx, _smoothed_adx) = signals::technical::adx(
-        df.column("Close").unwrap().as_series().unwrap().to_owned(),
-        df.column("High").unwrap().as_series().unwrap().to_owned(),
-        df.column("Low").unwrap().as_series().unwrap().to_owned(),
-        14,
-    );
-    let _awesome = signals::technical::awesome_oscillator(
-        df.column("High").unwrap().as_series().unwrap().to_owned(),
-        df.column("Low").unwrap().as_series().unwrap().to_owned(),
-        34,
-        5,
-    );
-    let (_donchian_low, _donchian_high, _donchian_med) = signals::technical::donchian(
-        df.column("High").unwrap().as_series().unwrap().to_owned(),
-        df.column("Low").unwrap().as_series().unwrap().to_owned(),
-        20,
-    );
-    let (_keltner_upper, _keltner_lower) = signals::technical::keltner_channel(
-        df.column("Close").unwrap().as_series().unwrap().to_owned(),
-        df.column("High").unwrap().as_series().unwrap().to_owned(),
-        df.column("Low").unwrap().as_series().unwrap().to_owned(),
-        60,
-        60,
-        20,
-    );
-    let _squeeze = signals::technical::squeeze(
-        df.column("Close").unwrap().as_series().unwrap().to_owned(),
-        df.column("High").unwrap().as_series().unwrap().to_owned(),
-        df.column("Low").unwrap().as_series().unwrap().to_owned(),
-        60,
-        10.,
-        60,
-        20,
-    );
-    let _supertrend = signals::technical::supertrend(
-        df.column("Close").unwrap().as_series().unwrap().to_owned(),
-        df.column("High").unwrap().as_series().unwrap().to_owned(),
-        df.column("Low").unwrap().as_series().unwrap().to_owned(),
-        10,
-        2.,
-    );
-    let _trend_intensity = signals::technical::trend_intensity_indicator(
-        df.column("Close").unwrap().as_series().unwrap().to_owned(),
-        20,
-    );
-    let _trix = signals::technical::trix(
-        df.column("Close").unwrap().as_series().unwrap().to_owned(),
-        20,
-    );
-    let _vertical_horizontal_ind = signals::technical::vertical_horizontal_indicator(
-        df.column("Close").unwrap().as_series().unwrap().to_owned(),
-        60,
-    );
-    let (_kijun, _tenkan, _senkou_span_a, _senkou_span_b) = signals::technical::ichimoku(
-        df.column("Close").unwrap().as_series().unwrap().to_owned(),
-        df.column("High").unwrap().as_series().unwrap().to_owned(),
-        df.column("Low").unwrap().as_series().unwrap().to_owned(),
-        26,
-        9,
-        26,
-        26,
-        52,
-    );
-    let countdown_indicator = signals::technical::countdown_indicator(
-        df.column("Open").unwrap().as_series().unwrap().to_owned(),
-        df.column("High").unwrap().as_series().unwrap().to_owned(),
-        df.column("Low").unwrap().as_series().unwrap().to_owned(),
-        df.column("Close").unwrap().as_series().unwrap().to_owned(),
-        8,
-        3,
-    );
-    let countdown_indicator_series = Series::new("".into(), &countdown_indicator);
-    let (_downward, _upward, _net) =
-        signals::technical::extreme_duration(countdown_indicator_series, 5., -5.);
-    let _demarker = signals::technical::demarker(
-        df.column("High").unwrap().as_series().unwrap().to_owned(),
-        df.column("Low").unwrap().as_series().unwrap().to_owned(),
-        14,
-    );
-    let _disparity = signals::technical::disparity_index(
-        df.column("Close").unwrap().as_series().unwrap().to_owned(),
-        14,
-    );
-    let _fisher = signals::technical::fisher_transform(
-        df.column("High").unwrap().as_series().unwrap().to_owned(),
-        df.column("Low").unwrap().as_series().unwrap().to_owned(),
-        df.column("Close").unwrap().as_series().unwrap().to_owned(),
-        14,
-    );
-    let _time_up = signals::technical::time_up(
-        df.column("Close").unwrap().as_series().unwrap().to_owned(),
-        1,
-    );
-    let _tsabm = signals::technical::time_spent_above_below_mean(
-        df.column("Close").unwrap().as_series().unwrap().to_owned(),
-        34,
-    );
-    let (ftp_buy, ftp_sell) = signals::technical::fibonacci_timing_pattern(
-        df.column("Close").unwrap().as_series().unwrap().to_owned(),
-        8.,
-        5,
-        3,
-        2,
-    );
+    postprocess_with(df, 0)
+}
 
-    let out = df
-        .lazy()
-        .with_columns([
-            // lit(Series::new("".into(), &sma)).alias("sma_20"),
-            // lit(Series::new("".into(), &ema)).alias("ema_20"),
-            // lit(Series::new("".into(), &smoothed_ma)).alias("smoothed_ma_20"),
-            // lit(Series::new("".into(), &vol)).alias("vol_20"),
-            // lit(Series::new("".into(), &atr)).alias("atr_10"),
-            // lit(Series::new("".into(), &rsi)).alias("rsi_20"),
-            // lit(Series::new("".into(), &out)).alias("stoch_osc_out"),
-            // lit(Series::new("".into(), &stoch)).alias("stoch_osc_stoch"),
-            // lit(Series::new("".into(), &signal)).alias("stoch_osc_signal"),
-            // lit(Series::new("".into(), &normalized_index)).alias("normalized_index"),
-            lit(Series::new("".into(), &upper_aug_bbands)).alias("upper_aug_bbands"),
-            lit(Series::new("".into(), &lower_aug_bbands)).alias("lower_aug_bbands"),
-            // lit(Series::new("".into(), &upper_kband)).alias("upper_kband"),
-            // lit(Series::new("".into(), &lower_kband)).alias("lower_kband"),
-            // lit(Series::new("".into(), &middle_kband)).alias("middle_kband"),
-            // lit(Series::new("".into(), &rsi_atr)).alias("rsi_atr"),
-            // lit(Series::new("".into(), &kama_10)).alias("kama_10"),
-            // lit(Series::new("".into(), &fma_high)).alias("fma_high"),
-            // lit(Series::new("".into(), &fma_low)).alias("fma_low"),
-            // lit(Series::new("".into(), &frama)).alias("frama"),
-            // lit(Series::new("".into(), &lwma)).alias("lwma"),
-            // lit(Series::new("".into(), &hull_ma)).alias("hull_ma"),
-            // lit(Series::new("".into(), &vama)).alias("vama"),
-            // lit(Series::new("".into(), &macd_diff)).alias("macd_diff"),
-            // lit(Series::new("".into(), &macd_signal)).alias("macd_signal"),
-            // lit(Series::new("".into(), &elder)).alias("elder"),
-            // lit(Series::new("".into(), &aroon_up)).alias("aroon_up"),
-            // lit(Series::new("".into(), &aroon_down)).alias("aroon_down"),
-            // lit(Series::new("".into(), &di_plus)).alias("di_plus"),
-            // lit(Series::new("".into(), &di_minus)).alias("di_minus"),
-            // lit(Series::new("".into(), &adx)).alias("adx"),
-            // lit(Series::new("".into(), &smoothed_adx)).alias("smoothed_adx"),
-            // lit(Series::new("".into(), &awesome)).alias("awesome_oscillator"),
-            // lit(Series::new("".into(), &donchian_low)).alias("donchian_low"),
-            // lit(Series::new("".into(), &donchian_high)).alias("donchian_high"),
-            // lit(Series::new("".into(), &donchian_med)).alias("donchian_med"),z
-            // lit(Series::new("".into(), &squeeze)).alias("squeeze"),
-            // lit(Series::new("".into(), &keltner_upper)).alias("keltner_upper"),
-            // lit(Series::new("".into(), &keltner_lower)).alias("keltner_lower"),
-            // lit(Series::new("".into(), &atr2)).alias("atr2"),
-            // lit(Series::new("".into(), &squeeze)).alias("squeeze"),
-            // lit(Series::new("".into(), &supertrend)).alias("supertrend"),
-            // lit(Series::new("".into(), &trend_intensity)).alias("trend_intensity"),
-            // lit(Series::new("".into(), &trix)).alias("trix"),
-            // lit(Series::new("".into(), &vertical_horizontal_ind)).alias("vh_indicator"),
-            // lit(Series::new("".into(), &kijun)).alias("kijun"),
-            // lit(Series::new("".into(), &tenkan)).alias("tenkan"),
-            // lit(Series::new("".into(), &senkou_span_a)).alias("senkou_span_a"),
-            // lit(Series::new("".into(), &senkou_span_b)).alias("senkou_span_b"),
-            // lit(Series::new("".into(), &countdown_indicator)).alias("countdown_indicator"),
-            // lit(Series::new("".into(), &downward)).alias("downward"),
-            // lit(Series::new("".into(), &upward)).alias("upward"),
-            // lit(Series::new("".into(), &net)).alias("net"),
-            // lit(Series::new("".into(), &demarker)).alias("demarker"),
-            // lit(Series::new("".into(), &disparity)).alias("disparity"),
-            // lit(Series::new("".into(), &fisher)).alias("fisher"),
-            // lit(Series::new("".into(), &time_up)).alias("time_up"),
-            // lit(Series::new("".into(), &tsabm)).alias("tsabm"),
-            lit(Series::new("".into(), &ftp_buy)).alias("ftp_buy"),
-            lit(Series::new("".into(), &ftp_sell)).alias("ftp_sell"),
-        ])
-        .collect()
-        .unwrap();
+/// Add the `POSTPROCESS_COLUMNS` indicators to `df`. The indicators are computed on
+/// `threads` workers (0 for rayon's global pool, 1 to run them in turn on the calling
+/// thread) and the columns are added in the battery's order either way. Only an explicit
+/// count builds a pool of its own.
+pub fn postprocess_with(df: DataFrame, threads: usize) -> Result<DataFrame, Box<dyn StdError>> {
+    let column = |name: &str| df.column(name).map(|c| c.as_materialized_series().clone());
+    let inputs = IndicatorInputs {
+        open: column("Open")?,
+        high: column("High")?,
+        low: column("Low")?,
+        close: column("Close")?,
+    };
+    let jobs = postprocess_indicators();
+    use rayon::prelude::*;
+    // An indexed parallel collect keeps the jobs' order
+    let parallel = || jobs.par_iter().map(|(_, job)| job(&inputs)).collect();
+    let results: Vec<Vec<Vec<f64>>> = match threads {
+        0 => parallel(),
+        1 => jobs.iter().map(|(_, job)| job(&inputs)).collect(),
+        _ => rayon::ThreadPoolBuilder::new().num_threads(threads).build()?.install(parallel),
+    };
 
+    let mut out = df;
+    for ((names, _), values) in jobs.iter().zip(results) {
+        for (name, values) in names.iter().zip(values) {
+            if POSTPROCESS_COLUMNS.contains(name) {
+                out.with_column(Series::new((*name).into(), values))?;
+            }
+        }
+    }
     Ok(out)
 }
 
+/// Write `df` as CSV to `path` so readers see either the previous file or the complete
+/// new one, never a truncated write: see `write_atomic_with`
+pub fn write_csv_atomic(df: &mut DataFrame, path: impl AsRef<Path>) -> Result<(), Box<dyn StdError>> {
+    write_atomic_with(path, |file| Ok(CsvWriter::new(file).finish(df)?))
+}
+
+/// Run `write` against `<path>.partial` and rename it over `path` only once it succeeded
+/// and was synced to disk. On failure the partial file is removed and any existing
+/// `path` is left as it was.
+pub fn write_atomic_with<F>(path: impl AsRef<Path>, write: F) -> Result<(), Box<dyn StdError>>
+where
+    F: FnOnce(&mut File) -> Result<(), Box<dyn StdError>>,
+{
+    let path = path.as_ref();
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(".partial");
+    let partial = PathBuf::from(partial);
+
+    let result = File::create(&partial)
+        .map_err(Box::<dyn StdError>::from)
+        .and_then(|mut file| {
+            write(&mut file)?;
+            file.sync_all()?;
+            Ok(())
+        })
+        .and_then(|()| Ok(std::fs::rename(&partial, path)?));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&partial);
+    }
+    result
+}
+
 pub async fn save_backtest(
     paths: &crate::config::PathConfig,
     bt: Vec<(Backtest, Vec<Decision>)>,
@@ -1783,7 +4135,10 @@ pub async fn save_backtest(
     // 3. Create cursor from json
     let cursor = Cursor::new(json);
     // 4. Create polars DataFrame from reading cursor as json
-    let mut df = JsonReader::new(cursor).finish()?;
+    let df = JsonReader::new(cursor).finish()?;
+    // JSON inference doesn't keep field order; write the schema's order so files are
+    // comparable (`backtest_output_complete` checks the header)
+    let mut df = df.select(create_backtest_schema().iter_names().cloned())?;
 
     let mode = if is_production { config::ExecutionMode::Production } else { config::ExecutionMode::Testing };
     let csv_path = paths.output_file(univ, &ticker, mode);
@@ -1792,8 +4147,7 @@ pub async fn save_backtest(
     let output_dir = paths.output_dir(univ, mode);
     tokio::fs::create_dir_all(&output_dir).await?;
 
-    let mut csvfile = File::create(csv_path.clone())?;
-    let _ = CsvWriter::new(&mut csvfile).finish(&mut df);
+    write_csv_atomic(&mut df, csv_path)?;
 
     // Save decisions
     if !is_production {
@@ -1825,8 +4179,7 @@ pub async fn save_backtest(
                 let cursor = Cursor::new(json);
                 let mut df_decisions = JsonReader::new(cursor).finish()?;
                 let decisions_path = format!("{}/{}_{}_decisions.csv", base_path, ticker, strategy);
-                let mut csvfile = File::create(decisions_path)?;
-                let _ = CsvWriter::new(&mut csvfile).finish(&mut df_decisions);
+                write_csv_atomic(&mut df_decisions, decisions_path)?;
             }
         }
     } else {
@@ -1848,15 +4201,160 @@ pub async fn save_backtest(
             let cursor = Cursor::new(json);
             let mut df_decisions = JsonReader::new(cursor).finish()?;
             let decisions_path = paths.decision_file(univ, &ticker);
-            tokio::fs::create_dir_all(format!("{}/decisions", paths.base)).await?;
-            let mut csvfile = File::create(decisions_path)?;
-            let _ = CsvWriter::new(&mut csvfile).finish(&mut df_decisions);
+            tokio::fs::create_dir_all(paths.decision_dir_for_universe(univ)).await?;
+            write_csv_atomic(&mut df_decisions, decisions_path)?;
         }
     }
 
     Ok(())
 }
 
+/// One ticker's results file as recorded in a universe manifest
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub ticker: String,
+    pub file: String,
+    /// Data rows, not counting the header
+    pub rows: usize,
+    /// FNV-1a hash of the file contents, in hex
+    pub hash: String,
+    /// The run failed to write this ticker's file; `verify_manifest` always reports it
+    #[serde(default)]
+    pub missing: bool,
+}
+
+/// Written after a universe's backtests so a downstream step can check that every
+/// results file is present and complete
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub universe: String,
+    pub run_id: String,
+    pub entries: Vec<ManifestEntry>,
+}
+
+/// A manifest entry whose file no longer matches
+#[derive(Debug, Clone, PartialEq)]
+pub enum ManifestIssue {
+    Missing { file: String },
+    Changed { file: String, rows: usize, expected_rows: usize },
+}
+
+/// 64-bit FNV-1a; stable across platforms and Rust versions unlike `DefaultHasher`
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325_u64, |h, &b| (h ^ b as u64).wrapping_mul(0x100000001b3))
+}
+
+/// `fnv1a` in hex
+fn content_hash(bytes: &[u8]) -> String {
+    format!("{:016x}", fnv1a(bytes))
+}
+
+fn manifest_entry(ticker: &str, file: &str) -> Result<ManifestEntry, Box<dyn StdError>> {
+    let bytes = std::fs::read(file)?;
+    let rows = csv::Reader::from_reader(bytes.as_slice()).records().count();
+    Ok(ManifestEntry { ticker: ticker.to_string(), file: file.to_string(), rows, hash: content_hash(&bytes), missing: false })
+}
+
+/// Whether the results file at `path` was written in full: it ends on a line break, its header is
+/// the backtest schema and every one of its (at least one) rows has all the columns. A run killed
+/// mid-write leaves a file that fails this, so `--resume` processes that ticker again.
+pub fn backtest_output_complete(path: impl AsRef<Path>) -> bool {
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(_) => return false,
+    };
+    if bytes.last() != Some(&b'\n') {
+        return false;
+    }
+    let mut reader = csv::Reader::from_reader(bytes.as_slice());
+    let schema = create_backtest_schema();
+    match reader.headers() {
+        Ok(headers) if headers.iter().eq(schema.iter_names().map(|n| n.as_str())) => {}
+        _ => return false,
+    }
+    let mut rows = 0;
+    for record in reader.records() {
+        match record {
+            Ok(record) if record.len() == schema.len() => rows += 1,
+            _ => return false,
+        }
+    }
+    rows > 0
+}
+
+/// Update the universe's `manifest_<Universe>.json` with the results file `save_backtest`
+/// wrote for each of `written`, and mark each of `failed` (and any written ticker whose file
+/// can't be read) as missing. Entries for tickers this run didn't touch (a `-t` run, or a
+/// run that stopped early) are kept from the existing manifest, so it never shrinks to a
+/// partial list. Entries are in ticker order.
+pub fn write_manifest(
+    paths: &crate::config::PathConfig,
+    univ: &str,
+    written: &[String],
+    failed: &[String],
+    is_production: bool,
+) -> Result<Manifest, Box<dyn StdError>> {
+    let mode = if is_production { config::ExecutionMode::Production } else { config::ExecutionMode::Testing };
+    let path = paths.manifest_file(univ, mode);
+    let mut entries: BTreeMap<String, ManifestEntry> = match std::fs::read(&path) {
+        Ok(bytes) => match serde_json::from_slice::<Manifest>(&bytes) {
+            Ok(previous) => previous.entries.into_iter().map(|e| (e.ticker.clone(), e)).collect(),
+            Err(e) => {
+                log::warn!("{}: replacing unreadable manifest {}: {}", univ, path, e);
+                BTreeMap::new()
+            }
+        },
+        Err(_) => BTreeMap::new(),
+    };
+    let missing = |ticker: &str, file: String| ManifestEntry {
+        ticker: ticker.to_string(),
+        file,
+        rows: 0,
+        hash: String::new(),
+        missing: true,
+    };
+    for ticker in written {
+        let file = paths.output_file(univ, ticker, mode);
+        let entry = manifest_entry(ticker, &file).unwrap_or_else(|e| {
+            log::warn!("{}: no results file for '{}' ({}): {}", univ, ticker, file, e);
+            missing(ticker, file)
+        });
+        entries.insert(ticker.clone(), entry);
+    }
+    for ticker in failed {
+        entries.insert(ticker.clone(), missing(ticker, paths.output_file(univ, ticker, mode)));
+    }
+    let manifest = Manifest { universe: univ.to_string(), run_id: run_id().to_string(), entries: entries.into_values().collect() };
+    std::fs::create_dir_all(paths.output_dir(univ, mode))?;
+    std::fs::write(&path, serde_json::to_string_pretty(&manifest)?)?;
+    let failures = manifest.entries.iter().filter(|e| e.missing).count();
+    log::info!("{}: wrote manifest of {} results files ({} missing) to {}", univ, manifest.entries.len(), failures, path);
+    Ok(manifest)
+}
+
+/// Check every file listed in the manifest at `path`, returning the missing, truncated or
+/// otherwise rewritten ones; an empty list means the results are complete
+pub fn verify_manifest(path: &str) -> Result<Vec<ManifestIssue>, Box<dyn StdError>> {
+    let manifest: Manifest = serde_json::from_slice(&std::fs::read(path)?)?;
+    let mut issues = Vec::new();
+    for expected in &manifest.entries {
+        if expected.missing {
+            issues.push(ManifestIssue::Missing { file: expected.file.clone() });
+            continue;
+        }
+        match manifest_entry(&expected.ticker, &expected.file) {
+            Err(_) => issues.push(ManifestIssue::Missing { file: expected.file.clone() }),
+            Ok(actual) if actual.hash != expected.hash => issues.push(ManifestIssue::Changed {
+                file: expected.file.clone(),
+                rows: actual.rows,
+                expected_rows: expected.rows,
+            }),
+            Ok(_) => {}
+        }
+    }
+    Ok(issues)
+}
+
 pub async fn read_price_file(file_path: String) -> Result<LazyFrame, Box<dyn StdError>> {
     // Manually create the schema and add fields
     let mut schema = Schema::with_capacity(8);
@@ -1877,6 +4375,262 @@ pub async fn read_price_file(file_path: String) -> Result<LazyFrame, Box<dyn Std
     Ok(lf)
 }
 
+/// Read a dividends file with `Date,Ticker,Dividend` columns (cash per share, on the ex-date)
+pub async fn read_dividends_file(file_path: String) -> Result<LazyFrame, Box<dyn StdError>> {
+    let mut schema = Schema::with_capacity(3);
+    schema.with_column("Date".into(), DataType::Date);
+    schema.with_column("Ticker".into(), DataType::String);
+    schema.with_column("Dividend".into(), DataType::Float64);
+
+    let lf = LazyCsvReader::new(file_path)
+        .with_schema(Some(Arc::new(schema)))
+        .with_has_header(true)
+        .finish()?;
+    Ok(lf)
+}
+
+/// Add `Dividend` (0 when none) and a dividend-reinvested `TRClose` column to a price frame.
+/// `Close` is left untouched for trade fills; `TRClose` is for buy-and-hold comparisons:
+/// TRClose[t] = TRClose[t-1] * (Close[t] + Dividend[t]) / Close[t-1], starting at Close.
+pub fn add_total_return_close(
+    prices: LazyFrame,
+    dividends: LazyFrame,
+) -> Result<LazyFrame, Box<dyn StdError>> {
+    let df = prices
+        .join(
+            dividends.group_by([col("Ticker"), col("Date")]).agg([col("Dividend").sum()]),
+            [col("Ticker"), col("Date")],
+            [col("Ticker"), col("Date")],
+            JoinArgs::new(JoinType::Left),
+        )
+        .with_column(col("Dividend").fill_null(lit(0.0)))
+        .sort(["Ticker", "Date"], SortMultipleOptions::default())
+        .collect()?;
+
+    let tickers = df.column("Ticker")?.str()?;
+    let closes = df.column("Close")?.f64()?;
+    let dividends = df.column("Dividend")?.f64()?;
+
+    let mut tr_close: Vec<Option<f64>> = Vec::with_capacity(df.height());
+    let mut prev: Option<(&str, f64, f64)> = None; // (ticker, close, tr_close)
+    for i in 0..df.height() {
+        let ticker = tickers.get(i).unwrap_or_default();
+        let value = match (closes.get(i), prev) {
+            (Some(close), Some((prev_ticker, prev_close, prev_tr)))
+                if prev_ticker == ticker && prev_close > 0.0 =>
+            {
+                let tr = prev_tr * (close + dividends.get(i).unwrap_or(0.0)) / prev_close;
+                prev = Some((ticker, close, tr));
+                Some(tr)
+            }
+            (Some(close), _) => {
+                prev = Some((ticker, close, close));
+                Some(close)
+            }
+            (None, _) => None,
+        };
+        tr_close.push(value);
+    }
+
+    let mut df = df;
+    df.with_column(Series::new("TRClose".into(), tr_close))?;
+    Ok(df.lazy())
+}
+
+/// Read a price file and, when a dividends file is given, add `Dividend` and `TRClose`
+pub async fn read_price_file_with_dividends(
+    file_path: String,
+    dividends_path: Option<String>,
+) -> Result<LazyFrame, Box<dyn StdError>> {
+    let lf = read_price_file(file_path).await?;
+    match dividends_path {
+        Some(path) => add_total_return_close(lf, read_dividends_file(path).await?),
+        None => Ok(lf),
+    }
+}
+
+/// Buy-and-hold return (fraction) from the first to the last value of `column`
+pub fn buy_and_hold_return(df: &DataFrame, column: &str) -> Result<f64, Box<dyn StdError>> {
+    let values = df.column(column)?.f64()?;
+    let first = values.into_iter().flatten().next();
+    let last = values.into_iter().flatten().last();
+    match (first, last) {
+        (Some(first), Some(last)) if first > 0.0 => Ok(last / first - 1.0),
+        _ => Err(format!("No {} values for a buy-and-hold return", column).into()),
+    }
+}
+
+/// Dollar-cost-averaged buy-and-hold return (fraction): the same dollar amount is
+/// invested at every value of `column`, and the shares are valued at the last one
+pub fn dca_return(df: &DataFrame, column: &str) -> Result<f64, Box<dyn StdError>> {
+    let values: Vec<f64> = df.column(column)?.f64()?.into_iter().flatten().filter(|&v| v > 0.0).collect();
+    match values.last() {
+        Some(&last) => {
+            let shares: f64 = values.iter().map(|v| 1.0 / v).sum();
+            Ok(shares * last / values.len() as f64 - 1.0)
+        }
+        None => Err(format!("No {} values for a dollar-cost-averaged return", column).into()),
+    }
+}
+
+/// Match a file name against a pattern where `*` stands for any run of characters
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if parts.len() == 1 {
+        return pattern == name;
+    }
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if !name.starts_with(first) || name.len() < first.len() + last.len() || !name.ends_with(last) {
+        return false;
+    }
+    let mut rest = &name[first.len()..name.len() - last.len()];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(idx) => rest = &rest[idx + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+/// Price shard files for a directory (every `.csv` inside) or a file-name pattern
+/// such as `data/testing/LC1_*.csv`, sorted by path
+pub fn price_shard_paths(pattern: &str) -> Result<Vec<PathBuf>, Box<dyn StdError>> {
+    let path = Path::new(pattern);
+    let (dir, file_pattern) = if path.is_dir() {
+        (path, "*.csv")
+    } else {
+        let dir = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+        let file_pattern = path.file_name().and_then(|f| f.to_str()).unwrap_or_default();
+        (dir, file_pattern)
+    };
+
+    let mut paths = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry_path = entry?.path();
+        let matches = entry_path
+            .file_name()
+            .and_then(|f| f.to_str())
+            .is_some_and(|name| wildcard_match(file_pattern, name));
+        if matches && entry_path.is_file() {
+            paths.push(entry_path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Read sharded price files (e.g. `LC1_2022.csv`, `LC1_2023.csv`) into one frame,
+/// keeping the first row for each (Ticker, Date) and sorting by Ticker then Date
+pub async fn read_price_glob(pattern: &str) -> Result<LazyFrame, Box<dyn StdError>> {
+    let paths = price_shard_paths(pattern)?;
+    if paths.is_empty() {
+        return Err(format!("No price files match {}", pattern).into());
+    }
+
+    let mut shards = Vec::with_capacity(paths.len());
+    for path in paths {
+        shards.push(read_price_file(path.to_string_lossy().into_owned()).await?);
+    }
+
+    let lf = concat(shards, UnionArgs::default())?
+        .unique_stable(
+            Some(vec!["Ticker".into(), "Date".into()]),
+            UniqueKeepStrategy::First,
+        )
+        .sort(["Ticker", "Date"], SortMultipleOptions::default());
+    Ok(lf)
+}
+
+/// Latest `Date` in a price frame (works for both Date and string columns)
+pub fn latest_price_date(lf: &LazyFrame) -> Result<chrono::NaiveDate, Box<dyn StdError>> {
+    let df = lf.clone().select([col("Date").max()]).collect()?;
+    match df.column("Date")?.get(0)? {
+        AnyValue::Null => Err("Price data has no dates".into()),
+        other => any_value_to_date(other.clone())
+            .ok_or_else(|| format!("Unsupported Date value: {:?}", other).into()),
+    }
+}
+
+/// Drop every ticker whose median `Close` is below `min_price` (penny stocks, dust-value
+/// coins), returning the kept frame and the excluded tickers in name order. Whole
+/// tickers go rather than single bars so no ticker's history is left with gaps.
+pub fn filter_min_price(lf: LazyFrame, min_price: f64) -> Result<(LazyFrame, Vec<String>), Box<dyn StdError>> {
+    let below = lf
+        .clone()
+        .group_by([col("Ticker")])
+        .agg([col("Close").median().alias("median_close")])
+        .filter(col("median_close").lt(lit(min_price)))
+        .sort(["Ticker"], Default::default())
+        .collect()?;
+    let excluded: Vec<String> = below
+        .column("Ticker")?
+        .str()?
+        .into_no_null_iter()
+        .map(String::from)
+        .collect();
+    if excluded.is_empty() {
+        return Ok((lf, excluded));
+    }
+    let below = below.column("Ticker")?.as_materialized_series().clone();
+    Ok((lf.filter(col("Ticker").is_in(lit(below)).not()), excluded))
+}
+
+/// Bars a ticker needs before every `preprocess` indicator has a full window (the
+/// 250-bar high/low)
+pub const WARMUP_BARS: usize = 250;
+
+/// Keep only the bars dated within `[start, end]` (either bound optional), so one period
+/// can be backtested on its own. Also returns, in name order, the tickers left with fewer
+/// than `WARMUP_BARS` bars, whose longer indicators will be missing or partial.
+pub fn filter_date_window(
+    lf: LazyFrame,
+    start: Option<chrono::NaiveDate>,
+    end: Option<chrono::NaiveDate>,
+) -> Result<(LazyFrame, Vec<String>), Box<dyn StdError>> {
+    if let (Some(start), Some(end)) = (start, end) {
+        if start > end {
+            return Err(format!("Start date {} is after end date {}", start, end).into());
+        }
+    }
+    let date = || col("Date").cast(DataType::Date);
+    let mut lf = lf;
+    if let Some(start) = start {
+        lf = lf.filter(date().gt_eq(lit(start).cast(DataType::Date)));
+    }
+    if let Some(end) = end {
+        lf = lf.filter(date().lt_eq(lit(end).cast(DataType::Date)));
+    }
+    let short = lf
+        .clone()
+        .group_by([col("Ticker")])
+        .agg([col("Date").count().alias("bars")])
+        .filter(col("bars").lt(lit(WARMUP_BARS as u32)))
+        .sort(["Ticker"], Default::default())
+        .collect()?;
+    let short = short.column("Ticker")?.str()?.into_no_null_iter().map(String::from).collect();
+    Ok((lf, short))
+}
+
+/// Error if the newest `Date` in the frame is more than `max_staleness_days` before today.
+/// Returns the latest date on success.
+pub fn assert_fresh(
+    lf: &LazyFrame,
+    max_staleness_days: i64,
+) -> Result<chrono::NaiveDate, Box<dyn StdError>> {
+    let latest = latest_price_date(lf)?;
+    let today = chrono::Local::now().date_naive();
+    let age = (today - latest).num_days();
+    if age > max_staleness_days {
+        return Err(format!(
+            "Stale price data: latest date {} is {} days old (max {})",
+            latest, age, max_staleness_days
+        )
+        .into());
+    }
+    Ok(latest)
+}
+
 pub fn print_dataframe_vertically(df: &DataFrame) {
     for idx in 0..df.height() {
         match df.get_row(idx) {
@@ -1892,7 +4646,7 @@ pub fn print_dataframe_vertically(df: &DataFrame) {
                     }
                 }
             }
-            Err(e) => eprintln!("Error retrieving row {}: {}", idx, e),
+            Err(e) => log::error!("Error retrieving row {}: {}", idx, e),
         }
         println!();
     }
@@ -1,7 +1,7 @@
 #![cfg_attr(debug_assertions, allow(dead_code, unused_imports))]
 use backtester::*;
 use polars::prelude::*;
-use std::{collections::HashSet, env, error::Error as StdError, fs, fs::File, process};
+use std::{collections::HashSet, env, error::Error as StdError, fs, fs::File, path::{Path, PathBuf}, process};
 use tokio;
 use clap::Parser;
 use log::{info, debug, warn, error};
@@ -13,10 +13,14 @@ mod display;
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
-    /// Universe: 'Crypto', 'SC', 'MC', 'LC', 'Micro', 'Stocks'
+    /// Universe: 'Crypto', 'SC', 'MC', 'LC', 'Micro', 'Stocks', 'All'
     #[arg(short, long, default_value = "Crypto")]
     universe: String,
 
+    /// Run every universe (crypto and all stock groups) in one pass; overrides --universe
+    #[arg(long)]
+    all: bool,
+
     /// Mode: 'production', 'testing', or 'demo'
     #[arg(short, long, default_value = "testing")]
     mode: String,
@@ -29,6 +33,10 @@ struct Args {
     #[arg(short, long)]
     strategy: Option<String>,
 
+    /// Run exactly the strategies listed in this file (one name per line)
+    #[arg(long)]
+    strategy_file: Option<String>,
+
     /// Working directory path
     #[arg(short, long)]
     path: Option<String>,
@@ -40,6 +48,64 @@ struct Args {
     /// Enable verbose logging
     #[arg(short, long, action = clap::ArgAction::Count)]
     verbose: u8,
+
+    /// Maximum age (days) of the latest price bar; production aborts on older data
+    #[arg(long, default_value = "4")]
+    max_staleness_days: i64,
+
+    /// Skip tickers whose median close is below this price (penny stocks, dust coins)
+    #[arg(long, value_name = "PRICE")]
+    min_price: Option<f64>,
+
+    /// Skip a strategy that is still running on a ticker after this many seconds
+    #[arg(long, value_name = "SECS")]
+    strategy_timeout: Option<u64>,
+
+    /// Backtest only bars on or after this date (YYYY-MM-DD)
+    #[arg(long, value_name = "DATE")]
+    start_date: Option<String>,
+
+    /// Backtest only bars on or before this date (YYYY-MM-DD)
+    #[arg(long, value_name = "DATE")]
+    end_date: Option<String>,
+
+    /// Fail instead of falling back to cached price files when ClickHouse is unreachable
+    #[arg(long)]
+    no_fallback: bool,
+
+    /// Print every registered strategy (name, category, default param) and exit
+    #[arg(long)]
+    list_strategies: bool,
+
+    /// After scoring, also write score/positions/ marking tickers still held from earlier
+    /// buys as "hold" next to today's "new buy" and "new sell"
+    #[arg(long)]
+    carry_positions: bool,
+
+    /// How a ticker with both a buy and a sell signal is scored: 'net' (sides offset),
+    /// 'max-conviction' (keep the stronger side) or 'separate' (one row per side)
+    #[arg(long, default_value = "net")]
+    score_aggregation: ScoreAggregation,
+
+    /// Insert a previously written score CSV into ClickHouse (replacing its tickers'
+    /// rows on its dates) and exit
+    #[arg(long, value_name = "PATH")]
+    import_score: Option<String>,
+
+    /// Write every output (backtests, decisions, performance, scores) under this
+    /// directory instead of the working directory; price data is still read from --path
+    #[arg(long, value_name = "DIR")]
+    output_root: Option<String>,
+
+    /// Continue an interrupted run: keep the existing output folder and skip tickers whose
+    /// results file is complete; truncated or malformed files are processed again
+    #[arg(long)]
+    resume: bool,
+
+    /// Reuse backtest results stored in this directory for unchanged strategies on
+    /// unchanged price data, and store new ones there
+    #[arg(long, value_name = "DIR")]
+    cache_dir: Option<String>,
 }
 
 mod signals {
@@ -60,9 +126,14 @@ pub async fn select_backtests(
     lf: LazyFrame,
     tag: &str,
     strategy_filter: Option<&str>,
+    strategy_timeout: Option<std::time::Duration>,
+    cache: Option<Arc<BacktestCache>>,
 ) -> Result<Vec<(Backtest, Vec<Decision>)>, Box<dyn StdError>> {
-    // Get strategies for the specified tag
-    let strategy_functions = strategy_config::get_strategies_for_tag(tag);
+    // Get strategies for the specified tag ("file:<path>" loads an explicit list)
+    let strategy_functions = match tag.strip_prefix("file:") {
+        Some(path) => strategy_config::strategies_from_file(path)?,
+        None => strategy_config::get_strategies_for_tag(tag),
+    };
 
     // Convert to Signal objects
     let mut signals: Vec<Signal> = strategy_functions
@@ -88,22 +159,30 @@ pub async fn select_backtests(
     }
 
     // Run all backtests
-    Ok(run_all_backtests(lf, signals).await?)
+    Ok(run_all_backtests_with(lf, signals, cache, strategy_timeout).await?)
 }
 
-/// Load price data and return LazyFrame with latest date
+/// Load price data (from `fallback` when the pull fell back to a cached file) and
+/// return LazyFrame with latest date
 async fn load_price_data(
     paths: &PathConfig,
     universe: &str,
     mode: ExecutionMode,
+    fallback: Option<&Path>,
 ) -> Result<(LazyFrame, String), Box<dyn StdError>> {
     let file_path = paths.data_file(universe, mode);
-    let lf = read_price_file(file_path).await?;
+    let lf = if let Some(cached) = fallback {
+        read_price_file(cached.to_string_lossy().into_owned()).await?
+    } else if Path::new(&file_path).exists() {
+        read_price_file(file_path).await?
+    } else {
+        read_price_glob(&paths.data_shard_pattern(universe, mode)).await?
+    };
 
     // Show latest date in the price data
     let latest_date_df = lf.clone().select([col("Date").max()]).collect()?;
     let latest_date = latest_date_df.column("Date")?.get(0)?.to_string();
-    println!("{}", display::format_price_loaded(universe, &latest_date));
+    info!("{}", display::format_price_loaded(universe, &latest_date));
 
     Ok((lf, latest_date))
 }
@@ -117,14 +196,18 @@ fn extract_unique_tickers(lf: &LazyFrame) -> Result<Vec<String>, Box<dyn StdErro
 
     let unique_tickers_series = unique_tickers_df.column("unique_tickers")?;
 
-    Ok(unique_tickers_series
+    // unique() order is not stable across runs; sort for reproducible output
+    let mut tickers: Vec<String> = unique_tickers_series
         .str()?
         .into_iter()
         .filter_map(|value| value.map(|v| v.to_string()))
-        .collect())
+        .collect();
+    tickers.sort();
+    Ok(tickers)
 }
 
-/// Scan output directory for already-processed tickers
+/// Scan output directory for already-processed tickers; a results file that is truncated or
+/// malformed (see `backtest_output_complete`) doesn't count, so that ticker is run again
 fn load_processed_tickers(
     paths: &PathConfig,
     universe: &str,
@@ -147,6 +230,10 @@ fn load_processed_tickers(
                         if filename_str.contains("_decisions") {
                             continue;
                         }
+                        if !backtest_output_complete(&path) {
+                            warn!("{}: {} is incomplete, re-running it", universe, path.display());
+                            continue;
+                        }
                         processed.insert(filename_str.to_string());
                     }
                 }
@@ -162,47 +249,96 @@ fn load_processed_tickers(
     Ok(processed)
 }
 
-/// Determine which tickers need to be processed
+/// Determine which tickers need to be processed, as (remaining, already done). Only this
+/// universe's tickers (or the custom ones) count as done: stock universes share an output
+/// folder, so `processed` can hold other universes' files.
 fn determine_tickers_to_process(
     lf: &LazyFrame,
-    paths: &PathConfig,
-    universe: &str,
-    mode: ExecutionMode,
+    processed: &HashSet<String>,
     custom_tickers: Option<Vec<String>>,
-) -> Result<Vec<String>, Box<dyn StdError>> {
-    if let Some(custom_tickers) = custom_tickers {
-        return Ok(custom_tickers);
-    }
-
-    let all_tickers = extract_unique_tickers(lf)?;
-    // let processed = load_processed_tickers(paths, universe, mode)?;
+) -> Result<(Vec<String>, Vec<String>), Box<dyn StdError>> {
+    let all_tickers = match custom_tickers {
+        Some(custom_tickers) => custom_tickers,
+        None => extract_unique_tickers(lf)?,
+    };
 
-    // let remaining: Vec<String> = all_tickers
-    //     .into_iter()
-    //     .filter(|ticker| !processed.contains(ticker))
-    //     .collect();
+    let (done, remaining): (Vec<String>, Vec<String>) =
+        all_tickers.into_iter().partition(|ticker| processed.contains(ticker));
 
-    // if !processed.is_empty() {
-    //     info!("Skipping {} already-processed tickers, {} remaining", processed.len(), remaining.len());
-    // }
+    if !done.is_empty() {
+        info!("Skipping {} already-processed tickers, {} remaining", done.len(), remaining.len());
+    }
 
-    Ok(all_tickers)
+    Ok((remaining, done))
 }
 
+/// Backtest every ticker of universe `u` under the run's configuration
 async fn backtest_helper(
-    paths: &PathConfig,
+    config: &BacktestConfig,
     u: &str,
-    batch_size: usize,
-    mode: ExecutionMode,
-    custom_tickers: Option<Vec<String>>,
-    strategy_filter: Option<&str>,
+    cache: Option<Arc<BacktestCache>>,
 ) -> Result<(), Box<dyn StdError>> {
-    let (lf, _latest_date) = load_price_data(paths, u, mode).await?;
+    let (paths, mode, batch_size) = (&config.paths, config.mode, config.batch_size);
+    let strategy_filter = config.strategy_filter.as_deref();
+    let strategy_file = config.strategy_file.as_deref();
+    let price_fallback = config.price_fallbacks.get(u).map(PathBuf::as_path);
+    let (lf, _latest_date) = load_price_data(paths, u, mode, price_fallback).await?;
+
+    // Never score stale prices in production; only warn in testing/demo
+    if let Err(e) = assert_fresh(&lf, config.max_staleness_days) {
+        if mode.is_production() {
+            return Err(e);
+        }
+        warn!("{}: {}", u, e);
+    }
 
-    let needed = determine_tickers_to_process(&lf, paths, u, mode, custom_tickers)?;
+    let lf = match config.min_price {
+        Some(min_price) => {
+            let (lf, excluded) = filter_min_price(lf, min_price)?;
+            if !excluded.is_empty() {
+                info!(
+                    "{}: skipping {} tickers with a median close below {}: {}",
+                    u,
+                    excluded.len(),
+                    min_price,
+                    excluded.join(", ")
+                );
+            }
+            lf
+        }
+        None => lf,
+    };
+
+    let lf = if config.start_date.is_some() || config.end_date.is_some() {
+        let (lf, short) = filter_date_window(lf, config.start_date, config.end_date)?;
+        if !short.is_empty() {
+            warn!(
+                "{}: {} tickers have fewer than {} bars in the date window; long indicators will be incomplete: {}",
+                u,
+                short.len(),
+                WARMUP_BARS,
+                short.join(", ")
+            );
+        }
+        lf
+    } else {
+        lf
+    };
+
+    // Custom tickers follow this universe's casing rule (matters for mixed runs)
+    let custom_tickers = config
+        .custom_tickers
+        .as_ref()
+        .map(|tickers| tickers.iter().map(|t| config::normalize_ticker(t, u)).collect());
+    let processed = if config.resume { load_processed_tickers(paths, u, mode)? } else { HashSet::new() };
+    let (needed, done) = determine_tickers_to_process(&lf, &processed, custom_tickers)?;
 
     let out_of = needed.len();
     let mut completed = 0; // Track completed backtests
+    let mut trade_counter = TradeCounter::default();
+    // Files kept from the interrupted run still belong in this run's manifest
+    let mut written = done;
+    let mut failed = Vec::new();
 
     for i in (0..needed.len()).step_by(batch_size) {
         let last = if i + batch_size > needed.len() {
@@ -220,6 +356,7 @@ async fn backtest_helper(
                 let ticker_clone: String = ticker.clone();
                 let paths_clone = paths.clone();
                 let u_clone = u.to_string();
+                let cache = cache.clone();
 
                 async move {
                     let filtered_lf = lf_clone.filter(col("Ticker").eq(lit(ticker_clone.clone())));
@@ -233,25 +370,28 @@ async fn backtest_helper(
                         }
                     }
 
-                    let tag: &str = match (mode, u_clone.as_str()) {
-                        ///////////////////////////////////
-                        // Update testing functions here //
-                        ///////////////////////////////////
-                        // "signal" = ALL (signal_functions)
-                        // "param" = param_functions
-                        // "testing" = testing_functions
-                        (ExecutionMode::Testing | ExecutionMode::Demo, _) => "signal",
-                        (ExecutionMode::Production, "Crypto") => "crypto",
-                        (ExecutionMode::Production, u) if u.starts_with("Micro") => "micro",
-                        (ExecutionMode::Production, u) if u.starts_with("SC") => "sc",
-                        (ExecutionMode::Production, u) if u.starts_with("MC") => "mc",
-                        (ExecutionMode::Production, u) if u.starts_with("LC") => "lc",
-                        (ExecutionMode::Production, _) => "prod",
+                    let tag: String = match strategy_file {
+                        Some(file) => format!("file:{}", file),
+                        None => match (mode, u_clone.as_str()) {
+                            ///////////////////////////////////
+                            // Update testing functions here //
+                            ///////////////////////////////////
+                            // "signal" = ALL (signal_functions)
+                            // "param" = param_functions
+                            // "testing" = testing_functions
+                            (ExecutionMode::Testing | ExecutionMode::Demo, _) => "signal",
+                            (ExecutionMode::Production, "Crypto") => "crypto",
+                            (ExecutionMode::Production, u) if u.starts_with("Micro") => "micro",
+                            (ExecutionMode::Production, u) if u.starts_with("SC") => "sc",
+                            (ExecutionMode::Production, u) if u.starts_with("MC") => "mc",
+                            (ExecutionMode::Production, u) if u.starts_with("LC") => "lc",
+                            (ExecutionMode::Production, _) => "prod",
+                        }.to_string(),
                     };
                     // ./target/release/backtester -u LC -m testing -t IBM
                     // cargo run -- -u Crypto -m testing -t btc
 
-                    match select_backtests(filtered_lf, tag, strategy_filter).await {
+                    match select_backtests(filtered_lf, &tag, strategy_filter, config.strategy_timeout, cache).await {
                         Ok(backtest_results) => {
                             if let Err(e) = save_backtest(
                                 &paths_clone,
@@ -262,12 +402,12 @@ async fn backtest_helper(
                             )
                             .await
                             {
-                                eprintln!("{}", display::format_save_error(e.as_ref()));
+                                error!("{}", display::format_save_error(e.as_ref()));
                             }
                             (ticker_clone, Ok(backtest_results))
                         }
                         Err(e) => {
-                            eprintln!("{}", display::format_execution_error(&ticker_clone, e.as_ref()));
+                            error!("{}", display::format_execution_error(&ticker_clone, e.as_ref()));
                             (ticker_clone, Err(e))
                         }
                     }
@@ -281,18 +421,20 @@ async fn backtest_helper(
         for (ticker, result) in results {
             match result {
                 Ok(backtest_results) => {
+                    for (bt, _) in &backtest_results {
+                        trade_counter.add(bt);
+                    }
                     if !backtest_results.is_empty() {
+                        written.push(ticker.clone());
                         completed += 1;
-                        println!(
-                            "{}",
-                            display::format_backtest_progress(u, &ticker, completed, out_of)
-                        );
+                        info!("{}", display::format_backtest_progress(u, &ticker, completed, out_of));
                     } else {
                         info!("Skipped '{}' - no data available", ticker);
                     }
                 }
                 Err(e) => {
                     error!("Failed to process '{}': {}", ticker, e);
+                    failed.push(ticker.clone());
                 }
             }
         }
@@ -305,19 +447,18 @@ async fn backtest_helper(
         tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
     }
 
+    // A strategy with no trades on any ticker is almost always a broken signal
+    trade_counter.warn_zero_trades(u);
+
+    write_manifest(paths, u, &written, &failed, mode.is_production())?;
+
     Ok(())
 }
 
 /// Setup logging based on verbosity level
 fn setup_logging(verbose: u8) {
-    let log_level = match verbose {
-        0 => "warn",
-        1 => "info",
-        2 => "debug",
-        _ => "trace",
-    };
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(log_level))
-        .init();
+    init_logging(verbose);
+    info!("Run id: {}", run_id());
 }
 
 /// Monitor memory usage and log statistics
@@ -354,8 +495,8 @@ fn monitor_memory(batch_num: usize) -> (f64, f64) {
 
 /// Clean up output directories and decision files based on mode
 async fn cleanup_files(config: &BacktestConfig) -> Result<(), Box<dyn StdError>> {
-    // Skip cleanup when using --output (resume mode for existing folders)
-    if config.paths.output_suffix.is_some() {
+    // Skip cleanup when using --output or --resume (resume mode for existing folders)
+    if config.paths.output_suffix.is_some() || config.resume {
         info!("Skipping cleanup: using existing output folder (resume mode)");
         return Ok(());
     }
@@ -388,66 +529,107 @@ async fn cleanup_files(config: &BacktestConfig) -> Result<(), Box<dyn StdError>>
 
 /// Run backtests for all configured universes
 async fn run_backtests(config: &BacktestConfig) -> Result<(), Box<dyn StdError>> {
-    for u in &config.universes {
-        info!("Backtest starting: {} (mode: {:?})", u, config.mode);
-
-        backtest_helper(
-            &config.paths,
-            u,
-            config.batch_size,
-            config.mode,
-            config.custom_tickers.clone(),
-            config.strategy_filter.as_deref(),
-        )
-        .await?;
+    // Fail fast on a bad strategy file rather than once per ticker
+    if let Some(ref file) = config.strategy_file {
+        let strategies = strategy_config::strategies_from_file(file)?;
+        info!("Running {} strategies from {}", strategies.len(), file);
     }
+
+    let cache = match &config.cache_dir {
+        Some(dir) => {
+            info!("Reusing cached backtests from {}", dir);
+            Some(Arc::new(BacktestCache::new(dir)?))
+        }
+        None => None,
+    };
+
+    for_each_universe(config, |u| {
+        let cache = cache.clone();
+        async move {
+            info!("Backtest starting: {} (mode: {:?})", u, config.mode);
+
+            backtest_helper(config, &u, cache).await
+        }
+    })
+    .await?;
     info!("Backtest processing complete");
     Ok(())
 }
 
+/// Run `backtest` on each configured universe in turn, stopping at the first error
+async fn for_each_universe<F, Fut>(config: &BacktestConfig, mut backtest: F) -> Result<(), Box<dyn StdError>>
+where
+    F: FnMut(String) -> Fut,
+    Fut: std::future::Future<Output = Result<(), Box<dyn StdError>>>,
+{
+    for u in &config.universes {
+        backtest(u.clone()).await?;
+    }
+    Ok(())
+}
+
 /// Generate performance summaries and insert scores for production mode
 async fn generate_summaries(config: &BacktestConfig) -> Result<(), Box<dyn StdError>> {
-    if config.mode.is_production() {
-        if config::any_crypto_universe(&config.universes) {
-            let (datetag, _out) = summary_performance_file(
-                &config.paths,
-                true,
-                false,
-                config.universes.clone(),
-                &config.universe_label,
-            )
-            .await?;
-
-            if let Err(e) = score(&datetag, "Crypto", &config.universe_label).await {
-                eprintln!("{}", display::format_score_error(e.as_ref()));
-            }
-        }
+    // Demo runs reuse existing data and produce no summaries
+    if config.mode.is_demo() {
+        return Ok(());
+    }
+
+    // One summary per asset class; only production inserts scores
+    for group in config.asset_class_groups() {
+        let (datetag, _out) = summary_performance_file(
+            &config.paths,
+            config.mode.is_production(),
+            group.stocks,
+            group.universes.clone(),
+            &group.label,
+        )
+        .await?;
 
-        if config::any_stock_universe(&config.universes) {
-            let (datetag, _out) = summary_performance_file(
-                &config.paths,
-                true,
-                true,
-                config.universes.clone(),
-                &config.universe_label,
-            )
-            .await?;
-
-            if let Err(e) = score(&datetag, "Stocks", &config.universe_label).await {
-                eprintln!("{}", display::format_score_error(e.as_ref()));
+        if config.mode.is_production() {
+            let univ_str = if group.stocks { "Stocks" } else { "Crypto" };
+            // A run limited to -t tickers only rescores those tickers
+            let only_tickers = config.custom_tickers.clone();
+            let options = ScoreOptions {
+                only_tickers,
+                carry_positions: config.carry_positions,
+                output_root: config.paths.output_root.clone(),
+                aggregation: config.score_aggregation,
+            };
+            if let Err(e) = score(&datetag, univ_str, &group.label, &options).await {
+                error!("{}", display::format_score_error(e.as_ref()));
             }
         }
-    } else {
-        // In testing mode, generate a summary but don't insert scores
-        let is_crypto = config::any_crypto_universe(&config.universes);
-        if !config.mode.is_demo() {
-            let _ = summary_performance_file(&config.paths, false, !is_crypto, config.universes.clone(), &config.universe_label)
-                .await?;
-        }
     }
     Ok(())
 }
 
+/// Build the run configuration from command-line arguments
+fn build_config(args: Args) -> Result<BacktestConfig, Box<dyn StdError>> {
+    let universe = if args.all { "All".to_string() } else { args.universe };
+    let mut config = BacktestConfig::new(
+        args.path,
+        universe,
+        args.mode,
+        args.tickers,
+        args.strategy,
+        args.output,
+    )?;
+    config.max_staleness_days = args.max_staleness_days;
+    config.min_price = args.min_price;
+    config.strategy_timeout = args.strategy_timeout.map(std::time::Duration::from_secs);
+    config.start_date = args.start_date.as_deref().map(parse_trade_date).transpose()?;
+    config.end_date = args.end_date.as_deref().map(parse_trade_date).transpose()?;
+    config.strategy_file = args.strategy_file;
+    config.price_fallback = !args.no_fallback;
+    config.carry_positions = args.carry_positions;
+    config.score_aggregation = args.score_aggregation;
+    config.paths = config.paths.with_output_root(args.output_root);
+    config.resume = args.resume;
+    config.cache_dir = args.cache_dir;
+    Ok(config)
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn StdError>> {
     let args = Args::parse();
@@ -455,15 +637,22 @@ async fn main() -> Result<(), Box<dyn StdError>> {
     // Setup logging
     setup_logging(args.verbose);
 
+    if args.list_strategies {
+        println!("{}", display::format_strategy_header());
+        for spec in strategy_config::list_all_signals() {
+            println!("{}", display::format_strategy_row(&spec.name, spec.category, spec.default_param));
+        }
+        return Ok(());
+    }
+
+    if let Some(path) = &args.import_score {
+        let rows = import_score_csv(path).await?;
+        println!("Imported {} score rows from {}", rows, path);
+        return Ok(());
+    }
+
     // Create configuration from args
-    let config = BacktestConfig::new(
-        args.path,
-        args.universe,
-        args.mode,
-        args.tickers,
-        args.strategy,
-        args.output,
-    )?;
+    let mut config = build_config(args)?;
 
     info!("Starting backtester with universe: {:?}, mode: {:?}", config.universes, config.mode);
     if let Some(ref t) = config.custom_tickers {
@@ -478,7 +667,12 @@ async fn main() -> Result<(), Box<dyn StdError>> {
 
     // Create price files if needed (skip in demo mode)
     if !config.mode.is_demo() {
-        create_price_files(config.universes.clone(), config.mode.is_production()).await?;
+        config.price_fallbacks = create_price_files(
+            config.universes.clone(),
+            config.mode.is_production(),
+            config.price_fallback,
+        )
+        .await?;
     }
 
     // Run backtests
@@ -516,14 +710,13 @@ pub async fn single_backtest(signal: Signal) -> Result<(), Box<dyn StdError>> {
     Ok(())
 }
 
-pub async fn single_backtest_sized(signal: Signal) -> Result<(), Box<dyn StdError>> {
+pub async fn single_backtest_sized(signal: Signal, initial_cash: f64) -> Result<(), Box<dyn StdError>> {
     // Step 1: Load your data into a LazyFrame
     let file_path = "/Users/rogerbos/rust_home/backtester/data/testing/crypto.csv";
     let lf = read_price_file(file_path.to_string()).await?;
     let ticker = "btc";
     let filtered_lf = lf.filter(col("Ticker").eq(lit(ticker)));
-    let entry_amount = 1000.0;
-    let exit_amount = 1000.0;
+    let sizing = SizingConfig { initial_cash, ..SizingConfig::new(1000.0, 1000.0) };
 
     // Step 2: Run the backtest using the signal
     let signal_name = signal.name.clone();
@@ -532,8 +725,7 @@ pub async fn single_backtest_sized(signal: Signal) -> Result<(), Box<dyn StdErro
         *signal.func.clone(),
         signal.param,
         signal_name.clone(),
-        entry_amount,
-        exit_amount,
+        &sizing,
     )
     .await?;
 
@@ -552,6 +744,6 @@ pub async fn testme() -> Result<(), Box<dyn StdError>> {
         param: 2.0,
     };
     let _ = single_backtest(signal).await?;
-    // let _ = single_backtest_sized(signal).await?;
+    // let _ = single_backtest_sized(signal, 100_000.0).await?;
     Ok(())
 }
@@ -3,19 +3,132 @@
 //! A standalone accounting library for tracking portfolio cash flows, positions,
 //! and performance with flexible position sizing and per-transaction commissions.
 
-use chrono::NaiveDate;
+use crate::config::AnnualizationBasis;
+use chrono::{Datelike, NaiveDate, Weekday};
 use polars::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::error::Error as StdError;
-use std::fs::File;
 
 /// Configuration for portfolio accounting
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct AccountingConfig {
     // Removed target_portfolio_size, commission_per_trade,
     // rebalance_frequency, and rebalance_threshold_pct
     // as these are no longer needed in the simplified version
+    /// How `RealizedPnL.holding_days` is counted
+    #[serde(default)]
+    pub holding_days: HoldingDayCount,
+}
+
+/// Day count used for holding periods
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub enum HoldingDayCount {
+    /// Calendar days between entry and exit
+    #[default]
+    Calendar,
+    /// Weekdays after the entry date up to and including the exit date (holidays count)
+    BusinessDays,
+    /// Dates of the price frame after the entry date up to and including the exit date;
+    /// build with `HoldingDayCount::trading_dates` so the list is sorted
+    TradingDates(Vec<NaiveDate>),
+}
+
+impl HoldingDayCount {
+    /// Trading-calendar count from the dates present in a price frame
+    pub fn trading_dates(dates: impl IntoIterator<Item = NaiveDate>) -> Self {
+        let mut dates: Vec<NaiveDate> = dates.into_iter().collect();
+        dates.sort();
+        dates.dedup();
+        Self::TradingDates(dates)
+    }
+
+    /// Days held from `entry` to `exit`
+    pub fn count(&self, entry: NaiveDate, exit: NaiveDate) -> i64 {
+        match self {
+            Self::Calendar => (exit - entry).num_days(),
+            Self::BusinessDays => entry
+                .iter_days()
+                .skip(1)
+                .take_while(|d| *d <= exit)
+                .filter(|d| !matches!(d.weekday(), Weekday::Sat | Weekday::Sun))
+                .count() as i64,
+            Self::TradingDates(dates) => {
+                (dates.partition_point(|d| *d <= exit) - dates.partition_point(|d| *d <= entry)) as i64
+            }
+        }
+    }
+}
+
+/// Period of `PortfolioAccounting::equity_curve_resampled`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EquityFrequency {
+    /// ISO weeks (Monday to Sunday)
+    Weekly,
+    /// Calendar months
+    Monthly,
+}
+
+impl EquityFrequency {
+    /// Key shared by every date in the same period
+    fn period(&self, date: NaiveDate) -> (i32, u32) {
+        match self {
+            Self::Weekly => (date.iso_week().year(), date.iso_week().week()),
+            Self::Monthly => (date.year(), date.month()),
+        }
+    }
+}
+
+/// Last (date, value) of each period of a date-ordered equity curve
+pub fn resample_equity(
+    curve: impl IntoIterator<Item = (NaiveDate, f64)>,
+    freq: EquityFrequency,
+) -> Vec<(NaiveDate, f64)> {
+    let mut points: Vec<(NaiveDate, f64)> = Vec::new();
+    for (date, value) in curve {
+        match points.last_mut() {
+            Some(last) if freq.period(last.0) == freq.period(date) => *last = (date, value),
+            _ => points.push((date, value)),
+        }
+    }
+    points
+}
+
+/// Monthly returns (%) of a date-ordered equity curve starting from `initial_value`, as
+/// one row per year with `Jan`..`Dec` columns and a compounded `YTD`. Each month runs
+/// from the previous month's last value (the initial value for the first month) to its
+/// own last value; months without any point are null.
+pub fn monthly_return_table(curve: &[(NaiveDate, f64)], initial_value: f64) -> DataFrame {
+    const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+    let month_ends = resample_equity(curve.iter().cloned(), EquityFrequency::Monthly);
+
+    let mut years: Vec<i32> = Vec::new();
+    let mut table: Vec<[Option<f64>; 12]> = Vec::new();
+    let mut previous = initial_value;
+    for (date, value) in month_ends {
+        if years.last() != Some(&date.year()) {
+            years.push(date.year());
+            table.push([None; 12]);
+        }
+        if previous > 0.0 {
+            table.last_mut().unwrap()[date.month0() as usize] = Some((value / previous - 1.0) * 100.0);
+        }
+        previous = value;
+    }
+
+    let mut columns = vec![Column::new("year".into(), &years)];
+    for (m, name) in MONTHS.iter().enumerate() {
+        columns.push(Column::new((*name).into(), table.iter().map(|row| row[m]).collect::<Vec<_>>()));
+    }
+    let ytd: Vec<Option<f64>> = table
+        .iter()
+        .map(|row| {
+            let growth = row.iter().flatten().map(|r| 1.0 + r / 100.0).product::<f64>();
+            Some((growth - 1.0) * 100.0)
+        })
+        .collect();
+    columns.push(Column::new("YTD".into(), ytd));
+    DataFrame::new(columns).expect("one value per year in every column")
 }
 
 /// Transaction type
@@ -67,6 +180,12 @@ pub struct DailySnapshot {
     pub total_unrealized_pnl: f64,
     pub total_realized_pnl_to_date: f64,
     pub daily_return_pct: f64,
+    /// Sum of absolute position values over total value
+    #[serde(default)]
+    pub gross_exposure: f64,
+    /// Signed sum of position values (shorts negative) over total value
+    #[serde(default)]
+    pub net_exposure: f64,
 }
 
 /// Cash flow event
@@ -127,11 +246,64 @@ pub struct PerformanceSummary {
     pub profit_factor: f64,
     pub max_drawdown_pct: f64,
     pub sharpe_ratio: f64,
+    /// Highest gross exposure across the daily snapshots
+    pub peak_gross_exposure: f64,
+    /// Percentage of daily snapshots holding at least one position
+    #[serde(default)]
+    pub pct_time_in_market: f64,
+    /// `total_return_pct` per unit of time in market (`total_return_pct` over the
+    /// fraction of days invested), so a strategy that is rarely invested is not
+    /// penalized against one that always is; 0 when never invested
+    #[serde(default)]
+    pub return_per_exposure: f64,
+    /// Return (%) on the deployed capital alone: the portfolio's P&L over the average
+    /// gross position value (longs plus absolute shorts) of the snapshots holding
+    /// positions, so idle cash doesn't dilute it the way it does `total_return_pct`;
+    /// 0 when never invested
+    #[serde(default)]
+    pub invested_return: f64,
     pub avg_holding_days: f64,
     pub max_holding_days: i64,
     pub min_holding_days: i64,
 }
 
+/// Drawdown kill switch: once the drawdown from `peak_value` exceeds `max_dd_pct`,
+/// new buys are rejected until `reset_halt()` or recovery to `reset_dd_pct`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RiskHalt {
+    pub max_dd_pct: f64,
+    pub reset_dd_pct: Option<f64>,
+    pub peak_value: f64,
+    pub halted: bool,
+}
+
+/// How the commission on a trade is charged
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum CommissionModel {
+    /// Flat fee per trade
+    PerTrade(f64),
+    /// Fraction of the traded notional (0.001 = 10 bps)
+    Percent(f64),
+}
+
+impl CommissionModel {
+    pub fn commission(&self, shares: f64, price: f64) -> f64 {
+        match *self {
+            Self::PerTrade(fee) => fee,
+            Self::Percent(rate) => shares * price * rate,
+        }
+    }
+
+    /// Most whole shares that `cash` can buy at `price` including commission
+    fn affordable_shares(&self, cash: f64, price: f64) -> f64 {
+        let shares = match *self {
+            Self::PerTrade(fee) => (cash - fee) / price,
+            Self::Percent(rate) => cash / (price * (1.0 + rate)),
+        };
+        shares.floor().max(0.0)
+    }
+}
+
 /// Main portfolio accounting system
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PortfolioAccounting {
@@ -143,13 +315,21 @@ pub struct PortfolioAccounting {
     pub daily_snapshots: Vec<DailySnapshot>,
     pub cash_flows: Vec<CashFlow>,
     pub realized_pnl: Vec<RealizedPnL>,
+    #[serde(default)]
+    pub risk_halt: Option<RiskHalt>,
+    /// Annualization for Sharpe and CAGR (defaults to 252 trading days and calendar years)
+    #[serde(default)]
+    pub annualization: AnnualizationBasis,
+    /// `rebalance_to` skips a ticker whose weight is within this distance of its target
+    #[serde(default)]
+    pub rebalance_band: f64,
     // Removed last_rebalance_date as we no longer track rebalancing
 }
 
 impl PortfolioAccounting {
     /// Create a new portfolio accounting instance
     pub fn new(initial_cash: f64) -> Self {
-        let config = AccountingConfig {};
+        let config = AccountingConfig::default();
         Self {
             config,
             cash_balance: initial_cash,
@@ -159,6 +339,64 @@ impl PortfolioAccounting {
             daily_snapshots: Vec::new(),
             cash_flows: Vec::new(),
             realized_pnl: Vec::new(),
+            risk_halt: None,
+            annualization: AnnualizationBasis::default(),
+            rebalance_band: 0.0,
+        }
+    }
+
+    // ============================================================================
+    // Risk Halt
+    // ============================================================================
+
+    /// Halt new entries once the drawdown (as measured by `calculate_max_drawdown`)
+    /// exceeds `max_dd_pct` percent. Sells are always allowed.
+    pub fn risk_halt(&mut self, max_dd_pct: f64) {
+        let peak_value = self.daily_snapshots.iter()
+            .map(|s| s.total_value)
+            .fold(self.initial_cash, f64::max);
+        self.risk_halt = Some(RiskHalt {
+            max_dd_pct,
+            reset_dd_pct: None,
+            peak_value,
+            halted: false,
+        });
+        self.update_risk_halt();
+    }
+
+    /// Lift an active halt automatically once drawdown recovers to `reset_dd_pct` percent
+    pub fn set_halt_reset_threshold(&mut self, reset_dd_pct: f64) {
+        if let Some(halt) = self.risk_halt.as_mut() {
+            halt.reset_dd_pct = Some(reset_dd_pct);
+        }
+    }
+
+    /// Manually lift the halt; drawdown is measured from the current value from here on
+    pub fn reset_halt(&mut self) {
+        let total_value = self.get_total_value();
+        if let Some(halt) = self.risk_halt.as_mut() {
+            halt.halted = false;
+            halt.peak_value = total_value;
+        }
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.risk_halt.as_ref().is_some_and(|h| h.halted)
+    }
+
+    /// Re-evaluate the halt against the latest snapshot
+    fn update_risk_halt(&mut self) {
+        let Some(total_value) = self.daily_snapshots.last().map(|s| s.total_value) else {
+            return;
+        };
+        if let Some(halt) = self.risk_halt.as_mut() {
+            halt.peak_value = halt.peak_value.max(total_value);
+            let dd = ((halt.peak_value - total_value) / halt.peak_value) * 100.0;
+            if dd > halt.max_dd_pct {
+                halt.halted = true;
+            } else if halt.halted && halt.reset_dd_pct.is_some_and(|reset| dd <= reset) {
+                halt.halted = false;
+            }
         }
     }
 
@@ -187,6 +425,13 @@ impl PortfolioAccounting {
             return Err("Commission cannot be negative".to_string());
         }
 
+        if let Some(halt) = self.risk_halt.as_ref().filter(|h| h.halted) {
+            return Err(format!(
+                "Risk halt: drawdown exceeded {:.2}%, new entries blocked",
+                halt.max_dd_pct
+            ));
+        }
+
         let gross_amount = shares * price;
         let net_amount = gross_amount + commission;
 
@@ -302,7 +547,7 @@ impl PortfolioAccounting {
             sell_commission: commission,
             net_pnl: net_amount - cost_for_shares,
             pnl_pct: ((net_amount / cost_for_shares) - 1.0) * 100.0,
-            holding_days: (date - position.entry_date).num_days(),
+            holding_days: self.config.holding_days.count(position.entry_date, date),
         };
 
         self.realized_pnl.push(realized_pnl);
@@ -390,6 +635,73 @@ impl PortfolioAccounting {
             });
     }
 
+    // ============================================================================
+    // Rebalancing
+    // ============================================================================
+
+    /// Set the no-trade band for `rebalance_to` (absolute weight, e.g. 0.02 = 2%)
+    pub fn set_rebalance_band(&mut self, band: f64) {
+        self.rebalance_band = band.max(0.0);
+    }
+
+    /// Trade toward `target_weights` (fractions of total value) in whole shares at
+    /// `prices`. Held tickers missing from the targets are sold. Sells go first to
+    /// fund the buys; tickers within `rebalance_band` of their target are left alone.
+    pub fn rebalance_to(
+        &mut self,
+        date: NaiveDate,
+        target_weights: &HashMap<String, f64>,
+        prices: &HashMap<String, f64>,
+        commission_model: CommissionModel,
+    ) -> Result<Vec<Transaction>, String> {
+        self.mark_to_market(date, prices);
+        let total_value = self.get_total_value();
+        if total_value <= 0.0 {
+            return Err("Cannot rebalance a portfolio with no value".to_string());
+        }
+
+        // Sorted for deterministic execution order
+        let mut tickers: Vec<String> = target_weights.keys().chain(self.positions.keys()).cloned().collect();
+        tickers.sort();
+        tickers.dedup();
+
+        let mut sells = Vec::new();
+        let mut buys = Vec::new();
+        for ticker in tickers {
+            let target = target_weights.get(&ticker).copied().unwrap_or(0.0);
+            let current_value = self.positions.get(&ticker).map_or(0.0, |p| p.current_value);
+            if (target - current_value / total_value).abs() <= self.rebalance_band {
+                continue;
+            }
+            let price = *prices
+                .get(&ticker)
+                .ok_or_else(|| format!("No price for {} to rebalance", ticker))?;
+            let shares = ((target * total_value - current_value) / price).trunc();
+            if shares < 0.0 {
+                sells.push((ticker, target, -shares, price));
+            } else if shares > 0.0 {
+                buys.push((ticker, shares, price));
+            }
+        }
+
+        let mut transactions = Vec::new();
+        for (ticker, target, shares, price) in sells {
+            let held = self.positions.get(&ticker).map_or(0.0, |p| p.shares);
+            let shares = if target <= 0.0 { held } else { shares.min(held) };
+            let commission = commission_model.commission(shares, price);
+            transactions.push(self.execute_sell_shares(date, &ticker, shares, price, commission)?);
+        }
+        for (ticker, shares, price) in buys {
+            let shares = shares.min(commission_model.affordable_shares(self.cash_balance, price));
+            if shares <= 0.0 {
+                continue;
+            }
+            let commission = commission_model.commission(shares, price);
+            transactions.push(self.execute_buy(date, &ticker, shares, price, commission)?);
+        }
+        Ok(transactions)
+    }
+
     /// Update position values with current prices (mark-to-market)
     pub fn mark_to_market(&mut self, date: NaiveDate, prices: &HashMap<String, f64>) {
         for (ticker, position) in self.positions.iter_mut() {
@@ -421,10 +733,22 @@ impl PortfolioAccounting {
         let total_unrealized_pnl: f64 = pnl_values.iter().map(|(_, v)| v).sum();
         let total_realized_pnl_to_date: f64 = self.realized_pnl.iter().map(|p| p.net_pnl).sum();
 
-        let daily_return_pct = if let Some(prev) = self.daily_snapshots.last() {
-            ((total_value / prev.total_value) - 1.0) * 100.0
+        let prev_value = self.daily_snapshots.last().map_or(self.initial_cash, |prev| prev.total_value);
+        let daily_return_pct = crate::ReturnMethod::Pct.between(prev_value, total_value);
+
+        // Sorted by ticker for a deterministic floating-point sum
+        let mut position_values: Vec<(&String, f64)> = self.positions
+            .iter()
+            .map(|(k, p)| (k, p.current_value))
+            .collect();
+        position_values.sort_by(|a, b| a.0.cmp(b.0));
+        let (gross_exposure, net_exposure) = if total_value > 0.0 {
+            (
+                position_values.iter().map(|(_, v)| v.abs()).sum::<f64>() / total_value,
+                position_values.iter().map(|(_, v)| v).sum::<f64>() / total_value,
+            )
         } else {
-            ((total_value / self.initial_cash) - 1.0) * 100.0
+            (0.0, 0.0)
         };
 
         let snapshot = DailySnapshot {
@@ -436,9 +760,12 @@ impl PortfolioAccounting {
             total_unrealized_pnl,
             total_realized_pnl_to_date,
             daily_return_pct,
+            gross_exposure,
+            net_exposure,
         };
 
         self.daily_snapshots.push(snapshot);
+        self.update_risk_halt();
     }
 
     // ============================================================================
@@ -540,6 +867,19 @@ impl PortfolioAccounting {
         result
     }
 
+    /// (date, total value) of the last snapshot in each week or month, e.g. month-end
+    /// equity for monthly-return tables. A trailing period that is still in progress
+    /// ends at its latest snapshot.
+    pub fn equity_curve_resampled(&self, freq: EquityFrequency) -> Vec<(NaiveDate, f64)> {
+        resample_equity(self.daily_snapshots.iter().map(|s| (s.date, s.total_value)), freq)
+    }
+
+    /// Calendar table of monthly returns (%) of the total value: see `monthly_return_table`
+    pub fn monthly_returns(&self) -> DataFrame {
+        let curve: Vec<(NaiveDate, f64)> = self.daily_snapshots.iter().map(|s| (s.date, s.total_value)).collect();
+        monthly_return_table(&curve, self.initial_cash)
+    }
+
     // ============================================================================
     // Performance Metrics
     // ============================================================================
@@ -616,6 +956,32 @@ impl PortfolioAccounting {
             0.0
         };
 
+        let peak_gross_exposure = self.daily_snapshots.iter().map(|s| s.gross_exposure).fold(0.0, f64::max);
+        let pct_time_in_market = if self.daily_snapshots.is_empty() {
+            0.0
+        } else {
+            let invested = self.daily_snapshots.iter().filter(|s| s.position_count > 0).count();
+            invested as f64 / self.daily_snapshots.len() as f64 * 100.0
+        };
+        let return_per_exposure = if pct_time_in_market > 0.0 {
+            total_return_pct / (pct_time_in_market / 100.0)
+        } else {
+            0.0
+        };
+        // Gross position value, so shorts count as deployed rather than offsetting longs
+        let deployed: Vec<f64> = self
+            .daily_snapshots
+            .iter()
+            .filter(|s| s.position_count > 0)
+            .map(|s| s.gross_exposure * s.total_value)
+            .collect();
+        let avg_deployed = if deployed.is_empty() { 0.0 } else { deployed.iter().sum::<f64>() / deployed.len() as f64 };
+        let invested_return = if avg_deployed > 0.0 {
+            (final_value - self.initial_cash) / avg_deployed * 100.0
+        } else {
+            0.0
+        };
+
         let max_holding_days = holding_days.iter().max().copied().unwrap_or(0);
         let min_holding_days = holding_days.iter().min().copied().unwrap_or(0);
 
@@ -636,6 +1002,10 @@ impl PortfolioAccounting {
             profit_factor,
             max_drawdown_pct,
             sharpe_ratio,
+            peak_gross_exposure,
+            pct_time_in_market,
+            return_per_exposure,
+            invested_return,
             avg_holding_days,
             max_holding_days,
             min_holding_days,
@@ -654,9 +1024,8 @@ impl PortfolioAccounting {
         let initial_value = first_snapshot.total_value;
         let final_value = last_snapshot.total_value;
 
-        // Calculate years as a fraction
-        let days = (last_snapshot.date - first_snapshot.date).num_days();
-        let years = days as f64 / 365.25;
+        // Calculate years as a fraction of the annualization basis
+        let years = self.annualization.years(&self.snapshot_dates());
 
         // Avoid division by zero and negative values
         if years <= 0.0 || initial_value <= 0.0 {
@@ -669,22 +1038,15 @@ impl PortfolioAccounting {
         cagr
     }
 
-    fn calculate_max_drawdown(&self) -> f64 {
-        let mut peak = self.initial_cash;
-        let mut max_dd = 0.0;
-
-        for snapshot in &self.daily_snapshots {
-            if snapshot.total_value > peak {
-                peak = snapshot.total_value;
-            }
-
-            let dd = ((peak - snapshot.total_value) / peak) * 100.0;
-            if dd > max_dd {
-                max_dd = dd;
-            }
-        }
+    fn snapshot_dates(&self) -> Vec<NaiveDate> {
+        self.daily_snapshots.iter().map(|s| s.date).collect()
+    }
 
-        max_dd
+    fn calculate_max_drawdown(&self) -> f64 {
+        let equity: Vec<f64> = std::iter::once(self.initial_cash)
+            .chain(self.daily_snapshots.iter().map(|s| s.total_value))
+            .collect();
+        crate::max_relative_drawdown(&equity).0 * 100.0
     }
 
     fn calculate_sharpe_ratio(&self) -> f64 {
@@ -708,7 +1070,8 @@ impl PortfolioAccounting {
         let std_dev = variance.sqrt();
 
         if std_dev > 0.0 {
-            (avg_daily_return / std_dev) * (252.0_f64).sqrt() // Annualized
+            // Annualized
+            (avg_daily_return / std_dev) * self.annualization.periods_per_year(&self.snapshot_dates()).sqrt()
         } else {
             0.0
         }
@@ -785,8 +1148,7 @@ impl PortfolioAccounting {
             "cash_impact" => cash_impact,
         }?;
 
-        let mut file = File::create(path)?;
-        CsvWriter::new(&mut file).finish(&mut df.clone())?;
+        crate::write_csv_atomic(&mut df.clone(), path)?;
         Ok(())
     }
 
@@ -816,8 +1178,7 @@ impl PortfolioAccounting {
             "unrealized_pnl_pct" => unrealized_pct,
         }?;
 
-        let mut file = File::create(path)?;
-        CsvWriter::new(&mut file).finish(&mut df.clone())?;
+        crate::write_csv_atomic(&mut df.clone(), path)?;
         Ok(())
     }
 
@@ -856,8 +1217,7 @@ impl PortfolioAccounting {
             "holding_days" => holding_days,
         }?;
 
-        let mut file = File::create(path)?;
-        CsvWriter::new(&mut file).finish(&mut df.clone())?;
+        crate::write_csv_atomic(&mut df.clone(), path)?;
         Ok(())
     }
 
@@ -879,6 +1239,8 @@ impl PortfolioAccounting {
             .iter()
             .map(|s| s.position_count as u32)
             .collect();
+        let gross: Vec<f64> = self.daily_snapshots.iter().map(|s| s.gross_exposure).collect();
+        let net: Vec<f64> = self.daily_snapshots.iter().map(|s| s.net_exposure).collect();
 
         let df = df! {
             "date" => dates,
@@ -886,10 +1248,11 @@ impl PortfolioAccounting {
             "equity_value" => equity,
             "total_value" => total,
             "position_count" => positions,
+            "gross_exposure" => gross,
+            "net_exposure" => net,
         }?;
 
-        let mut file = File::create(path)?;
-        CsvWriter::new(&mut file).finish(&mut df.clone())?;
+        crate::write_csv_atomic(&mut df.clone(), path)?;
         Ok(())
     }
 
@@ -914,41 +1277,29 @@ impl PortfolioAccounting {
             "cash_balance_after" => balances,
         }?;
 
-        let mut file = File::create(path)?;
-        CsvWriter::new(&mut file).finish(&mut df.clone())?;
+        crate::write_csv_atomic(&mut df.clone(), path)?;
         Ok(())
     }
 
     pub fn print_performance_summary(&self, summary: &PerformanceSummary) {
-        // Helper to format numbers with commas
-        fn fmt_money(val: f64) -> String {
-            let abs_val = val.abs();
-            let sign = if val < 0.0 { "-" } else { "" };
-            if abs_val >= 1_000_000.0 {
-                format!("{}{:>3},{:03},{:06.2}", sign, (abs_val / 1_000_000.0) as u64,
-                        ((abs_val % 1_000_000.0) / 1_000.0) as u64, abs_val % 1_000.0)
-            } else if abs_val >= 1_000.0 {
-                format!("{}{:>3},{:06.2}", sign, (abs_val / 1_000.0) as u64, abs_val % 1_000.0)
-            } else {
-                format!("{}{:>10.2}", sign, abs_val)
-            }
-        }
-
         println!("\n╔══════════════════════════════════════════════════════════════╗");
         println!("║              PORTFOLIO PERFORMANCE SUMMARY                   ║");
         println!("╚══════════════════════════════════════════════════════════════╝");
 
         println!("\n┌─ Portfolio Value ────────────────────────────────────────────┐");
-        println!("│  Initial Value:            ${:>17}                │", fmt_money(summary.initial_value));
-        println!("│  Final Value:              ${:>17}                │", fmt_money(summary.final_value));
+        println!("│  Initial Value:            ${:>17}                │", format_money(summary.initial_value, 2));
+        println!("│  Final Value:              ${:>17}                │", format_money(summary.final_value, 2));
         println!("│  Total Return:              {:>17.2}%               │", summary.total_return_pct);
         println!("│  CAGR:                      {:>17.2}%               │", summary.cagr);
+        println!("│  Time in Market:            {:>17.1}%               │", summary.pct_time_in_market);
+        println!("│  Return per Exposure:       {:>17.2}%               │", summary.return_per_exposure);
+        println!("│  Invested Return:           {:>17.2}%               │", summary.invested_return);
         println!("└──────────────────────────────────────────────────────────────┘");
 
         println!("\n┌─ P&L Summary ────────────────────────────────────────────────┐");
-        println!("│  Realized P&L:             ${:>17}                │", fmt_money(summary.total_realized_pnl));
-        println!("│  Unrealized P&L:           ${:>17}                │", fmt_money(summary.total_unrealized_pnl));
-        println!("│  Total Commissions:        ${:>17}                │", fmt_money(summary.total_commissions));
+        println!("│  Realized P&L:             ${:>17}                │", format_money(summary.total_realized_pnl, 2));
+        println!("│  Unrealized P&L:           ${:>17}                │", format_money(summary.total_unrealized_pnl, 2));
+        println!("│  Total Commissions:        ${:>17}                │", format_money(summary.total_commissions, 2));
         println!("└──────────────────────────────────────────────────────────────┘");
 
         println!("\n┌─ Trade Statistics ───────────────────────────────────────────┐");
@@ -963,6 +1314,7 @@ impl PortfolioAccounting {
         println!("\n┌─ Risk Metrics ───────────────────────────────────────────────┐");
         println!("│  Max Drawdown:              {:>16.2}%                │", summary.max_drawdown_pct);
         println!("│  Sharpe Ratio:              {:>17.3}                │", summary.sharpe_ratio);
+        println!("│  Peak Gross Exposure:       {:>16.2}%                │", summary.peak_gross_exposure * 100.0);
         println!("└──────────────────────────────────────────────────────────────┘");
 
         println!("\n┌─ Holding Period ─────────────────────────────────────────────┐");
@@ -973,6 +1325,33 @@ impl PortfolioAccounting {
     }
 }
 
+/// Format a money amount with comma thousands grouping and a fixed number of decimals,
+/// e.g. `format_money(-1234567.891, 2)` gives `-1,234,567.89`
+pub fn format_money(value: f64, decimals: usize) -> String {
+    // Round first so carries (999.999 -> 1,000.00) are grouped correctly
+    let formatted = format!("{:.*}", decimals, value.abs());
+    let (int_part, frac_part) = match formatted.split_once('.') {
+        Some((int_part, frac_part)) => (int_part, Some(frac_part)),
+        None => (formatted.as_str(), None),
+    };
+
+    let mut grouped = String::with_capacity(int_part.len() + int_part.len() / 3);
+    for (i, ch) in int_part.chars().enumerate() {
+        if i > 0 && (int_part.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(ch);
+    }
+
+    // No sign when the value rounds to zero
+    let is_zero = formatted.chars().all(|c| c == '0' || c == '.');
+    let sign = if value < 0.0 && !is_zero { "-" } else { "" };
+    match frac_part {
+        Some(frac_part) => format!("{}{}.{}", sign, grouped, frac_part),
+        None => format!("{}{}", sign, grouped),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -984,6 +1363,78 @@ mod tests {
         assert_eq!(acct.get_position_count(), 0);
     }
 
+    #[test]
+    fn test_equity_curve_resampled() {
+        let mut acct = PortfolioAccounting::new(100_000.0);
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        acct.execute_buy(start, "AAPL", 100.0, 50.0, 0.0).unwrap();
+        // Jan 1 through Feb 29, the price rising a dollar a day
+        for day in 0..60 {
+            let date = start + chrono::Duration::days(day);
+            acct.mark_to_market(date, &HashMap::from([("AAPL".to_string(), 50.0 + day as f64)]));
+            acct.take_daily_snapshot(date);
+        }
+        let value = |day: i64| 95_000.0 + 100.0 * (50.0 + day as f64);
+
+        let monthly = acct.equity_curve_resampled(EquityFrequency::Monthly);
+        assert_eq!(
+            monthly,
+            vec![
+                (NaiveDate::from_ymd_opt(2024, 1, 31).unwrap(), value(30)),
+                (NaiveDate::from_ymd_opt(2024, 2, 29).unwrap(), value(59)),
+            ]
+        );
+
+        // Jan 1 2024 is a Monday; the last week (Feb 26 - Mar 3) is partial
+        let weekly = acct.equity_curve_resampled(EquityFrequency::Weekly);
+        assert_eq!(weekly.len(), 9);
+        assert_eq!(weekly[0], (NaiveDate::from_ymd_opt(2024, 1, 7).unwrap(), value(6)));
+        assert_eq!(weekly[8], (NaiveDate::from_ymd_opt(2024, 2, 29).unwrap(), value(59)));
+    }
+
+    #[test]
+    fn test_monthly_returns_compound_daily_returns() {
+        let mut acct = PortfolioAccounting::new(100_000.0);
+        let start = NaiveDate::from_ymd_opt(2023, 11, 15).unwrap();
+        acct.execute_buy(start, "AAPL", 1000.0, 50.0, 0.0).unwrap();
+        // Nov 15 2023 to Feb 10 2024 with a wobbling price; no snapshots in January
+        let mut day = 0;
+        let mut date = start;
+        while date <= NaiveDate::from_ymd_opt(2024, 2, 10).unwrap() {
+            if date.month() != 1 {
+                let price = 50.0 + (day as f64 / 3.0).sin() * 4.0 + day as f64 * 0.05;
+                acct.mark_to_market(date, &HashMap::from([("AAPL".to_string(), price)]));
+                acct.take_daily_snapshot(date);
+            }
+            day += 1;
+            date = start + chrono::Duration::days(day);
+        }
+
+        let table = acct.monthly_returns();
+        assert_eq!(table.height(), 2);
+        assert_eq!(table.column("year").unwrap().i32().unwrap().get(0), Some(2023));
+        let month = |row: usize, name: &str| table.column(name).unwrap().f64().unwrap().get(row);
+
+        // Each month equals its compounded daily returns
+        let compounded = |year: i32, month: u32| {
+            let growth: f64 = acct
+                .daily_snapshots
+                .iter()
+                .filter(|s| s.date.year() == year && s.date.month() == month)
+                .map(|s| 1.0 + s.daily_return_pct / 100.0)
+                .product();
+            (growth - 1.0) * 100.0
+        };
+        for (row, year, m, name) in [(0, 2023, 11, "Nov"), (0, 2023, 12, "Dec"), (1, 2024, 2, "Feb")] {
+            assert!((month(row, name).unwrap() - compounded(year, m)).abs() < 1e-9, "{} {}", name, year);
+        }
+        assert_eq!(month(1, "Jan"), None);
+        assert_eq!(month(0, "Oct"), None);
+
+        let ytd = (1.0 + month(0, "Nov").unwrap() / 100.0) * (1.0 + month(0, "Dec").unwrap() / 100.0);
+        assert!((month(0, "YTD").unwrap() - (ytd - 1.0) * 100.0).abs() < 1e-9);
+    }
+
     #[test]
     fn test_buy_sell() {
         let mut acct = PortfolioAccounting::new(100_000.0);
@@ -1163,6 +1614,118 @@ mod tests {
         assert!(summary.total_commissions > 0.0);
     }
 
+    #[test]
+    fn test_return_per_exposure_favors_less_time_invested() {
+        // Both make $1,000 on a 50 -> 60 move over ten daily snapshots
+        let days: Vec<NaiveDate> = (1..=10).map(|d| NaiveDate::from_ymd_opt(2024, 1, d).unwrap()).collect();
+        let run = |sell_day: usize| {
+            let mut acct = PortfolioAccounting::new(100_000.0);
+            acct.execute_buy(days[0], "AAPL", 100.0, 50.0, 0.0).unwrap();
+            for (i, &day) in days.iter().enumerate() {
+                if i == sell_day {
+                    acct.execute_sell(day, "AAPL", 60.0, 0.0).unwrap();
+                }
+                acct.take_daily_snapshot(day);
+            }
+            acct.calculate_performance_summary()
+        };
+        let brief = run(1);
+        let patient = run(9);
+
+        assert_eq!(brief.total_return_pct, patient.total_return_pct);
+        assert_eq!(brief.pct_time_in_market, 10.0);
+        assert_eq!(patient.pct_time_in_market, 90.0);
+        assert!((brief.return_per_exposure - brief.total_return_pct / 0.1).abs() < 1e-9);
+        assert!((patient.return_per_exposure - patient.total_return_pct / 0.9).abs() < 1e-9);
+        assert!(brief.return_per_exposure > patient.return_per_exposure);
+
+        // Never invested: no exposure to divide by
+        let idle = PortfolioAccounting::new(100_000.0).calculate_performance_summary();
+        assert_eq!((idle.pct_time_in_market, idle.return_per_exposure), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_invested_return_ignores_idle_cash() {
+        // $5,000 of $100,000 goes into AAPL at 50 and comes out at 60
+        let days: Vec<NaiveDate> = (1..=3).map(|d| NaiveDate::from_ymd_opt(2024, 1, d).unwrap()).collect();
+        let mut acct = PortfolioAccounting::new(100_000.0);
+        acct.execute_buy(days[0], "AAPL", 100.0, 50.0, 0.0).unwrap();
+        acct.take_daily_snapshot(days[0]);
+        acct.mark_to_market(days[1], &HashMap::from([("AAPL".to_string(), 60.0)]));
+        acct.take_daily_snapshot(days[1]);
+        acct.execute_sell(days[2], "AAPL", 60.0, 0.0).unwrap();
+        acct.take_daily_snapshot(days[2]);
+
+        let summary = acct.calculate_performance_summary();
+        assert!((summary.total_return_pct - 1.0).abs() < 1e-9);
+        // $1,000 on an average $5,500 deployed over the two invested days
+        assert!((summary.invested_return - 1_000.0 / 5_500.0 * 100.0).abs() < 1e-9);
+        assert!(summary.invested_return > summary.total_return_pct);
+
+        let idle = PortfolioAccounting::new(100_000.0).calculate_performance_summary();
+        assert_eq!(idle.invested_return, 0.0);
+    }
+
+    #[test]
+    fn test_invested_return_counts_shorts_as_deployed() {
+        // A $20,000 short in MSFT at 200, marked down to 180
+        let days: Vec<NaiveDate> = (1..=2).map(|d| NaiveDate::from_ymd_opt(2024, 1, d).unwrap()).collect();
+        let mut acct = PortfolioAccounting::new(100_000.0);
+        // Shorts aren't executable yet; book the short and its sale proceeds directly
+        acct.positions.insert("MSFT".to_string(), Position {
+            ticker: "MSFT".to_string(),
+            shares: -100.0,
+            avg_cost_basis: 200.0,
+            total_cost: -20_000.0,
+            current_price: 200.0,
+            current_value: -20_000.0,
+            unrealized_pnl: 0.0,
+            unrealized_pnl_pct: 0.0,
+            entry_date: days[0],
+            last_update_date: days[0],
+        });
+        acct.cash_balance += 20_000.0;
+        acct.take_daily_snapshot(days[0]);
+        acct.mark_to_market(days[1], &HashMap::from([("MSFT".to_string(), 180.0)]));
+        acct.take_daily_snapshot(days[1]);
+
+        let summary = acct.calculate_performance_summary();
+        assert!((summary.total_return_pct - 2.0).abs() < 1e-9);
+        // $2,000 on an average $19,000 short, not 0% for a negative net equity value
+        assert!((summary.invested_return - 2_000.0 / 19_000.0 * 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_performance_summary_fields_from_ledger() {
+        let mut acct = PortfolioAccounting::new(100_000.0);
+        let d1 = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let d3 = NaiveDate::from_ymd_opt(2024, 1, 3).unwrap();
+        let d6 = NaiveDate::from_ymd_opt(2024, 1, 6).unwrap();
+
+        // One +20% winner held 2 days, one -10% loser held 5 days, no commissions
+        acct.execute_buy(d1, "AAPL", 100.0, 50.0, 0.0).unwrap();
+        acct.execute_buy(d1, "MSFT", 100.0, 100.0, 0.0).unwrap();
+        acct.execute_sell(d3, "AAPL", 60.0, 0.0).unwrap();
+        acct.execute_sell(d6, "MSFT", 90.0, 0.0).unwrap();
+
+        let summary = acct.calculate_performance_summary();
+        assert_eq!(summary.final_value, 100_000.0);
+        assert_eq!(summary.total_return_pct, 0.0);
+        assert_eq!(summary.total_realized_pnl, 0.0);
+        assert_eq!(summary.total_unrealized_pnl, 0.0);
+        assert_eq!(summary.total_commissions, 0.0);
+        assert_eq!(summary.total_trades, 2);
+        assert_eq!(summary.winning_trades, 1);
+        assert_eq!(summary.losing_trades, 1);
+        assert_eq!(summary.win_rate_pct, 50.0);
+        assert!((summary.avg_win_pct - 20.0).abs() < 1e-9);
+        assert!((summary.avg_loss_pct + 10.0).abs() < 1e-9);
+        assert!((summary.profit_factor - 2.0).abs() < 1e-9);
+        assert_eq!(summary.avg_holding_days, 3.5);
+        assert_eq!(summary.max_holding_days, 5);
+        assert_eq!(summary.min_holding_days, 2);
+    }
+
     #[test]
     fn test_error_handling() {
         let mut acct = PortfolioAccounting::new(100_000.0);
@@ -1308,4 +1871,182 @@ mod tests {
         let result = acct.save_all_reports("test_reports", false); // json_only=true to avoid printing
         assert!(result.is_ok());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_format_money() {
+        assert_eq!(format_money(-1234567.89, 2), "-1,234,567.89");
+        assert_eq!(format_money(0.0, 2), "0.00");
+        assert_eq!(format_money(999.99, 2), "999.99");
+        assert_eq!(format_money(1000.00, 2), "1,000.00");
+        assert_eq!(format_money(1234.5, 2), "1,234.50");
+        assert_eq!(format_money(1002.5, 2), "1,002.50");
+        assert_eq!(format_money(999.999, 2), "1,000.00");
+        assert_eq!(format_money(-0.001, 2), "0.00");
+        assert_eq!(format_money(1234567.0, 0), "1,234,567");
+    }
+
+    #[test]
+    fn test_risk_halt_blocks_buys_but_allows_sells() {
+        let mut acct = PortfolioAccounting::new(10_000.0);
+        acct.risk_halt(10.0);
+        let d1 = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let d2 = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+        let d3 = NaiveDate::from_ymd_opt(2024, 1, 3).unwrap();
+
+        acct.execute_buy(d1, "AAPL", 100.0, 100.0, 0.0).unwrap();
+        acct.take_daily_snapshot(d1);
+        assert!(!acct.is_halted());
+
+        // 15% portfolio drawdown trips the 10% limit
+        let mut prices = HashMap::new();
+        prices.insert("AAPL".to_string(), 85.0);
+        acct.mark_to_market(d2, &prices);
+        acct.take_daily_snapshot(d2);
+        assert!(acct.is_halted());
+
+        let err = acct.execute_buy(d3, "MSFT", 1.0, 50.0, 0.0).unwrap_err();
+        assert!(err.contains("Risk halt"));
+        assert!(acct.execute_sell(d3, "AAPL", 85.0, 0.0).is_ok());
+        assert!(!acct.has_position("AAPL"));
+
+        // Manual reset re-enables entries
+        acct.reset_halt();
+        assert!(acct.execute_buy(d3, "MSFT", 1.0, 50.0, 0.0).is_ok());
+    }
+
+    #[test]
+    fn test_risk_halt_lifts_on_recovery() {
+        let mut acct = PortfolioAccounting::new(10_000.0);
+        acct.risk_halt(10.0);
+        acct.set_halt_reset_threshold(5.0);
+        let d1 = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        acct.execute_buy(d1, "AAPL", 100.0, 100.0, 0.0).unwrap();
+
+        let mut prices = HashMap::new();
+        for (day, price) in [(2, 85.0), (3, 93.0), (4, 96.0)] {
+            let date = NaiveDate::from_ymd_opt(2024, 1, day).unwrap();
+            prices.insert("AAPL".to_string(), price);
+            acct.mark_to_market(date, &prices);
+            acct.take_daily_snapshot(date);
+        }
+
+        // 4% below peak is inside the 5% reset threshold
+        assert!(!acct.is_halted());
+        assert_eq!(acct.calculate_max_drawdown(), 15.0);
+    }
+
+    #[test]
+    fn test_rebalance_to_equal_weight() {
+        let mut acct = PortfolioAccounting::new(100_000.0);
+        let date = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        acct.execute_buy(date, "AAPL", 1000.0, 50.0, 0.0).unwrap();
+        acct.execute_buy(date, "MSFT", 50.0, 100.0, 0.0).unwrap();
+        acct.execute_buy(date, "IBM", 100.0, 30.0, 0.0).unwrap();
+        acct.set_rebalance_band(0.01);
+
+        let next = NaiveDate::from_ymd_opt(2024, 2, 1).unwrap();
+        let prices: HashMap<String, f64> = [("AAPL", 60.0), ("MSFT", 90.0), ("IBM", 33.0), ("GOOG", 140.0)]
+            .iter()
+            .map(|(t, p)| (t.to_string(), *p))
+            .collect();
+        // Equal weight across four names, IBM dropped in favour of GOOG
+        let targets: HashMap<String, f64> = ["AAPL", "MSFT", "GOOG", "NVDA"]
+            .iter()
+            .map(|t| (t.to_string(), 0.25))
+            .collect();
+        // NVDA has no price
+        assert!(acct.clone().rebalance_to(next, &targets, &prices, CommissionModel::PerTrade(1.0)).is_err());
+
+        let targets: HashMap<String, f64> = ["AAPL", "MSFT", "GOOG"]
+            .iter()
+            .map(|t| (t.to_string(), 1.0 / 3.0))
+            .collect();
+        let transactions = acct
+            .rebalance_to(next, &targets, &prices, CommissionModel::PerTrade(1.0))
+            .unwrap();
+        // Sells (AAPL trim, IBM exit) execute before buys (MSFT, GOOG)
+        let actions: Vec<(&str, TransactionType)> =
+            transactions.iter().map(|t| (t.ticker.as_str(), t.action)).collect();
+        assert_eq!(actions, vec![
+            ("AAPL", TransactionType::Sell),
+            ("IBM", TransactionType::Sell),
+            ("GOOG", TransactionType::Buy),
+            ("MSFT", TransactionType::Buy),
+        ]);
+        assert!(!acct.has_position("IBM"));
+        assert!(acct.get_cash_balance() >= 0.0);
+
+        let weights = acct.get_all_position_weights();
+        assert_eq!(weights.len(), 3);
+        for (ticker, weight) in &weights {
+            assert!((weight - 1.0 / 3.0).abs() <= 0.01, "{} weight {}", ticker, weight);
+        }
+
+        // A second pass is already inside the band and trades nothing
+        let again = acct.rebalance_to(next, &targets, &prices, CommissionModel::Percent(0.001)).unwrap();
+        assert!(again.is_empty());
+    }
+
+    #[test]
+    fn test_holding_days_business_and_trading_calendar() {
+        let friday = NaiveDate::from_ymd_opt(2024, 1, 5).unwrap();
+        let monday = NaiveDate::from_ymd_opt(2024, 1, 8).unwrap();
+        let hold = |config: AccountingConfig| {
+            let mut acct = PortfolioAccounting::new(10_000.0);
+            acct.config = config;
+            acct.execute_buy(friday, "AAPL", 10.0, 100.0, 0.0).unwrap();
+            acct.execute_sell(monday, "AAPL", 101.0, 0.0).unwrap();
+            let summary = acct.calculate_performance_summary();
+            (acct.realized_pnl[0].holding_days, summary.avg_holding_days)
+        };
+
+        // Default stays calendar days
+        assert_eq!(hold(AccountingConfig::default()), (3, 3.0));
+        assert_eq!(
+            hold(AccountingConfig { holding_days: HoldingDayCount::BusinessDays }),
+            (1, 1.0)
+        );
+
+        // Trading calendar from the price frame: Monday 2024-01-15 is a market holiday
+        let dates = ["2024-01-12", "2024-01-16", "2024-01-11"]
+            .iter()
+            .map(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap());
+        let calendar = HoldingDayCount::trading_dates(dates);
+        let thursday = NaiveDate::from_ymd_opt(2024, 1, 11).unwrap();
+        let tuesday = NaiveDate::from_ymd_opt(2024, 1, 16).unwrap();
+        assert_eq!(calendar.count(thursday, tuesday), 2);
+        assert_eq!(HoldingDayCount::BusinessDays.count(thursday, tuesday), 3);
+        assert_eq!(HoldingDayCount::Calendar.count(thursday, tuesday), 5);
+    }
+
+    #[test]
+    fn test_exposure_with_long_and_short() {
+        let mut acct = PortfolioAccounting::new(100_000.0);
+        let date = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+        acct.execute_buy(date, "AAPL", 200.0, 100.0, 0.0).unwrap();
+        // Shorts aren't executable yet; book a $20k short and its sale proceeds directly
+        acct.positions.insert("MSFT".to_string(), Position {
+            ticker: "MSFT".to_string(),
+            shares: -100.0,
+            avg_cost_basis: 200.0,
+            total_cost: -20_000.0,
+            current_price: 200.0,
+            current_value: -20_000.0,
+            unrealized_pnl: 0.0,
+            unrealized_pnl_pct: 0.0,
+            entry_date: date,
+            last_update_date: date,
+        });
+        acct.cash_balance += 20_000.0;
+
+        acct.take_daily_snapshot(date);
+        let snapshot = acct.daily_snapshots.last().unwrap();
+        assert_eq!(snapshot.total_value, 100_000.0);
+        assert!(snapshot.net_exposure.abs() < 1e-12);
+        // 20% long + 20% short
+        assert!((snapshot.gross_exposure - 0.4).abs() < 1e-12);
+
+        let summary = acct.calculate_performance_summary();
+        assert!((summary.peak_gross_exposure - 0.4).abs() < 1e-12);
+    }
+}
@@ -13,11 +13,12 @@ use std::{
 };
 use clap::Parser;
 use log::{info, debug, warn};
+use backtester::config::normalize_ticker;
+use backtester::clickhouse::test_connection;
+use backtester::parse_trade_date;
 use backtester::portfolio_accounting::PortfolioAccounting;
 use clickhouse::{Client, Row};
 use serde::{Deserialize, Serialize};
-use tokio::net::TcpStream;
-use tokio::time::{timeout, Duration};
 
 /// ClickHouse connection type
 #[derive(Clone, Copy)]
@@ -26,16 +27,27 @@ pub enum ChConnectionType {
     Remote,
 }
 
+impl ChConnectionType {
+    /// The same server as the library's connection type, for its shared helpers
+    fn server(self) -> backtester::clickhouse::ChConnectionType {
+        match self {
+            ChConnectionType::Local => backtester::clickhouse::ChConnectionType::Ace,
+            ChConnectionType::Remote => backtester::clickhouse::ChConnectionType::Mini,
+        }
+    }
+}
+
 /// Portfolio backtester with position limits and priority-based ranking
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Clone)]
 #[command(author, version, about, long_about = None)]
 struct Args {
     /// Signal date folder (e.g., output/testing_20260204 or just 20260204)
     #[arg(long, default_value = "20260204")]
     signal_date: String,
 
-    /// Start date for backtest (format: YYYY-MM-DD), filters signals to this date or later
-    #[arg(long, default_value = "2020-01-02")]
+    /// Start date for backtest (format: YYYY-MM-DD), filters signals to this date or later.
+    /// Buys before the cutoff are dropped, so every ticker starts flat on this date
+    #[arg(long, alias = "since", default_value = "2020-01-02")]
     start_date: Option<String>,
 
     /// Maximum number of positions in portfolio
@@ -63,12 +75,20 @@ struct Args {
     #[arg(long)]
     sector: Option<String>,
 
+    /// Starting cash for the portfolio
+    #[arg(long, default_value = "10000.0")]
+    initial_cash: f64,
+
+    /// Fixed dollar amount per new position (default: equal weight of portfolio value)
+    #[arg(long)]
+    position_value: Option<f64>,
+
     /// Commission (default commission-free)
     #[arg(long, default_value = "0.50")]
     commission: f64,
 
-    /// prefix for filenames
-    #[arg(short, long, default_value = "portfolio")]
+    /// prefix for filenames (-p is taken by --priority-strategy)
+    #[arg(short = 'f', long, default_value = "portfolio")]
     prefix: String,
 
     /// Filter by specific ticker(s) - comma separated (optional)
@@ -99,10 +119,25 @@ struct Args {
     #[arg(long, default_value = "1")]
     min_buy_signals: usize,
 
+    /// Carry each strategy's last signal for a ticker forward over this many later signal
+    /// dates (bars), so slower strategies keep voting between their signals (default 0 = off)
+    #[arg(long, default_value = "0")]
+    signal_persistence: usize,
+
     /// Output single-line CSV summary (for batch runs)
     #[arg(long)]
     oneline: bool,
 
+    /// Run each strategy as its own sleeve with this much starting cash, then report the
+    /// combined result and each sleeve's contribution
+    #[arg(long)]
+    sleeve_capital: Option<f64>,
+
+    /// Move decisions dated on a day without a price bar for their ticker (weekend,
+    /// holiday) to the ticker's next priced day instead of dropping them
+    #[arg(long)]
+    snap_to_trading_day: bool,
+
 }
 
 #[derive(Debug, Row, Serialize, Deserialize)]
@@ -116,7 +151,7 @@ struct Signal {
     ticker: String,
     strategy: String,
     date: NaiveDate,
-    action: String, // "buy" or "sell"
+    action: String, // "buy", "sell" or "flat" (exit without reversing)
 }
 
 /// ClickHouse price data row
@@ -143,31 +178,6 @@ fn read_env_var(key: &str) -> String {
     env::var(key).unwrap_or_else(|_| panic!("{} env variable should be set", key))
 }
 
-/// Test connection to ClickHouse
-pub async fn test_connection(connection_type: ChConnectionType) -> bool {
-    let (host, port) = match connection_type {
-        ChConnectionType::Local => ("192.168.86.46", 8123),
-        ChConnectionType::Remote => ("192.168.86.56", 8123),
-    };
-
-    info!("Testing connection to {}:{}...", host, port);
-
-    match timeout(Duration::from_secs(5), TcpStream::connect((host, port))).await {
-        Ok(Ok(_)) => {
-            info!("✓ TCP connection to {}:{} successful", host, port);
-            true
-        }
-        Ok(Err(e)) => {
-            warn!("✗ TCP connection to {}:{} failed: {}", host, port, e);
-            false
-        }
-        Err(_) => {
-            warn!("✗ TCP connection to {}:{} timed out after 5 seconds", host, port);
-            false
-        }
-    }
-}
-
 /// Get ClickHouse client
 pub async fn get_ch_client(connection_type: ChConnectionType) -> Result<Client, Box<dyn StdError>> {
     let (host, conn_type_str) = match connection_type {
@@ -241,9 +251,15 @@ pub async fn load_price_data_native(
         all_data.push(row);
     }
 
-    // Convert to Polars DataFrame
-    let dates: Vec<&str> = all_data.iter().map(|r| r.date.as_str()).collect();
-    let tickers: Vec<&str> = all_data.iter().map(|r| r.ticker.as_str()).collect();
+    // Convert to Polars DataFrame; dates are canonicalized to YYYY-MM-DD so lookups
+    // match regardless of the format ClickHouse returned
+    let dates: Vec<String> = all_data.iter()
+        .map(|r| match parse_trade_date(&r.date) {
+            Ok(date) => date.format("%Y-%m-%d").to_string(),
+            Err(_) => r.date.clone(),
+        })
+        .collect();
+    let tickers: Vec<String> = all_data.iter().map(|r| normalize_ticker(&r.ticker, universe)).collect();
     let closes: Vec<Option<f64>> = all_data.iter().map(|r| r.close).collect();
 
     let df = df! {
@@ -321,7 +337,7 @@ async fn load_universe_tickers(
     let mut tickers = Vec::new();
     let mut cursor = client.query(&query).fetch::<UnivRow>()?;
     while let Some(row) = cursor.next().await? {
-        tickers.push(row.ticker.to_uppercase());
+        tickers.push(normalize_ticker(&row.ticker, universe));
     }
 
     info!("Universe filter matched {} tickers", tickers.len());
@@ -332,10 +348,13 @@ async fn load_universe_tickers(
 // Signal and Price Loading Functions
 // ============================================================================
 
-/// Read all decision files from signal folder (output/testing_DATE or output_crypto/testing_DATE)
-// Update the read_decision_files function to extract just the signal name
+/// Read all decision files from signal folder (output/testing_DATE or output_crypto/testing_DATE).
+/// Tickers and strategy names come from each file's `ticker`/`strategy` columns, not its name.
+/// With a `signal_list`, only files named `*_<strategy>_decisions.csv` for a listed strategy
+/// are read, and their rows are still checked against the list.
 fn read_decision_files(
     signal_folder: &str,
+    universe: &str,
     ticker_filter: Option<Vec<String>>,
     signal_list: Option<Vec<String>>,
 ) -> Result<(Vec<Signal>, Vec<String>), Box<dyn StdError>> {
@@ -363,21 +382,9 @@ fn read_decision_files(
                 continue;
             }
 
-            // Extract signal name from filename (remove _decisions.csv suffix)
-            let full_signal_name = filename.trim_end_matches("_decisions.csv").to_string();
-
-            // Extract just the signal part (after the ticker)
-            let signal_name = if let Some(underscore_pos) = full_signal_name.find('_') {
-                full_signal_name[underscore_pos + 1..].to_string()
-            } else {
-                full_signal_name.clone()
-            };
-
-            available_signals.push(signal_name.clone()); // Add to available signals
-
-            // Skip if signal_list is provided and this signal is not in the list
+            // Skip files that can't be one of the selected strategies without reading them
             if let Some(ref signals_to_process) = signal_list {
-                if !signals_to_process.contains(&signal_name) {
+                if !signals_to_process.iter().any(|s| filename.ends_with(&format!("_{}_decisions.csv", s))) {
                     continue;
                 }
             }
@@ -404,6 +411,19 @@ fn read_decision_files(
             let dates_col = df.column("date")?.str()?;
             let actions_col = df.column("action")?.str()?;
 
+            // The file is named <ticker>_<strategy>_decisions.csv, but tickers and strategies
+            // may both contain underscores, so the name cannot be split reliably; the
+            // ticker/strategy columns are authoritative and the name is only checked
+            let stem = filename.trim_end_matches("_decisions.csv");
+            if let (Some(ticker), Some(strategy)) = (tickers_col.get(0), strategies_col.get(0)) {
+                if stem != format!("{}_{}", ticker, strategy) {
+                    warn!(
+                        "{} holds decisions for ticker '{}', strategy '{}'; using the file contents",
+                        filename, ticker, strategy
+                    );
+                }
+            }
+
             for i in 0..df.height() {
                 if let (Some(ticker_str), Some(strategy_str), Some(date_str), Some(action_str)) = (
                     tickers_col.get(i),
@@ -411,7 +431,20 @@ fn read_decision_files(
                     dates_col.get(i),
                     actions_col.get(i),
                 ) {
-                    let ticker = ticker_str.to_uppercase();
+                    let signal_name = strategy_str.to_string();
+                    // Deduplicated after the scan; a file is usually a single strategy
+                    if available_signals.last() != Some(&signal_name) {
+                        available_signals.push(signal_name.clone());
+                    }
+
+                    // Skip if signal_list is provided and this signal is not in the list
+                    if let Some(ref signals_to_process) = signal_list {
+                        if !signals_to_process.contains(&signal_name) {
+                            continue;
+                        }
+                    }
+
+                    let ticker = normalize_ticker(ticker_str, universe);
 
                     // Apply ticker filter if provided
                     if let Some(ref filter) = ticker_filter {
@@ -420,13 +453,14 @@ fn read_decision_files(
                         }
                     }
 
-                    if let Ok(date) = NaiveDate::parse_from_str(date_str, "%Y-%m-%d") {
-                        signals.push(Signal {
+                    match parse_trade_date(date_str) {
+                        Ok(date) => signals.push(Signal {
                             ticker,
-                            strategy: strategy_str.to_string(),
+                            strategy: signal_name,
                             date,
-                            action: action_str.to_lowercase(),
-                        });
+                            action: normalize_action(action_str),
+                        }),
+                        Err(e) => warn!("Skipping signal in {}: {}", path.display(), e),
                     }
                 }
             }
@@ -441,6 +475,16 @@ fn read_decision_files(
             .then_with(|| a.action.cmp(&b.action))
     });
 
+    // A strategy that emits the same decision twice on a date would be counted twice in
+    // the buy tally; keep one row per (ticker, strategy, date, action)
+    let before = signals.len();
+    signals.dedup_by(|a, b| {
+        a.date == b.date && a.ticker == b.ticker && a.strategy == b.strategy && a.action == b.action
+    });
+    if signals.len() < before {
+        warn!("Dropped {} duplicate decision rows", before - signals.len());
+    }
+
     // Sort available_signals for deterministic ordering
     available_signals.sort();
     available_signals.dedup();
@@ -524,13 +568,113 @@ fn get_prices_for_date(
 
     for i in 0..aggregated.height() {
         if let (Some(ticker), Some(price)) = (tickers.get(i), closes.get(i)) {
-            prices.insert(ticker.to_uppercase(), price);
+            // Tickers are normalized when the price frame is loaded
+            prices.insert(ticker.to_string(), price);
         }
     }
 
     Ok(prices)
 }
 
+/// Check decision dates against each ticker's price bars. Decisions on a day without a
+/// bar for their ticker would be skipped by `backtest_portfolio`; they are reported and,
+/// with `snap`, moved forward to the ticker's next priced day (those with none later
+/// stay as they are). Returns the decisions and the distinct orphaned dates.
+fn align_to_price_calendar(
+    signals: Vec<Signal>,
+    prices_df: &DataFrame,
+    snap: bool,
+) -> Result<(Vec<Signal>, Vec<NaiveDate>), Box<dyn StdError>> {
+    let dates = prices_df.column("Date")?.cast(&DataType::String)?;
+    let tickers = prices_df.column("Ticker")?.str()?.clone();
+    let mut calendar: HashMap<String, std::collections::BTreeSet<NaiveDate>> = HashMap::new();
+    for (ticker, date) in tickers.into_iter().zip(dates.str()?) {
+        if let (Some(ticker), Some(Ok(date))) = (ticker, date.map(parse_trade_date)) {
+            calendar.entry(ticker.to_string()).or_default().insert(date);
+        }
+    }
+
+    let mut orphans = 0;
+    let mut orphan_dates = std::collections::BTreeSet::new();
+    let aligned = signals
+        .into_iter()
+        .map(|mut signal| {
+            let bars = calendar.get(&signal.ticker);
+            if !bars.is_some_and(|bars| bars.contains(&signal.date)) {
+                orphans += 1;
+                orphan_dates.insert(signal.date);
+                if let Some(&next) = bars.and_then(|bars| bars.range(signal.date..).next()).filter(|_| snap) {
+                    signal.date = next;
+                }
+            }
+            signal
+        })
+        .collect();
+
+    if orphans > 0 {
+        let listed: Vec<String> = orphan_dates.iter().map(|d| d.to_string()).collect();
+        warn!("{} decisions on {} dates have no price bar ({}): {}",
+              orphans, orphan_dates.len(), if snap { "snapped to the next trading day" } else { "dropped" }, listed.join(", "));
+    }
+    Ok((aligned, orphan_dates.into_iter().collect()))
+}
+
+/// Each strategy's most recent signal per ticker over the `persistence` signal dates before
+/// `date`, skipping dates already inside the `lookback_days` calendar window
+fn persisted_signals(
+    signals_by_date: &BTreeMap<NaiveDate, Vec<Signal>>,
+    date: NaiveDate,
+    lookback_days: i64,
+    persistence: usize,
+) -> Vec<Signal> {
+    let mut latest: BTreeMap<(String, String), Signal> = BTreeMap::new();
+    let window: Vec<(&NaiveDate, &Vec<Signal>)> = signals_by_date.range(..date).rev().take(persistence).collect();
+    for (signal_date, signals) in window.into_iter().rev() {
+        if (date - *signal_date).num_days() < lookback_days {
+            continue;
+        }
+        for signal in signals {
+            latest.insert((signal.ticker.clone(), signal.strategy.clone()), signal.clone());
+        }
+    }
+    latest.into_values().collect()
+}
+
+/// Tickers whose buys can never reach `min_buy_signals`. Each strategy contributes at most one
+/// buy per day of the lookback window, so a ticker bought by fewer than
+/// `min_buy_signals / lookback_days` strategies is never entered. Those tickers are logged, so a
+/// run that buys nothing because of the threshold says so.
+fn unreachable_min_buy_signals(signals: &[Signal], min_buy_signals: usize, lookback_days: i64) -> Vec<String> {
+    let mut buy_strategies: BTreeMap<&str, std::collections::HashSet<&str>> = BTreeMap::new();
+    for s in signals.iter().filter(|s| s.action == "buy") {
+        buy_strategies.entry(&s.ticker).or_default().insert(&s.strategy);
+    }
+    let per_strategy = lookback_days.max(1) as usize;
+    let unreachable: Vec<String> = buy_strategies
+        .iter()
+        .filter(|(_, strategies)| strategies.len() * per_strategy < min_buy_signals)
+        .map(|(ticker, _)| ticker.to_string())
+        .collect();
+
+    if !unreachable.is_empty() && unreachable.len() == buy_strategies.len() {
+        let most = buy_strategies.values().map(|s| s.len()).max().unwrap_or(0);
+        warn!(
+            "--min-buy-signals {} can never be met: no ticker has buys from more than {} strategies \
+             over a {}-day lookback, so nothing will be bought",
+            min_buy_signals, most, per_strategy
+        );
+    } else if !unreachable.is_empty() {
+        warn!(
+            "{} of {} tickers have too few buying strategies to ever reach --min-buy-signals {}: {}",
+            unreachable.len(),
+            buy_strategies.len(),
+            min_buy_signals,
+            unreachable.join(", ")
+        );
+    }
+    unreachable
+}
+
 /// Rank buy candidates by priority strategy signal count
 fn rank_buy_candidates(
     buy_signals: &[Signal],
@@ -558,16 +702,26 @@ fn rank_buy_candidates(
     ranked
 }
 
+/// Normalize a decision action; "exit" is treated as an alias for "flat"
+fn normalize_action(action: &str) -> String {
+    match action.trim().to_lowercase().as_str() {
+        "exit" => "flat".to_string(),
+        other => other.to_string(),
+    }
+}
+
 /// Run the portfolio backtest
 async fn backtest_portfolio(
     args: &Args,
     signals: Vec<Signal>,
     prices_df: DataFrame,
 ) -> Result<PortfolioAccounting, Box<dyn StdError>> {
+    if args.min_buy_signals > 1 {
+        unreachable_min_buy_signals(&signals, args.min_buy_signals, args.lookback_days);
+    }
 
     // Initialize portfolio accounting
-    let initial_cash = 10_000.0;
-    let mut portfolio = PortfolioAccounting::new(initial_cash);
+    let mut portfolio = PortfolioAccounting::new(args.initial_cash);
     let commission = args.commission;
 
     // Track last rebalance date if rebalancing is enabled
@@ -575,7 +729,7 @@ async fn backtest_portfolio(
 
     // Parse start_date if provided
     let start_date_filter: Option<NaiveDate> = if let Some(ref start_date_str) = args.start_date {
-        match NaiveDate::parse_from_str(start_date_str, "%Y-%m-%d") {
+        match parse_trade_date(start_date_str) {
             Ok(date) => {
                 info!("Filtering signals to start from {}", date);
                 Some(date)
@@ -657,18 +811,20 @@ async fn backtest_portfolio(
             }
         }
 
-        // 2. Process sell signals (day after signal, so check if we have positions)
-        let sell_signals: Vec<&Signal> = day_signals.iter()
-            .filter(|s| s.action == "sell")
+        // 2. Process sell and flat signals (day after signal, so check if we have positions).
+        // Both close an open long; neither ever opens a short.
+        let exit_signals: Vec<&Signal> = day_signals.iter()
+            .filter(|s| s.action == "sell" || s.action == "flat")
             .collect();
 
-        for sell_signal in sell_signals {
-            if portfolio.has_position(&sell_signal.ticker) {
-                // Close position on day after sell signal
+        for exit_signal in exit_signals {
+            if portfolio.has_position(&exit_signal.ticker) {
+                // Close position on day after the exit signal
                 let next_date = date + chrono::Duration::days(1);
-                if let Ok(Some(exit_price)) = get_price(&prices_df, &sell_signal.ticker, next_date) {
-                    if let Ok(_) = portfolio.execute_sell(next_date, &sell_signal.ticker, exit_price, commission) {
-                        info!("SELL-SIGNAL {} on {}: @ ${:.2}", sell_signal.ticker, next_date, exit_price);
+                if let Ok(Some(exit_price)) = get_price(&prices_df, &exit_signal.ticker, next_date) {
+                    if let Ok(_) = portfolio.execute_sell(next_date, &exit_signal.ticker, exit_price, commission) {
+                        let label = if exit_signal.action == "flat" { "FLAT-SIGNAL" } else { "SELL-SIGNAL" };
+                        info!("{} {} on {}: @ ${:.2}", label, exit_signal.ticker, next_date, exit_price);
                     }
                 }
             }
@@ -696,12 +852,31 @@ async fn backtest_portfolio(
                             .cloned()
                     );
                     // Track tickers that also have sell signals in the window
+                    // (a "flat" only asks to be out, so it does not veto a buy)
                     for s in past_signals.iter().filter(|s| s.action == "sell") {
                         tickers_with_sell_signals.insert(s.ticker.clone());
                     }
                 }
             }
 
+            // Carried-forward signals count like fresh ones, unless the same strategy has
+            // signaled the ticker again inside the lookback window
+            if args.signal_persistence > 0 {
+                let fresh: std::collections::HashSet<(String, String)> = (0..args.lookback_days)
+                    .filter_map(|lookback| signals_by_date.get(&(date - chrono::Duration::days(lookback))))
+                    .flatten()
+                    .map(|s| (s.ticker.clone(), s.strategy.clone()))
+                    .collect();
+                let carried = persisted_signals(&signals_by_date, date, args.lookback_days, args.signal_persistence);
+                for s in carried.into_iter().filter(|s| !fresh.contains(&(s.ticker.clone(), s.strategy.clone()))) {
+                    if s.action == "sell" {
+                        tickers_with_sell_signals.insert(s.ticker.clone());
+                    } else if s.action == "buy" && !portfolio.has_position(&s.ticker) {
+                        multi_day_buy_signals.push(s);
+                    }
+                }
+            }
+
             // Remove buy candidates that also had sell signals in the lookback window
             if !tickers_with_sell_signals.is_empty() {
                 let before = multi_day_buy_signals.len();
@@ -757,16 +932,23 @@ async fn backtest_portfolio(
                     let next_date = date + chrono::Duration::days(1);
 
                     if let Ok(Some(entry_price)) = get_price(&prices_df, ticker, next_date) {
-                        // Calculate position size: equal weight allocation
-                        let target_positions = args.portfolio_size as f64;
-                        let position_value = portfolio.get_total_value() / target_positions;
+                        // Calculate position size: fixed amount or equal weight allocation
+                        let position_value = args.position_value.unwrap_or_else(|| {
+                            portfolio.get_total_value() / args.portfolio_size as f64
+                        });
                         let shares = (position_value / entry_price).floor();
 
                         if shares > 0.0 {
-                            // Execute buy through accounting system with explicit shares and commission
-                            if let Ok(txn) = portfolio.execute_buy(next_date, ticker, shares, entry_price, commission) {
-                                info!("BUY {} on {}: {} shares @ ${:.2} (total value: ${:.2})",
-                                      ticker, next_date, txn.shares, entry_price, portfolio.get_total_value());
+                            // Execute buy through accounting system; buys that exceed remaining
+                            // cash are rejected, so a free slot alone does not open a position
+                            match portfolio.execute_buy(next_date, ticker, shares, entry_price, commission) {
+                                Ok(txn) => {
+                                    info!("BUY {} on {}: {} shares @ ${:.2} (total value: ${:.2})",
+                                          ticker, next_date, txn.shares, entry_price, portfolio.get_total_value());
+                                }
+                                Err(e) => {
+                                    info!("SKIP BUY {} on {}: {}", ticker, next_date, e);
+                                }
                             }
                         }
                     }
@@ -880,18 +1062,117 @@ async fn backtest_portfolio(
     Ok(portfolio)
 }
 
+/// One strategy sleeve's share of a `backtest_strategy_sleeves` run
+#[derive(Debug, Clone)]
+struct SleeveAttribution {
+    strategy: String,
+    capital: f64,
+    final_value: f64,
+    /// Sleeve return on its own capital
+    return_pct: f64,
+    /// Sleeve P&L as a percentage of the combined capital; these sum to the combined return
+    contribution_pct: f64,
+}
+
+/// The sleeves' values on one date of the combined equity curve
+struct SleeveCurvePoint {
+    date: NaiveDate,
+    /// Each sleeve's value, in the order of `SleeveBacktest::sleeves`
+    sleeve_values: Vec<f64>,
+    total_value: f64,
+}
+
+/// Strategies run as independent sleeves and the aggregate of their accounts
+struct SleeveBacktest {
+    sleeves: BTreeMap<String, PortfolioAccounting>,
+    /// Summed sleeve values on every date any sleeve took a snapshot
+    equity_curve: Vec<SleeveCurvePoint>,
+    attribution: Vec<SleeveAttribution>,
+    /// Combined return on the combined capital
+    return_pct: f64,
+}
+
+impl SleeveBacktest {
+    /// Write the combined equity curve to `path`: the date, each sleeve's value under its
+    /// strategy name, and the `total_value`
+    fn save_equity_curve_csv(&self, path: &str) -> Result<(), Box<dyn StdError>> {
+        let dates: Vec<String> = self.equity_curve.iter().map(|p| p.date.format("%Y-%m-%d").to_string()).collect();
+        let mut columns = vec![Column::new("date".into(), dates)];
+        for (i, strategy) in self.sleeves.keys().enumerate() {
+            let values: Vec<f64> = self.equity_curve.iter().map(|p| p.sleeve_values[i]).collect();
+            columns.push(Column::new(strategy.as_str().into(), values));
+        }
+        let totals: Vec<f64> = self.equity_curve.iter().map(|p| p.total_value).collect();
+        columns.push(Column::new("total_value".into(), totals));
+        backtester::write_csv_atomic(&mut DataFrame::new(columns)?, path)
+    }
+}
+
+/// Run every strategy's decisions as its own `PortfolioAccounting` sleeve starting with
+/// `capital_per_sleeve[strategy]` cash. Each sleeve ranks candidates by its own strategy;
+/// all other settings come from `args`.
+async fn backtest_strategy_sleeves(
+    args: &Args,
+    decisions_by_strategy: BTreeMap<String, Vec<Signal>>,
+    capital_per_sleeve: &HashMap<String, f64>,
+    prices_df: DataFrame,
+) -> Result<SleeveBacktest, Box<dyn StdError>> {
+    let mut sleeves = BTreeMap::new();
+    for (strategy, signals) in decisions_by_strategy {
+        let capital = *capital_per_sleeve
+            .get(&strategy)
+            .ok_or_else(|| format!("No capital allocated to sleeve {}", strategy))?;
+        let sleeve_args = Args {
+            initial_cash: capital,
+            priority_strategy: strategy.clone(),
+            ..args.clone()
+        };
+        info!("Running sleeve {} with ${:.2}", strategy, capital);
+        let portfolio = backtest_portfolio(&sleeve_args, signals, prices_df.clone()).await?;
+        sleeves.insert(strategy, portfolio);
+    }
+
+    // A sleeve is worth its last snapshot on or before each date (its capital before the
+    // first); each sleeve's snapshots are walked once, in step with the dates
+    let mut dates: Vec<NaiveDate> = sleeves.values()
+        .flat_map(|p| p.daily_snapshots.iter().map(|s| s.date))
+        .collect();
+    dates.sort();
+    dates.dedup();
+    let mut cursors = vec![0; sleeves.len()];
+    let mut values: Vec<f64> = sleeves.values().map(|p| p.initial_cash).collect();
+    let equity_curve = dates.into_iter().map(|date| {
+        for ((p, cursor), value) in sleeves.values().zip(&mut cursors).zip(&mut values) {
+            while let Some(snapshot) = p.daily_snapshots.get(*cursor).filter(|s| s.date <= date) {
+                *value = snapshot.total_value;
+                *cursor += 1;
+            }
+        }
+        SleeveCurvePoint { date, sleeve_values: values.clone(), total_value: values.iter().sum() }
+    }).collect();
+
+    let total_capital: f64 = sleeves.values().map(|p| p.initial_cash).sum();
+    let attribution: Vec<SleeveAttribution> = sleeves.iter().map(|(strategy, p)| {
+        let final_value = p.get_total_value();
+        SleeveAttribution {
+            strategy: strategy.clone(),
+            capital: p.initial_cash,
+            final_value,
+            return_pct: (final_value / p.initial_cash - 1.0) * 100.0,
+            contribution_pct: (final_value - p.initial_cash) / total_capital * 100.0,
+        }
+    }).collect();
+    let return_pct = attribution.iter().map(|a| a.contribution_pct).sum();
+
+    Ok(SleeveBacktest { sleeves, equity_curve, attribution, return_pct })
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn StdError>> {
     let args = Args::parse();
 
     // Setup logging
-    let log_level = match args.verbose {
-        0 => "warn",
-        1 => "info",
-        2 => "debug",
-        _ => "trace",
-    };
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(log_level)).init();
+    backtester::init_logging(args.verbose);
 
     info!("Portfolio Backtester starting");
     info!("Signal date: {}", args.signal_date);
@@ -904,6 +1185,9 @@ async fn main() -> Result<(), Box<dyn StdError>> {
     if args.lookback_days > 1 || args.min_buy_signals > 1 {
         info!("Buy signal lookback: {} days, minimum signals: {}", args.lookback_days, args.min_buy_signals);
     }
+    if args.signal_persistence > 0 {
+        info!("Signal persistence: {} signal dates", args.signal_persistence);
+    }
 
     // Determine signal folder path based on universe and date
     let signal_folder = if args.signal_date.starts_with("output") {
@@ -924,7 +1208,7 @@ async fn main() -> Result<(), Box<dyn StdError>> {
     // Parse ticker filter if provided
     let ticker_filter = args.tickers.as_ref().map(|t| {
         t.split(',')
-        .map(|s| s.trim().to_uppercase())
+        .map(|s| normalize_ticker(s, &args.universe))
         .collect::<Vec<String>>()
     });
 
@@ -940,10 +1224,10 @@ async fn main() -> Result<(), Box<dyn StdError>> {
     // Determine ClickHouse connection type
     let connection_type = {
         info!("Testing ClickHouse connection...");
-        if test_connection(ChConnectionType::Local).await {
+        if test_connection(ChConnectionType::Local.server()).await {
             info!("Using Local ClickHouse connection (192.168.86.46)");
             ChConnectionType::Local
-        } else if test_connection(ChConnectionType::Remote).await {
+        } else if test_connection(ChConnectionType::Remote.server()).await {
             info!("Using Remote ClickHouse connection (192.168.86.56)");
             ChConnectionType::Remote
         } else {
@@ -976,7 +1260,7 @@ async fn main() -> Result<(), Box<dyn StdError>> {
     };
 
     // Call the function with the signal list
-    let (signals, _available_signals) = read_decision_files(&signal_folder, ticker_filter, signal_list.clone())?;
+    let (signals, _available_signals) = read_decision_files(&signal_folder, &args.universe, ticker_filter, signal_list.clone())?;
 
 
     if signals.is_empty() {
@@ -1032,6 +1316,29 @@ async fn main() -> Result<(), Box<dyn StdError>> {
     .await?;
 
     info!("Loaded price data: {} rows, {} columns", prices_df.height(), prices_df.width());
+    let (signals, _orphan_dates) = align_to_price_calendar(signals, &prices_df, args.snap_to_trading_day)?;
+
+    if let Some(capital) = args.sleeve_capital {
+        let mut decisions_by_strategy: BTreeMap<String, Vec<Signal>> = BTreeMap::new();
+        for signal in signals {
+            decisions_by_strategy.entry(signal.strategy.clone()).or_default().push(signal);
+        }
+        let capital_per_sleeve: HashMap<String, f64> = decisions_by_strategy.keys()
+            .map(|strategy| (strategy.clone(), capital))
+            .collect();
+        info!("Running {} strategy sleeves...", decisions_by_strategy.len());
+        let result = backtest_strategy_sleeves(&args, decisions_by_strategy, &capital_per_sleeve, prices_df).await?;
+        println!("{:<40} {:>12} {:>12} {:>9} {:>13}", "sleeve", "capital", "final", "return%", "contribution%");
+        for a in &result.attribution {
+            println!("{:<40} {:>12.2} {:>12.2} {:>9.2} {:>13.2}",
+                a.strategy, a.capital, a.final_value, a.return_pct, a.contribution_pct);
+        }
+        println!("{:<40} {:>12} {:>12} {:>9.2}", "combined", "", "", result.return_pct);
+        let curve_path = format!("{}_sleeves_daily.csv", args.prefix);
+        result.save_equity_curve_csv(&curve_path)?;
+        println!("\nCombined equity curve saved to {}", curve_path);
+        return Ok(());
+    }
 
     // Run backtest
     info!("Running backtest...");
@@ -1103,3 +1410,325 @@ async fn main() -> Result<(), Box<dyn StdError>> {
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use backtester::portfolio_accounting::TransactionType;
+
+    fn test_args() -> Args {
+        Args::parse_from(["portfolio_backtest", "--priority-strategy", "test", "--universe", "LC1"])
+    }
+
+    fn test_prices() -> DataFrame {
+        df! {
+            "Date" => &["2024-01-01", "2024-01-02", "2024-01-03", "2024-01-04"],
+            "Ticker" => &["AAPL", "AAPL", "AAPL", "AAPL"],
+            "Close" => &[100.0, 101.0, 102.0, 103.0],
+        }.unwrap()
+    }
+
+    fn signal(date: &str, action: &str) -> Signal {
+        Signal {
+            ticker: "AAPL".to_string(),
+            strategy: "test".to_string(),
+            date: NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap(),
+            action: normalize_action(action),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_limited_cash_blocks_third_entry() {
+        let args = Args::parse_from([
+            "portfolio_backtest", "--priority-strategy", "test", "--universe", "LC1",
+            "--initial-cash", "2500", "--position-value", "1000",
+        ]);
+        let prices = df! {
+            "Date" => &["2024-01-01", "2024-01-01", "2024-01-01", "2024-01-02", "2024-01-02", "2024-01-02"],
+            "Ticker" => &["AAPL", "MSFT", "NVDA", "AAPL", "MSFT", "NVDA"],
+            "Close" => &[100.0, 100.0, 100.0, 100.0, 100.0, 100.0],
+        }.unwrap();
+        let signals = ["AAPL", "MSFT", "NVDA"].iter().map(|t| Signal {
+            ticker: t.to_string(),
+            ..signal("2024-01-01", "buy")
+        }).collect();
+
+        let portfolio = backtest_portfolio(&args, signals, prices).await.unwrap();
+
+        // Slots are free for all three, but only two $1,000 entries fit in $2,500
+        let buys = portfolio.transactions.iter()
+            .filter(|t| t.action == TransactionType::Buy)
+            .count();
+        assert_eq!(buys, 2);
+        assert!(portfolio.get_cash_balance() >= 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_since_drops_earlier_decisions() {
+        let signals = || vec![
+            signal("2024-01-01", "buy"),
+            signal("2024-01-02", "exit"),
+            signal("2024-01-03", "buy"),
+        ];
+        let count = |portfolio: &PortfolioAccounting, action: TransactionType| {
+            portfolio.transactions.iter().filter(|t| t.action == action).count()
+        };
+
+        let all = backtest_portfolio(&test_args(), signals(), test_prices()).await.unwrap();
+        // Two round trips: the second is closed at the end of the data
+        assert_eq!((count(&all, TransactionType::Buy), all.realized_pnl.len()), (2, 2));
+
+        // The 01-01 buy is before the cutoff: the 01-02 exit finds nothing to close
+        let args = Args::parse_from([
+            "portfolio_backtest", "--priority-strategy", "test", "--universe", "LC1",
+            "--since", "2024-01-02",
+        ]);
+        let recent = backtest_portfolio(&args, signals(), test_prices()).await.unwrap();
+        assert_eq!((count(&recent, TransactionType::Buy), recent.realized_pnl.len()), (1, 1));
+        assert!(recent.transactions[0].date >= NaiveDate::from_ymd_opt(2024, 1, 3).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_signal_persistence_carries_lone_buy() {
+        let dates: Vec<String> = (1..=10).map(|d| format!("2024-01-{:02}", d)).collect();
+        let prices = df! {
+            "Date" => &dates,
+            "Ticker" => vec!["AAPL"; dates.len()],
+            "Close" => vec![100.0; dates.len()],
+        }.unwrap();
+        // A weekly strategy buys AAPL on 01-01; a daily strategy buys it on `day`.
+        // MSFT flats give every date a signal without trading anything.
+        let run = |persistence: &'static str, day: usize| {
+            let prices = prices.clone();
+            async move {
+                let args = Args::parse_from([
+                    "portfolio_backtest", "--priority-strategy", "daily", "--universe", "LC1",
+                    "--min-buy-signals", "2", "--signal-persistence", persistence,
+                ]);
+                let mut signals: Vec<Signal> = (1..=9).map(|d| Signal {
+                    ticker: "MSFT".to_string(),
+                    ..signal(&format!("2024-01-{:02}", d), "flat")
+                }).collect();
+                signals.push(Signal { strategy: "weekly".to_string(), ..signal("2024-01-01", "buy") });
+                signals.push(Signal { strategy: "daily".to_string(), ..signal(&format!("2024-01-{:02}", day), "buy") });
+                let portfolio = backtest_portfolio(&args, signals, prices).await.unwrap();
+                portfolio.transactions.iter()
+                    .find(|t| t.action == TransactionType::Buy)
+                    .map(|t| t.date)
+            }
+        };
+
+        // Without persistence the two buys never share a date
+        assert_eq!(run("0", 2).await, None);
+
+        // With persistence 5 the weekly buy still votes on the next five dates
+        for day in 2..=6 {
+            let entry = NaiveDate::from_ymd_opt(2024, 1, day as u32 + 1).unwrap();
+            assert_eq!(run("5", day).await, Some(entry));
+        }
+        assert_eq!(run("5", 7).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_min_buy_signals_above_strategy_count() {
+        let buy = |ticker: &str, strategy: &str| Signal {
+            ticker: ticker.to_string(),
+            strategy: strategy.to_string(),
+            ..signal("2024-01-01", "buy")
+        };
+        let signals = vec![buy("AAPL", "a"), buy("AAPL", "b"), buy("MSFT", "a")];
+
+        // MSFT has one buying strategy, so two agreeing signals only ever come from AAPL
+        assert_eq!(unreachable_min_buy_signals(&signals, 2, 1), vec!["MSFT".to_string()]);
+        // A two-day lookback lets a single strategy's signals add up to two
+        assert!(unreachable_min_buy_signals(&signals, 2, 2).is_empty());
+
+        // More agreement than any ticker has strategies: every ticker is reported, and the
+        // run that follows buys nothing
+        assert_eq!(unreachable_min_buy_signals(&signals, 10, 1), vec!["AAPL".to_string(), "MSFT".to_string()]);
+        let args = Args::parse_from([
+            "portfolio_backtest", "--priority-strategy", "a", "--universe", "LC1",
+            "--min-buy-signals", "10",
+        ]);
+        let portfolio = backtest_portfolio(&args, signals, test_prices()).await.unwrap();
+        assert!(portfolio.transactions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_decision_rows_count_once() {
+        let dir = std::env::temp_dir().join("portfolio_backtest_duplicate_decisions_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("AAPL_test_decisions.csv"),
+            "ticker,strategy,date,action\n\
+             AAPL,test,2024-01-01,buy\n\
+             AAPL,test,2024-01-01,buy\n\
+             AAPL,test,2024-01-02,sell\n",
+        ).unwrap();
+
+        let (signals, _) = read_decision_files(&dir.to_string_lossy(), "LC1", None, None).unwrap();
+        assert_eq!(signals.len(), 2);
+
+        // One buy is not enough for a two-signal minimum
+        let args = Args::parse_from([
+            "portfolio_backtest", "--priority-strategy", "test", "--universe", "LC1",
+            "--min-buy-signals", "2",
+        ]);
+        let portfolio = backtest_portfolio(&args, signals, test_prices()).await.unwrap();
+        assert!(portfolio.transactions.is_empty());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_orphan_decision_dates_reported_and_snapped() {
+        // 2024-01-06 is missing from the prices; the buy on it has no bar to fill on
+        let prices = df! {
+            "Date" => &["2024-01-05", "2024-01-08", "2024-01-09"],
+            "Ticker" => &["AAPL", "AAPL", "AAPL"],
+            "Close" => &[100.0, 101.0, 102.0],
+        }.unwrap();
+        let signals = || vec![signal("2024-01-06", "buy"), signal("2024-01-09", "sell")];
+        let jan = |d: u32| NaiveDate::from_ymd_opt(2024, 1, d).unwrap();
+
+        let (kept, orphans) = align_to_price_calendar(signals(), &prices, false).unwrap();
+        assert_eq!(orphans, vec![jan(6)]);
+        assert_eq!(kept[0].date, jan(6));
+        let portfolio = backtest_portfolio(&test_args(), kept, prices.clone()).await.unwrap();
+        assert!(portfolio.transactions.is_empty());
+
+        let (snapped, orphans) = align_to_price_calendar(signals(), &prices, true).unwrap();
+        assert_eq!(orphans, vec![jan(6)]);
+        assert_eq!((snapped[0].date, snapped[1].date), (jan(8), jan(9)));
+        // The snapped buy on 01-08 enters the next day
+        let portfolio = backtest_portfolio(&test_args(), snapped, prices).await.unwrap();
+        assert_eq!(portfolio.transactions[0].date, jan(9));
+    }
+
+    #[test]
+    fn test_decision_file_contents_override_filename() {
+        let dir = std::env::temp_dir().join("portfolio_backtest_decision_filename_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        // Named for AAPL/test, but the rows are MSFT/hammer
+        std::fs::write(
+            dir.join("AAPL_test_decisions.csv"),
+            "ticker,strategy,date,action\n\
+             MSFT,hammer,2024-01-01,buy\n",
+        ).unwrap();
+        // An underscore in the ticker would have made "B_test" the strategy
+        std::fs::write(
+            dir.join("BRK_B_test_decisions.csv"),
+            "ticker,strategy,date,action\n\
+             BRK_B,test,2024-01-02,buy\n",
+        ).unwrap();
+        let folder = dir.to_string_lossy().into_owned();
+
+        let (signals, available) = read_decision_files(&folder, "LC1", None, None).unwrap();
+        assert_eq!(available, vec!["hammer".to_string(), "test".to_string()]);
+        let rows: Vec<(&str, &str)> = signals.iter().map(|s| (s.ticker.as_str(), s.strategy.as_str())).collect();
+        assert_eq!(rows, vec![("MSFT", "hammer"), ("BRK_B", "test")]);
+
+        // Strategy selection reads only the files named for the strategy, then goes by
+        // the in-file strategy: AAPL_test holds hammer rows, so only BRK_B is selected
+        let only = Some(vec!["test".to_string()]);
+        let (signals, _) = read_decision_files(&folder, "LC1", None, only).unwrap();
+        assert_eq!(signals.len(), 1);
+        assert_eq!(signals[0].ticker, "BRK_B");
+
+        // A file not named for a selected strategy isn't read at all
+        let only = Some(vec!["hammer".to_string()]);
+        let (signals, _) = read_decision_files(&folder, "LC1", None, only).unwrap();
+        assert!(signals.is_empty());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_strategy_sleeves_blend_by_capital() {
+        let prices = df! {
+            "Date" => &["2024-01-01", "2024-01-02", "2024-01-03", "2024-01-04",
+                        "2024-01-01", "2024-01-02", "2024-01-03", "2024-01-04"],
+            "Ticker" => &["AAPL", "AAPL", "AAPL", "AAPL", "MSFT", "MSFT", "MSFT", "MSFT"],
+            "Close" => &[100.0, 101.0, 102.0, 103.0, 100.0, 99.0, 98.0, 97.0],
+        }.unwrap();
+        let decision = |ticker: &str, strategy: &str, date: &str, action: &str| Signal {
+            ticker: ticker.to_string(),
+            strategy: strategy.to_string(),
+            ..signal(date, action)
+        };
+        let decisions = BTreeMap::from([
+            ("winner".to_string(), vec![
+                decision("AAPL", "winner", "2024-01-01", "buy"),
+                decision("AAPL", "winner", "2024-01-03", "sell"),
+            ]),
+            ("loser".to_string(), vec![
+                decision("MSFT", "loser", "2024-01-01", "buy"),
+                decision("MSFT", "loser", "2024-01-03", "sell"),
+            ]),
+        ]);
+        let capital = HashMap::from([("winner".to_string(), 10000.0), ("loser".to_string(), 5000.0)]);
+
+        let result = backtest_strategy_sleeves(&test_args(), decisions, &capital, prices).await.unwrap();
+
+        let sleeve = |name: &str| result.attribution.iter().find(|a| a.strategy == name).unwrap().clone();
+        let (winner, loser) = (sleeve("winner"), sleeve("loser"));
+        assert!(winner.return_pct > 0.0 && loser.return_pct < 0.0);
+        assert_eq!(result.sleeves["winner"].realized_pnl.len(), 1);
+
+        // The combined return is the capital-weighted blend of the sleeve returns
+        let blended = (winner.return_pct * 10000.0 + loser.return_pct * 5000.0) / 15000.0;
+        assert!((result.return_pct - blended).abs() < 1e-9);
+        let combined_final = winner.final_value + loser.final_value;
+        assert!((result.return_pct - (combined_final / 15000.0 - 1.0) * 100.0).abs() < 1e-9);
+
+        // The curve sums both sleeves on each snapshot date
+        assert_eq!(result.equity_curve.len(), 2);
+        let first = &result.equity_curve[0];
+        let sleeve_values: Vec<f64> = result.sleeves.values().map(|p| p.daily_snapshots[0].total_value).collect();
+        assert_eq!(first.sleeve_values, sleeve_values);
+        assert!((first.total_value - sleeve_values.iter().sum::<f64>()).abs() < 1e-9);
+
+        // ...and is written out with a column per sleeve
+        let path = std::env::temp_dir().join(format!("portfolio_sleeves_daily_test_{}.csv", std::process::id()));
+        result.save_equity_curve_csv(&path.to_string_lossy()).unwrap();
+        let saved = CsvReadOptions::default()
+            .with_has_header(true)
+            .try_into_reader_with_file_path(Some(path.clone()))
+            .unwrap()
+            .finish()
+            .unwrap();
+        let names: Vec<&str> = saved.get_column_names().into_iter().map(|n| n.as_str()).collect();
+        assert_eq!(names, ["date", "loser", "winner", "total_value"]);
+        assert_eq!(saved.height(), 2);
+        assert!((saved.column("total_value").unwrap().f64().unwrap().get(0).unwrap() - first.total_value).abs() < 1e-9);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_normalize_action() {
+        assert_eq!(normalize_action("EXIT"), "flat");
+        assert_eq!(normalize_action("Flat"), "flat");
+        assert_eq!(normalize_action("buy"), "buy");
+    }
+
+    #[test]
+    fn test_short_flags_parse() {
+        let args = Args::parse_from(["portfolio_backtest", "-p", "test", "-u", "LC1", "-f", "mytest"]);
+        assert_eq!(args.priority_strategy, "test");
+        assert_eq!(args.prefix, "mytest");
+    }
+
+    #[tokio::test]
+    async fn test_flat_after_buy_closes_without_shorting() {
+        let signals = vec![signal("2024-01-01", "buy"), signal("2024-01-02", "exit")];
+        let portfolio = backtest_portfolio(&test_args(), signals, test_prices()).await.unwrap();
+
+        assert_eq!(portfolio.get_position_count(), 0);
+        assert_eq!(portfolio.transactions.len(), 2);
+        assert!(portfolio.transactions.iter().all(|t| t.shares > 0.0));
+        assert_eq!(portfolio.realized_pnl.len(), 1);
+        assert_eq!(portfolio.realized_pnl[0].exit_price, 102.0);
+    }
+}
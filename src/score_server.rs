@@ -0,0 +1,91 @@
+//! Serves the latest scores as JSON for dashboards:
+//!
+//!     cargo run --features server --bin score_server -- --port 8080
+//!     curl localhost:8080/scores/crypto
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    routing::get,
+    Json, Router,
+};
+use backtester::latest_scores_json;
+use clap::Parser;
+use log::info;
+use std::{env, error::Error as StdError};
+
+/// JSON server for the newest score files
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    /// Backtester home holding the score/ folder (default: CLICKHOUSE_USER_PATH/rust_home/backtester)
+    #[arg(short, long)]
+    path: Option<String>,
+
+    /// Port to listen on
+    #[arg(long, default_value = "8080")]
+    port: u16,
+
+    /// Enable verbose logging
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    verbose: u8,
+}
+
+/// `GET /scores/{asset_type}`: today's ranked buys and sells, see `latest_scores_json`
+async fn scores(
+    State(score_dir): State<String>,
+    Path(asset_type): Path<String>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    latest_scores_json(&score_dir, &asset_type)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::NOT_FOUND, e.to_string()))
+}
+
+fn app(score_dir: String) -> Router {
+    Router::new().route("/scores/:asset_type", get(scores)).with_state(score_dir)
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn StdError>> {
+    let args = Args::parse();
+    backtester::init_logging(args.verbose);
+
+    let path = args.path.unwrap_or_else(|| {
+        let user_path = env::var("CLICKHOUSE_USER_PATH").unwrap_or_else(|_| String::from("/srv"));
+        format!("{}/rust_home/backtester", user_path)
+    });
+    let score_dir = format!("{}/score", path);
+
+    let listener = tokio::net::TcpListener::bind(("0.0.0.0", args.port)).await?;
+    info!("Serving scores from {} on port {}", score_dir, args.port);
+    axum::serve(listener, app(score_dir)).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_scores_route_returns_ranked_json() {
+        let dir = std::env::temp_dir().join(format!("score_server_test_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("Crypto_20240301.csv"),
+            "date,universe,ticker,side,risk_reward,sharpe_ratio,sortino_ratio,max_drawdown,\
+             calmar_ratio,win_loss_ratio,recovery_factor,profit_per_trade,expectancy,profit_factor\n\
+             2024-03-01,Crypto,btc,2,1,1,1,1,1,1,1,1,0.5,3.0\n",
+        ).unwrap();
+        let score_dir = dir.to_string_lossy().into_owned();
+
+        let Json(body) = scores(State(score_dir.clone()), Path("crypto".to_string())).await.unwrap();
+        assert_eq!(body["buys"][0]["ticker"], "btc");
+        assert_eq!(body["sells"], serde_json::json!([]));
+
+        let (status, _) = scores(State(score_dir), Path("stocks".to_string())).await.unwrap_err();
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -3,9 +3,151 @@
 
 use crate::signals;
 use backtester::SignalFunctionWithParam;
+use log::{info, warn};
+use std::{collections::HashSet, env, fs, path::Path};
 
-/// Get the appropriate strategy list for a given universe tag
+/// One strategy table entry: (name, signal function, parameter)
+pub type StrategyEntry = (String, SignalFunctionWithParam, f64);
+
+/// Env var holding strategies to drop from every run (comma-separated, or a file path)
+pub const STRATEGY_DENY_ENV: &str = "BACKTESTER_STRATEGY_DENY";
+/// Env var holding the only strategies allowed to run (comma-separated, or a file path)
+pub const STRATEGY_ALLOW_ENV: &str = "BACKTESTER_STRATEGY_ALLOW";
+
+/// Runtime allow/deny list applied on top of the compiled strategy tables
+#[derive(Debug, Clone, Default)]
+pub struct StrategyFilter {
+    /// If set, only these strategies are kept
+    pub allow: Option<HashSet<String>>,
+    /// Strategies that are always removed
+    pub deny: HashSet<String>,
+}
+
+impl StrategyFilter {
+    /// Build the filter from `BACKTESTER_STRATEGY_ALLOW` / `BACKTESTER_STRATEGY_DENY`
+    pub fn from_env() -> Self {
+        Self::from_values(
+            env::var(STRATEGY_ALLOW_ENV).ok().as_deref(),
+            env::var(STRATEGY_DENY_ENV).ok().as_deref(),
+        )
+    }
+
+    /// Build the filter from allow/deny values as `from_env` reads them. A list that
+    /// can't be read is left unset, so a mistyped allow path runs every strategy rather
+    /// than none.
+    pub fn from_values(allow: Option<&str>, deny: Option<&str>) -> Self {
+        Self {
+            allow: allow.and_then(|v| parse_strategy_list(STRATEGY_ALLOW_ENV, v)),
+            deny: deny.and_then(|v| parse_strategy_list(STRATEGY_DENY_ENV, v)).unwrap_or_default(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.allow.is_none() && self.deny.is_empty()
+    }
+
+    pub fn is_enabled(&self, name: &str) -> bool {
+        !self.deny.contains(name) && self.allow.as_ref().is_none_or(|a| a.contains(name))
+    }
+
+    /// Remove disabled strategies, preserving the order of the remaining entries
+    pub fn apply(&self, strategies: Vec<StrategyEntry>) -> Vec<StrategyEntry> {
+        if self.is_empty() {
+            return strategies;
+        }
+        let before = strategies.len();
+        let kept: Vec<_> = strategies
+            .into_iter()
+            .filter(|(name, _, _)| self.is_enabled(name))
+            .collect();
+        if kept.len() != before {
+            info!("Strategy allow/deny list removed {} of {} strategies", before - kept.len(), before);
+        }
+        kept
+    }
+}
+
+/// Parse the strategy list in `var`: either a path to a file with one name per line
+/// ('#' starts a comment) or a comma-separated list of names. A path (a value naming a
+/// file, or containing '/' or ending in ".txt") that can't be read is warned about and
+/// gives None.
+fn parse_strategy_list(var: &str, value: &str) -> Option<HashSet<String>> {
+    let value = value.trim();
+    let contents = if Path::new(value).is_file() || value.contains('/') || value.ends_with(".txt") {
+        match fs::read_to_string(value) {
+            Ok(contents) => contents,
+            Err(e) => {
+                warn!("Ignoring {}: cannot read strategy list {}: {}", var, value, e);
+                return None;
+            }
+        }
+    } else {
+        value.replace(',', "\n")
+    };
+    Some(
+        contents
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or("").trim())
+            .filter(|name| !name.is_empty())
+            .map(|name| name.to_string())
+            .collect(),
+    )
+}
+
+/// Load an explicit strategy list from a file (one name per line, '#' starts a comment).
+/// Names are resolved against the full strategy table; unknown names are an error.
+pub fn strategies_from_file(path: &str) -> Result<Vec<StrategyEntry>, Box<dyn std::error::Error>> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("Cannot read strategy file {}: {}", path, e))?;
+    let registry = all_strategies();
+
+    let mut strategies = Vec::new();
+    for (line_no, line) in contents.lines().enumerate() {
+        let name = line.split('#').next().unwrap_or("").trim();
+        if name.is_empty() {
+            continue;
+        }
+        match registry.iter().find(|(n, _, _)| n == name) {
+            Some(entry) => strategies.push(entry.clone()),
+            None => {
+                return Err(format!(
+                    "Unknown strategy '{}' at {}:{}", name, path, line_no + 1
+                ).into())
+            }
+        }
+    }
+    Ok(dedup_strategies(strategies))
+}
+
+/// Get the appropriate strategy list for a given universe tag, with duplicate
+/// entries dropped and the runtime allow/deny list applied
 pub fn get_strategies_for_tag(tag: &str) -> Vec<(String, SignalFunctionWithParam, f64)> {
+    StrategyFilter::from_env().apply(dedup_strategies(strategies_for_tag(tag)))
+}
+
+/// Keep the first entry of each (name, param) pair and warn about the rest, so a
+/// strategy listed twice is not backtested and saved twice
+pub fn dedup_strategies(strategies: Vec<StrategyEntry>) -> Vec<StrategyEntry> {
+    let mut seen = HashSet::new();
+    let mut duplicates = Vec::new();
+    let kept = strategies
+        .into_iter()
+        .filter(|(name, _, param)| {
+            let first = seen.insert((name.clone(), param.to_bits()));
+            if !first {
+                duplicates.push(format!("{}({})", name, param));
+            }
+            first
+        })
+        .collect();
+    if !duplicates.is_empty() {
+        warn!("Dropped {} duplicate strategy entries: {}", duplicates.len(), duplicates.join(", "));
+    }
+    kept
+}
+
+/// Full compiled strategy list for a given universe tag, before dedup and filtering
+pub fn strategies_for_tag(tag: &str) -> Vec<(String, SignalFunctionWithParam, f64)> {
     match tag {
         "lc" => large_cap_strategies(),
         "mc" => mid_cap_strategies(),
@@ -395,364 +537,186 @@ pub fn testing_strategies() -> Vec<(String, SignalFunctionWithParam, f64)> {
     strategies
 }
 
-/// All strategies - comprehensive list for testing/evaluation
-pub fn all_strategies() -> Vec<(String, SignalFunctionWithParam, f64)> {
+/// Registry entry for one strategy: output name, signal module (category),
+/// function name and default parameter
+#[derive(Debug, Clone)]
+pub struct SignalSpec {
+    pub name: String,
+    pub category: &'static str,
+    pub function: &'static str,
+    pub func: SignalFunctionWithParam,
+    pub default_param: f64,
+}
+
+/// Output names that intentionally differ from their signal function; kept so
+/// historical output CSVs and scores keep the same strategy name
+const NAME_ALIASES: &[(&str, &str)] = &[
+    ("trend_fol_3candle_ma", "trend_following_3candle_ma"),
+    ("double_trouble", "double_trouble_1"),
+    ("awesome", "awesome_indicator"),
+    ("supertrend", "supertrend_indicator"),
+    ("pattern_td_camouflauge", "pattern_td_camouflage"),
+];
+
+impl SignalSpec {
+    /// Whether the output name refers to the wired function: the same name, the name
+    /// plus a parameter suffix (e.g. `_2.0`), or a known alias
+    pub fn name_matches_function(&self) -> bool {
+        self.name == self.function
+            || self
+                .name
+                .strip_prefix(self.function)
+                .is_some_and(|suffix| suffix.starts_with('_'))
+            || NAME_ALIASES.contains(&(self.name.as_str(), self.function))
+    }
+}
+
+macro_rules! spec {
+    ($name:expr, $category:ident :: $function:ident, $param:expr) => {
+        SignalSpec {
+            name: $name.to_string(),
+            category: stringify!($category),
+            function: stringify!($function),
+            func: signals::$category::$function,
+            default_param: $param,
+        }
+    };
+}
+
+/// Canonical registry of every strategy, each name listed once
+pub fn list_all_signals() -> Vec<SignalSpec> {
     vec![
-        (
-            "candlestick_double_trouble_2.0".to_string(),
-            signals::mfpr::candlestick_double_trouble,
-            2.0,
-        ),
-        ("three_candles".to_string(), signals::mfpr::three_candles, 0.0),
-        (
-            "trend_fol_3candle_ma".to_string(),
-            signals::mfpr::trend_following_3candle_ma,
-            0.0,
-        ),
-        ("pattern_marubozu".to_string(), signals::bots::pattern_marubozu, 0.0),
-        ("pattern_hammer".to_string(), signals::bots::pattern_hammer, 0.0),
-        ("hammer".to_string(), signals::mfpr::hammer, 0.0),
-        ("tweezers".to_string(), signals::mfpr::tweezers, 0.0),
-        ("hikkake".to_string(), signals::mfpr::hikkake, 0.0),
-        ("slingshot".to_string(), signals::mfpr::slingshot, 0.0),
-        ("quintuplets_0005".to_string(), signals::mfpr::quintuplets_0005, 0.0),
-        ("quintuplets_2".to_string(), signals::mfpr::quintuplets_2, 0.0),
-        ("quintuplets_10".to_string(), signals::mfpr::quintuplets_10, 0.0),
-        ("quintuplets_50".to_string(), signals::mfpr::quintuplets_50, 0.0),
-        ("marubozu".to_string(), signals::mfpr::marubozu, 0.0),
-        ("tasuki".to_string(), signals::mfpr::tasuki, 0.0),
-        ("three_methods".to_string(), signals::mfpr::three_methods, 0.0),
-        (
-            "fibonacci_range".to_string(),
-            signals::trend_following::fibonacci_range,
-            0.0,
-        ),
-        (
-            "adx_indicator".to_string(),
-            signals::trend_following::adx_indicator,
-            0.0,
-        ),
-        (
-            "donchian_indicator".to_string(),
-            signals::trend_following::donchian_indicator,
-            0.0,
-        ),
-        (
-            "donchian_indicator_inverse".to_string(),
-            signals::trend_following::donchian_indicator_inverse,
-            0.0,
-        ),
-        ("tower".to_string(), signals::mfpr::tower, 0.0),
-        ("bottle".to_string(), signals::mfpr::bottle, 0.0),
-        ("double_trouble".to_string(), signals::mfpr::double_trouble_1, 0.0),
-        ("h".to_string(), signals::mfpr::h, 0.0),
-        ("abandoned_baby".to_string(), signals::mfpr::abandoned_baby, 0.0),
-        ("doji".to_string(), signals::mfpr::doji, 0.0),
-        ("engulfing".to_string(), signals::mfpr::engulfing, 0.0),
-        ("harami_flexible".to_string(), signals::mfpr::harami_flexible, 0.0),
-        ("harami_strict".to_string(), signals::mfpr::harami_strict, 0.0),
-        ("inside_up_down".to_string(), signals::mfpr::inside_up_down, 0.0),
-        ("on_neck".to_string(), signals::mfpr::on_neck, 0.0),
-        ("piercing".to_string(), signals::mfpr::piercing, 0.0),
-        ("spinning_top".to_string(), signals::mfpr::spinning_top, 0.0),
-        ("star".to_string(), signals::mfpr::star, 0.0),
-        ("stick_sandwich".to_string(), signals::mfpr::stick_sandwich, 0.0),
-        ("barrier".to_string(), signals::mfpr::barrier, 0.0),
-        ("blockade".to_string(), signals::mfpr::blockade, 1.5),
-        ("doppleganger".to_string(), signals::mfpr::doppleganger, 0.0),
-        ("euphoria".to_string(), signals::mfpr::euphoria, 0.0),
-        ("mirror".to_string(), signals::mfpr::mirror, 0.0),
-        ("shrinking".to_string(), signals::mfpr::shrinking, 0.0),
-        ("heikin_ashi_doji".to_string(), signals::mfpr::heikin_ashi_doji, 0.0),
-        (
-            "heikin_ashi_double_trouble".to_string(),
-            signals::mfpr::heikin_ashi_double_trouble,
-            0.0,
-        ),
-        (
-            "heikin_ashi_euphoria".to_string(),
-            signals::mfpr::heikin_ashi_euphoria,
-            0.0,
-        ),
-        ("heikin_ashi_tasuki".to_string(), signals::mfpr::heikin_ashi_tasuki, 0.0),
-        ("candlestick_doji".to_string(), signals::mfpr::candlestick_doji, 0.0),
-        (
-            "candlestick_double_trouble".to_string(),
-            signals::mfpr::candlestick_double_trouble,
-            2.0,
-        ),
-        ("candlestick_tasuki".to_string(), signals::mfpr::candlestick_tasuki, 0.0),
-        (
-            "trend_fol_bottle_stoch".to_string(),
-            signals::mfpr::trend_fol_bottle_stoch,
-            1.5,
-        ),
-        (
-            "trend_fol_2trouble_rsi".to_string(),
-            signals::mfpr::trend_fol_2trouble_rsi,
-            1.5,
-        ),
-        (
-            "trend_fol_h_trend_intensity".to_string(),
-            signals::mfpr::trend_fol_h_trend_intensity,
-            1.5,
-        ),
-        (
-            "trend_fol_marubozu_k_vol_bands".to_string(),
-            signals::mfpr::trend_fol_marubozu_k_vol_bands,
-            1.5,
-        ),
-        (
-            "contrarian_barrier_rsi_atr".to_string(),
-            signals::mfpr::contrarian_barrier_rsi_atr,
-            1.5,
-        ),
-        (
-            "contrarian_doji_rsi".to_string(),
-            signals::mfpr::contrarian_doji_rsi,
-            1.5,
-        ),
-        (
-            "contrarian_engulfing_bbands".to_string(),
-            signals::mfpr::contrarian_engulfing_bbands,
-            1.5,
-        ),
-        (
-            "contrarian_euphoria_k_env".to_string(),
-            signals::mfpr::contrarian_euphoria_k_env,
-            0.0,
-        ),
-        (
-            "contrarian_piercing_stoch".to_string(),
-            signals::mfpr::contrarian_piercing_stoch,
-            0.0,
-        ),
-        (
-            "elder_impulse_1".to_string(),
-            signals::trend_following::elder_impulse_1,
-            0.0,
-        ),
-        (
-            "elder_impulse_2".to_string(),
-            signals::trend_following::elder_impulse_2,
-            0.0,
-        ),
-        (
-            "elder_impulse_3".to_string(),
-            signals::trend_following::elder_impulse_3,
-            0.0,
-        ),
-        ("gri_index".to_string(), signals::trend_following::gri_index, 0.0),
-        (
-            "slope_indicator".to_string(),
-            signals::trend_following::slope_indicator,
-            0.0,
-        ),
-        ("heikin_ashi".to_string(), signals::trend_following::heikin_ashi, 0.0),
-        (
-            "inside_candle".to_string(),
-            signals::trend_following::inside_candle,
-            0.0,
-        ),
-        (
-            "aroon_oscillator".to_string(),
-            signals::trend_following::aroon_oscillator,
-            0.0,
-        ),
-        ("awesome".to_string(), signals::trend_following::awesome_indicator, 0.0),
-        ("macd_change".to_string(), signals::trend_following::macd_change, 0.0),
-        (
-            "squeeze_momentum".to_string(),
-            signals::trend_following::squeeze_momentum,
-            0.0,
-        ),
-        (
-            "supertrend".to_string(),
-            signals::trend_following::supertrend_indicator,
-            0.0,
-        ),
-        (
-            "trend_intensity_ind".to_string(),
-            signals::trend_following::trend_intensity_ind,
-            0.0,
-        ),
-        (
-            "vertical_horizontal_cross".to_string(),
-            signals::trend_following::vertical_horizontal_cross,
-            0.0,
-        ),
-        (
-            "ichimoku_cloud".to_string(),
-            signals::trend_following::ichimoku_cloud,
-            0.0,
-        ),
-        ("tf1_ma".to_string(), signals::trend_following::tf1_ma, 0.0),
-        ("tf2_ma".to_string(), signals::trend_following::tf2_ma, 0.0),
-        ("tf3_rsi_ma".to_string(), signals::trend_following::tf3_rsi_ma, 0.0),
-        ("tf4_macd".to_string(), signals::trend_following::tf4_macd, 0.0),
-        ("tf5_ma_slope".to_string(), signals::trend_following::tf5_ma_slope, 0.0),
-        (
-            "tf6_supertrend_flip".to_string(),
-            signals::trend_following::tf6_supertrend_flip,
-            0.0,
-        ),
-        ("tf7_psar_ma".to_string(), signals::trend_following::tf7_psar_ma, 0.0),
-        ("tf9_tii".to_string(), signals::trend_following::tf9_tii, 0.0),
-        ("tf10_ma".to_string(), signals::trend_following::tf10_ma, 0.0),
-        (
-            "tf11_rsi_neutrality".to_string(),
-            signals::trend_following::tf11_rsi_neutrality,
-            0.0,
-        ),
-        ("tf12_vama".to_string(), signals::trend_following::tf12_vama, 0.0),
-        (
-            "tf13_rsi_supertrend".to_string(),
-            signals::trend_following::tf13_rsi_supertrend,
-            0.0,
-        ),
-        (
-            "tf14_catapult".to_string(),
-            signals::trend_following::tf14_catapult,
-            0.0,
-        ),
-        (
-            "contrarian_aug_bbands".to_string(),
-            signals::bots::contrarian_aug_bbands,
-            0.0,
-        ),
-        ("contrarian_bbands".to_string(), signals::bots::contrarian_bbands, 0.0),
-        (
-            "contrarian_dual_bbands".to_string(),
-            signals::bots::contrarian_dual_bbands,
-            0.0,
-        ),
-        (
-            "contrarian_countdown_cross".to_string(),
-            signals::bots::contrarian_countdown_cross,
-            0.0,
-        ),
-        (
-            "contrarian_countdown_duration".to_string(),
-            signals::bots::contrarian_countdown_duration,
-            0.0,
-        ),
-        ("key_reversal".to_string(), signals::bots::key_reversal, 0.0),
-        ("k_extreme_duration".to_string(), signals::bots::k_extreme_duration, 0.0),
-        (
-            "contrarian_countdown_extremes".to_string(),
-            signals::bots::contrarian_countdown_extremes,
-            0.0,
-        ),
-        (
-            "contrarian_demarker_cross".to_string(),
-            signals::bots::contrarian_demarker_cross,
-            0.0,
-        ),
-        (
-            "contrarian_demarker_extremes".to_string(),
-            signals::bots::contrarian_demarker_extremes,
-            0.0,
-        ),
-        (
-            "contrarian_disparity_extremes".to_string(),
-            signals::bots::contrarian_disparity_extremes,
-            0.0,
-        ),
-        (
-            "contrarian_fisher_duration".to_string(),
-            signals::bots::contrarian_fisher_duration,
-            0.0,
-        ),
-        (
-            "contrarian_fisher_extremes".to_string(),
-            signals::bots::contrarian_fisher_extremes,
-            0.0,
-        ),
-        (
-            "contrarian_real_range_extremes".to_string(),
-            signals::bots::contrarian_real_range_extremes,
-            0.0,
-        ),
-        ("pattern_piercing".to_string(), signals::bots::pattern_piercing, 0.0),
-        (
-            "pattern_td_camouflauge".to_string(),
-            signals::bots::pattern_td_camouflage,
-            0.0,
-        ),
-        ("pattern_td_clopwin".to_string(), signals::bots::pattern_td_clopwin, 0.0),
-        ("pattern_td_waldo_2".to_string(), signals::bots::pattern_td_waldo_2, 0.0),
-        (
-            "contrarian_rsi_cross".to_string(),
-            signals::bots::contrarian_rsi_cross,
-            0.0,
-        ),
-        (
-            "contrarian_rsi_divergences".to_string(),
-            signals::bots::contrarian_rsi_divergences,
-            0.0,
-        ),
-        (
-            "contrarian_rsi_duration".to_string(),
-            signals::bots::contrarian_rsi_duration,
-            0.0,
-        ),
-        (
-            "contrarian_rsi_extremes".to_string(),
-            signals::bots::contrarian_rsi_extremes,
-            0.0,
-        ),
-        (
-            "contrarian_stochastic_cross".to_string(),
-            signals::bots::contrarian_stochastic_cross,
-            0.0,
-        ),
-        (
-            "contrarian_stochastic_divergences".to_string(),
-            signals::bots::contrarian_stochastic_divergences,
-            0.0,
-        ),
-        (
-            "contrarian_stochastic_duration".to_string(),
-            signals::bots::contrarian_stochastic_duration,
-            0.0,
-        ),
-        (
-            "contrarian_stochastic_extremes".to_string(),
-            signals::bots::contrarian_stochastic_extremes,
-            0.0,
-        ),
-        (
-            "contrarian_time_up_extremes".to_string(),
-            signals::bots::contrarian_time_up_extremes,
-            0.0,
-        ),
-        ("contrarian_tsabm".to_string(), signals::bots::contrarian_tsabm, 0.0),
-        (
-            "pattern_differentials".to_string(),
-            signals::bots::pattern_differentials,
-            0.0,
-        ),
-        ("pattern_engulfing".to_string(), signals::bots::pattern_engulfing, 0.0),
-        (
-            "pattern_fibonacci_timing".to_string(),
-            signals::bots::pattern_fibonacci_timing,
-            0.0,
-        ),
-        ("pattern_td_clop".to_string(), signals::bots::pattern_td_clop, 0.0),
-        ("pattern_td_open".to_string(), signals::bots::pattern_td_open, 0.0),
-        ("pattern_td_trap".to_string(), signals::bots::pattern_td_trap, 0.0),
-        ("pattern_td_waldo_5".to_string(), signals::bots::pattern_td_waldo_5, 0.0),
-        ("pattern_td_waldo_6".to_string(), signals::bots::pattern_td_waldo_6, 0.0),
-        ("pattern_td_waldo_8".to_string(), signals::bots::pattern_td_waldo_8, 0.0),
-        (
-            "pattern_three_line_strike".to_string(),
-            signals::bots::pattern_three_line_strike,
-            0.0,
-        ),
-        (
-            "pattern_three_methods".to_string(),
-            signals::bots::pattern_three_methods,
-            0.0,
-        ),
+        spec!("candlestick_double_trouble_2.0", mfpr::candlestick_double_trouble, 2.0),
+        spec!("three_candles", mfpr::three_candles, 0.0),
+        spec!("trend_fol_3candle_ma", mfpr::trend_following_3candle_ma, 0.0),
+        spec!("pattern_marubozu", bots::pattern_marubozu, 0.0),
+        spec!("pattern_hammer", bots::pattern_hammer, 0.0),
+        spec!("hammer", mfpr::hammer, 0.0),
+        spec!("tweezers", mfpr::tweezers, 0.0),
+        spec!("hikkake", mfpr::hikkake, 0.0),
+        spec!("slingshot", mfpr::slingshot, 0.0),
+        spec!("quintuplets_0005", mfpr::quintuplets_0005, 0.0),
+        spec!("quintuplets_2", mfpr::quintuplets_2, 0.0),
+        spec!("quintuplets_10", mfpr::quintuplets_10, 0.0),
+        spec!("quintuplets_50", mfpr::quintuplets_50, 0.0),
+        spec!("marubozu", mfpr::marubozu, 0.0),
+        spec!("tasuki", mfpr::tasuki, 0.0),
+        spec!("three_methods", mfpr::three_methods, 0.0),
+        spec!("fibonacci_range", trend_following::fibonacci_range, 0.0),
+        spec!("adx_indicator", trend_following::adx_indicator, 0.0),
+        spec!("donchian_indicator", trend_following::donchian_indicator, 0.0),
+        spec!("donchian_indicator_inverse", trend_following::donchian_indicator_inverse, 0.0),
+        spec!("tower", mfpr::tower, 0.0),
+        spec!("bottle", mfpr::bottle, 0.0),
+        spec!("double_trouble", mfpr::double_trouble_1, 0.0),
+        spec!("h", mfpr::h, 0.0),
+        spec!("abandoned_baby", mfpr::abandoned_baby, 0.0),
+        spec!("doji", mfpr::doji, 0.0),
+        spec!("engulfing", mfpr::engulfing, 0.0),
+        spec!("harami_flexible", mfpr::harami_flexible, 0.0),
+        spec!("harami_strict", mfpr::harami_strict, 0.0),
+        spec!("inside_up_down", mfpr::inside_up_down, 0.0),
+        spec!("on_neck", mfpr::on_neck, 0.0),
+        spec!("piercing", mfpr::piercing, 0.0),
+        spec!("spinning_top", mfpr::spinning_top, 0.0),
+        spec!("star", mfpr::star, 0.0),
+        spec!("stick_sandwich", mfpr::stick_sandwich, 0.0),
+        spec!("barrier", mfpr::barrier, 0.0),
+        spec!("blockade", mfpr::blockade, 1.5),
+        spec!("doppleganger", mfpr::doppleganger, 0.0),
+        spec!("euphoria", mfpr::euphoria, 0.0),
+        spec!("mirror", mfpr::mirror, 0.0),
+        spec!("shrinking", mfpr::shrinking, 0.0),
+        spec!("heikin_ashi_doji", mfpr::heikin_ashi_doji, 0.0),
+        spec!("heikin_ashi_double_trouble", mfpr::heikin_ashi_double_trouble, 0.0),
+        spec!("heikin_ashi_euphoria", mfpr::heikin_ashi_euphoria, 0.0),
+        spec!("heikin_ashi_tasuki", mfpr::heikin_ashi_tasuki, 0.0),
+        spec!("candlestick_doji", mfpr::candlestick_doji, 0.0),
+        spec!("candlestick_double_trouble", mfpr::candlestick_double_trouble, 2.0),
+        spec!("candlestick_tasuki", mfpr::candlestick_tasuki, 0.0),
+        spec!("trend_fol_bottle_stoch", mfpr::trend_fol_bottle_stoch, 1.5),
+        spec!("trend_fol_2trouble_rsi", mfpr::trend_fol_2trouble_rsi, 1.5),
+        spec!("trend_fol_h_trend_intensity", mfpr::trend_fol_h_trend_intensity, 1.5),
+        spec!("trend_fol_marubozu_k_vol_bands", mfpr::trend_fol_marubozu_k_vol_bands, 1.5),
+        spec!("contrarian_barrier_rsi_atr", mfpr::contrarian_barrier_rsi_atr, 1.5),
+        spec!("contrarian_doji_rsi", mfpr::contrarian_doji_rsi, 1.5),
+        spec!("contrarian_engulfing_bbands", mfpr::contrarian_engulfing_bbands, 1.5),
+        spec!("contrarian_euphoria_k_env", mfpr::contrarian_euphoria_k_env, 0.0),
+        spec!("contrarian_piercing_stoch", mfpr::contrarian_piercing_stoch, 0.0),
+        spec!("elder_impulse_1", trend_following::elder_impulse_1, 0.0),
+        spec!("elder_impulse_2", trend_following::elder_impulse_2, 0.0),
+        spec!("elder_impulse_3", trend_following::elder_impulse_3, 0.0),
+        spec!("gri_index", trend_following::gri_index, 0.0),
+        spec!("slope_indicator", trend_following::slope_indicator, 0.0),
+        spec!("heikin_ashi", trend_following::heikin_ashi, 0.0),
+        spec!("inside_candle", trend_following::inside_candle, 0.0),
+        spec!("aroon_oscillator", trend_following::aroon_oscillator, 0.0),
+        spec!("awesome", trend_following::awesome_indicator, 0.0),
+        spec!("macd_change", trend_following::macd_change, 0.0),
+        spec!("squeeze_momentum", trend_following::squeeze_momentum, 0.0),
+        spec!("supertrend", trend_following::supertrend_indicator, 0.0),
+        spec!("trend_intensity_ind", trend_following::trend_intensity_ind, 0.0),
+        spec!("vertical_horizontal_cross", trend_following::vertical_horizontal_cross, 0.0),
+        spec!("ichimoku_cloud", trend_following::ichimoku_cloud, 0.0),
+        spec!("tf1_ma", trend_following::tf1_ma, 0.0),
+        spec!("tf2_ma", trend_following::tf2_ma, 0.0),
+        spec!("tf3_rsi_ma", trend_following::tf3_rsi_ma, 0.0),
+        spec!("tf4_macd", trend_following::tf4_macd, 0.0),
+        spec!("tf5_ma_slope", trend_following::tf5_ma_slope, 0.0),
+        spec!("tf6_supertrend_flip", trend_following::tf6_supertrend_flip, 0.0),
+        spec!("tf7_psar_ma", trend_following::tf7_psar_ma, 0.0),
+        spec!("tf9_tii", trend_following::tf9_tii, 0.0),
+        spec!("tf10_ma", trend_following::tf10_ma, 0.0),
+        spec!("tf11_rsi_neutrality", trend_following::tf11_rsi_neutrality, 0.0),
+        spec!("tf12_vama", trend_following::tf12_vama, 0.0),
+        spec!("tf13_rsi_supertrend", trend_following::tf13_rsi_supertrend, 0.0),
+        spec!("tf14_catapult", trend_following::tf14_catapult, 0.0),
+        spec!("contrarian_aug_bbands", bots::contrarian_aug_bbands, 0.0),
+        spec!("contrarian_bbands", bots::contrarian_bbands, 0.0),
+        spec!("contrarian_dual_bbands", bots::contrarian_dual_bbands, 0.0),
+        spec!("contrarian_countdown_cross", bots::contrarian_countdown_cross, 0.0),
+        spec!("contrarian_countdown_duration", bots::contrarian_countdown_duration, 0.0),
+        spec!("key_reversal", bots::key_reversal, 0.0),
+        spec!("k_extreme_duration", bots::k_extreme_duration, 0.0),
+        spec!("contrarian_countdown_extremes", bots::contrarian_countdown_extremes, 0.0),
+        spec!("contrarian_demarker_cross", bots::contrarian_demarker_cross, 0.0),
+        spec!("contrarian_demarker_extremes", bots::contrarian_demarker_extremes, 0.0),
+        spec!("contrarian_disparity_extremes", bots::contrarian_disparity_extremes, 0.0),
+        spec!("contrarian_fisher_duration", bots::contrarian_fisher_duration, 0.0),
+        spec!("contrarian_fisher_extremes", bots::contrarian_fisher_extremes, 0.0),
+        spec!("contrarian_real_range_extremes", bots::contrarian_real_range_extremes, 0.0),
+        spec!("pattern_piercing", bots::pattern_piercing, 0.0),
+        spec!("pattern_td_camouflauge", bots::pattern_td_camouflage, 0.0),
+        spec!("pattern_td_clopwin", bots::pattern_td_clopwin, 0.0),
+        spec!("pattern_td_waldo_2", bots::pattern_td_waldo_2, 0.0),
+        spec!("contrarian_rsi_cross", bots::contrarian_rsi_cross, 0.0),
+        spec!("contrarian_rsi_divergences", bots::contrarian_rsi_divergences, 0.0),
+        spec!("contrarian_rsi_duration", bots::contrarian_rsi_duration, 0.0),
+        spec!("contrarian_rsi_extremes", bots::contrarian_rsi_extremes, 0.0),
+        spec!("contrarian_stochastic_cross", bots::contrarian_stochastic_cross, 0.0),
+        spec!("contrarian_stochastic_divergences", bots::contrarian_stochastic_divergences, 0.0),
+        spec!("contrarian_stochastic_duration", bots::contrarian_stochastic_duration, 0.0),
+        spec!("contrarian_stochastic_extremes", bots::contrarian_stochastic_extremes, 0.0),
+        spec!("contrarian_time_up_extremes", bots::contrarian_time_up_extremes, 0.0),
+        spec!("contrarian_tsabm", bots::contrarian_tsabm, 0.0),
+        spec!("pattern_differentials", bots::pattern_differentials, 0.0),
+        spec!("pattern_engulfing", bots::pattern_engulfing, 0.0),
+        spec!("pattern_fibonacci_timing", bots::pattern_fibonacci_timing, 0.0),
+        spec!("pattern_td_clop", bots::pattern_td_clop, 0.0),
+        spec!("pattern_td_open", bots::pattern_td_open, 0.0),
+        spec!("pattern_td_trap", bots::pattern_td_trap, 0.0),
+        spec!("pattern_td_waldo_5", bots::pattern_td_waldo_5, 0.0),
+        spec!("pattern_td_waldo_6", bots::pattern_td_waldo_6, 0.0),
+        spec!("pattern_td_waldo_8", bots::pattern_td_waldo_8, 0.0),
+        spec!("pattern_three_line_strike", bots::pattern_three_line_strike, 0.0),
+        spec!("pattern_three_methods", bots::pattern_three_methods, 0.0),
     ]
 }
+
+/// All strategies - comprehensive list for testing/evaluation
+pub fn all_strategies() -> Vec<(String, SignalFunctionWithParam, f64)> {
+    list_all_signals()
+        .into_iter()
+        .map(|spec| (spec.name, spec.func, spec.default_param))
+        .collect()
+}
@@ -38,6 +38,56 @@ fn create_multi_ticker_data() -> DataFrame {
     }.unwrap()
 }
 
+/// Create a daily single-ticker price series (one bar per calendar day from 2023-01-01)
+/// where every OHLC field equals the given open
+fn create_daily_price_data(opens: &[f64]) -> DataFrame {
+    let start = chrono::NaiveDate::from_ymd_opt(2023, 1, 1).unwrap();
+    let dates: Vec<chrono::NaiveDate> = (0..opens.len())
+        .map(|i| start + chrono::Duration::days(i as i64))
+        .collect();
+    let n = opens.len();
+    df! {
+        "Date" => &dates,
+        "Ticker" => &vec!["btc"; n],
+        "Universe" => &vec!["Crypto"; n],
+        "Open" => opens,
+        "High" => opens,
+        "Low" => opens,
+        "Close" => opens,
+        "Volume" => &vec![1000.0; n],
+    }.unwrap()
+}
+
+/// Records every log message (level, text) so tests can assert on what a run logged
+struct CaptureLogger {
+    records: std::sync::Mutex<Vec<(log::Level, String)>>,
+}
+
+impl log::Log for CaptureLogger {
+    fn enabled(&self, _: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        self.records.lock().unwrap().push((record.level(), record.args().to_string()));
+    }
+
+    fn flush(&self) {}
+}
+
+/// Install the process-wide capture logger (once) and return it
+fn capture_logger() -> &'static CaptureLogger {
+    static LOGGER: std::sync::OnceLock<&'static CaptureLogger> = std::sync::OnceLock::new();
+    LOGGER.get_or_init(|| {
+        let logger: &'static CaptureLogger = Box::leak(Box::new(CaptureLogger {
+            records: std::sync::Mutex::new(Vec::new()),
+        }));
+        log::set_logger(logger).unwrap();
+        log::set_max_level(log::LevelFilter::Trace);
+        logger
+    })
+}
+
 // ============================================================================
 // SIGNAL TESTS
 // ============================================================================
@@ -130,6 +180,43 @@ mod universe_tests {
         println!("✓ Universe expansion works correctly");
     }
 
+    #[tokio::test]
+    async fn test_all_mode_processes_each_universe_once() {
+        let args = Args::parse_from(["backtester", "--all", "-m", "testing"]);
+        let config = build_config(args).unwrap();
+
+        let expected = [
+            "Crypto", "SC1", "SC2", "SC3", "SC4", "MC1", "MC2", "LC1", "LC2",
+            "Micro1", "Micro2", "Micro3", "Micro4",
+        ];
+        let mut calls: std::collections::HashMap<String, usize> = Default::default();
+        for_each_universe(&config, |u| {
+            *calls.entry(u).or_default() += 1;
+            async { Ok(()) }
+        })
+        .await
+        .unwrap();
+        assert_eq!(calls.len(), expected.len());
+        for u in expected {
+            assert_eq!(calls.get(u), Some(&1), "{} should be backtested exactly once", u);
+        }
+
+        // Post-run scoring covers each universe exactly once, split by asset class
+        let groups = config.asset_class_groups();
+        assert_eq!(groups.len(), 2);
+        assert_eq!((groups[0].stocks, groups[0].label.as_str()), (false, "Crypto"));
+        assert_eq!((groups[1].stocks, groups[1].label.as_str()), (true, "Stocks"));
+        let mut scored: std::collections::HashMap<&str, usize> = Default::default();
+        for u in groups.iter().flat_map(|g| &g.universes) {
+            *scored.entry(u.as_str()).or_default() += 1;
+        }
+        assert_eq!(scored.len(), expected.len());
+        for u in expected {
+            assert_eq!(scored.get(u), Some(&1), "{} should be scored exactly once", u);
+        }
+        println!("✓ All-universe mode works correctly");
+    }
+
     #[test]
     fn test_universe_type_detection() {
         let stock_universes = vec!["LC1", "LC2", "MC1", "MC2", "SC1", "SC2", "SC3", "SC4",
@@ -143,17 +230,58 @@ mod universe_tests {
         println!("✓ Universe type detection works correctly");
     }
 
+    #[test]
+    fn test_universe_names_ignore_case_and_whitespace() {
+        use backtester::config::{AnnualizationBasis, UniverseConfig};
+
+        assert_eq!(UniverseConfig::canonical(" crypto "), "Crypto");
+        assert_eq!(UniverseConfig::canonical("lc1"), "LC1");
+        assert!(UniverseConfig::is_crypto(" crypto "));
+        assert!(UniverseConfig::is_stock("micro2 "));
+        assert_eq!(AnnualizationBasis::for_universe(" crypto "), AnnualizationBasis::Days365);
+        assert_eq!(UniverseConfig::output_folder_type(" crypto "), "output_crypto");
+        assert_eq!(UniverseConfig::expand("mc"), vec!["MC1".to_string(), "MC2".to_string()]);
+
+        let config = build_config(Args::parse_from(["backtester", "-u", " crypto "])).unwrap();
+        assert_eq!(config.universes, vec!["Crypto".to_string()]);
+
+        // A price file spelling the universe " crypto " is backtested as crypto
+        let opens: Vec<f64> = (0..40).map(|i| 100.0 + (i % 7) as f64).collect();
+        let side = || {
+            let mut side = BuySell { buy: vec![0; 40], sell: vec![0; 40] };
+            for i in (0..40).step_by(4) {
+                side.buy[i] = 1;
+                side.sell[i + 2] = -1;
+            }
+            side
+        };
+        let canonical = create_daily_price_data(&opens);
+        let mut messy = canonical.clone();
+        messy.with_column(Series::new("Universe".into(), vec![" crypto "; 40])).unwrap();
+
+        let (expected, _, _) = backtest_performance(canonical, side(), "test").unwrap();
+        let (bt, _, _) = backtest_performance(messy, side(), "test").unwrap();
+        assert_eq!(bt.universe, "Crypto");
+        assert_eq!(bt.sharpe_ratio, expected.sharpe_ratio);
+        assert_ne!(bt.sharpe_ratio, 0.0);
+        println!("✓ Universe name normalization works correctly");
+    }
+
     #[test]
     fn test_ticker_case_normalization() {
+        use backtester::config::normalize_ticker;
+
         // Crypto tickers should be lowercase
-        let crypto_ticker = "BTC";
-        let normalized = crypto_ticker.to_lowercase();
-        assert_eq!(normalized, "btc");
+        assert_eq!(normalize_ticker("BTC", "crypto"), "btc");
+        assert_eq!(normalize_ticker(" Eth ", "Crypto"), "eth");
 
         // Stock tickers should be uppercase
-        let stock_ticker = "aapl";
-        let normalized = stock_ticker.to_uppercase();
-        assert_eq!(normalized, "AAPL");
+        assert_eq!(normalize_ticker("aapl", "stock"), "AAPL");
+        assert_eq!(normalize_ticker("msft", "LC1"), "MSFT");
+
+        // Already-correct tickers pass through unchanged
+        assert_eq!(normalize_ticker("sol", "Crypto"), "sol");
+        assert_eq!(normalize_ticker("IBM", "SC2"), "IBM");
 
         println!("✓ Ticker case normalization works correctly");
     }
@@ -167,6 +295,50 @@ mod universe_tests {
 mod data_loading_tests {
     use super::*;
 
+    #[test]
+    fn test_preprocess_relaxed_warmup() {
+        let opens: Vec<f64> = (0..210).map(|i| 100.0 + i as f64).collect();
+        let df = create_daily_price_data(&opens);
+        let last = |df: &DataFrame, name: &str| df.column(name).unwrap().f64().unwrap().get(df.height() - 1);
+
+        // 210 bars never fill a 250-bar window
+        let strict = preprocess(df.clone().lazy()).unwrap();
+        assert_eq!(last(&strict, "max_high_250"), None);
+        assert_eq!(last(&strict, "MA_200"), Some(209.5));
+
+        // At 80% the 250-bar columns start at bar 200
+        let relaxed = preprocess_with(df.lazy(), 0.8).unwrap();
+        assert_eq!(last(&relaxed, "max_high_250"), Some(309.0));
+        assert_eq!(last(&relaxed, "min_low_250"), Some(100.0));
+        let high = relaxed.column("max_high_250").unwrap().f64().unwrap();
+        assert_eq!((high.get(198), high.get(199)), (None, Some(299.0)));
+        println!("✓ Relaxed preprocess warmup works correctly");
+    }
+
+    #[test]
+    fn test_preprocess_sorts_each_ticker_by_date() {
+        let df = df! {
+            "Date" => &["2024-01-03", "2024-01-02", "2024-01-01", "2024-01-01", "2024-01-03", "2024-01-02"],
+            "Ticker" => &["eth", "btc", "eth", "btc", "btc", "eth"],
+            "Open" => &[203.0, 102.0, 201.0, 101.0, 103.0, 202.0],
+            "High" => &[203.0, 102.0, 201.0, 101.0, 103.0, 202.0],
+            "Low" => &[203.0, 102.0, 201.0, 101.0, 103.0, 202.0],
+            "Close" => &[203.0, 102.0, 201.0, 101.0, 103.0, 202.0],
+            "Volume" => &[1.0; 6],
+        }.unwrap();
+
+        let out = preprocess(df.lazy()).unwrap();
+        let text = |name: &str| out.column(name).unwrap().str().unwrap().into_no_null_iter().map(String::from).collect::<Vec<_>>();
+        assert_eq!(text("Ticker"), ["btc", "btc", "btc", "eth", "eth", "eth"]);
+        assert_eq!(text("Date"), ["2024-01-01", "2024-01-02", "2024-01-03", "2024-01-01", "2024-01-02", "2024-01-03"]);
+        // Per-ticker returns run forward in time
+        let ret: Vec<Option<f64>> = out.column("Ret").unwrap().f64().unwrap().into_iter().collect();
+        assert_eq!((ret[0], ret[3]), (None, None));
+        assert!((ret[1].unwrap() - (102.0 / 101.0 - 1.0)).abs() < 1e-12);
+        assert!((ret[5].unwrap() - (203.0 / 202.0 - 1.0)).abs() < 1e-12);
+        println!("✓ Preprocess per-ticker date order works correctly");
+    }
+
     #[tokio::test]
     async fn test_lazy_frame_filtering() {
         let df = create_multi_ticker_data();
@@ -211,6 +383,208 @@ mod data_loading_tests {
         assert_eq!(date, "\"2024-01-05\"");
         println!("✓ Latest date extraction works correctly");
     }
+
+    #[test]
+    fn test_stale_price_data_detected() {
+        let today = chrono::Local::now().date_naive();
+        let dates: Vec<chrono::NaiveDate> = (7..10).rev()
+            .map(|d| today - chrono::Duration::days(d))
+            .collect();
+        let df = df! {
+            "Date" => &dates,
+            "Ticker" => &["btc", "btc", "btc"],
+            "Close" => &[100.0, 101.0, 102.0],
+        }.unwrap();
+
+        let err = assert_fresh(&df.clone().lazy(), 3).unwrap_err();
+        assert!(err.to_string().contains("Stale price data"));
+        assert_eq!(assert_fresh(&df.lazy(), 7).unwrap(), today - chrono::Duration::days(7));
+        println!("✓ Stale price data detection works correctly");
+    }
+
+    #[tokio::test]
+    async fn test_read_price_glob_concats_shards() {
+        let dir = std::env::temp_dir().join("backtester_price_shards_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let header = "Date,Ticker,Universe,Open,High,Low,Close,Volume\n";
+        std::fs::write(
+            dir.join("LC1_2023.csv"),
+            format!("{}2023-12-29,MSFT,LC1,1,1,1,1,100\n2023-12-28,AAPL,LC1,1,1,1,1,100\n2023-12-29,AAPL,LC1,1,1,1,1,100\n", header),
+        ).unwrap();
+        std::fs::write(
+            dir.join("LC1_2024.csv"),
+            format!("{}2024-01-02,AAPL,LC1,2,2,2,2,200\n2024-01-02,MSFT,LC1,2,2,2,2,200\n", header),
+        ).unwrap();
+        // Other universes in the same folder are not picked up
+        std::fs::write(dir.join("MC1_2024.csv"), format!("{}2024-01-02,XYZ,MC1,1,1,1,1,1\n", header)).unwrap();
+
+        let pattern = dir.join("LC1_*.csv").to_string_lossy().into_owned();
+        let df = read_price_glob(&pattern).await.unwrap().collect().unwrap();
+
+        assert_eq!(df.height(), 5);
+        let tickers: Vec<&str> = df.column("Ticker").unwrap().str().unwrap().into_no_null_iter().collect();
+        assert_eq!(tickers, vec!["AAPL", "AAPL", "AAPL", "MSFT", "MSFT"]);
+        let dates: Vec<String> = df.column("Date").unwrap().date().unwrap()
+            .as_date_iter()
+            .map(|d| d.unwrap().to_string())
+            .collect();
+        assert_eq!(dates, vec!["2023-12-28", "2023-12-29", "2024-01-02", "2023-12-29", "2024-01-02"]);
+
+        let _ = std::fs::remove_dir_all(&dir);
+        println!("✓ Sharded price file loading works correctly");
+    }
+
+    #[tokio::test]
+    async fn test_price_pull_falls_back_to_cached_file() {
+        let dir = std::env::temp_dir().join("backtester_price_fallback_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("production")).unwrap();
+        std::fs::create_dir_all(dir.join("testing")).unwrap();
+        // Yesterday's cache lives in the testing folder; production has no file yet
+        std::fs::write(
+            dir.join("testing").join("Crypto.csv"),
+            "Date,Ticker,Universe,Open,High,Low,Close,Volume\n\
+             2024-01-01,btc,Crypto,100,106,99,105,1000\n\
+             2024-01-02,btc,Crypto,105,107,103,104,1100\n\
+             2024-01-03,btc,Crypto,103,109,102,108,900\n",
+        ).unwrap();
+        let data_dir = dir.to_string_lossy().into_owned();
+        let unreachable = |u: String| async move {
+            Err::<(), Box<dyn StdError>>(format!("connection refused for {}", u).into())
+        };
+
+        // Strict runs (--no-fallback) still fail
+        let strict = create_price_files_from(&data_dir, vec!["Crypto".to_string()], true, false, unreachable).await;
+        assert!(strict.is_err());
+        assert!(!dir.join("production").join("Crypto.csv").exists());
+
+        let fallbacks = create_price_files_from(&data_dir, vec!["Crypto".to_string()], true, true, unreachable).await.unwrap();
+        let cached = fallbacks["Crypto"].clone();
+        assert_eq!(cached, dir.join("testing").join("Crypto.csv"));
+        // The cache is read in place, not written over the production file
+        assert!(!dir.join("production").join("Crypto.csv").exists());
+        let df = read_price_file(cached.to_string_lossy().into_owned()).await.unwrap().collect().unwrap();
+        assert_eq!(df.height(), 3);
+
+        // The backtest proceeds on the cached prices
+        let side = BuySell { buy: vec![1, 0, 0], sell: vec![0, -1, 0] };
+        let (_, bt_se, _) = backtest_performance(df, side, "test").unwrap();
+        assert_eq!(bt_se.trades, 1);
+
+        // A universe with no cache anywhere is still an error
+        let missing = create_price_files_from(&data_dir, vec!["LC1".to_string()], true, true, unreachable).await;
+        assert!(missing.is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
+        println!("✓ Cached price fallback works correctly");
+    }
+
+    #[tokio::test]
+    async fn test_price_file_run_emits_log_records() {
+        let logger = capture_logger();
+        let dir = std::env::temp_dir().join("backtester_price_logging_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("production")).unwrap();
+        std::fs::create_dir_all(dir.join("testing")).unwrap();
+        std::fs::write(
+            dir.join("testing").join("MC2.csv"),
+            "Date,Ticker,Universe,Open,High,Low,Close,Volume\n2024-01-02,XYZ,MC2,1,1,1,1,1\n",
+        ).unwrap();
+        let data_dir = dir.to_string_lossy().into_owned();
+
+        create_price_files_from(&data_dir, vec!["MC2".to_string()], true, true, |u| async move {
+            Err::<(), Box<dyn StdError>>(format!("connection refused for {}", u).into())
+        })
+        .await
+        .unwrap();
+
+        // Progress and the stale-data warning go through `log`, not stdout
+        let records = logger.records.lock().unwrap();
+        let mine: Vec<&(log::Level, String)> = records.iter().filter(|(_, msg)| msg.contains(&data_dir)).collect();
+        assert!(mine.iter().any(|(level, msg)| *level == log::Level::Info && msg.starts_with("Price file generating")));
+        assert!(mine.iter().any(|(level, msg)| *level == log::Level::Warn && msg.contains("STALE DATA")));
+        assert!(!run_id().is_empty());
+
+        let _ = std::fs::remove_dir_all(&dir);
+        println!("✓ Structured run logging works correctly");
+    }
+
+    #[tokio::test]
+    async fn test_dividends_raise_buy_and_hold_total_return() {
+        let dir = std::env::temp_dir().join("backtester_dividends_test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let prices_path = dir.join("LC1.csv");
+        let dividends_path = dir.join("LC1_dividends.csv");
+        std::fs::write(
+            &prices_path,
+            "Date,Ticker,Universe,Open,High,Low,Close,Volume\n\
+             2024-01-02,IBM,LC1,100,100,100,100,1\n\
+             2024-01-03,IBM,LC1,100,100,100,100,1\n\
+             2024-01-04,IBM,LC1,98,98,98,98,1\n\
+             2024-01-05,IBM,LC1,102,102,102,102,1\n",
+        ).unwrap();
+        std::fs::write(&dividends_path, "Date,Ticker,Dividend\n2024-01-04,IBM,2.0\n").unwrap();
+
+        let df = read_price_file_with_dividends(
+            prices_path.to_string_lossy().into_owned(),
+            Some(dividends_path.to_string_lossy().into_owned()),
+        ).await.unwrap().collect().unwrap();
+
+        // Raw close is kept for fills
+        let closes: Vec<f64> = df.column("Close").unwrap().f64().unwrap().into_no_null_iter().collect();
+        assert_eq!(closes, vec![100.0, 100.0, 98.0, 102.0]);
+
+        let price_only = buy_and_hold_return(&df, "Close").unwrap();
+        let total_return = buy_and_hold_return(&df, "TRClose").unwrap();
+        assert!((price_only - 0.02).abs() < 1e-12);
+        // The $2 dividend is reinvested at 98: 100 -> 100 * (100/98) * (102/100)
+        assert!((total_return - (102.0 / 98.0 - 1.0)).abs() < 1e-12);
+        assert!(total_return > price_only);
+
+        let _ = std::fs::remove_dir_all(&dir);
+        println!("✓ Dividend-adjusted total return works correctly");
+    }
+
+    #[test]
+    fn test_parse_trade_date_formats() {
+        let expected = chrono::NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+        for s in [
+            "2024-01-02",
+            "2024-01-02 15:30:00",
+            "2024-01-02 15:30:00.250",
+            "2024-01-02T15:30:00",
+            "2024-01-02T15:30:00Z",
+            "2024-01-02T23:30:00-05:00",
+            " 2024-01-02 ",
+        ] {
+            assert_eq!(parse_trade_date(s).unwrap(), expected, "failed to parse {:?}", s);
+        }
+
+        let err = parse_trade_date("01/02/2024").unwrap_err();
+        assert!(err.to_string().contains("Unparseable trade date '01/02/2024'"));
+        assert!(parse_trade_date("").is_err());
+        println!("✓ Trade date parsing works correctly");
+    }
+
+    #[test]
+    fn test_parallel_postprocess_matches_serial() {
+        let opens: Vec<f64> = (0..300).map(|i| 100.0 + 10.0 * (i as f64 / 7.0).sin() + i as f64 * 0.1).collect();
+        let df = create_daily_price_data(&opens);
+
+        let serial = postprocess_with(df.clone(), 1).unwrap();
+        assert_eq!(&serial.get_column_names()[df.width()..], ["upper_aug_bbands", "lower_aug_bbands", "ftp_buy", "ftp_sell"]);
+        // Rayon's global pool (0) and a pool of our own (4)
+        for threads in [0, 4] {
+            let parallel = postprocess_with(df.clone(), threads).unwrap();
+            assert_eq!(serial.get_column_names(), parallel.get_column_names());
+            for column in serial.get_columns() {
+                let other = parallel.column(column.name()).unwrap();
+                assert!(column.equals_missing(other), "column {} differs on {} threads", column.name(), threads);
+            }
+        }
+        println!("✓ Parallel postprocess works correctly");
+    }
 }
 
 // ============================================================================
@@ -384,7 +758,7 @@ mod ticker_filtering_tests {
         // Crypto - lowercase
         let crypto_tickers: Vec<String> = "BTC,ETH,SOL"
             .split(',')
-            .map(|s| s.trim().to_lowercase())
+            .map(|s| backtester::config::normalize_ticker(s, "Crypto"))
             .collect();
 
         assert_eq!(crypto_tickers, vec!["btc", "eth", "sol"]);
@@ -392,7 +766,7 @@ mod ticker_filtering_tests {
         // Stocks - uppercase
         let stock_tickers: Vec<String> = "aapl,msft,googl"
             .split(',')
-            .map(|s| s.trim().to_uppercase())
+            .map(|s| backtester::config::normalize_ticker(s, "Stocks"))
             .collect();
 
         assert_eq!(stock_tickers, vec!["AAPL", "MSFT", "GOOGL"]);
@@ -420,6 +794,72 @@ mod ticker_filtering_tests {
         assert!(needed.contains(&"dot".to_string()));
         println!("✓ Ticker deduplication works correctly");
     }
+
+    #[test]
+    fn test_min_price_excludes_untradable_tickers() {
+        let mut df = create_multi_ticker_data();
+        let dust = df! {
+            "Date" => &["2024-01-01", "2024-01-02", "2024-01-03"],
+            "Ticker" => &["dust", "dust", "dust"],
+            "Universe" => &["Crypto", "Crypto", "Crypto"],
+            "Open" => &[0.001, 0.002, 3.0],
+            "High" => &[0.001, 0.002, 3.0],
+            "Low" => &[0.001, 0.002, 3.0],
+            // One spike above the threshold doesn't make it tradable
+            "Close" => &[0.001, 0.002, 3.0],
+            "Volume" => &[1e9, 1e9, 1e9],
+        }.unwrap();
+        df.vstack_mut(&dust).unwrap();
+
+        let (lf, excluded) = filter_min_price(df.clone().lazy(), 1.0).unwrap();
+        assert_eq!(excluded, vec!["dust".to_string()]);
+        let (needed, _) = determine_tickers_to_process(&lf, &HashSet::new(), None).unwrap();
+        assert_eq!(needed, vec!["btc".to_string(), "eth".to_string()]);
+
+        // Nothing below the threshold: the frame is passed through
+        let (lf, excluded) = filter_min_price(df.clone().lazy(), 0.0001).unwrap();
+        assert!(excluded.is_empty());
+        assert_eq!(lf.collect().unwrap().height(), df.height());
+        println!("✓ Minimum price filter works correctly");
+    }
+
+    #[test]
+    fn test_date_window_backtests_only_that_period() {
+        // 300 daily bars from 2023-01-01: up from 100 to 200, then down to 150
+        let opens: Vec<f64> = (0..300)
+            .map(|i| if i < 200 { 100.0 + i as f64 * 0.5 } else { 200.0 - (i - 200) as f64 * 0.5 })
+            .collect();
+        let df = create_daily_price_data(&opens);
+        let hold_whole_frame = |df: &DataFrame| {
+            let mut side = BuySell { buy: vec![0; df.height()], sell: vec![0; df.height()] };
+            side.buy[0] = 1;
+            side.sell[df.height() - 1] = -1;
+            backtest_performance(df.clone(), side, "window").unwrap().0
+        };
+
+        let full = hold_whole_frame(&df);
+        assert_eq!(full.hit_ratio, 100.0);
+
+        // From bar 200 (2023-07-20) on, the same hold only catches the decline
+        let start = chrono::NaiveDate::from_ymd_opt(2023, 7, 20);
+        let (lf, short) = filter_date_window(df.clone().lazy(), start, None).unwrap();
+        let window = lf.collect().unwrap();
+        assert_eq!(window.height(), 100);
+        assert_eq!(short, vec!["btc".to_string()]);
+        let bt = hold_whole_frame(&window);
+        assert_eq!(bt.trades, 1);
+        assert_eq!(bt.hit_ratio, 0.0);
+        assert!(bt.max_loss < 0.0);
+
+        // A window with enough bars raises no warmup warning
+        let end = chrono::NaiveDate::from_ymd_opt(2023, 9, 30);
+        let (lf, short) = filter_date_window(df.clone().lazy(), None, end).unwrap();
+        assert_eq!(lf.collect().unwrap().height(), 273);
+        assert!(short.is_empty());
+
+        assert!(filter_date_window(df.lazy(), end, start).is_err());
+        println!("✓ Date window filter works correctly");
+    }
 }
 
 // ============================================================================
@@ -438,6 +878,7 @@ mod backtest_result_tests {
             strategy: "hammer".to_string(),
             expectancy: 0.5,
             profit_factor: 1.5,
+            profit_factor_capped: false,
             hit_ratio: 0.6,
             realized_risk_reward: 1.2,
             avg_gain: 100.0,
@@ -446,14 +887,35 @@ mod backtest_result_tests {
             max_loss: -200.0,
             sharpe_ratio: 1.8,
             sortino_ratio: 2.0,
+            sharpe_tstat: 2.0,
             max_drawdown: -0.15,
             calmar_ratio: 3.0,
+            profit_to_dd: 0.4,
             win_loss_ratio: 1.5,
             recovery_factor: 2.5,
             profit_per_trade: 25.0,
+            max_consecutive_wins: 3,
+            max_consecutive_losses: 2,
+            cagr: 12.0,
+            annualized_return: 11.0,
+            avg_gain_atr: 0.0,
+            avg_loss_atr: 0.0,
+            information_ratio: 0.0,
+            beta_to_benchmark: 0.0,
+            avg_rolling_beta: 0.0,
+            beta_adjusted_return: 0.0,
+            expectancy_per_day: 0.0,
+            expectancy_r: 0.0,
+            r_multiple_std: 0.0,
+            buy_and_hold_return: 0.0,
+            dca_return: 0.0,
             buys: 10,
             sells: 10,
             trades: 10,
+            signal_exits: 10,
+            stop_loss_exits: 0,
+            end_of_data_exits: 0,
+            holding_period_histogram: Vec::new(),
             date: "2024-01-01".to_string(),
             buy: 0,
             sell: 0,
@@ -490,6 +952,1685 @@ mod backtest_result_tests {
         assert_eq!(buysell.sell[0], 1);
         println!("✓ BuySell struct creation works correctly");
     }
+
+    #[tokio::test]
+    async fn test_buysell_rejects_positive_sell() {
+        assert!(BuySell::new(vec![1, 0, 0], vec![0, 0, -1]).is_ok());
+
+        let err = BuySell::new(vec![1, 0, 0], vec![0, 0, 1]).unwrap_err();
+        assert!(err.to_string().contains("sell[2] is 1"), "{}", err);
+        assert!(BuySell::new(vec![0, -1], vec![0, 0]).is_err());
+        assert!(BuySell::new(vec![0, 0], vec![0]).is_err());
+
+        // A malformed signal is refused before it is backtested
+        fn positive_sell(df: DataFrame, _param: f64) -> BuySell {
+            let mut side = BuySell { buy: vec![0; df.height()], sell: vec![0; df.height()] };
+            side.sell[1] = 1;
+            side
+        }
+        let df = create_test_price_data().lazy();
+        let err = sig(df, positive_sell, 0.0, "positive_sell".to_string()).await.unwrap_err();
+        assert!(err.to_string().contains("positive_sell"), "{}", err);
+        println!("✓ BuySell validation works correctly");
+    }
+
+    fn backtest_row(ticker: &str, strategy: &str, x: f64) -> Backtest {
+        Backtest {
+            ticker: ticker.to_string(),
+            universe: "Crypto".to_string(),
+            strategy: strategy.to_string(),
+            expectancy: x,
+            profit_factor: 1.5,
+            profit_factor_capped: false,
+            hit_ratio: x * 3.0,
+            realized_risk_reward: x,
+            avg_gain: x,
+            avg_loss: x,
+            max_gain: x,
+            max_loss: -x,
+            sharpe_ratio: x,
+            sortino_ratio: x,
+            sharpe_tstat: x,
+            max_drawdown: x,
+            calmar_ratio: x,
+            profit_to_dd: x,
+            win_loss_ratio: x,
+            recovery_factor: x,
+            profit_per_trade: x,
+            max_consecutive_wins: 2,
+            max_consecutive_losses: 1,
+            cagr: x,
+            annualized_return: x,
+            avg_gain_atr: x,
+            avg_loss_atr: x,
+            information_ratio: x,
+            beta_to_benchmark: x,
+            avg_rolling_beta: x,
+            beta_adjusted_return: x,
+            expectancy_per_day: x,
+            expectancy_r: x,
+            r_multiple_std: x,
+            buy_and_hold_return: x,
+            dca_return: x,
+            buys: 5,
+            sells: 5,
+            trades: 10,
+            signal_exits: 10,
+            stop_loss_exits: 0,
+            end_of_data_exits: 0,
+            holding_period_histogram: Vec::new(),
+            date: "2024-01-01".to_string(),
+            buy: 0,
+            sell: 0,
+        }
+    }
+
+    fn summary_rows_csv(rows: &[Backtest]) -> Vec<u8> {
+        let mut wtr = csv::Writer::from_writer(Vec::new());
+        for row in rows {
+            wtr.serialize(row).unwrap();
+        }
+        wtr.into_inner().unwrap()
+    }
+
+    fn summary_csv(rows: &[Backtest]) -> Vec<u8> {
+        let df = CsvReader::new(std::io::Cursor::new(summary_rows_csv(rows))).finish().unwrap();
+
+        let mut out = summary_performance(df, None).unwrap();
+        let mut buf = Vec::new();
+        CsvWriter::new(&mut buf).finish(&mut out).unwrap();
+        buf
+    }
+
+    #[test]
+    fn test_summary_null_policy() {
+        // Sharpe missing for two of four tickers: one null (CSV gap) and one NaN
+        let rows: Vec<Backtest> = ["ada", "btc", "eth", "sol"]
+            .iter()
+            .map(|t| backtest_row(t, "hammer", 1.0))
+            .collect();
+        let mut df = CsvReader::new(std::io::Cursor::new(summary_rows_csv(&rows))).finish().unwrap();
+        df.with_column(Series::new("sharpe_ratio".into(), [Some(2.0), None, Some(4.0), Some(f64::NAN)]))
+            .unwrap();
+
+        let sharpe = |policy| {
+            let out = summary_performance_with(df.clone(), policy).unwrap();
+            assert_eq!(out.height(), 1);
+            out.column("sharpe_ratio").unwrap().f64().unwrap().get(0)
+        };
+        assert_eq!(sharpe(NullPolicy::Skip), Some(3.0));
+        assert_eq!(sharpe(NullPolicy::ZeroFill), Some(1.5));
+        assert_eq!(sharpe(NullPolicy::Propagate), None);
+
+        // Complete columns are unaffected by the policy
+        let out = summary_performance_with(df.clone(), NullPolicy::Propagate).unwrap();
+        assert_eq!(out.column("sortino_ratio").unwrap().f64().unwrap().get(0), Some(1.0));
+        println!("✓ Summary null policies work correctly");
+    }
+
+    #[test]
+    fn test_capped_profit_factor_left_out_of_summary_mean() {
+        let mut rows: Vec<Backtest> = ["btc", "eth", "sol"].iter().map(|t| backtest_row(t, "hammer", 1.0)).collect();
+        rows[0].profit_factor = 2.0;
+        rows[1].profit_factor = 1.0;
+        // No losing trades: reported at the cap
+        rows[2].profit_factor = PROFIT_FACTOR_CAP;
+        rows[2].profit_factor_capped = true;
+        let df = CsvReader::new(std::io::Cursor::new(summary_rows_csv(&rows))).finish().unwrap();
+        let out = summary_performance(df, None).unwrap();
+        assert_eq!(out.column("profit_factor").unwrap().f64().unwrap().get(0), Some(1.5));
+        assert_eq!(out.column("profit_factor_capped").unwrap().u32().unwrap().get(0), Some(1));
+
+        // Every ticker capped: no ratio to average
+        for row in &mut rows {
+            row.profit_factor = PROFIT_FACTOR_CAP;
+            row.profit_factor_capped = true;
+        }
+        let df = CsvReader::new(std::io::Cursor::new(summary_rows_csv(&rows))).finish().unwrap();
+        let out = summary_performance(df, None).unwrap();
+        assert_eq!(out.column("profit_factor").unwrap().f64().unwrap().get(0), None);
+        println!("✓ Capped profit factors are left out of the summary");
+    }
+
+    #[test]
+    fn test_strategy_coverage_threshold_follows_strategy_count() {
+        let prices = df! {
+            "Ticker" => &["btc", "eth", "sol"],
+            "observations" => &[100u32, 100, 100],
+        }.unwrap();
+        let results = |rows: &[(&str, &str)]| df! {
+            "ticker" => rows.iter().map(|r| r.0).collect::<Vec<_>>(),
+            "strategy" => rows.iter().map(|r| r.1).collect::<Vec<_>>(),
+        }.unwrap();
+        let flagged = |out: DataFrame| -> Vec<String> {
+            out.column("Ticker").unwrap().str().unwrap().into_no_null_iter().map(String::from).collect()
+        };
+
+        // Two strategies run: only sol missed one
+        let two = results(&[("btc", "hammer"), ("btc", "doji"), ("eth", "hammer"), ("eth", "doji"), ("sol", "hammer")]);
+        assert_eq!(flagged(strategy_coverage(prices.clone(), &two, None).unwrap()), ["sol"]);
+
+        // A third strategy that only ran on btc leaves eth under-covered too
+        let mut rows = vec![("btc", "hammer"), ("btc", "doji"), ("btc", "engulfing")];
+        rows.extend([("eth", "hammer"), ("eth", "doji"), ("sol", "hammer")]);
+        assert_eq!(flagged(strategy_coverage(prices.clone(), &results(&rows), None).unwrap()), ["sol", "eth"]);
+
+        // An explicit expectation overrides the count from the data
+        assert!(flagged(strategy_coverage(prices, &two, Some(1)).unwrap()).is_empty());
+        println!("✓ Strategy coverage threshold works correctly");
+    }
+
+    #[test]
+    fn test_summary_column_selection() {
+        let rows: Vec<Backtest> = ["ada", "btc", "eth", "sol"]
+            .iter()
+            .map(|t| backtest_row(t, "hammer", 1.0))
+            .collect();
+        let df = CsvReader::new(std::io::Cursor::new(summary_rows_csv(&rows))).finish().unwrap();
+
+        let out = summary_performance(df.clone(), Some(vec!["profit_factor", "strategy", "N"])).unwrap();
+        assert_eq!(out.get_column_names_str(), vec!["profit_factor", "strategy", "N"]);
+        assert_eq!(out.height(), 1);
+
+        let err = summary_performance(df.clone(), Some(vec!["strategy", "no_such_metric"])).unwrap_err();
+        assert!(err.to_string().contains("no_such_metric"));
+
+        let all = summary_performance(df, None).unwrap();
+        assert!(all.width() > 3);
+        println!("✓ Summary column selection works correctly");
+    }
+
+    #[test]
+    fn test_summary_ties_rank_more_tickers_first() {
+        // Same profit factor and expectancy everywhere; "zzz" has results on more tickers
+        let mut rows: Vec<Backtest> = ["btc", "eth"].iter().map(|t| backtest_row(t, "aaa", 1.0)).collect();
+        rows.extend(["btc", "eth", "sol"].iter().map(|t| backtest_row(t, "zzz", 1.0)));
+        // "mid" ties on profit factor too but has the higher expectancy
+        rows.push(backtest_row("btc", "mid", 2.0));
+        let df = CsvReader::new(std::io::Cursor::new(summary_rows_csv(&rows))).finish().unwrap();
+
+        let out = summary_performance(df, Some(vec!["strategy", "N"])).unwrap();
+        let strategies: Vec<&str> = out.column("strategy").unwrap().str().unwrap().into_no_null_iter().collect();
+        assert_eq!(strategies, ["mid", "zzz", "aaa"]);
+        println!("✓ Summary tie-breaking works correctly");
+    }
+
+    #[test]
+    fn test_summary_csv_is_order_independent() {
+        // Equal profit factors and sums that depend on addition order
+        let mut rows = Vec::new();
+        for (i, ticker) in ["btc", "eth", "sol"].iter().enumerate() {
+            for strategy in ["hammer", "doji"] {
+                rows.push(backtest_row(ticker, strategy, 0.1 * (i + 1) as f64 + 1e-9));
+            }
+        }
+        let first = summary_csv(&rows);
+        rows.reverse();
+        let second = summary_csv(&rows);
+        rows.swap(0, 3);
+        let third = summary_csv(&rows);
+
+        assert_eq!(first, second);
+        assert_eq!(first, third);
+        let text = String::from_utf8(first).unwrap();
+        let strategies: Vec<&str> = text.lines().skip(1).map(|l| l.split(',').next().unwrap()).collect();
+        assert_eq!(strategies, vec!["doji", "hammer"]);
+        println!("✓ Deterministic summary ordering works correctly");
+    }
+
+    #[test]
+    fn test_unique_tickers_sorted() {
+        let lf = df! {
+            "Ticker" => &["sol", "btc", "eth", "btc", "sol"],
+        }.unwrap().lazy();
+        assert_eq!(extract_unique_tickers(&lf).unwrap(), vec!["btc", "eth", "sol"]);
+        println!("✓ Sorted unique tickers works correctly");
+    }
+
+    fn buy_first_sell_fourth(df: DataFrame, _param: f64) -> BuySell {
+        let mut side = BuySell { buy: vec![0; df.height()], sell: vec![0; df.height()] };
+        side.buy[0] = 1;
+        side.sell[3] = -1;
+        side
+    }
+
+    fn alternate(df: DataFrame, _param: f64) -> BuySell {
+        let n = df.height();
+        BuySell {
+            buy: (0..n).map(|i| if i % 6 == 0 { 1 } else { 0 }).collect(),
+            sell: (0..n).map(|i| if i % 6 == 3 { -1 } else { 0 }).collect(),
+        }
+    }
+
+    fn alternate_inverse(df: DataFrame, param: f64) -> BuySell {
+        let side = alternate(df, param);
+        BuySell {
+            buy: side.sell.iter().map(|s| -s).collect(),
+            sell: side.buy.iter().map(|b| -b).collect(),
+        }
+    }
+
+    #[test]
+    fn test_allow_shorts_profits_on_falling_price() {
+        // Sell on the first bar, buy on the fourth, while the price falls 10 a day
+        let side = BuySell { buy: vec![0, 0, 0, 1, 0], sell: vec![-1, 0, 0, 0, 0] };
+        let opens: Vec<Option<f64>> = [100.0, 90.0, 80.0, 70.0, 60.0].iter().map(|&o| Some(o)).collect();
+        let total = |positions: &[i32]| {
+            position_returns(&opens, positions).iter().fold(1.0, |acc, r| acc * (1.0 + r)) - 1.0
+        };
+
+        // Long-only: the sell has no long to close and the buy opens one
+        let long_only = decision_states(&side, false);
+        assert_eq!(long_only, [0, 0, 0, 1, 1]);
+        assert!(total(&long_only) < 0.0);
+
+        // With shorts the sell opens a short and the buy covers it
+        let shorts = decision_states(&side, true);
+        assert_eq!(shorts, [-1, -1, -1, 0, 0]);
+        let expected = (1.0 + 0.1) * (1.0 + 10.0 / 90.0) * (1.0 + 10.0 / 80.0) - 1.0;
+        assert!((total(&shorts) - expected).abs() < 1e-12);
+        assert!(total(&shorts) > 0.0);
+        println!("✓ Short positions from sell decisions work correctly");
+    }
+
+    #[test]
+    fn test_strategy_correlation_matrix() {
+        let opens: Vec<f64> = (0..60).map(|i| 100.0 + 10.0 * (i as f64 * 0.7).sin() + i as f64 * 0.1).collect();
+        let signal = |name: &str, func: SignalFunctionWithParam| Signal {
+            name: name.to_string(),
+            func: Arc::new(func),
+            param: 0.0,
+        };
+        let signals = vec![
+            signal("alternate", alternate),
+            signal("alternate_copy", alternate),
+            signal("alternate_inverse", alternate_inverse),
+        ];
+
+        let matrix = strategy_correlation(&create_daily_price_data(&opens), &signals).unwrap();
+        assert_eq!(matrix.shape(), (3, 4));
+        let names: Vec<&str> = matrix.column("strategy").unwrap().str().unwrap().into_no_null_iter().collect();
+        assert_eq!(names, vec!["alternate", "alternate_copy", "alternate_inverse"]);
+
+        let corr = |row: usize, column: &str| matrix.column(column).unwrap().f64().unwrap().get(row).unwrap();
+        assert!((corr(0, "alternate") - 1.0).abs() < 1e-9);
+        assert!((corr(1, "alternate") - 1.0).abs() < 1e-9);
+        assert!((corr(2, "alternate") + 1.0).abs() < 1e-9);
+        assert!((corr(0, "alternate_inverse") + 1.0).abs() < 1e-9);
+        println!("✓ Strategy correlation matrix works correctly");
+    }
+
+    #[test]
+    fn test_simulate_through_portfolio_accounting() {
+        let df = create_test_price_data();
+        let portfolio = simulate(&df, buy_first_sell_fourth, 0.0, 10_000.0).unwrap();
+
+        assert_eq!(portfolio.daily_snapshots.len(), df.height());
+        assert_eq!(portfolio.transactions.len(), 2);
+        assert_eq!(portfolio.realized_pnl.len(), 1);
+        let trade = &portfolio.realized_pnl[0];
+        assert_eq!(trade.entry_price, 100.0);
+        assert_eq!(trade.exit_price, 108.0);
+        assert_eq!(trade.shares, 100.0);
+        assert_eq!(portfolio.get_position_count(), 0);
+        assert_eq!(portfolio.daily_snapshots.last().unwrap().total_value, 10_800.0);
+
+        let summary = portfolio.calculate_performance_summary();
+        assert_eq!(summary.total_trades, 1);
+        println!("✓ Portfolio simulation works correctly");
+    }
+
+    #[test]
+    fn test_consecutive_streaks() {
+        // Longest loss streak at the very start; bars without a trade (0.0) don't break it
+        let results = [-1.0, 0.0, -2.0, -1.5, 3.0, 0.0, 2.0, -1.0, 1.0];
+        assert_eq!(consecutive_streaks(&results), (2, 3));
+
+        // Longest loss streak at the very end
+        let results = [1.0, 1.0, -1.0, 2.0, -1.0, -1.0, -1.0, -1.0];
+        assert_eq!(consecutive_streaks(&results), (2, 4));
+
+        assert_eq!(consecutive_streaks(&[0.0, 0.0]), (0, 0));
+        assert_eq!(consecutive_streaks(&[]), (0, 0));
+        println!("✓ Consecutive win/loss streaks work correctly");
+    }
+
+    #[test]
+    fn test_calmar_uses_annualized_return_over_pct_drawdown() {
+        // One year of daily bars: long -10%, short +10%, long +20%
+        let mut opens = vec![100.0; 366];
+        opens[100] = 90.0;
+        opens[200] = 81.0;
+        opens[365] = 97.2;
+        let mut side = BuySell { buy: vec![0; 366], sell: vec![0; 366] };
+        side.buy[0] = 1;
+        side.sell[100] = -1;
+        side.buy[200] = 1;
+        side.sell[365] = -1;
+
+        let (bt, _, _) = backtest_performance(create_daily_price_data(&opens), side, "test").unwrap();
+
+        let returns = [-0.1, 0.1, 0.2];
+        let years = 365.0 / 365.25;
+        let annualized = (0.9_f64 * 1.1 * 1.2).powf(1.0 / years) - 1.0;
+        assert!((annualized_return(&returns, years) - annualized).abs() < 1e-12);
+        assert!((max_drawdown_pct(&returns) - 0.1).abs() < 1e-12);
+        assert!((bt.calmar_ratio - annualized / 0.1).abs() < 1e-9);
+        println!("✓ Calmar ratio works correctly");
+    }
+
+    #[test]
+    fn test_max_drawdown_peak_and_trough() {
+        let equity = [100.0, 120.0, 90.0, 130.0, 95.0, 125.0];
+        assert_eq!(max_drawdown(&equity), (35.0, 3, 4));
+
+        // The largest dollar decline (200 -> 120) is not the deepest relative one (100 -> 50)
+        let equity = [100.0, 50.0, 200.0, 120.0];
+        assert_eq!(max_drawdown(&equity), (80.0, 2, 3));
+        assert_eq!(max_relative_drawdown(&equity), (0.5, 0, 1));
+        assert_eq!(max_drawdown_pct(&[-0.5, 3.0, -0.4]), 0.5);
+
+        // A curve that reaches or crosses zero loses all of (or more than) its peak
+        assert_eq!(max_relative_drawdown(&[100.0, 120.0, 0.0, 30.0]), (1.0, 1, 2));
+        assert_eq!(max_drawdown_pct(&[-0.5, -1.5]), 1.25);
+
+        assert_eq!(max_drawdown(&[1.0, 2.0, 3.0]), (0.0, 0, 0));
+        assert_eq!(max_relative_drawdown(&[]), (0.0, 0, 0));
+        println!("✓ Max drawdown helper works correctly");
+    }
+
+    #[test]
+    fn test_compute_metrics_on_bare_returns() {
+        // Five periods at five periods per year: exactly one year
+        let returns = [0.1, -0.05, 0.0, 0.2, -0.1];
+        let m = compute_metrics(&returns, 5.0);
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-9;
+
+        // Equity 1 -> 1.1 -> 1.045 -> 1.045 -> 1.254 -> 1.1286
+        let total = 1.1 * 0.95 * 1.2 * 0.9 - 1.0;
+        assert_eq!(m.periods, 5);
+        assert!(close(m.total_return, total));
+        assert!(close(m.annualized_return, total));
+        // Mean 0.03; squared deviations sum to 0.058 over 4 degrees of freedom
+        let sd = (0.058_f64 / 4.0).sqrt();
+        assert!(close(m.volatility, sd * 5.0_f64.sqrt()));
+        assert!(close(m.sharpe_ratio, 0.03 / sd * 5.0_f64.sqrt()));
+        // Downside deviation sqrt((0.05^2 + 0.1^2) / 5) = 0.05
+        assert!(close(m.sortino_ratio, 0.03 / 0.05 * 5.0_f64.sqrt()));
+        // 1.254 -> 1.1286 is the deepest decline
+        assert!(close(m.max_drawdown, 0.1));
+        assert!(close(m.calmar_ratio, total / 0.1));
+        assert!(close(m.profit_factor, 0.3 / 0.15));
+        assert!(close(m.win_rate, 50.0));
+        assert!(close(m.avg_gain, 0.15));
+        assert!(close(m.avg_loss, 0.075));
+        assert!(close(m.expectancy, 0.5 * 0.15 - 0.5 * 0.075));
+        assert_eq!((m.max_consecutive_wins, m.max_consecutive_losses), (1, 1));
+
+        let empty = compute_metrics(&[], 252.0);
+        assert_eq!((empty.sharpe_ratio, empty.max_drawdown, empty.profit_factor), (0.0, 0.0, 0.0));
+        println!("✓ Metrics on a bare return vector work correctly");
+    }
+
+    #[test]
+    fn test_sharpe_tstat_newey_west() {
+        // 2%, 0%, 2%, 0%, ...: mean 1%, deviations of ±1% that alternate in sign
+        let returns: Vec<f64> = (0..100).map(|t| if t % 2 == 0 { 0.02 } else { 0.0 }).collect();
+        // Four lags at n = 100; autocovariance at lag l is (-1)^l (100 - l) / 100 * 1e-4, so
+        // the long-run variance is 1e-4 * (1 + 2 * (-0.8 * 0.99 + 0.6 * 0.98 - 0.4 * 0.97 + 0.2 * 0.96))
+        let long_run_variance: f64 = 1e-4 * (1.0 + 2.0 * (-0.792 + 0.588 - 0.388 + 0.192));
+        let expected = 0.01 / (long_run_variance / 100.0).sqrt();
+        let t = sharpe_tstat(&returns);
+        assert!((t - expected).abs() < 1e-6, "t-stat {} vs {}", t, expected);
+        // Negatively autocorrelated returns are more significant than the iid t-stat of 10
+        assert!(t > 20.0 && t < 25.0);
+        let losses: Vec<f64> = returns.iter().map(|r| -r).collect();
+        assert!((sharpe_tstat(&losses) + expected).abs() < 1e-6);
+        assert_eq!(sharpe_tstat(&[0.0; 40]), 0.0);
+
+        // Too short to test
+        assert!(sharpe_tstat(&returns[..MIN_TSTAT_OBSERVATIONS - 1]).is_nan());
+        let df = create_test_price_data();
+        let (bt, _, _) = backtest_performance(df, BuySell { buy: vec![1, 0, 0, 0, 0], sell: vec![0, 0, -1, 0, 0] }, "test").unwrap();
+        assert!(bt.sharpe_tstat.is_nan());
+        println!("✓ Newey-West Sharpe t-stat works correctly");
+    }
+
+    #[test]
+    fn test_signal_exit_trades_match_nested_scan() {
+        // Deterministic pseudo-random signals (LCG) over 1000 bars
+        let len = 1000;
+        let mut seed: u64 = 42;
+        let mut next = || {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (seed >> 33) % 100
+        };
+        let mut side = BuySell { buy: vec![0; len], sell: vec![0; len] };
+        for i in 0..len {
+            let r = next();
+            if r < 3 { side.buy[i] = 1; }
+            if (3..6).contains(&r) || r == 99 { side.sell[i] = -1; }
+        }
+
+        // Reference: the original nested forward scan
+        let scan = |is_entry: &dyn Fn(usize) -> bool| {
+            let mut trades = Vec::new();
+            for i in 0..len {
+                if is_entry(i) {
+                    for a in i + 1..std::cmp::min(i + 1000, len) {
+                        if side.buy[a] == 1 || side.sell[a] == -1 {
+                            trades.push((i, a));
+                            break;
+                        }
+                    }
+                }
+            }
+            trades
+        };
+        let expected_long = scan(&|i| side.buy[i] == 1);
+        let expected_short = scan(&|i| side.sell[i] == -1);
+
+        let (long_trades, short_trades) = signal_exit_trades(&side);
+        assert!(!expected_long.is_empty() && !expected_short.is_empty());
+        assert_eq!(long_trades, expected_long);
+        assert_eq!(short_trades, expected_short);
+
+        // A lone signal beyond the holding horizon is never closed
+        let mut sparse = BuySell { buy: vec![0; 1200], sell: vec![0; 1200] };
+        sparse.buy[0] = 1;
+        sparse.sell[1000] = -1;
+        assert!(signal_exit_trades(&sparse).0.is_empty());
+        println!("✓ Next-signal trade scan works correctly");
+    }
+
+    #[test]
+    fn test_sized_backtest_pyramiding_and_cap() {
+        // Three consecutive buys at 100, then the price doubles
+        let opens = [100.0, 100.0, 100.0, 200.0];
+        let buys = || BuySell { buy: vec![1, 1, 1, 0], sell: vec![0, 0, 0, 0] };
+        let run = |cap: Option<f64>, pyramiding: bool| {
+            let sizing = SizingConfig { max_position_value: cap, pyramiding, ..SizingConfig::new(1000.0, 1000.0) };
+            backtest_performance_sized(create_daily_price_data(&opens), buys(), "test", &sizing).unwrap().0
+        };
+
+        // Pyramiding off: only the first buy is taken (10 shares)
+        assert!((run(None, false).max_gain - 1000.0).abs() < 1e-9);
+        // Pyramiding on without a cap: all three buys (30 shares)
+        assert!((run(None, true).max_gain - 3000.0).abs() < 1e-9);
+        // Pyramiding on with a 2500 cap: the third add is clipped to 500
+        assert!((run(Some(2500.0), true).max_gain - 2500.0).abs() < 1e-9);
+        println!("✓ Sized backtest pyramiding works correctly");
+    }
+
+    #[test]
+    fn test_sized_backtest_volume_participation() {
+        // Want 10 shares at 100, but each bar only trades 5 shares
+        let mut df = create_daily_price_data(&[100.0, 100.0, 200.0]);
+        df.with_column(Series::new("Volume".into(), vec![5.0, 5.0, 5.0])).unwrap();
+        let side = || BuySell { buy: vec![1, 0, 0], sell: vec![0, 0, 0] };
+        let run = |policy: UnfilledPolicy| {
+            let sizing = SizingConfig {
+                max_participation: Some(0.1),
+                unfilled_policy: policy,
+                ..SizingConfig::new(1000.0, 1000.0)
+            };
+            backtest_performance_sized(df.clone(), side(), "test", &sizing).unwrap().0
+        };
+
+        // Drop: only 0.1 * 5 = 0.5 shares fill, worth +50 when the price doubles
+        assert!((run(UnfilledPolicy::Drop).max_gain - 50.0).abs() < 1e-9);
+        // Carry: another 0.5 shares fill on the next bar, so 1.0 share rides the move
+        assert!((run(UnfilledPolicy::Carry).max_gain - 100.0).abs() < 1e-9);
+        println!("✓ Sized backtest volume participation works correctly");
+    }
+
+    #[test]
+    fn test_sized_backtest_initial_cash() {
+        let df = create_daily_price_data(&[100.0, 100.0, 100.0]);
+        let side = BuySell { buy: vec![1, 0, 0], sell: vec![0, 0, 0] };
+        let weight = |initial_cash: f64| {
+            let sizing = SizingConfig { initial_cash, ..SizingConfig::new(1000.0, 1000.0) };
+            let path = simulate_sized(&df, &side, &sizing).unwrap();
+            assert!((path.portfolio_value[2] - initial_cash).abs() < 1e-9);
+            path.holdings_value[2] / path.portfolio_value[2]
+        };
+
+        assert!((weight(100_000.0) - 0.01).abs() < 1e-12);
+        assert!((weight(200_000.0) - weight(100_000.0) / 2.0).abs() < 1e-12);
+        println!("✓ Sized backtest initial cash works correctly");
+    }
+
+    #[test]
+    fn test_sized_backtest_whole_share_rounding() {
+        let df = create_daily_price_data(&[150.0, 150.0, 150.0, 150.0]);
+        let side = BuySell { buy: vec![1, 0, 0, 0], sell: vec![0, 0, -1, 0] };
+        let run = |round_shares: ShareRounding| {
+            let sizing = SizingConfig { round_shares, ..SizingConfig::new(1000.0, 500.0) };
+            simulate_sized(&df, &side, &sizing).unwrap()
+        };
+
+        // $1000 at $150 buys 6 shares; the other $100 stays in cash
+        let path = run(ShareRounding::Whole);
+        assert_eq!(path.holdings_value[0], 900.0);
+        assert_eq!(path.cash[0], 99_100.0);
+        // A $500 trim sells 3 of them
+        assert_eq!(path.holdings_value[2], 450.0);
+
+        let path = run(ShareRounding::Lot(5));
+        assert_eq!(path.holdings_value[0], 750.0);
+        let path = run(ShareRounding::None);
+        assert!((path.holdings_value[0] - 1000.0).abs() < 1e-9);
+        println!("✓ Sized backtest share rounding works correctly");
+    }
+
+    fn noop_signal(df: DataFrame, _param: f64) -> BuySell {
+        BuySell { buy: vec![0; df.height()], sell: vec![0; df.height()] }
+    }
+
+    #[tokio::test]
+    async fn test_zero_trade_strategy_warning() {
+        let mut counter = TradeCounter::default();
+        for df in [create_test_price_data(), create_test_price_data()] {
+            let signals = vec![Signal { name: "noop".to_string(), func: Arc::new(noop_signal), param: 0.0 }];
+            for (bt, _) in run_all_backtests(df.lazy(), signals).await.unwrap() {
+                counter.add(&bt);
+            }
+        }
+
+        assert_eq!(counter.warn_zero_trades("Crypto"), vec!["noop", "noop_se"]);
+        println!("✓ Zero-trade strategy warning works correctly");
+    }
+
+    fn stuck_signal(df: DataFrame, _param: f64) -> BuySell {
+        std::thread::sleep(std::time::Duration::from_millis(1500));
+        noop_signal(df, 0.0)
+    }
+
+    #[tokio::test]
+    async fn test_strategy_timeout_skips_runaway_signal() {
+        let signals = vec![
+            Signal { name: "noop".to_string(), func: Arc::new(noop_signal), param: 0.0 },
+            Signal { name: "stuck".to_string(), func: Arc::new(stuck_signal), param: 0.0 },
+            Signal { name: "buy_first".to_string(), func: Arc::new(buy_first_bar), param: 0.0 },
+        ];
+        let started = std::time::Instant::now();
+        let results = run_all_backtests_with(
+            create_test_price_data().lazy(),
+            signals,
+            None,
+            Some(std::time::Duration::from_millis(200)),
+        )
+        .await
+        .unwrap();
+
+        // The timeout frees the stuck strategy's slot, so the others don't wait for it even
+        // when there is only one slot
+        assert!(started.elapsed() < std::time::Duration::from_millis(1500));
+        let strategies: Vec<&str> = results.iter().map(|(bt, _)| bt.strategy.as_str()).collect();
+        assert_eq!(strategies, vec!["noop", "noop_se", "buy_first", "buy_first_se"]);
+        println!("✓ Strategy timeout works correctly");
+    }
+
+    fn buy_first_bar(df: DataFrame, _param: f64) -> BuySell {
+        let mut side = noop_signal(df, 0.0);
+        side.buy[0] = 1;
+        side
+    }
+
+    #[tokio::test]
+    async fn test_output_root_redirects_writes() {
+        let dir = std::env::temp_dir().join(format!("backtester_output_root_test_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let canonical = dir.join("home");
+        let scratch = dir.join("scratch");
+        std::fs::create_dir_all(&canonical).unwrap();
+        let paths = PathConfig::new(canonical.to_string_lossy().into_owned(), None)
+            .with_output_root(Some(scratch.to_string_lossy().into_owned()));
+
+        let bt = vec![(backtest_row("btc", "hammer", 1.0), Vec::new())];
+        save_backtest(&paths, bt, "Crypto", "btc".to_string(), false).await.unwrap();
+        let backtest_file = paths.output_file("Crypto", "btc", ExecutionMode::Testing);
+        let (_, out) = summary_performance_file(&paths, false, false, vec!["Crypto".to_string()], "Crypto")
+            .await
+            .unwrap();
+        assert_eq!(out.height(), 1);
+
+        assert!(backtest_file.starts_with(scratch.to_str().unwrap()));
+        assert!(std::path::Path::new(&backtest_file).exists());
+        assert!(scratch.join("performance").join("crypto_testing.csv").exists());
+        assert!(scratch.join("final_testing").join("Crypto_testing.csv").exists());
+        assert_eq!(std::fs::read_dir(&canonical).unwrap().count(), 0);
+
+        // Price data is still resolved against the canonical home
+        assert!(paths.data_file("Crypto", ExecutionMode::Testing).starts_with(&paths.base));
+
+        let _ = std::fs::remove_dir_all(&dir);
+        println!("✓ Output root override works correctly");
+    }
+
+    #[tokio::test]
+    async fn test_summary_reads_results_files_by_column_name() {
+        let dir = std::env::temp_dir().join(format!("backtester_old_layout_test_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let paths = PathConfig::new(dir.to_string_lossy().into_owned(), None);
+        let mut row = backtest_row("btc", "hammer", 1.0);
+        row.hit_ratio = 60.0;
+        save_backtest(&paths, vec![(row, Vec::new())], "Crypto", "btc".to_string(), false).await.unwrap();
+
+        // Rewrite the file as saved before `profit_factor_capped` and `sharpe_tstat` existed
+        let file = paths.output_file("Crypto", "btc", ExecutionMode::Testing);
+        let mut reader = csv::Reader::from_path(&file).unwrap();
+        let header = reader.headers().unwrap().clone();
+        let keep: Vec<usize> = (0..header.len())
+            .filter(|&i| !["profit_factor_capped", "sharpe_tstat"].contains(&&header[i]))
+            .collect();
+        let records: Vec<csv::StringRecord> = reader.records().map(|r| r.unwrap()).collect();
+        let mut writer = csv::Writer::from_path(&file).unwrap();
+        writer.write_record(keep.iter().map(|&i| &header[i])).unwrap();
+        for record in &records {
+            writer.write_record(keep.iter().map(|&i| &record[i])).unwrap();
+        }
+        writer.flush().unwrap();
+
+        let (_, out) = summary_performance_file(&paths, false, false, vec!["Crypto".to_string()], "Crypto")
+            .await
+            .unwrap();
+        assert_eq!(out.height(), 1);
+        assert_eq!(out.column("hit_ratio").unwrap().f64().unwrap().get(0), Some(60.0));
+        assert_eq!(out.column("profit_factor").unwrap().f64().unwrap().get(0), Some(1.5));
+        assert_eq!(out.column("sharpe_tstat").unwrap().f64().unwrap().get(0), None);
+
+        let _ = std::fs::remove_dir_all(&dir);
+        println!("✓ Older results files are read by column name");
+    }
+
+    #[test]
+    fn test_failed_csv_write_leaves_previous_file() {
+        use std::io::Write;
+        let dir = std::env::temp_dir().join(format!("backtester_atomic_write_test_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("btc.csv");
+        let partial = dir.join("btc.csv.partial");
+
+        let mut df = create_test_price_data();
+        write_csv_atomic(&mut df, &path).unwrap();
+        let original = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(CsvReader::new(File::open(&path).unwrap()).finish().unwrap().height(), df.height());
+
+        // The process "dies" after writing half a row
+        let failed = write_atomic_with(&path, |file| {
+            file.write_all(b"Date,Ticker\n2024-01-0")?;
+            Err("killed mid-write".into())
+        });
+        assert!(failed.is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), original);
+        assert!(!partial.exists());
+
+        // Without a previous file there is nothing rather than a truncated one
+        let fresh = dir.join("eth.csv");
+        assert!(write_atomic_with(&fresh, |_| Err("killed".into())).is_err());
+        assert!(!fresh.exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+        println!("✓ Atomic CSV writes work correctly");
+    }
+
+    #[tokio::test]
+    async fn test_manifest_detects_missing_and_truncated_files() {
+        let dir = std::env::temp_dir().join(format!("backtester_manifest_test_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let paths = PathConfig::new(dir.to_string_lossy().into_owned(), None);
+        for ticker in ["btc", "eth"] {
+            let bt = vec![
+                (backtest_row(ticker, "hammer", 1.0), Vec::new()),
+                (backtest_row(ticker, "donchian", 2.0), Vec::new()),
+            ];
+            save_backtest(&paths, bt, "Crypto", ticker.to_string(), false).await.unwrap();
+        }
+
+        let tickers: Vec<String> = ["btc", "eth"].iter().map(|t| t.to_string()).collect();
+        let manifest = write_manifest(&paths, "Crypto", &tickers, &[], false).unwrap();
+        let listed: Vec<&str> = manifest.entries.iter().map(|e| e.ticker.as_str()).collect();
+        assert_eq!(listed, vec!["btc", "eth"]);
+        for entry in &manifest.entries {
+            assert_eq!(entry.file, paths.output_file("Crypto", &entry.ticker, ExecutionMode::Testing));
+            assert_eq!(entry.rows, 2);
+        }
+        let manifest_path = paths.manifest_file("Crypto", ExecutionMode::Testing);
+        assert!(verify_manifest(&manifest_path).unwrap().is_empty());
+
+        // A later `-t sol` run that fails keeps btc and eth and records sol as missing
+        let manifest = write_manifest(&paths, "Crypto", &[], &["sol".to_string()], false).unwrap();
+        let listed: Vec<(&str, bool)> = manifest.entries.iter().map(|e| (e.ticker.as_str(), e.missing)).collect();
+        assert_eq!(listed, vec![("btc", false), ("eth", false), ("sol", true)]);
+        let sol = paths.output_file("Crypto", "sol", ExecutionMode::Testing);
+        assert_eq!(verify_manifest(&manifest_path).unwrap(), vec![ManifestIssue::Missing { file: sol.clone() }]);
+
+        // Drop one file and cut the last row off the other
+        std::fs::remove_file(&manifest.entries[0].file).unwrap();
+        let eth = &manifest.entries[1].file;
+        let content = std::fs::read_to_string(eth).unwrap();
+        let truncated: Vec<&str> = content.lines().take(2).collect();
+        std::fs::write(eth, truncated.join("\n") + "\n").unwrap();
+
+        let issues = verify_manifest(&manifest_path).unwrap();
+        assert_eq!(issues, vec![
+            ManifestIssue::Missing { file: manifest.entries[0].file.clone() },
+            ManifestIssue::Changed { file: eth.clone(), rows: 1, expected_rows: 2 },
+            ManifestIssue::Missing { file: sol },
+        ]);
+
+        let _ = std::fs::remove_dir_all(&dir);
+        println!("✓ Results manifest works correctly");
+    }
+
+    #[tokio::test]
+    async fn test_resume_reruns_truncated_output_only() {
+        let dir = std::env::temp_dir().join(format!("backtester_resume_test_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let paths = PathConfig::new(dir.to_string_lossy().into_owned(), None);
+        for ticker in ["btc", "eth"] {
+            let bt = vec![
+                (backtest_row(ticker, "hammer", 1.0), Vec::new()),
+                (backtest_row(ticker, "donchian", 2.0), Vec::new()),
+            ];
+            save_backtest(&paths, bt, "Crypto", ticker.to_string(), false).await.unwrap();
+        }
+        let btc = paths.output_file("Crypto", "btc", ExecutionMode::Testing);
+        let eth = paths.output_file("Crypto", "eth", ExecutionMode::Testing);
+        assert!(backtest_output_complete(&btc));
+
+        // Killed partway through the second row
+        let content = std::fs::read_to_string(&eth).unwrap();
+        std::fs::write(&eth, &content[..content.len() - 10]).unwrap();
+        assert!(!backtest_output_complete(&eth));
+
+        let processed = load_processed_tickers(&paths, "Crypto", ExecutionMode::Testing).unwrap();
+        assert_eq!(processed, HashSet::from(["btc".to_string()]));
+
+        let lf = df! {
+            "Ticker" => &["btc", "eth", "sol"],
+        }.unwrap().lazy();
+        let (needed, done) = determine_tickers_to_process(&lf, &processed, None).unwrap();
+        assert_eq!(needed, vec!["eth", "sol"]);
+        assert_eq!(done, vec!["btc"]);
+
+        // Without --resume nothing is skipped
+        let (all, done) = determine_tickers_to_process(&lf, &HashSet::new(), None).unwrap();
+        assert_eq!(all, vec!["btc", "eth", "sol"]);
+        assert!(done.is_empty());
+
+        // Another universe's file in the shared folder is neither skipped nor claimed
+        let other = df! { "Ticker" => &["ada", "eth"] }.unwrap().lazy();
+        let (needed, done) = determine_tickers_to_process(&other, &processed, None).unwrap();
+        assert_eq!((needed, done), (vec!["ada".to_string(), "eth".to_string()], Vec::<String>::new()));
+        // With -t, only the requested tickers count
+        let custom = Some(vec!["btc".to_string(), "sol".to_string()]);
+        let (needed, done) = determine_tickers_to_process(&lf, &processed, custom).unwrap();
+        assert_eq!((needed, done), (vec!["sol".to_string()], vec!["btc".to_string()]));
+
+        let _ = std::fs::remove_dir_all(&dir);
+        println!("✓ Resume skips only complete output files");
+    }
+
+    #[tokio::test]
+    async fn test_backtest_cache_hits_unchanged_inputs() {
+        let dir = std::env::temp_dir().join(format!("backtester_cache_test_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let cache = Arc::new(BacktestCache::new(&dir).unwrap());
+        let signals = |param: f64| {
+            vec![
+                Signal { name: "noop".to_string(), func: Arc::new(noop_signal), param },
+                Signal { name: "buy_first".to_string(), func: Arc::new(buy_first_bar), param: 1.0 },
+            ]
+        };
+        let run = |param: f64| {
+            run_all_backtests_with(create_test_price_data().lazy(), signals(param), Some(Arc::clone(&cache)), None)
+        };
+
+        let first = run(0.0).await.unwrap();
+        assert_eq!(cache.misses(), 2);
+        let second = run(0.0).await.unwrap();
+        assert_eq!(cache.misses(), 2);
+        assert_eq!(second.len(), first.len());
+        for ((a, da), (b, db)) in first.iter().zip(&second) {
+            assert_eq!((&a.strategy, a.buys, a.trades, da.len()), (&b.strategy, b.buys, b.trades, db.len()));
+        }
+        // Five bars are too few for a t-stat: the NaN and the histogram survive the round trip
+        for ((a, _), (b, _)) in first.iter().zip(&second) {
+            assert!(a.sharpe_tstat.is_nan() && b.sharpe_tstat.is_nan());
+            assert_eq!(a.holding_period_histogram, b.holding_period_histogram);
+        }
+        assert!(second.iter().any(|(bt, _)| !bt.holding_period_histogram.is_empty()));
+        let entry = std::fs::read_dir(&dir).unwrap().next().unwrap().unwrap().path();
+        assert!(std::fs::read_to_string(entry).unwrap().contains("NaN"));
+
+        // A changed param misses; the unchanged strategy still hits
+        run(2.0).await.unwrap();
+        assert_eq!(cache.misses(), 3);
+
+        // So does changed data
+        let mut df = create_test_price_data();
+        df.with_column(Series::new("Close".into(), [105.0, 104.0, 108.0, 111.0, 114.0])).unwrap();
+        run_all_backtests_with(df.lazy(), signals(0.0), Some(Arc::clone(&cache)), None).await.unwrap();
+        assert_eq!(cache.misses(), 5);
+
+        let _ = std::fs::remove_dir_all(&dir);
+        println!("✓ Backtest cache works correctly");
+    }
+
+    #[test]
+    fn test_portfolio_sharpe_diversifies_offsetting_curves() {
+        // Same drift, opposite swings, plus a little independent noise
+        let swing = |t: usize| if t.is_multiple_of(2) { 0.02 } else { -0.02 };
+        let noise = |t: usize| if t.is_multiple_of(3) { 0.002 } else { -0.001 };
+        let curve = |sign: f64| -> Vec<f64> {
+            std::iter::once(1.0)
+                .chain((0..60).scan(1.0, |equity, t| {
+                    *equity *= 1.0 + 0.003 + sign * swing(t) + noise(t);
+                    Some(*equity)
+                }))
+                .collect()
+        };
+        let (a, b) = (curve(1.0), curve(-1.0));
+        let own = |c: &Vec<f64>| portfolio_sharpe(std::slice::from_ref(c), &[1.0], 252.0);
+
+        let combined = portfolio_sharpe(&[a.clone(), b.clone()], &[0.5, 0.5], 252.0);
+        assert!(own(&a) > 0.0 && own(&b) > 0.0);
+        assert!(combined > own(&a) && combined > own(&b));
+        assert!(combined > 3.0 * own(&a).max(own(&b)));
+
+        // A single curve built from the trade ledger goes through the same path
+        let trades = vec![Trade {
+            long: true,
+            entry_index: 0,
+            exit_index: 2,
+            entry_date: None,
+            exit_date: None,
+            entry_price: 100.0,
+            exit_price: 110.0,
+            exit_reason: ExitReason::OppositeSignal,
+            initial_risk: None,
+        }];
+        assert_eq!(trade_equity_curve(&trades, 4), vec![1.0, 1.0, 1.1, 1.1]);
+        println!("✓ Portfolio Sharpe works correctly");
+    }
+
+    #[test]
+    fn test_aggregate_equity_curves_weights_aligned_dates() {
+        let day = |d: u32| chrono::NaiveDate::from_ymd_opt(2024, 1, d).unwrap();
+        let curves = std::collections::HashMap::from([
+            ("btc".to_string(), vec![(day(1), 1.0), (day(2), 1.1), (day(3), 1.2)]),
+            ("eth".to_string(), vec![(day(2), 1.0), (day(4), 0.9)]),
+        ]);
+
+        // btc holds 1.2 on day 4; eth is idle at 1.0 before day 2 and holds it on day 3
+        let out = aggregate_equity_curves(&curves, None).unwrap();
+        assert_eq!(out.get_column_names(), ["Date", "btc", "eth", "equity"]);
+        let column = |name: &str| out.column(name).unwrap().f64().unwrap().into_no_null_iter().collect::<Vec<f64>>();
+        assert_eq!(column("btc"), vec![1.0, 1.1, 1.2, 1.2]);
+        assert_eq!(column("eth"), vec![1.0, 1.0, 1.0, 0.9]);
+        for ((total, btc), eth) in column("equity").iter().zip(column("btc")).zip(column("eth")) {
+            assert!((total - (0.5 * btc + 0.5 * eth)).abs() < 1e-12);
+        }
+
+        let weights = std::collections::HashMap::from([("btc".to_string(), 0.75), ("eth".to_string(), 0.25)]);
+        let out = aggregate_equity_curves(&curves, Some(&weights)).unwrap();
+        let equity = out.column("equity").unwrap().f64().unwrap().get(3).unwrap();
+        assert!((equity - (0.75 * 1.2 + 0.25 * 0.9)).abs() < 1e-12);
+
+        let err = aggregate_equity_curves(&curves, Some(&std::collections::HashMap::from([("btc".to_string(), 1.0)]))).unwrap_err();
+        assert!(err.to_string().contains("No weight for ticker 'eth'"));
+        println!("✓ Equity curve aggregation works correctly");
+    }
+
+    #[test]
+    fn test_expectancy_per_day_ranks_shorter_holds_higher() {
+        // Both strategies make +10 on one trade: A in two days, B in six
+        let df = create_daily_price_data(&[100.0, 100.0, 105.0, 110.0, 110.0, 110.0, 110.0]);
+        let run = |entry: usize, exit: usize| {
+            let mut side = BuySell { buy: vec![0; 7], sell: vec![0; 7] };
+            side.buy[entry] = 1;
+            side.sell[exit] = -1;
+            backtest_performance(df.clone(), side, "test").unwrap().1
+        };
+        let (fast, slow) = (run(1, 3), run(0, 6));
+
+        assert_eq!(fast.expectancy, slow.expectancy);
+        assert!((fast.expectancy_per_day - fast.expectancy / 2.0).abs() < 1e-9);
+        assert!((slow.expectancy_per_day - slow.expectancy / 6.0).abs() < 1e-9);
+        assert!(fast.expectancy_per_day > slow.expectancy_per_day);
+
+        // No trades: no holding period to divide by
+        let idle = backtest_performance(df, BuySell { buy: vec![0; 7], sell: vec![0; 7] }, "test").unwrap().1;
+        assert_eq!(idle.expectancy_per_day, 0.0);
+        println!("✓ Expectancy per day works correctly");
+    }
+
+    #[test]
+    fn test_dca_benchmark_beats_lump_sum_on_v_shape() {
+        // Falls to 40 and recovers to where it started: lump sum breaks even
+        let df = create_daily_price_data(&[100.0, 80.0, 60.0, 40.0, 60.0, 80.0, 100.0]);
+        let side = || BuySell { buy: vec![0; 7], sell: vec![0; 7] };
+
+        let (bt, bt_se, _) = backtest_performance(df.clone(), side(), "test").unwrap();
+        assert!(bt.buy_and_hold_return.abs() < 1e-9);
+        assert_eq!(bt.dca_return, 0.0);
+        assert_eq!(bt_se.buy_and_hold_return, bt.buy_and_hold_return);
+
+        let config = PerformanceConfig { dca_benchmark: true, ..Default::default() };
+        let (bt, _, _) = backtest_performance_with(df.clone(), side(), "test", &config).unwrap();
+        // Equal dollars bought cheaply near the bottom are worth more at 100
+        let shares: f64 = [100.0, 80.0, 60.0, 40.0, 60.0, 80.0, 100.0].iter().map(|p| 1.0 / p).sum();
+        let expected = (shares * 100.0 / 7.0 - 1.0) * 100.0;
+        assert!((bt.dca_return - expected).abs() < 1e-9, "dca {}", bt.dca_return);
+        assert!(bt.dca_return > bt.buy_and_hold_return);
+        assert!((dca_return(&df, "Close").unwrap() * 100.0 - expected).abs() < 1e-9);
+        println!("✓ DCA buy-and-hold benchmark works correctly");
+    }
+
+    #[test]
+    fn test_buy_and_hold_covers_only_the_active_window() {
+        // Doubles in the first half, falls 20% in the second, where the strategy trades
+        let opens = [100.0, 125.0, 150.0, 175.0, 200.0, 200.0, 190.0, 180.0, 170.0, 160.0];
+        let df = create_daily_price_data(&opens);
+        let side = || {
+            let mut side = BuySell { buy: vec![0; 10], sell: vec![0; 10] };
+            side.buy[5] = 1;
+            side.sell[9] = -1;
+            side
+        };
+
+        let (bt, bt_se, _) = backtest_performance(df.clone(), side(), "test").unwrap();
+        assert!((bt.buy_and_hold_return - (160.0 / 200.0 - 1.0) * 100.0).abs() < 1e-9);
+        assert_eq!(bt_se.buy_and_hold_return, bt.buy_and_hold_return);
+
+        let config = PerformanceConfig { full_window_benchmark: true, ..Default::default() };
+        let (bt, _, _) = backtest_performance_with(df, side(), "test", &config).unwrap();
+        assert!((bt.buy_and_hold_return - 60.0).abs() < 1e-9);
+        println!("✓ Active-window buy-and-hold works correctly");
+    }
+
+    #[test]
+    fn test_flip_pays_two_transaction_costs() {
+        // Flat at 100: long from bar 1, flipped short on bar 5, closed at the end on bar 9
+        let df = create_daily_price_data(&[100.0; 10]);
+        let side = || {
+            let mut side = BuySell { buy: vec![0; 10], sell: vec![0; 10] };
+            side.buy[1] = 1;
+            side.sell[5] = -1;
+            side
+        };
+        let config = PerformanceConfig { mark_open_at_end: true, transaction_cost: 0.001, ..Default::default() };
+        let (_, trades_se) = trade_ledger(&df, &side(), &config).unwrap();
+        let costs = transaction_costs(&trades_se, config.transaction_cost, 10);
+        // Fills are charged on each trade's exit bar: the long's two on bar 5, the short's
+        // entry (at the flip) on bar 9. The short is only marked closed there, so it has
+        // no exit fill to pay for.
+        assert_eq!(trades_se[1].exit_reason, ExitReason::EndOfData);
+        assert_eq!(costs[1], 0.0);
+        assert!((costs[5] - 0.2).abs() < 1e-12);
+        assert!((costs[9] - 0.1).abs() < 1e-12);
+
+        // The same long closed on bar 5 without opening the short is a plain exit. Past
+        // the long's entry, the flip's two trades pay twice what the plain exit does.
+        let entry = config.transaction_cost * trades_se[0].entry_price;
+        let plain_exit = transaction_costs(&trades_se[..1], config.transaction_cost, 10).iter().sum::<f64>() - entry;
+        let flip = costs.iter().sum::<f64>() - entry;
+        assert!((plain_exit - 0.1).abs() < 1e-12);
+        assert!((flip - 2.0 * plain_exit).abs() < 1e-12);
+
+        // Gross P&L is zero, so the long loses its two fills and the short its entry
+        let (_, bt_se, _) = backtest_performance_with(df, side(), "test", &config).unwrap();
+        assert!((bt_se.max_loss + 0.2).abs() < 1e-12, "max loss {}", bt_se.max_loss);
+        assert_eq!(bt_se.trades, 2);
+        println!("✓ Flip transaction costs work correctly");
+    }
+
+    #[test]
+    fn test_transaction_costs_keep_trade_count() {
+        // Long 100 -> 110 (win), short 110 -> 105 (win), long 105 -> 100 (loss)
+        let df = create_daily_price_data(&[100.0, 100.0, 110.0, 110.0, 105.0, 105.0, 100.0]);
+        let side = || BuySell { buy: vec![0, 1, 0, 0, 1, 0, 0], sell: vec![0, 0, -1, 0, 0, 0, -1] };
+        let (_, gross, _) = backtest_performance(df.clone(), side(), "test").unwrap();
+        let config = PerformanceConfig { transaction_cost: 0.001, ..Default::default() };
+        let (_, net, _) = backtest_performance_with(df, side(), "test", &config).unwrap();
+
+        assert_eq!(gross.trades, 3);
+        assert_eq!(net.trades, gross.trades);
+        assert_eq!(net.hit_ratio, gross.hit_ratio);
+        // Each round trip pays both fills out of its own P&L
+        assert!((gross.max_gain - net.max_gain - 0.21).abs() < 1e-9);
+        assert!((net.avg_loss - gross.avg_loss - 0.205).abs() < 1e-9);
+        println!("✓ Transaction costs keep the trade count");
+    }
+
+    #[test]
+    fn test_post_loss_cooldown_suppresses_reentry() {
+        // Long from bar 1 at 100, sold at a loss on bar 3 at 90; buys follow on bars 4 and 6
+        let df = create_daily_price_data(&[100.0, 100.0, 95.0, 90.0, 92.0, 94.0, 96.0, 98.0, 100.0, 102.0]);
+        let mut side = BuySell { buy: vec![0; 10], sell: vec![0; 10] };
+        side.buy[1] = 1;
+        side.sell[3] = -1;
+        side.buy[4] = 1;
+        side.buy[6] = 1;
+        side.sell[8] = -1;
+        let spans = |trades: &[Trade], long: bool| -> Vec<(usize, usize)> {
+            trades.iter().filter(|t| t.long == long).map(|t| (t.entry_index, t.exit_index)).collect()
+        };
+
+        let (trades, trades_se) = trade_ledger(&df, &side, &PerformanceConfig::default()).unwrap();
+        assert_eq!(spans(&trades_se, true), vec![(1, 3), (4, 8)]);
+        assert_eq!(spans(&trades_se, false), vec![(3, 4)]);
+        assert_eq!(trades.len(), 4);
+
+        // Bars 3 to 5 are cooling down: the buy on bar 4 is dropped and bar 6 enters instead
+        let config = PerformanceConfig { post_loss_cooldown: 2, ..Default::default() };
+        let (trades, trades_se) = trade_ledger(&df, &side, &config).unwrap();
+        assert_eq!(spans(&trades_se, true), vec![(1, 3), (6, 8)]);
+        assert!((trades_se[1].entry_price - 96.0).abs() < 1e-12);
+        // The long's loss also blocks the short the sell on bar 3 would have opened
+        assert_eq!(spans(&trades_se, false), vec![]);
+        // Approach 1 also drops the flip short on bar 3 and the long on bar 4
+        assert_eq!(trades.iter().map(|t| (t.entry_index, t.exit_index)).collect::<Vec<_>>(), vec![(1, 3), (6, 8)]);
+        println!("✓ Post-loss cool-down works correctly");
+    }
+
+    #[test]
+    fn test_compute_returns_methods() {
+        let prices = Series::new("Close".into(), [100.0, 110.0, 99.0, 99.0]);
+        let cases = [
+            (ReturnMethod::Simple, [0.1, -0.1, 0.0]),
+            (ReturnMethod::Log, [1.1f64.ln(), 0.9f64.ln(), 0.0]),
+            (ReturnMethod::Pct, [10.0, -10.0, 0.0]),
+            (ReturnMethod::PointDiff, [10.0, -11.0, 0.0]),
+        ];
+        let df = DataFrame::new(vec![prices.clone().into_column()]).unwrap();
+        for (method, expected) in cases {
+            let returns = compute_returns(&prices, method).unwrap();
+            let values: Vec<Option<f64>> = returns.f64().unwrap().into_iter().collect();
+            assert_eq!(values[0], None, "{:?}", method);
+            for (got, want) in values[1..].iter().zip(expected) {
+                assert!((got.unwrap() - want).abs() < 1e-9, "{:?}: {:?} vs {}", method, got, want);
+            }
+
+            // The lazy form agrees with the eager one
+            let lazy = df.clone().lazy().select([method.expr(col("Close"))]).collect().unwrap();
+            let lazy: Vec<Option<f64>> = lazy.column("Close").unwrap().f64().unwrap().into_iter().collect();
+            assert_eq!(lazy, values, "{:?}", method);
+        }
+        println!("✓ Return conventions work correctly");
+    }
+
+    #[test]
+    fn test_final_signal_from_position_state() {
+        let df = create_daily_price_data(&[100.0, 101.0, 102.0, 103.0, 104.0]);
+        // Long since bar 1; the indicator fires again on the last bar
+        let held = || BuySell { buy: vec![0, 1, 0, 0, 1], sell: vec![0; 5] };
+
+        let (raw, _, _) = backtest_performance(df.clone(), held(), "test").unwrap();
+        assert_eq!(raw.action(), "buy");
+
+        let config = PerformanceConfig { final_signal: FinalSignal::PositionState, ..Default::default() };
+        let (bt, bt_se, _) = backtest_performance_with(df.clone(), held(), "test", &config).unwrap();
+        assert_eq!((bt.buy, bt.sell), (0, 0));
+        assert_eq!(bt.action(), "hold");
+        assert_eq!(bt_se.action(), "hold");
+
+        // A buy that opens the position is still reported, as is a reversal
+        let fresh = BuySell { buy: vec![0, 0, 0, 0, 1], sell: vec![0; 5] };
+        let (bt, _, _) = backtest_performance_with(df.clone(), fresh, "test", &config).unwrap();
+        assert_eq!(bt.action(), "buy");
+        let reversal = BuySell { buy: vec![0, 1, 0, 0, 0], sell: vec![0, 0, 0, 0, -1] };
+        let (bt, _, _) = backtest_performance_with(df, reversal, "test", &config).unwrap();
+        assert_eq!(bt.action(), "sell");
+        println!("✓ Position-state final signal works correctly");
+    }
+
+    #[test]
+    fn test_profit_factor_cap_flag() {
+        // Winning long only: no losses at all
+        let df = create_daily_price_data(&[100.0, 110.0, 120.0]);
+        let side = BuySell { buy: vec![1, 0, 0], sell: vec![0, 0, -1] };
+        let (_, bt_se, _) = backtest_performance(df, side, "test").unwrap();
+        assert_eq!((bt_se.profit_factor, bt_se.profit_factor_capped), (PROFIT_FACTOR_CAP, true));
+
+        // +100 on the long, -0.05 on the short: a true ratio of 2000
+        let df = create_daily_price_data(&[100.0, 200.0, 200.05, 200.05]);
+        let side = || BuySell { buy: vec![1, 0, 1, 0], sell: vec![0, -1, 0, -1] };
+        let (_, capped, _) = backtest_performance(df.clone(), side(), "test").unwrap();
+        assert_eq!((capped.profit_factor, capped.profit_factor_capped), (PROFIT_FACTOR_CAP, true));
+
+        let config = PerformanceConfig { profit_factor_cap: Some(f64::INFINITY), ..Default::default() };
+        let (_, uncapped, _) = backtest_performance_with(df, side(), "test", &config).unwrap();
+        assert!((uncapped.profit_factor - 2000.0).abs() < 1e-6);
+        assert!(!uncapped.profit_factor_capped);
+        println!("✓ Profit factor cap flag works correctly");
+    }
+
+    #[test]
+    fn test_next_bar_confirmation_drops_reversed_signals() {
+        let df = create_daily_price_data(&[100.0, 101.0, 99.0, 100.0, 102.0, 104.0, 103.0, 103.0, 105.0]);
+        // The buy on bar 1 is reversed by bar 2; the buy on bar 3 is confirmed by bar 4's close
+        let side = || BuySell { buy: vec![0, 1, 0, 1, 0, 0, 0, 0, 0], sell: vec![0, 0, 0, 0, 0, 0, -1, 0, 0] };
+        let long = |confirmation: Confirmation| {
+            let config = PerformanceConfig { confirmation, ..Default::default() };
+            let (_, bt_se, decisions) = backtest_performance_with(df.clone(), side(), "test", &config).unwrap();
+            let confirmed = confirmation.apply(&df, &side()).unwrap();
+            let (_, trades_se) = trade_ledger(&df, &confirmed, &config).unwrap();
+            let t = trades_se.into_iter().find(|t| t.long).unwrap();
+            (t.entry_index, t.exit_index, bt_se.buys, decisions.len())
+        };
+
+        assert_eq!(long(Confirmation::None), (1, 6, 2, 3));
+        // Confirmed at bar 4's close, so the earliest fill is bar 5's open
+        assert_eq!(long(Confirmation::NextBarClose), (5, 6, 1, 2));
+        assert_eq!(long(Confirmation::NBars(1)), long(Confirmation::NextBarClose));
+        // Two bars: the bar 3 buy is confirmed by bars 4 and 5 and acted on at bar 6
+        let two = Confirmation::NBars(2).apply(&df, &side()).unwrap();
+        assert_eq!(two.buy.iter().position(|&b| b == 1), Some(6));
+        // Exits are not confirmed or delayed
+        assert_eq!(two.sell, side().sell);
+
+        // Confirmed by the last bar's close leaves no bar to fill on
+        let late = BuySell { buy: vec![0, 0, 0, 0, 0, 0, 0, 1, 0], sell: vec![0; 9] };
+        assert!(Confirmation::NextBarClose.apply(&df, &late).unwrap().buy.iter().all(|&b| b == 0));
+        println!("✓ Signal confirmation works correctly");
+    }
+
+    #[test]
+    fn test_typical_and_weighted_price_fills() {
+        let df = create_test_price_data();
+        let side = || {
+            let mut side = BuySell { buy: vec![0; 5], sell: vec![0; 5] };
+            side.buy[1] = 1;
+            side.sell[3] = -1;
+            side
+        };
+        let long = |config: &PerformanceConfig| {
+            let (_, trades_se) = trade_ledger(&df, &side(), config).unwrap();
+            let t = trades_se.into_iter().find(|t| t.long).unwrap();
+            (t.entry_price, t.exit_price)
+        };
+
+        assert_eq!(long(&PerformanceConfig::default()), (105.0, 108.0));
+        // Typical price of bar 1 (107, 103, 104) and bar 3 (112, 107, 111)
+        let config = PerformanceConfig { price_source: PriceSource::Typical, ..Default::default() };
+        let (entry, exit) = long(&config);
+        assert!((entry - 314.0 / 3.0).abs() < 1e-9);
+        assert!((exit - 110.0).abs() < 1e-9);
+
+        // Three parts open to one part close
+        let config = PerformanceConfig { price_source: PriceSource::Weighted { open: 3.0, close: 1.0 }, ..Default::default() };
+        assert_eq!(long(&config), (104.75, 108.75));
+        println!("✓ Typical and weighted fill prices work correctly");
+    }
+
+    #[test]
+    fn test_exit_reasons_tag_stop_and_signal_exits() {
+        let opens = [100.0, 100.0, 100.0, 95.0, 96.0, 100.0, 102.0, 105.0, 110.0, 110.0];
+        let mut df = create_daily_price_data(&opens);
+        // Bar 3 trades down to 85, through a 10% stop on the first long
+        let mut lows = opens.to_vec();
+        lows[3] = 85.0;
+        df.with_column(Series::new("Low".into(), lows)).unwrap();
+
+        let mut side = BuySell { buy: vec![0; 10], sell: vec![0; 10] };
+        side.buy[1] = 1;
+        side.buy[5] = 1;
+        side.sell[8] = -1;
+        let config = PerformanceConfig { stop_loss_pct: Some(0.10), ..Default::default() };
+        let (_, trades_se) = trade_ledger(&df, &side, &config).unwrap();
+        let longs: Vec<&Trade> = trades_se.iter().filter(|t| t.long).collect();
+        assert_eq!(longs.len(), 2);
+        assert_eq!((longs[0].exit_index, longs[0].exit_price, longs[0].exit_reason), (3, 90.0, ExitReason::StopLoss));
+        assert_eq!((longs[1].exit_index, longs[1].exit_price, longs[1].exit_reason), (8, 110.0, ExitReason::OppositeSignal));
+
+        // The short opened on bar 8 is only closed when open positions are marked at the end
+        let config = PerformanceConfig { stop_loss_pct: Some(0.10), mark_open_at_end: true, ..Default::default() };
+        let (_, bt_se, _) = backtest_performance_with(df, side, "test", &config).unwrap();
+        assert_eq!((bt_se.signal_exits, bt_se.stop_loss_exits, bt_se.end_of_data_exits), (1, 1, 1));
+        println!("✓ Exit reason tagging works correctly");
+    }
+
+    #[test]
+    fn test_r_multiples_against_stop_distance() {
+        let opens = [100.0, 100.0, 100.0, 95.0, 96.0, 100.0, 102.0, 105.0, 120.0, 120.0];
+        let mut df = create_daily_price_data(&opens);
+        let mut lows = opens.to_vec();
+        lows[3] = 85.0;
+        df.with_column(Series::new("Low".into(), lows)).unwrap();
+
+        let side = || {
+            let mut side = BuySell { buy: vec![0; 10], sell: vec![0; 10] };
+            side.buy[1] = 1;
+            side.buy[5] = 1;
+            side.sell[8] = -1;
+            side
+        };
+        let config = PerformanceConfig { stop_loss_pct: Some(0.10), ..Default::default() };
+        let (_, trades_se) = trade_ledger(&df, &side(), &config).unwrap();
+        // Both longs enter at 100 with the stop 10 below: stopped at 90 is -10/10 = -1R,
+        // the exit at 120 is +20/10 = +2R
+        let r: Vec<Option<f64>> = trades_se.iter().map(|t| t.r_multiple()).collect();
+        assert_eq!(trades_se[0].initial_risk, Some(10.0));
+        assert_eq!(r, vec![Some(-1.0), Some(2.0)]);
+        assert_eq!(r_multiple_distribution(&trades_se), std::collections::BTreeMap::from([(-1, 1), (2, 1)]));
+
+        let (_, bt_se, _) = backtest_performance_with(df.clone(), side(), "test", &config).unwrap();
+        assert!((bt_se.expectancy_r - 0.5).abs() < 1e-9);
+        assert!((bt_se.r_multiple_std - 1.5).abs() < 1e-9);
+
+        // Without a stop there is no risk unit
+        let (_, bt_se, _) = backtest_performance_with(df, side(), "test", &PerformanceConfig::default()).unwrap();
+        assert_eq!((bt_se.expectancy_r, bt_se.r_multiple_std), (0.0, 0.0));
+        println!("✓ R-multiple tracking works correctly");
+    }
+
+    #[test]
+    fn test_holding_period_histogram_buckets() {
+        // No dates, so holding periods are counted in bars
+        let trades: Vec<Trade> = [0, 1, 3, 4, 7, 60, 150]
+            .iter()
+            .map(|&held| Trade {
+                long: true,
+                entry_index: 0,
+                exit_index: held,
+                entry_date: None,
+                exit_date: None,
+                entry_price: 100.0,
+                exit_price: 101.0,
+                exit_reason: ExitReason::NextSignal,
+                initial_risk: None,
+            })
+            .collect();
+
+        assert_eq!(
+            holding_period_histogram(&trades, HOLDING_PERIOD_BUCKETS),
+            vec![(0, 1), (1, 1), (2, 2), (5, 1), (10, 0), (20, 0), (50, 1), (100, 1)]
+        );
+        // Holds below the first bound land in the first bucket
+        assert_eq!(holding_period_histogram(&trades, &[2, 30]), vec![(2, 5), (30, 2)]);
+
+        // backtest_performance fills it from the ledger: one 2-day and one 4-day hold
+        let df = create_daily_price_data(&[100.0; 8]);
+        let mut side = BuySell { buy: vec![0; 8], sell: vec![0; 8] };
+        side.buy[1] = 1;
+        side.sell[3] = -1;
+        side.buy[7] = 1;
+        let config = PerformanceConfig { holding_period_buckets: vec![0, 3], ..Default::default() };
+        let (_, bt_se, _) = backtest_performance_with(df, side, "test", &config).unwrap();
+        assert_eq!(bt_se.holding_period_histogram, vec![(0, 1), (3, 1)]);
+        println!("✓ Holding period histogram works correctly");
+    }
+
+    #[test]
+    fn test_benchmark_beta_against_own_returns() {
+        let opens: Vec<f64> = (0..60).map(|i| 100.0 + 10.0 * (i as f64 / 5.0).sin() + i as f64 * 0.2).collect();
+        // Closes swing away from the opens, so close-to-close returns differ from the
+        // open-to-open returns the strategy earns
+        let closes: Vec<f64> = opens.iter().enumerate().map(|(i, o)| o * if i % 2 == 0 { 1.03 } else { 0.98 }).collect();
+        let mut df = create_daily_price_data(&opens);
+        df.with_column(Series::new("Close".into(), closes.clone())).unwrap();
+        let start = chrono::NaiveDate::from_ymd_opt(2023, 1, 1).unwrap();
+
+        // The benchmark file is the asset's own prices, starting ten days earlier
+        let path = std::env::temp_dir().join("backtester_benchmark_test.csv");
+        let mut csv = String::from("Date,Open,Close\n");
+        for i in -10..60i64 {
+            let (open, close) = if i < 0 { (100.0, 100.0) } else { (opens[i as usize], closes[i as usize]) };
+            csv.push_str(&format!("{},{},{}\n", start + chrono::Duration::days(i), open, close));
+        }
+        std::fs::write(&path, csv).unwrap();
+        let mut benchmark = load_benchmark_returns(path.to_str().unwrap()).unwrap();
+        assert_eq!(benchmark.len(), 69);
+        // A gap in the benchmark only drops that date from the comparison
+        benchmark.remove(&(start + chrono::Duration::days(30)));
+
+        // Long from the first bar to the last
+        let mut side = BuySell { buy: vec![0; 60], sell: vec![0; 60] };
+        side.buy[0] = 1;
+        side.sell[59] = -1;
+        let config = PerformanceConfig { benchmark: Some(Arc::new(benchmark)), ..Default::default() };
+        let (_, bt_se, _) = backtest_performance_with(df.clone(), side, "test", &config).unwrap();
+        assert!((bt_se.beta_to_benchmark - 1.0).abs() < 1e-9);
+        assert_eq!(bt_se.information_ratio, 0.0);
+
+        // Without a benchmark both stay at zero
+        let mut side = BuySell { buy: vec![0; 60], sell: vec![0; 60] };
+        side.buy[0] = 1;
+        side.sell[59] = -1;
+        let (_, plain, _) = backtest_performance(df, side, "test").unwrap();
+        assert_eq!((plain.information_ratio, plain.beta_to_benchmark), (0.0, 0.0));
+
+        let _ = std::fs::remove_file(&path);
+        println!("✓ Benchmark beta and information ratio work correctly");
+    }
+
+    #[test]
+    fn test_rolling_beta_strips_pure_market_exposure() {
+        let opens: Vec<f64> = (0..80).map(|i| 100.0 + 10.0 * (i as f64 / 4.0).sin() + i as f64 * 0.3).collect();
+        let mut df = create_daily_price_data(&opens);
+        let closes: Vec<f64> = opens.iter().enumerate().map(|(i, o)| o * if i % 3 == 0 { 1.04 } else { 0.99 }).collect();
+        df.with_column(Series::new("Close".into(), closes)).unwrap();
+        let start = chrono::NaiveDate::from_ymd_opt(2023, 1, 1).unwrap();
+
+        // The factor's open moves half as much as the asset's every day; its closes are
+        // unrelated noise that must not leak into the comparison
+        let path = std::env::temp_dir().join("backtester_rolling_beta_test.csv");
+        let mut csv = String::from("Date,Open,Close\n");
+        let mut factor_open = 50.0;
+        for i in 0..80 {
+            if i > 0 {
+                factor_open *= 1.0 + (opens[i] / opens[i - 1] - 1.0) / 2.0;
+            }
+            let close = factor_open * if i % 2 == 0 { 1.05 } else { 0.97 };
+            csv.push_str(&format!("{},{},{}\n", start + chrono::Duration::days(i as i64), factor_open, close));
+        }
+        std::fs::write(&path, csv).unwrap();
+        let factor = load_benchmark_returns(path.to_str().unwrap()).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        // Long throughout: the strategy's return is exactly twice the factor's
+        let mut side = BuySell { buy: vec![0; 80], sell: vec![0; 80] };
+        side.buy[0] = 1;
+        side.sell[79] = -1;
+        let config = PerformanceConfig {
+            benchmark: Some(Arc::new(factor)),
+            rolling_beta_window: Some(20),
+            ..Default::default()
+        };
+        let (_, bt_se, _) = backtest_performance_with(df, side, "test", &config).unwrap();
+        assert!((bt_se.avg_rolling_beta - 2.0).abs() < 1e-9, "beta {}", bt_se.avg_rolling_beta);
+        assert!(bt_se.beta_adjusted_return.abs() < 1e-9, "adjusted {}", bt_se.beta_adjusted_return);
+
+        // Returns unrelated to the factor are all alpha
+        let pairs: Vec<(f64, f64)> = (0..30).map(|t| (0.01, if t % 2 == 0 { 0.02 } else { -0.02 })).collect();
+        let (beta, adjusted) = rolling_beta(&pairs, 10);
+        assert!(beta.abs() < 1e-12);
+        assert!((adjusted - 20.0 * 0.01 * 100.0).abs() < 1e-9);
+        assert_eq!(rolling_beta(&pairs[..10], 10), (0.0, 0.0));
+        println!("✓ Rolling beta works correctly");
+    }
+
+    #[test]
+    fn test_atr_units_normalize_across_price_levels() {
+        // Same percentage path at 100 and at 200: the second asset's ATR is twice the first's
+        let asset = |scale: f64| {
+            let opens: Vec<f64> = (0..40).map(|i| scale * (100.0 + (i % 7) as f64 * 3.0)).collect();
+            let mut df = create_daily_price_data(&opens);
+            let high: Vec<f64> = opens.iter().map(|o| o * 1.02).collect();
+            let low: Vec<f64> = opens.iter().map(|o| o * 0.98).collect();
+            df.with_column(Series::new("High".into(), high)).unwrap();
+            df.with_column(Series::new("Low".into(), low)).unwrap();
+            df
+        };
+        let side = || {
+            let mut side = BuySell { buy: vec![0; 40], sell: vec![0; 40] };
+            for i in [5, 17, 29] {
+                side.buy[i] = 1;
+                side.sell[i + 4] = -1;
+            }
+            side
+        };
+        let config = PerformanceConfig { atr_lookback: Some(3), ..Default::default() };
+
+        let (cheap, cheap_se, _) = backtest_performance_with(asset(1.0), side(), "test", &config).unwrap();
+        let (dear, dear_se, _) = backtest_performance_with(asset(2.0), side(), "test", &config).unwrap();
+
+        // Point P&L doubles, ATR-unit P&L does not move
+        assert!((dear.avg_gain - 2.0 * cheap.avg_gain).abs() < 1e-9);
+        assert!(cheap.avg_gain_atr > 0.0 && cheap.avg_loss_atr > 0.0);
+        assert!((dear.avg_gain_atr - cheap.avg_gain_atr).abs() < 1e-9);
+        assert!((dear.avg_loss_atr - cheap.avg_loss_atr).abs() < 1e-9);
+        assert!((dear_se.avg_gain_atr - cheap_se.avg_gain_atr).abs() < 1e-9);
+
+        // Off by default
+        let (plain, _, _) = backtest_performance(asset(1.0), side(), "test").unwrap();
+        assert_eq!((plain.avg_gain_atr, plain.avg_loss_atr), (0.0, 0.0));
+        println!("✓ ATR-unit trade results work correctly");
+    }
+
+    #[test]
+    fn test_cagr_and_annualized_return() {
+        // Long -10%, short +10%, long +20%: total return 0.9 * 1.1 * 1.2 - 1 = 18.8%
+        let run = |days: usize| {
+            let mut opens = vec![100.0; days + 1];
+            opens[100] = 90.0;
+            opens[200] = 81.0;
+            opens[days] = 97.2;
+            let mut side = BuySell { buy: vec![0; days + 1], sell: vec![0; days + 1] };
+            side.buy[0] = 1;
+            side.sell[100] = -1;
+            side.buy[200] = 1;
+            side.sell[days] = -1;
+            // 365 daily bars to the year, so the spans below are whole years
+            let basis = backtester::config::AnnualizationBasis::Days365;
+            let config = PerformanceConfig { annualization: Some(basis), ..Default::default() };
+            backtest_performance_with(create_daily_price_data(&opens), side, "test", &config).unwrap().0
+        };
+        let total_return = (0.9 * 1.1 * 1.2 - 1.0) * 100.0;
+
+        // One year: CAGR is the total return
+        let one_year = run(365);
+        assert!((one_year.cagr - total_return).abs() < 1e-9);
+        assert!((one_year.annualized_return - 20.0).abs() < 1e-9);
+
+        // Two years: compounded down to the per-year rate
+        let two_years = run(730);
+        assert!((two_years.cagr - ((1.188_f64).sqrt() - 1.0) * 100.0).abs() < 1e-9);
+        assert!(two_years.cagr < total_return / 2.0);
+        assert!((two_years.annualized_return - 10.0).abs() < 1e-9);
+        println!("✓ CAGR and annualized return work correctly");
+    }
+
+    #[test]
+    fn test_recovery_factor_differs_from_calmar() {
+        // Two years of daily bars: long +10 then long -5 then short +8
+        let mut opens = vec![100.0; 731];
+        opens[200] = 110.0;
+        opens[400] = 105.0;
+        opens[730] = 97.0;
+        let mut side = BuySell { buy: vec![0; 731], sell: vec![0; 731] };
+        side.buy[0] = 1;
+        side.buy[200] = 1;
+        side.sell[400] = -1;
+        side.buy[730] = 1;
+
+        let (bt, _, _) = backtest_performance(create_daily_price_data(&opens), side, "test").unwrap();
+
+        // Net profit 13 over the dollar drawdown
+        assert!(bt.max_drawdown > 0.0);
+        assert!((bt.recovery_factor - 13.0 / bt.max_drawdown).abs() < 1e-9);
+        assert!((bt.recovery_factor - bt.calmar_ratio).abs() > 1e-6);
+        println!("✓ Recovery factor works correctly");
+    }
+
+    #[test]
+    fn test_filter_trades_recomputes_metrics() {
+        // Longs entered on up days (close > open) at bars 0, 4, 8 and down days at 2, 6
+        let opens = [100.0, 106.0, 100.0, 90.0, 100.0, 103.0, 100.0, 102.0, 100.0, 97.0];
+        let closes = [105.0, 106.0, 95.0, 90.0, 104.0, 103.0, 98.0, 102.0, 101.0, 97.0];
+        let mut df = create_daily_price_data(&opens);
+        df.with_column(Series::new("Close".into(), closes.to_vec())).unwrap();
+        let side = || BuySell {
+            buy: vec![1, 0, 1, 0, 1, 0, 1, 0, 1, 0],
+            sell: vec![0, -1, 0, -1, 0, -1, 0, -1, 0, -1],
+        };
+        let config = PerformanceConfig::default();
+
+        let (_, trades_se) = trade_ledger(&df, &side(), &config).unwrap();
+        // Sell-exit approach: five longs plus the four shorts between them
+        assert_eq!(trades_se.len(), 9);
+        assert_eq!(trades_se[0].entry_date, chrono::NaiveDate::from_ymd_opt(2023, 1, 1));
+        assert_eq!((trades_se[0].entry_price, trades_se[0].exit_price), (100.0, 106.0));
+
+        // The unfiltered ledger reproduces backtest_performance
+        let (_, bt_se, _) = backtest_performance(df.clone(), side(), "test").unwrap();
+        let all = metrics_from_trades(&df, &side(), "test_se", &trades_se, &config).unwrap();
+        assert_eq!(all.hit_ratio, bt_se.hit_ratio);
+        assert_eq!(all.trades, bt_se.trades);
+
+        let longs = filter_trades(&trades_se, |t| t.long);
+        let up_day = filter_trades(&longs, |t| closes[t.entry_index] > t.entry_price);
+        assert_eq!((longs.len(), up_day.len()), (5, 3));
+        let longs = metrics_from_trades(&df, &side(), "test_se", &longs, &config).unwrap();
+        let up_day = metrics_from_trades(&df, &side(), "test_se", &up_day, &config).unwrap();
+        assert!((longs.hit_ratio - 60.0).abs() < 1e-9);
+        assert!(up_day.hit_ratio > longs.hit_ratio);
+        println!("✓ Trade filtering works correctly");
+    }
+
+    #[test]
+    fn test_trimming_outlier_trades_lowers_profit_factor() {
+        let df = create_daily_price_data(&[100.0; 8]);
+        let side = BuySell { buy: vec![0; 8], sell: vec![0; 8] };
+        // One lucky +50 trade carries the record
+        let trades: Vec<Trade> = [50.0, 3.0, -2.0, 2.0, -4.0, 1.0]
+            .iter()
+            .enumerate()
+            .map(|(i, &pnl)| Trade {
+                long: true,
+                entry_index: i,
+                exit_index: i + 1,
+                entry_date: None,
+                exit_date: None,
+                entry_price: 100.0,
+                exit_price: 100.0 + pnl,
+                exit_reason: ExitReason::NextSignal,
+                initial_risk: None,
+            })
+            .collect();
+
+        let trimmed = trim_trades(&trades, 0.1);
+        let pnls: Vec<f64> = trimmed.iter().map(|t| t.pnl()).collect();
+        assert_eq!(pnls, vec![3.0, -2.0, 2.0, 1.0]);
+        assert!(trim_trades(&trades, 0.5).is_empty());
+
+        let full = metrics_from_trades(&df, &side, "test", &trades, &PerformanceConfig::default()).unwrap();
+        let config = PerformanceConfig { trim_fraction: 0.1, ..Default::default() };
+        let robust = metrics_from_trades(&df, &side, "test", &trades, &config).unwrap();
+        assert!((full.profit_factor - 56.0 / 6.0).abs() < 0.01, "pf {}", full.profit_factor);
+        assert!((robust.profit_factor - 3.0).abs() < 0.01, "pf {}", robust.profit_factor);
+        assert_eq!(robust.trades, 4);
+        println!("✓ Outlier trade trimming works correctly");
+    }
+
+    #[test]
+    fn test_annualization_basis_from_data() {
+        use backtester::config::{bars_per_year, AnnualizationBasis};
+        use chrono::{Datelike, NaiveDate, Weekday};
+
+        // The first 250 weekdays of each year over 2021-2023
+        let mut dates = Vec::new();
+        for year in 2021..2024 {
+            let weekdays = NaiveDate::from_ymd_opt(year, 1, 1).unwrap()
+                .iter_days()
+                .take_while(|d| d.year() == year)
+                .filter(|d| !matches!(d.weekday(), Weekday::Sat | Weekday::Sun));
+            dates.extend(weekdays.take(250));
+        }
+        let factor = AnnualizationBasis::FromData.periods_per_year(&dates);
+        assert!((factor - 250.0).abs() < 1.0, "factor {}", factor);
+        assert_eq!(AnnualizationBasis::Days252.periods_per_year(&dates), 252.0);
+        assert_eq!(AnnualizationBasis::Days365.periods_per_year(&dates), 365.0);
+
+        // The default keeps sqrt(252) for the ratios and calendar years for CAGR
+        assert_eq!(AnnualizationBasis::default(), AnnualizationBasis::Calendar);
+        assert_eq!(AnnualizationBasis::Calendar.periods_per_year(&dates), 252.0);
+        let span = (dates[dates.len() - 1] - dates[0]).num_days() as f64 / 365.25;
+        assert_eq!(AnnualizationBasis::Calendar.years(&dates), span);
+
+        // A partial year is scaled up from its bar density (five weekdays in seven)
+        let half = bars_per_year(&dates[..125]).unwrap();
+        assert!((half - 365.25 * 5.0 / 7.0).abs() < 1.0, "half-year factor {}", half);
+
+        // Sharpe scales with the square root of the factor
+        let opens: Vec<f64> = (0..60).map(|i| [100.0, 110.0, 100.0, 95.0][i % 4]).collect();
+        let side = || BuySell {
+            buy: (0..60).map(|i| if i % 2 == 0 { 1 } else { 0 }).collect(),
+            sell: (0..60).map(|i| if i % 2 == 1 { -1 } else { 0 }).collect(),
+        };
+        let run = |basis| {
+            let config = PerformanceConfig { annualization: Some(basis), ..Default::default() };
+            backtest_performance_with(create_daily_price_data(&opens), side(), "test", &config).unwrap().1
+        };
+        let (stocks, crypto) = (run(AnnualizationBasis::Days252), run(AnnualizationBasis::Days365));
+        assert!((crypto.sharpe_ratio / stocks.sharpe_ratio - (365.0_f64 / 252.0).sqrt()).abs() < 1e-9);
+        println!("✓ Annualization basis works correctly");
+    }
+
+    #[test]
+    fn test_log_return_sharpe_penalizes_large_swings() {
+        // Every long enters at 100 and exits alternately at 200 (+100%) or 70 (-30%),
+        // so dollar P&L is proportional to the arithmetic return
+        let opens: Vec<f64> = (0..40).map(|i| [100.0, 200.0, 100.0, 70.0][i % 4]).collect();
+        let n = opens.len();
+        let side = || BuySell {
+            buy: (0..n).map(|i| if i % 2 == 0 { 1 } else { 0 }).collect(),
+            sell: (0..n).map(|i| if i % 2 == 1 { -1 } else { 0 }).collect(),
+        };
+
+        let (_, arithmetic, _) = backtest_performance(create_daily_price_data(&opens), side(), "test").unwrap();
+        let config = PerformanceConfig { use_log_returns: true, ..Default::default() };
+        let (_, log, _) =
+            backtest_performance_with(create_daily_price_data(&opens), side(), "test", &config).unwrap();
+
+        // Log returns compress the big gain (ln 2 < 1) and stretch the loss
+        // (ln 0.7 < -0.3), so the risk-adjusted figures come out lower; trade
+        // P&L metrics are unchanged
+        assert_eq!(arithmetic.max_gain, 100.0);
+        assert!(log.sharpe_ratio > 0.0);
+        assert!(log.sharpe_ratio < arithmetic.sharpe_ratio);
+        assert!(log.sortino_ratio < arithmetic.sortino_ratio);
+        assert_eq!(log.profit_factor, arithmetic.profit_factor);
+        println!("✓ Log-return risk metrics work correctly");
+    }
+
+    #[test]
+    fn test_zero_open_is_skipped_as_exit() {
+        // Buy at 100; the exit signal lands on a bar with a zero open (data gap)
+        let opens = [100.0, 101.0, 0.0, 104.0, 105.0];
+        let side = || BuySell { buy: vec![1, 0, 0, 0, 0], sell: vec![0, 0, -1, 0, 0] };
+
+        let (bt, bt_se, _) = backtest_performance(create_daily_price_data(&opens), side(), "test").unwrap();
+
+        // Both approaches close at the next valid open (104) instead of booking -100
+        assert_eq!(bt.trades, 1);
+        assert_eq!(bt.max_gain, 4.0);
+        assert_eq!(bt.max_loss, 0.0);
+        assert_eq!(bt_se.trades, 1);
+        assert_eq!(bt_se.max_gain, 4.0);
+
+        // A zero open on the entry bar moves the entry forward too
+        let opens = [0.0, 101.0, 102.0, 104.0, 105.0];
+        let (bt, bt_se, _) = backtest_performance(create_daily_price_data(&opens), side(), "test").unwrap();
+        assert_eq!(bt.max_gain, 1.0);
+        assert_eq!(bt_se.max_gain, 1.0);
+        println!("✓ Zero-open bars are skipped correctly");
+    }
+
+    #[test]
+    fn test_mark_open_at_end_books_final_position() {
+        // Closed long 100 -> 104, then a long opened at 104 that is never closed
+        let opens = [100.0, 102.0, 104.0, 106.0, 110.0];
+        let side = || BuySell { buy: vec![1, 0, 1, 0, 0], sell: vec![0, 0, 0, 0, 0] };
+
+        let (bt, _, _) = backtest_performance(create_daily_price_data(&opens), side(), "test").unwrap();
+        assert_eq!(bt.trades, 1);
+        assert_eq!(bt.max_gain, 4.0);
+
+        let config = PerformanceConfig { mark_open_at_end: true, ..Default::default() };
+        let (bt, bt_se, _) =
+            backtest_performance_with(create_daily_price_data(&opens), side(), "test", &config).unwrap();
+        assert_eq!(bt.trades, 2);
+        assert_eq!(bt.max_gain, 6.0);
+        // Sell-exit approach: the first buy is never closed, so it is booked 100 -> 110
+        assert_eq!(bt_se.trades, 1);
+        assert_eq!(bt_se.max_gain, 10.0);
+        println!("✓ Marking open positions at end works correctly");
+    }
 }
 
 // ============================================================================
@@ -571,6 +2712,153 @@ mod strategy_filter_tests {
         assert_eq!(filtered.len(), 4);
         println!("✓ No strategy filter works correctly");
     }
+
+    #[test]
+    fn test_deny_list_removes_only_that_strategy() {
+        use crate::strategy_config::{crypto_strategies, StrategyFilter};
+
+        let full = crypto_strategies();
+        let filter = StrategyFilter {
+            allow: None,
+            deny: ["contrarian_rsi_extremes".to_string()].into_iter().collect(),
+        };
+        let filtered = filter.apply(crypto_strategies());
+
+        let expected: Vec<&str> = full.iter()
+            .map(|(name, _, _)| name.as_str())
+            .filter(|name| *name != "contrarian_rsi_extremes")
+            .collect();
+        let names: Vec<&str> = filtered.iter().map(|(name, _, _)| name.as_str()).collect();
+        assert_eq!(full.len() - 1, filtered.len());
+        assert_eq!(names, expected);
+        println!("✓ Strategy deny list works correctly");
+    }
+
+    #[test]
+    fn test_unreadable_allow_list_is_ignored() {
+        use crate::strategy_config::{crypto_strategies, StrategyFilter};
+
+        // A mistyped allow path must not turn into an empty list that disables everything
+        let missing = std::env::temp_dir().join("backtester_no_such_allow_list.txt");
+        let filter = StrategyFilter::from_values(Some(&missing.to_string_lossy()), None);
+        assert!(filter.allow.is_none());
+        assert_eq!(filter.apply(crypto_strategies()).len(), crypto_strategies().len());
+
+        // Plain comma-separated names still parse
+        let filter = StrategyFilter::from_values(Some("hammer,doji"), Some(" doji "));
+        assert!(filter.is_enabled("hammer"));
+        assert!(!filter.is_enabled("doji"));
+        assert!(!filter.is_enabled("engulfing"));
+        println!("✓ Unreadable strategy list handling works correctly");
+    }
+
+    #[test]
+    fn test_strategy_tables_have_no_duplicates() {
+        use crate::strategy_config::{dedup_strategies, production_strategies, strategies_for_tag};
+
+        // Check the raw tables: get_strategies_for_tag dedups, so it would hide a duplicate
+        for tag in ["lc", "mc", "sc", "micro", "crypto", "prod", "param", "signal", "test"] {
+            let mut seen = HashSet::new();
+            for (name, _, param) in strategies_for_tag(tag) {
+                assert!(seen.insert((name.clone(), param.to_bits())), "{} lists {}({}) twice", tag, name, param);
+            }
+        }
+
+        // A table listed twice collapses back to one copy in the original order;
+        // the same name with another parameter is a different backtest and stays
+        let mut doubled = production_strategies();
+        doubled.extend(production_strategies());
+        let (name, func, _) = doubled[0].clone();
+        doubled.push((name, func, 5.0));
+        let deduped = dedup_strategies(doubled);
+        assert_eq!(deduped.len(), production_strategies().len() + 1);
+        let names: Vec<&str> = deduped.iter().map(|(name, _, _)| name.as_str()).collect();
+        assert_eq!(names[0], names[names.len() - 1]);
+        println!("✓ Strategy table dedup works correctly");
+    }
+
+    #[test]
+    fn test_strategy_file_selects_exact_list() {
+        let path = std::env::temp_dir().join("backtester_strategy_file_test.txt");
+        std::fs::write(&path, "hammer\n# comment\n\ndoji\n").unwrap();
+
+        let strategies = crate::strategy_config::strategies_from_file(path.to_str().unwrap()).unwrap();
+        let names: Vec<&str> = strategies.iter().map(|(name, _, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["hammer", "doji"]);
+
+        std::fs::write(&path, "hammer\nnot_a_strategy\n").unwrap();
+        let err = crate::strategy_config::strategies_from_file(path.to_str().unwrap()).unwrap_err();
+        assert!(err.to_string().contains("not_a_strategy"));
+        assert!(err.to_string().ends_with(":2"));
+
+        let _ = std::fs::remove_file(&path);
+        println!("✓ Strategy file selection works correctly");
+    }
+
+    #[test]
+    fn test_signal_registry_unique_and_callable() {
+        let specs = crate::strategy_config::list_all_signals();
+        let mut seen = HashSet::new();
+        for spec in &specs {
+            assert!(seen.insert(spec.name.clone()), "duplicate strategy name {}", spec.name);
+        }
+
+        let n = 300;
+        let opens: Vec<f64> = (0..n).map(|i| 100.0 + 10.0 * (i as f64 / 7.0).sin() + i as f64 * 0.05).collect();
+        let df = df! {
+            "Date" => &(0..n).map(|i| chrono::NaiveDate::from_ymd_opt(2023, 1, 1).unwrap() + chrono::Duration::days(i)).collect::<Vec<_>>(),
+            "Ticker" => &vec!["btc"; n as usize],
+            "Universe" => &vec!["Crypto"; n as usize],
+            "Open" => &opens,
+            "High" => &opens.iter().map(|o| o * 1.01).collect::<Vec<_>>(),
+            "Low" => &opens.iter().map(|o| o * 0.99).collect::<Vec<_>>(),
+            "Close" => &opens.iter().map(|o| o * 1.002).collect::<Vec<_>>(),
+            "Volume" => &vec![1000.0; n as usize],
+        }.unwrap();
+
+        for spec in &specs {
+            let side = (spec.func)(df.clone(), spec.default_param);
+            assert_eq!(side.buy.len(), df.height(), "{} buy length", spec.name);
+            assert_eq!(side.sell.len(), df.height(), "{} sell length", spec.name);
+        }
+        println!("✓ Signal registry works correctly");
+    }
+
+    #[test]
+    fn test_strategy_names_match_wired_functions() {
+        use crate::strategy_config::*;
+
+        let specs = list_all_signals();
+        for spec in &specs {
+            assert!(
+                spec.name_matches_function(),
+                "{} is wired to {}::{}",
+                spec.name, spec.category, spec.function
+            );
+        }
+
+        // Every tier table must wire a registered name to the registry's function
+        let tables = [
+            production_strategies(),
+            crypto_strategies(),
+            micro_cap_strategies(),
+            small_cap_strategies(),
+            mid_cap_strategies(),
+            large_cap_strategies(),
+            parameterized_strategies(),
+            testing_strategies(),
+        ];
+        for (name, func, _) in tables.iter().flatten() {
+            if let Some(spec) = specs.iter().find(|s| &s.name == name) {
+                assert_eq!(
+                    *func as usize, spec.func as usize,
+                    "{} is not wired to {}::{}",
+                    name, spec.category, spec.function
+                );
+            }
+        }
+        println!("✓ Strategy names match their functions");
+    }
 }
 
 // ============================================================================
@@ -634,6 +2922,425 @@ mod summary_tests {
         println!("✓ Metric rounding works correctly");
     }
 
+    #[tokio::test]
+    async fn test_score_only_tickers() {
+        let dir = std::env::temp_dir().join("backtester_score_only_tickers_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("final")).unwrap();
+        std::fs::create_dir_all(dir.join("performance")).unwrap();
+        std::fs::write(
+            dir.join("final").join("crypto_testing.csv"),
+            "universe,strategy,risk_reward,sharpe_ratio,sortino_ratio,max_drawdown,calmar_ratio,\
+             win_loss_ratio,recovery_factor,profit_per_trade,expectancy,profit_factor\n\
+             Crypto,hammer,1.5,1.2,1.8,-10.0,0.9,1.1,2.0,0.5,0.4,1.6\n",
+        ).unwrap();
+        std::fs::write(
+            dir.join("performance").join("crypto_buys_20240301.csv"),
+            "ticker,universe,strategy,date,buy,sell\n\
+             btc,Crypto,hammer,2024-03-01,1,0\n\
+             eth,Crypto,hammer,2024-03-01,1,0\n\
+             sol,Crypto,hammer,2024-03-01,1,0\n",
+        ).unwrap();
+        std::fs::write(
+            dir.join("performance").join("crypto_sells_20240301.csv"),
+            "ticker,universe,strategy,date,buy,sell\n\
+             eth,Crypto,hammer,2024-03-01,0,-1\n",
+        ).unwrap();
+        let path = dir.to_string_lossy().into_owned();
+        let score_path = dir.join("score").join("Crypto_20240301.csv");
+        let run = |only: Option<Vec<String>>| {
+            let path = path.clone();
+            async move {
+                let inserted = Arc::new(std::sync::Mutex::new(None));
+                let sink = inserted.clone();
+                let options = ScoreOptions { only_tickers: only, ..Default::default() };
+                score_from(&path, "20240301", "Crypto", "Crypto", &options, |df, tickers| async move {
+                    *sink.lock().unwrap() = Some((df, tickers));
+                    Ok(())
+                })
+                .await
+                .unwrap();
+                let taken = inserted.lock().unwrap().take();
+                taken.unwrap()
+            }
+        };
+        let tickers = |df: &DataFrame| -> Vec<String> {
+            let mut t: Vec<String> = df.column("ticker").unwrap().str().unwrap()
+                .into_no_null_iter().map(String::from).collect();
+            t.sort();
+            t
+        };
+
+        // Full run scores and inserts every ticker
+        let (full, scope) = run(None).await;
+        assert_eq!(tickers(&full), ["btc", "eth", "sol"]);
+        assert_eq!(scope, None);
+
+        // Rescoring eth computes and inserts only eth, with both of its sides
+        let (eth, scope) = run(Some(vec!["eth".to_string()])).await;
+        assert_eq!(tickers(&eth), ["eth"]);
+        assert_eq!(eth.column("side").unwrap().i64().unwrap().get(0), Some(0));
+        assert_eq!(scope, Some(vec!["eth".to_string()]));
+
+        // The score file keeps the other tickers from the full run
+        let file = CsvReader::new(File::open(&score_path).unwrap()).finish().unwrap();
+        assert_eq!(tickers(&file), ["btc", "eth", "sol"]);
+
+        let _ = std::fs::remove_dir_all(&dir);
+        println!("✓ Targeted rescoring works correctly");
+    }
+
+    #[tokio::test]
+    async fn test_score_under_output_root_skips_insert() {
+        let dir = std::env::temp_dir().join(format!("backtester_score_output_root_test_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let (home, scratch) = (dir.join("home"), dir.join("scratch"));
+        std::fs::create_dir_all(home.join("final")).unwrap();
+        std::fs::create_dir_all(scratch.join("performance")).unwrap();
+        std::fs::write(
+            home.join("final").join("crypto_testing.csv"),
+            "universe,strategy,risk_reward,sharpe_ratio,sortino_ratio,max_drawdown,calmar_ratio,\
+             win_loss_ratio,recovery_factor,profit_per_trade,expectancy,profit_factor\n\
+             Crypto,hammer,1.5,1.2,1.8,-10.0,0.9,1.1,2.0,0.5,0.4,1.6\n",
+        ).unwrap();
+        std::fs::write(
+            scratch.join("performance").join("crypto_buys_20240301.csv"),
+            "ticker,universe,strategy,date,buy,sell\nbtc,Crypto,hammer,2024-03-01,1,0\n",
+        ).unwrap();
+        std::fs::write(
+            scratch.join("performance").join("crypto_sells_20240301.csv"),
+            "ticker,universe,strategy,date,buy,sell\n",
+        ).unwrap();
+
+        let inserted = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let sink = inserted.clone();
+        let options = ScoreOptions { output_root: Some(scratch.to_string_lossy().into_owned()), ..Default::default() };
+        score_from(&home.to_string_lossy(), "20240301", "Crypto", "Crypto", &options, |_, _| async move {
+            sink.store(true, std::sync::atomic::Ordering::SeqCst);
+            Ok(())
+        })
+        .await
+        .unwrap();
+
+        // The experimental scores stay on disk under the root and out of ClickHouse
+        assert!(!inserted.load(std::sync::atomic::Ordering::SeqCst));
+        assert!(scratch.join("score").join("Crypto_20240301.csv").exists());
+        assert!(!home.join("score").exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+        println!("✓ Output root scoring skips the insert correctly");
+    }
+
+    #[tokio::test]
+    async fn test_score_aggregation_of_conflicting_signals() {
+        let dir = std::env::temp_dir().join(format!("backtester_score_aggregation_test_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("final")).unwrap();
+        std::fs::create_dir_all(dir.join("performance")).unwrap();
+        std::fs::write(
+            dir.join("final").join("crypto_testing.csv"),
+            "universe,strategy,risk_reward,sharpe_ratio,sortino_ratio,max_drawdown,calmar_ratio,\
+             win_loss_ratio,recovery_factor,profit_per_trade,expectancy,profit_factor\n\
+             Crypto,hammer,1.5,1.2,1.8,-10.0,0.9,1.1,2.0,0.5,0.4,1.6\n\
+             Crypto,donchian,1.0,0.8,1.0,-5.0,0.5,1.0,1.0,0.2,0.3,2.5\n",
+        ).unwrap();
+        // eth has a hammer buy and a donchian sell on the same day
+        std::fs::write(
+            dir.join("performance").join("crypto_buys_20240301.csv"),
+            "ticker,universe,strategy,date,buy,sell\n\
+             eth,Crypto,hammer,2024-03-01,1,0\n",
+        ).unwrap();
+        std::fs::write(
+            dir.join("performance").join("crypto_sells_20240301.csv"),
+            "ticker,universe,strategy,date,buy,sell\n\
+             eth,Crypto,donchian,2024-03-01,0,-1\n",
+        ).unwrap();
+        let path = dir.to_string_lossy().into_owned();
+        let run = |aggregation: ScoreAggregation| {
+            let path = path.clone();
+            async move {
+                let inserted = Arc::new(std::sync::Mutex::new(None));
+                let sink = inserted.clone();
+                let options = ScoreOptions { aggregation, ..Default::default() };
+                score_from(&path, "20240301", "Crypto", "Crypto", &options, |df, _| async move {
+                    *sink.lock().unwrap() = Some(df);
+                    Ok(())
+                })
+                .await
+                .unwrap();
+                let df = inserted.lock().unwrap().take().unwrap();
+                let side: Vec<i64> = df.column("side").unwrap().i64().unwrap().into_no_null_iter().collect();
+                let pf: Vec<f64> = df.column("profit_factor").unwrap().f64().unwrap().into_no_null_iter().collect();
+                (side, pf)
+            }
+        };
+
+        // Net: the sell's negated profit factor offsets the buy's, 1.6 - 2.5
+        assert_eq!(run(ScoreAggregation::Net).await, (vec![0], vec![-0.9]));
+        // Max conviction: the sell's 2.5 outweighs the buy's 1.6, so only the sell is scored
+        assert_eq!(run(ScoreAggregation::MaxConviction).await, (vec![-1], vec![-2.5]));
+        // Separate: one row per side, buy first
+        assert_eq!(run(ScoreAggregation::Separate).await, (vec![1, -1], vec![1.6, -2.5]));
+        assert_eq!("max-conviction".parse(), Ok(ScoreAggregation::MaxConviction));
+        assert!("sum".parse::<ScoreAggregation>().is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
+        println!("✓ Score aggregation works correctly");
+    }
+
+    #[tokio::test]
+    async fn test_import_score_csv_matches_direct_insert() {
+        let dir = std::env::temp_dir().join("backtester_import_score_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("final")).unwrap();
+        std::fs::create_dir_all(dir.join("performance")).unwrap();
+        std::fs::write(
+            dir.join("final").join("crypto_testing.csv"),
+            "universe,strategy,risk_reward,sharpe_ratio,sortino_ratio,max_drawdown,calmar_ratio,\
+             win_loss_ratio,recovery_factor,profit_per_trade,expectancy,profit_factor\n\
+             Crypto,hammer,1.5,1.2,1.8,-10.0,0.9,1.1,2.0,0.5,0.4,1.6\n",
+        ).unwrap();
+        std::fs::write(
+            dir.join("performance").join("crypto_buys_20240301.csv"),
+            "ticker,universe,strategy,date,buy,sell\n\
+             btc,Crypto,hammer,2024-03-01,1,0\n\
+             eth,Crypto,hammer,2024-03-01,1,0\n",
+        ).unwrap();
+        std::fs::write(
+            dir.join("performance").join("crypto_sells_20240301.csv"),
+            "ticker,universe,strategy,date,buy,sell\n\
+             sol,Crypto,hammer,2024-03-01,0,-1\n",
+        ).unwrap();
+        let path = dir.to_string_lossy().into_owned();
+
+        let direct = Arc::new(std::sync::Mutex::new(None));
+        let sink = direct.clone();
+        score_from(&path, "20240301", "Crypto", "Crypto", &ScoreOptions::default(), |df, _| async move {
+            *sink.lock().unwrap() = Some(df);
+            Ok(())
+        })
+        .await
+        .unwrap();
+        let direct = direct.lock().unwrap().take().unwrap();
+
+        let imported = Arc::new(std::sync::Mutex::new(None));
+        let sink = imported.clone();
+        let score_path = dir.join("score").join("Crypto_20240301.csv");
+        let rows = import_score_csv_with(score_path.to_str().unwrap(), |df, tickers| async move {
+            *sink.lock().unwrap() = Some((df, tickers));
+            Ok(())
+        })
+        .await
+        .unwrap();
+        let (imported, tickers) = imported.lock().unwrap().take().unwrap();
+
+        assert_eq!(rows, 3);
+        assert!(imported.equals_missing(&direct));
+        assert_eq!(tickers, ["btc", "eth", "sol"]);
+        let _ = std::fs::remove_dir_all(&dir);
+        println!("✓ Score CSV import works correctly");
+    }
+
+    #[tokio::test]
+    async fn test_score_positions_carry_holds_forward() {
+        let dir = std::env::temp_dir().join("backtester_score_positions_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("score")).unwrap();
+        let header = "date,universe,ticker,side,risk_reward,sharpe_ratio,sortino_ratio,max_drawdown,\
+                      calmar_ratio,win_loss_ratio,recovery_factor,profit_per_trade,expectancy,profit_factor\n";
+        let write = |datetag: &str, rows: &[(&str, i64)]| {
+            let mut csv = header.to_string();
+            for (ticker, side) in rows {
+                csv.push_str(&format!("2024-03-01,Crypto,{},{},1.0,1.0,1.0,-1.0,1.0,1.0,1.0,1.0,1.0,1.0\n", ticker, side));
+            }
+            std::fs::write(dir.join("score").join(format!("Crypto_{}.csv", datetag)), csv).unwrap();
+        };
+        let path = dir.to_string_lossy().into_owned();
+        let statuses = |df: &DataFrame| -> Vec<(String, String)> {
+            let tickers = df.column("ticker").unwrap().str().unwrap();
+            let status = df.column("status").unwrap().str().unwrap();
+            (0..df.height())
+                .map(|i| (tickers.get(i).unwrap().to_string(), status.get(i).unwrap().to_string()))
+                .collect()
+        };
+        let pair = |t: &str, s: &str| (t.to_string(), s.to_string());
+
+        // Day 1 buys btc and ada; day 2 has no btc signal, sells ada and buys sol
+        write("20240228", &[("btc", 2), ("ada", 1), ("eth", -1)]);
+        write("20240229", &[("ada", -1), ("sol", 1), ("xrp", 0)]);
+        let day2 = score_positions(&path, "Crypto", "20240229").await.unwrap();
+        assert_eq!(statuses(&day2), [pair("ada", "new sell"), pair("btc", "hold"), pair("sol", "new buy")]);
+        assert_eq!(day2.column("side").unwrap().i64().unwrap().get(1), Some(0));
+        assert!(dir.join("score").join("positions").join("Crypto_20240229.csv").exists());
+
+        // Day 3 carries the holds from day 2's positions file, not just day 2's buys
+        write("20240301", &[("eth", 1)]);
+        let day3 = score_positions(&path, "Crypto", "20240301").await.unwrap();
+        assert_eq!(statuses(&day3), [pair("btc", "hold"), pair("eth", "new buy"), pair("sol", "hold")]);
+
+        // The positions folder doesn't disturb the score history
+        assert_eq!(load_score_history(&dir.join("score").to_string_lossy(), "Crypto").await.unwrap().height(), 7);
+
+        // Separately scored tickers keep both rows, and a ticker sold that day isn't held after it
+        write("20240302", &[("dot", 1), ("dot", -1), ("sol", 1), ("sol", -1)]);
+        let day4 = score_positions(&path, "Crypto", "20240302").await.unwrap();
+        assert_eq!(statuses(&day4), [
+            pair("btc", "hold"),
+            pair("dot", "new sell"),
+            pair("dot", "new buy"),
+            pair("eth", "hold"),
+            pair("sol", "new sell"),
+            pair("sol", "new buy"),
+        ]);
+        write("20240303", &[]);
+        let day5 = score_positions(&path, "Crypto", "20240303").await.unwrap();
+        assert_eq!(statuses(&day5), [pair("btc", "hold"), pair("eth", "hold")]);
+
+        // A scored day without its positions file can't be skipped over: day 7 would
+        // otherwise carry btc from day 5's positions past day 6's sell
+        write("20240304", &[("btc", -1)]);
+        write("20240305", &[]);
+        let err = score_positions(&path, "Crypto", "20240305").await.unwrap_err();
+        assert!(err.to_string().contains("Crypto_20240304.csv"), "{}", err);
+        assert!(!dir.join("score").join("positions").join("Crypto_20240305.csv").exists());
+        score_positions(&path, "Crypto", "20240304").await.unwrap();
+        let day7 = score_positions(&path, "Crypto", "20240305").await.unwrap();
+        assert_eq!(statuses(&day7), [pair("eth", "hold")]);
+        let _ = std::fs::remove_dir_all(&dir);
+        println!("✓ Score position carry-forward works correctly");
+    }
+
+    #[tokio::test]
+    async fn test_latest_scores_json_shape() {
+        let dir = std::env::temp_dir().join(format!("backtester_scores_json_test_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let header = "date,universe,ticker,side,risk_reward,sharpe_ratio,sortino_ratio,max_drawdown,\
+                      calmar_ratio,win_loss_ratio,recovery_factor,profit_per_trade,expectancy,profit_factor\n";
+        // An older file that must not be served
+        std::fs::write(dir.join("Crypto_20240229.csv"), format!("{}2024-02-29,Crypto,doge,5,1,1,1,1,1,1,1,1,1,9\n", header)).unwrap();
+        std::fs::write(
+            dir.join("Crypto_20240301.csv"),
+            format!(
+                "{}2024-03-01,Crypto,eth,1,1,0.8,1,1,1,1,1,1,0.2,1.4\n\
+                 2024-03-01,Crypto,btc,2,1,1.1,1,1,1,1,1,1,0.5,1.8\n\
+                 2024-03-01,Crypto,sol,-1,-1,-0.5,-1,-1,-1,-1,-1,-1,-0.1,-1.2\n\
+                 2024-03-01,Crypto,ada,-2,-1,-0.9,-1,-1,-1,-1,-1,-1,-0.3,-2.5\n\
+                 2024-03-01,Crypto,xrp,0,0,0,0,0,0,0,0,0,0,0\n",
+                header
+            ),
+        ).unwrap();
+
+        let body = latest_scores_json(&dir.to_string_lossy(), " crypto ").await.unwrap();
+        assert_eq!(body, serde_json::json!({
+            "label": "Crypto",
+            "date": "2024-03-01",
+            "buys": [
+                {"rank": 1, "ticker": "btc", "universe": "Crypto", "side": 2, "profit_factor": 1.8, "expectancy": 0.5, "sharpe_ratio": 1.1},
+                {"rank": 2, "ticker": "eth", "universe": "Crypto", "side": 1, "profit_factor": 1.4, "expectancy": 0.2, "sharpe_ratio": 0.8},
+            ],
+            "sells": [
+                {"rank": 1, "ticker": "ada", "universe": "Crypto", "side": -2, "profit_factor": -2.5, "expectancy": -0.3, "sharpe_ratio": -0.9},
+                {"rank": 2, "ticker": "sol", "universe": "Crypto", "side": -1, "profit_factor": -1.2, "expectancy": -0.1, "sharpe_ratio": -0.5},
+            ],
+        }));
+
+        assert!(latest_scores_json(&dir.to_string_lossy(), "stocks").await.is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+        println!("✓ Latest scores JSON works correctly");
+    }
+
+    #[test]
+    fn test_score_diff_between_runs() {
+        let dir = std::env::temp_dir().join("backtester_score_diff_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("score")).unwrap();
+        let header = "date,universe,ticker,side,risk_reward,sharpe_ratio,sortino_ratio,max_drawdown,\
+                      calmar_ratio,win_loss_ratio,recovery_factor,profit_per_trade,expectancy,profit_factor\n";
+        let write = |datetag: &str, rows: &[(&str, i64)]| {
+            let mut csv = header.to_string();
+            for (ticker, side) in rows {
+                csv.push_str(&format!("2024-03-01,Crypto,{},{},1.0,1.0,1.0,-1.0,1.0,1.0,1.0,1.0,1.0,1.0\n", ticker, side));
+            }
+            std::fs::write(dir.join("score").join(format!("Crypto_{}.csv", datetag)), csv).unwrap();
+        };
+        write("20240228", &[("btc", 2), ("ada", 1), ("eth", -1), ("xrp", 0)]);
+        // btc still a buy, ada and eth flip, sol and xrp are new, dot a new sell
+        write("20240229", &[("btc", 1), ("ada", -1), ("eth", 2), ("sol", 1), ("xrp", 1), ("dot", -1)]);
+
+        let diff = score_diff(&dir.to_string_lossy(), "20240228", "20240229", "Crypto").unwrap();
+        let tickers = diff.column("ticker").unwrap().str().unwrap();
+        let changes = diff.column("change").unwrap().str().unwrap();
+        let rows: Vec<(&str, &str)> = (0..diff.height())
+            .map(|i| (changes.get(i).unwrap(), tickers.get(i).unwrap()))
+            .collect();
+        assert_eq!(rows, [
+            ("flipped to buy", "eth"),
+            ("flipped to sell", "ada"),
+            ("new buy", "sol"),
+            ("new buy", "xrp"),
+            ("new sell", "dot"),
+        ]);
+        assert_eq!(diff.column("prev_side").unwrap().i64().unwrap().get(0), Some(-1));
+
+        // Separately scored buy and sell rows are each compared on their own
+        write("20240301", &[("btc", 1), ("btc", -1), ("ada", 1), ("ada", -1)]);
+        let diff = score_diff(&dir.to_string_lossy(), "20240229", "20240301", "Crypto").unwrap();
+        let tickers = diff.column("ticker").unwrap().str().unwrap();
+        let changes = diff.column("change").unwrap().str().unwrap();
+        let rows: Vec<(&str, &str)> = (0..diff.height())
+            .map(|i| (changes.get(i).unwrap(), tickers.get(i).unwrap()))
+            .collect();
+        assert_eq!(rows, [("flipped to buy", "ada"), ("flipped to sell", "btc")]);
+
+        let _ = std::fs::remove_dir_all(&dir);
+        println!("✓ Score diff works correctly");
+    }
+
+    #[tokio::test]
+    async fn test_load_score_history() {
+        let dir = std::env::temp_dir().join("backtester_score_history_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let header = "date,universe,ticker,side,risk_reward,sharpe_ratio,sortino_ratio,max_drawdown,\
+                      calmar_ratio,win_loss_ratio,recovery_factor,profit_per_trade,expectancy,profit_factor\n";
+        let row = |date: &str, ticker: &str, side: i64| {
+            format!("{},Crypto,{},{},1.0,1.0,1.0,-1.0,1.0,1.0,1.0,1.0,1.0,1.0\n", date, ticker, side)
+        };
+        for (datetag, date, tickers) in [
+            ("20240301", "2024-02-29", vec!["btc", "eth"]),
+            ("20240302", "2024-03-01", vec!["btc"]),
+            ("20240304", "2024-03-01", vec!["eth", "sol"]),
+        ] {
+            let body: String = tickers.iter().map(|t| row(date, t, 2)).collect();
+            std::fs::write(dir.join(format!("Crypto_{}.csv", datetag)), format!("{}{}", header, body)).unwrap();
+        }
+        // Not part of the history: a malformed date and another tag
+        std::fs::write(dir.join("Crypto_latest.csv"), format!("{}{}", header, row("2024-03-01", "xrp", 1))).unwrap();
+        std::fs::write(dir.join("Stocks_20240301.csv"), format!("{}{}", header, row("2024-03-01", "IBM", 1))).unwrap();
+
+        let history = load_score_history(&dir.to_string_lossy(), "Crypto").await.unwrap();
+        assert_eq!(history.height(), 5);
+        assert_eq!(history.get_column_names()[0].as_str(), "score_date");
+
+        let tagged: Vec<(String, String)> = (0..history.height())
+            .map(|i| {
+                let date = history.column("score_date").unwrap().get(i).unwrap().to_string();
+                let ticker = history.column("ticker").unwrap().str().unwrap().get(i).unwrap().to_string();
+                (date, ticker)
+            })
+            .collect();
+        let expected = [
+            ("2024-03-01", "btc"), ("2024-03-01", "eth"),
+            ("2024-03-02", "btc"),
+            ("2024-03-04", "eth"), ("2024-03-04", "sol"),
+        ];
+        assert_eq!(tagged, expected.map(|(d, t)| (d.to_string(), t.to_string())));
+
+        let _ = std::fs::remove_dir_all(&dir);
+        println!("✓ Score history loading works correctly");
+    }
+
     #[test]
     fn test_column_selection() {
         let all_cols = vec!["strategy", "universe", "hit_ratio", "sharpe_ratio",